@@ -0,0 +1,66 @@
+from rusty_graph import KnowledgeGraph
+
+
+def network(edges, relation='R'):
+    kg = KnowledgeGraph()
+    names = sorted({name for edge in edges for name in edge[:2]})
+    kg.add_nodes([[name, name] for name in names], ['id', 'name'], 'N', 'id', 'name')
+    columns = ['from', 'to', 'capacity'] if len(edges[0]) > 2 else ['from', 'to']
+    kg.add_relationships([list(edge) for edge in edges], columns, relation, 'N', 'from', 'N', 'to')
+    return kg
+
+
+def index(kg, name):
+    return kg.get_nodes('N', [{'unique_id': name}])[0]
+
+
+def test_reachable_from_counts():
+    # a -> b -> c -> d, e -> b, and d -> b closing a cycle
+    kg = network([('a', 'b'), ('b', 'c'), ('c', 'd'), ('e', 'b'), ('d', 'b')])
+    sources = [index(kg, name) for name in 'abde']
+    counts = kg.reachable_from(sources, 'R', counts=True)
+    assert counts == list(zip(sources, [3, 3, 3, 3]))
+    counts = kg.reachable_from(sources, 'R', max_depth=1, counts=True)
+    assert counts == list(zip(sources, [1, 1, 1, 1]))
+    counts = kg.reachable_from(sources, 'R', max_depth=2, direction='incoming', counts=True)
+    assert counts == list(zip(sources, [0, 4, 2, 0]))
+    assert 'reachable_from(max_depth=Some(2), counts=True)' in kg.explain()
+
+
+
+def test_max_flow_known_value():
+    # The network of Cormen et al., Introduction to Algorithms, figure 26.1, with a maximum flow of 23
+    kg = network([
+        ('s', 'v1', 16), ('s', 'v2', 13), ('v2', 'v1', 4), ('v1', 'v3', 12), ('v3', 'v2', 9),
+        ('v2', 'v4', 14), ('v4', 'v3', 7), ('v3', 't', 20), ('v4', 't', 4),
+    ])
+    result = kg.max_flow([index(kg, 's')], [index(kg, 't')], 'capacity')
+    assert result['value'] == 23
+    cut = kg.get_edge_attributes(result['min_cut'], None)
+    assert sorted((edge['source_id'], edge['target_id']) for edge in cut) == [('v1', 'v3'), ('v4', 't'), ('v4', 'v3')]
+    assert sum(edge['capacity'] for edge in cut) == 23
+
+
+def test_centrality_known_values():
+    # a and b point to c, which points to d and e
+    kg = network([('a', 'c'), ('b', 'c'), ('c', 'd'), ('c', 'e')])
+    names = {index(kg, name): name for name in 'abcde'}
+
+    def scores(measure):
+        return {names[node]: round(score, 6) for node, score in kg.centrality(measure)}
+
+    assert scores('degree') == {'a': 0.25, 'b': 0.25, 'c': 1.0, 'd': 0.25, 'e': 0.25}
+    # c lies on the 4 shortest paths from a and b to d and e, out of 4 * 3 ordered pairs of other nodes
+    assert scores('betweenness') == {'a': 0.0, 'b': 0.0, 'c': round(4 / 12, 6), 'd': 0.0, 'e': 0.0}
+    # Reaching 3 of 4 nodes at total distance 5 gives 3/4 * 3/5
+    assert scores('closeness') == {'a': 0.45, 'b': 0.45, 'c': 0.5, 'd': 0.0, 'e': 0.0}
+    pagerank = scores('pagerank')
+    assert abs(sum(pagerank.values()) - 1) < 1e-5
+    assert pagerank['c'] > pagerank['d'] == pagerank['e'] > pagerank['a'] == pagerank['b']
+
+
+if __name__ == '__main__':
+    for name, test in list(globals().items()):
+        if name.startswith('test_'):
+            test()
+            print(f'{name}: ok')
@@ -0,0 +1,86 @@
+from rusty_graph import KnowledgeGraph
+
+
+def wells():
+    kg = KnowledgeGraph()
+    kg.add_nodes(
+        [['1', 'a', '5', None], ['2', 'b', None, 'x'], ['3', 'c', '7', 'y']],
+        ['id', 'name', 'depth', 'note'], 'Well', 'id', 'name', column_types={'depth': 'Int'},
+    )
+    kg.add_nodes([['9', 'F']], ['id', 'name'], 'Field', 'id', 'name')
+    kg.add_relationships([['1', '9'], ['2', '9'], ['3', '9']], ['well', 'field'], 'IN', 'Well', 'well', 'Field', 'field')
+    kg.add_relationships([['1', '2']], ['from', 'to'], 'NEAR', 'Well', 'from', 'Well', 'to')
+    return kg
+
+
+def ids(kg, node_type):
+    return sorted(node['unique_id'] for node in kg.get_node_attributes(kg.get_nodes(node_type), ['unique_id']))
+
+
+def test_delete_nodes_removes_their_relations():
+    kg = wells()
+    result = kg.delete_nodes(kg.get_nodes('Well', [{'title': 'b'}]))
+    assert result == {'nodes_deleted': 1, 'edges_deleted': 2}
+    assert ids(kg, 'Well') == ['1', '3']
+    # Indices may be renumbered, so the field is looked up again
+    field = kg.get_node_attributes(kg.get_nodes('Field'), None)[0]
+    assert sorted(relation['source_id'] for relation in field['incoming_relations']) == ['1', '3']
+    assert len(kg.select_edges('NEAR')) == 0
+
+
+def test_delete_nodes_by_ids_reports_missing():
+    kg = wells()
+    result = kg.delete_nodes_by_ids('Well', ['3', '404'])
+    assert result == {'nodes_deleted': 1, 'edges_deleted': 1, 'missing': ['404']}
+    assert ids(kg, 'Well') == ['1', '2']
+    assert ids(kg, 'Field') == ['9']
+
+
+def test_delete_connections_and_edges():
+    kg = wells()
+    assert kg.delete_connections('IN', ['1'], None) == 1
+    assert len(kg.select_edges('IN')) == 2
+    assert kg.delete_connections('IN') == 2
+    assert kg.select_edges('IN') == []
+    assert kg.delete_edges(kg.select_edges('NEAR')) == 1
+    assert kg.select_edges() == []
+    assert ids(kg, 'Well') == ['1', '2', '3']
+
+
+def test_update_connection_properties():
+    kg = wells()
+    result = kg.update_connection_properties([['1', '9', 'half'], ['3', '404', 'all']], ['well', 'field', 'share'], 'IN', 'Well', 'well', 'Field', 'field')
+    assert result == {'updated': 1, 'missing': [('3', '404')]}
+    shares = kg.get_edge_attributes(kg.select_edges('IN'), ['share'])
+    assert sorted(str(edge.get('share')) for edge in shares) == ['None', 'None', 'half']
+
+
+def test_merge_nodes_keeps_survivor_values():
+    kg = wells()
+    result = kg.merge_nodes('Well', '1', ['2', '404'])
+    assert result['merged'] == ['2'] and result['missing'] == ['404']
+    # The duplicate IN relation and the NEAR relation, which would join the survivor to itself, are dropped
+    assert result['edges_moved'] == 0 and result['edges_dropped'] == 2
+    assert ids(kg, 'Well') == ['1', '3']
+    survivor = kg.get_node_attributes([result['survivor']], ['unique_id', 'title', 'depth', 'note'])[0]
+    assert survivor == {'unique_id': '1', 'title': 'a', 'depth': 5, 'note': 'x'}
+    assert kg.get_merge_log() == [{'node_type': 'Well', 'duplicate_id': '2', 'survivor_id': '1'}]
+
+
+def test_merge_nodes_moves_relations():
+    kg = wells()
+    kg.add_nodes([['4', 'd']], ['id', 'name'], 'Well', 'id', 'name')
+    kg.add_relationships([['4', '3']], ['from', 'to'], 'NEAR', 'Well', 'from', 'Well', 'to')
+    result = kg.merge_nodes('Well', '1', ['4'], 'prefer_duplicates')
+    assert result['edges_moved'] == 1
+    survivor = kg.get_node_attributes([result['survivor']], ['title'], None)[0]
+    assert survivor['title'] == 'd'
+    near = kg.get_edge_attributes(kg.select_edges('NEAR'), None)
+    assert sorted((edge['source_id'], edge['target_id']) for edge in near) == [('1', '2'), ('1', '3')]
+
+
+if __name__ == '__main__':
+    for name, test in list(globals().items()):
+        if name.startswith('test_'):
+            test()
+            print(f'{name}: ok')
@@ -0,0 +1,79 @@
+from rusty_graph import KnowledgeGraph
+
+
+def wells():
+    kg = KnowledgeGraph()
+    kg.add_nodes(
+        [
+            ['1', '25/1-1', 'OIL', '1200', '2020-03-01', 'report 2020-01-01 final'],
+            ['2', '25/2-4', 'GAS', '3400', '2021-08-15', 'Café notes'],
+            ['3', '34/10-7', 'OIL', '800', None, None],
+        ],
+        ['id', 'name', 'content', 'depth', 'spud_date', 'note'], 'Well', 'id', 'name',
+        column_types={'depth': 'Int', 'spud_date': 'Date'},
+    )
+    kg.add_nodes_stream(iter([{'id': '4', 'name': 'Equinor', 'tags': ['gas', 'oil']}]), 'Company', 'id', 'name')
+    return kg
+
+
+def ids(kg, filters, node_type='Well', **options):
+    indices = kg.get_nodes(node_type, filters, **options)
+    return sorted(node['unique_id'] for node in kg.get_node_attributes(indices, ['unique_id']))
+
+
+def test_composition():
+    kg = wells()
+    assert ids(kg, [{'$or': [{'content': 'GAS'}, {'depth': {'<': 1000}}]}]) == ['2', '3']
+    assert ids(kg, [{'$not': {'content': 'OIL'}}]) == ['2']
+    assert ids(kg, [{'$and': [{'content': 'OIL'}, {'$not': {'depth': 800}}]}]) == ['1']
+
+
+def test_missing_values():
+    kg = wells()
+    assert ids(kg, [{'spud_date': None}]) == ['3']
+    assert ids(kg, [{'spud_date': {'!=': None}}]) == ['1', '2']
+
+
+def test_dates_parse_iso_strings():
+    kg = wells()
+    assert ids(kg, [{'spud_date': {'between': ['2020-01-01', '2020-12-31']}}]) == ['1']
+    assert ids(kg, [{'spud_date': {'>': '2021-01-01'}}]) == ['2']
+
+
+def test_contains_matches_date_like_text():
+    kg = wells()
+    assert ids(kg, [{'note': {'contains': '2020-01-01'}}]) == ['1']
+    assert ids(kg, [{'note': {'contains': 'final'}}]) == ['1']
+
+
+def test_list_operators():
+    kg = wells()
+    assert ids(kg, [{'tags': {'contains': 'oil'}}], 'Company') == ['4']
+    assert ids(kg, [{'tags': {'contains': 'coal'}}], 'Company') == []
+    assert ids(kg, [{'tags': {'length': {'>': 1}}}], 'Company') == ['4']
+
+
+def test_regex_and_glob():
+    kg = wells()
+    assert ids(kg, [{'title': {'regex': r'^25/\d+'}}]) == ['1', '2']
+    assert ids(kg, [{'title': {'glob': '34/10-*'}}]) == ['3']
+
+
+def test_fuzzy():
+    kg = wells()
+    assert ids(kg, [{'title': {'fuzzy': ('Eqionor', 0.85)}}], 'Company') == ['4']
+    assert ids(kg, [{'title': {'fuzzy': ('Shell', 0.85)}}], 'Company') == []
+
+
+def test_case_insensitive():
+    kg = wells()
+    assert ids(kg, [{'content': 'oil'}], case_insensitive=True) == ['1', '3']
+    assert ids(kg, [{'note': {'contains': 'cafe'}}]) == []
+    assert ids(kg, [{'note': {'contains': 'CAFÉ'}}], case_insensitive=True) == ['2']
+
+
+if __name__ == '__main__':
+    for name, test in list(globals().items()):
+        if name.startswith('test_'):
+            test()
+            print(f'{name}: ok')
@@ -0,0 +1,125 @@
+from rusty_graph import KnowledgeGraph
+
+
+def wells():
+    kg = KnowledgeGraph()
+    kg.track_changes()
+    kg.add_nodes([['1', 'a', '5'], ['2', 'b', '6']], ['id', 'name', 'depth'], 'Well', 'id', 'name', column_types={'depth': 'Int'})
+    kg.add_nodes([['9', 'F']], ['id', 'name'], 'Field', 'id', 'name')
+    kg.add_relationships([['1', '9'], ['2', '9']], ['well', 'field'], 'IN', 'Well', 'well', 'Field', 'field')
+    return kg
+
+
+def actions(changes):
+    return [(change['operation'], change['action'], change['entity']) for change in changes]
+
+
+def test_history_records_old_and_new_values():
+    kg = wells()
+    kg.add_nodes([['1', 'a', '7']], ['id', 'name', 'depth'], 'Well', 'id', 'name', column_types={'depth': 'Int'})
+    changes = kg.history('1', node_type='Well')
+    assert actions(changes) == [
+        ('add_nodes', 'created', 'node'),
+        ('add_relationships', 'created', 'relation'),
+        ('add_nodes', 'updated', 'node'),
+    ]
+    assert changes[0]['changes'] == {'title': (None, 'a'), 'depth': (None, 5)}
+    assert changes[2]['changes'] == {'depth': (5, 7)}
+
+
+def test_history_records_deletes():
+    kg = wells()
+    kg.delete_nodes_by_ids('Field', ['9'])
+    deleted = [change for change in kg.history() if change['operation'] == 'delete_nodes_by_ids']
+    assert sorted(actions(deleted)) == [('delete_nodes_by_ids', 'deleted', 'node')] + [('delete_nodes_by_ids', 'deleted', 'relation')] * 2
+    field = next(change for change in deleted if change['entity'] == 'node')
+    assert (field['node_type'], field['unique_id'], field['changes']) == ('Field', '9', {'title': ('F', None)})
+
+
+def test_history_follows_a_node_through_a_type_change():
+    kg = wells()
+    kg.change_node_type(kg.get_nodes('Well', [{'title': 'b'}]), 'Prospect')
+    changes = kg.history('2')
+    assert actions(changes)[-1] == ('change_node_type', 'updated', 'node')
+    assert (changes[-1]['node_type'], changes[-1]['changes']) == ('Prospect', {'node_type': ('Well', 'Prospect')})
+    assert not any(change['action'] == 'deleted' for change in changes)
+
+
+def test_history_keeps_nodes_sharing_a_unique_id_apart():
+    kg = KnowledgeGraph()
+    kg.track_changes()
+    kg.add_node('Well', '1', {'depth': 5}, 'a')
+    kg.add_node('Well', '1', {'depth': 6}, 'b')
+    changes = kg.history('1')
+    assert actions(changes) == [('add_node', 'created', 'node')] * 2
+    assert [change['changes']['title'] for change in changes] == [(None, 'a'), (None, 'b')]
+
+
+def test_restore_undoes_changes_since_the_checkpoint():
+    kg = wells()
+    before = kg.get_node_attributes(kg.get_nodes('Well'), None)
+    kg.checkpoint('start')
+    kg.add_nodes([['1', 'a', '7'], ['3', 'c', '1']], ['id', 'name', 'depth'], 'Well', 'id', 'name', column_types={'depth': 'Int'})
+    kg.delete_nodes_by_ids('Well', ['2'])
+    kg.checkpoint('later')
+    kg.delete_connections('IN')
+    kg.restore('start')
+    # Indices included, as the nodes were before
+    assert kg.get_node_attributes(kg.get_nodes('Well'), None) == before
+    assert kg.list_checkpoints() == ['start']
+    restored = [change for change in kg.history() if change['operation'] == 'restore' and change['entity'] == 'node']
+    assert sorted((change['action'], change['unique_id'], tuple(change['changes'].get('depth', ()))) for change in restored) == [
+        ('created', '2', (None, 6)), ('deleted', '3', (1, None)), ('updated', '1', (7, 5)),
+    ]
+    kg.delete_nodes(kg.get_nodes('Well'))
+    kg.restore('start')
+    assert kg.get_node_attributes(kg.get_nodes('Well'), None) == before
+
+
+
+def test_transaction_runs_staged_calls_when_the_block_ends():
+    kg = wells()
+    with kg.transaction() as tx:
+        assert tx.add_nodes([['3', 'c']], ['id', 'name'], 'Well', 'id', 'name') is None
+        assert len(kg.get_nodes('Well')) == 2
+    assert len(kg.get_nodes('Well')) == 3
+
+
+def test_transaction_undoes_earlier_calls_when_one_fails():
+    kg = wells()
+    before = kg.get_node_attributes(kg.get_nodes('Well'), None)
+    try:
+        with kg.transaction() as tx:
+            tx.add_nodes([['3', 'c', '1']], ['id', 'name', 'depth'], 'Well', 'id', 'name', column_types={'depth': 'Int'})
+            tx.delete_nodes_by_ids('Well', ['1'])
+            tx.add_nodes([['2', 'x', '1']], ['id', 'name', 'depth'], 'Well', 'id', 'name', conflict_handling='error')
+        raise AssertionError('the failing call should raise')
+    except ValueError:
+        pass
+    assert kg.get_node_attributes(kg.get_nodes('Well'), None) == before
+    rolled_back = [change for change in kg.history() if change['operation'] == 'rollback']
+    assert sorted((change['entity'], change['action'], change.get('unique_id', change.get('source_id'))) for change in rolled_back) == [
+        ('node', 'created', '1'), ('node', 'deleted', '3'), ('relation', 'created', '1'),
+    ]
+
+
+def test_transaction_drops_staged_calls_on_error_or_rollback():
+    kg = wells()
+    try:
+        with kg.transaction() as tx:
+            tx.delete_nodes_by_ids('Well', ['1'])
+            raise KeyError('stop')
+    except KeyError:
+        pass
+    with kg.transaction() as tx:
+        tx.delete_nodes_by_ids('Well', ['1'])
+        tx.rollback()
+        tx.delete_nodes_by_ids('Well', ['2'])
+    assert [node['unique_id'] for node in kg.get_node_attributes(kg.get_nodes('Well'), ['unique_id'])] == ['1']
+
+
+if __name__ == '__main__':
+    for name, test in list(globals().items()):
+        if name.startswith('test_'):
+            test()
+            print(f'{name}: ok')
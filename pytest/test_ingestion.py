@@ -0,0 +1,236 @@
+import json
+import os
+import sqlite3
+import tempfile
+
+from rusty_graph import KnowledgeGraph
+
+WELL_COLUMNS = ['id', 'name', 'fluid', 'depth']
+WELL_TYPES = {'fluid': 'Category', 'depth': 'Int'}
+
+
+def wells():
+    kg = KnowledgeGraph()
+    kg.add_nodes([['1', 'a', 'gas', '5']], WELL_COLUMNS, 'Well', 'id', 'name', column_types=WELL_TYPES)
+    return kg
+
+
+def constrained_wells():
+    # A Category property, a range constraint and a default, which every ingestion path must honour
+    kg = wells()
+    kg.add_constraint('Well', 'depth', 'range', 0, 100)
+    kg.set_default('Well', 'status', 'active')
+    return kg
+
+
+def well_values(kg):
+    return sorted(
+        (node['unique_id'], node.get('fluid'), node.get('status'), node.get('depth'))
+        for node in kg.get_node_attributes(kg.get_nodes('Well'), ['unique_id', 'fluid', 'status', 'depth'])
+    )
+
+
+def assert_ingested(kg):
+    assert kg.get_node_schemas()['Well']['attributes']['fluid'] == 'Category'
+    assert well_values(kg) == [('1', 'gas', 'active', 5), ('2', 'oil', 'active', 7)]
+
+
+def assert_rejected(ingest):
+    kg = constrained_wells()
+    try:
+        ingest(kg)
+    except ValueError as e:
+        assert 'no changes were made' in str(e)
+    else:
+        raise AssertionError('depth 700 was not rejected')
+    assert well_values(kg) == [('1', 'gas', 'active', 5)]
+
+
+def temp_path(name):
+    return os.path.join(tempfile.mkdtemp(), name)
+
+
+def write_csv(depth):
+    path = temp_path('wells.csv')
+    with open(path, 'w') as f:
+        f.write(f'id,name,fluid,depth\n2,b,oil,{depth}\n')
+    return path
+
+
+def write_sqlite(depth):
+    path = temp_path('wells.db')
+    connection = sqlite3.connect(path)
+    connection.execute('CREATE TABLE wells (id TEXT, name TEXT, fluid TEXT, depth INTEGER)')
+    connection.execute('INSERT INTO wells VALUES (?, ?, ?, ?)', ('2', 'b', 'oil', depth))
+    connection.commit()
+    connection.close()
+    return 'sqlite:///' + path
+
+
+def write_jsonl(depth):
+    path = temp_path('nodes.jsonl')
+    with open(path, 'w') as f:
+        record = {'node_type': 'Well', 'unique_id': '2', 'title': 'b', 'properties': {'fluid': 'oil', 'depth': depth}}
+        f.write(json.dumps(record) + '\n')
+    return path
+
+
+def test_add_nodes_conflict_handling():
+    kg = wells()
+    kg.add_nodes([['1', 'a2', 'oil', '6']], WELL_COLUMNS, 'Well', 'id', 'name', 'update', column_types=WELL_TYPES)
+    assert well_values(kg) == [('1', 'oil', None, 6)]
+    kg.add_nodes([['1', 'a3', 'gas']], ['id', 'name', 'fluid'], 'Well', 'id', 'name', 'replace', column_types=WELL_TYPES)
+    assert well_values(kg) == [('1', 'gas', None, None)]
+    kg.add_nodes([['1', 'a4', 'oil']], ['id', 'name', 'fluid'], 'Well', 'id', 'name', 'skip', column_types=WELL_TYPES)
+    assert well_values(kg) == [('1', 'gas', None, None)]
+    assert kg.get_node_attributes(kg.get_nodes('Well'), ['title']) == [{'title': 'a3'}]
+
+
+def test_add_nodes_error_rolls_back():
+    kg = wells()
+    try:
+        kg.add_nodes([['2', 'b', 'oil', '7'], ['1', 'a', 'gas', '5']], WELL_COLUMNS, 'Well', 'id', 'name', 'error', column_types=WELL_TYPES)
+    except ValueError as e:
+        assert 'no nodes were added' in str(e)
+    else:
+        raise AssertionError('the existing ID was not reported')
+    assert well_values(kg) == [('1', 'gas', None, 5)]
+
+
+def test_add_nodes_matches_rows_of_the_same_call():
+    kg = KnowledgeGraph()
+    rows = [
+        ['6F9619FF-8B86-D011-B42D-00C04FC964FF', 'a', '1'],
+        ['{6f9619ff-8b86-d011-b42d-00c04fc964ff}', None, '2'],
+        ['6f9619ff8b86d011b42d00c04fc964ff', None, '3'],
+    ]
+    result = kg.add_nodes(rows, ['id', 'name', 'depth'], 'Well', 'id', 'name', column_types={'id': 'UUID', 'depth': 'Int'}, report=True)
+    assert result['created'] == 1 and result['updated'] == 2
+    wells = kg.get_node_attributes(kg.get_nodes('Well'), ['unique_id', 'title', 'depth'])
+    assert wells == [{'unique_id': '6f9619ff-8b86-d011-b42d-00c04fc964ff', 'title': 'a', 'depth': 3}]
+
+
+def test_add_relationships_conflict_handling():
+    kg = wells()
+    kg.add_nodes([['9', 'F']], ['id', 'name'], 'Field', 'id', 'name')
+    columns = ['well', 'field', 'share']
+    kg.add_relationships([['1', '9', 'half']], columns, 'IN', 'Well', 'well', 'Field', 'field')
+    result = kg.add_relationships([['1', '9', 'all']], columns, 'IN', 'Well', 'well', 'Field', 'field', conflict_handling='skip_duplicates')
+    assert result['skipped'] == 1
+    kg.add_relationships([['1', '9', 'third']], ['well', 'field', 'note'], 'IN', 'Well', 'well', 'Field', 'field', conflict_handling='update')
+    edges = kg.select_edges('IN')
+    assert len(edges) == 1
+    assert kg.get_edge_attributes(edges, ['share', 'note']) == [{'share': 'half', 'note': 'third'}]
+    kg.add_relationships([['1', '9', 'all']], columns, 'IN', 'Well', 'well', 'Field', 'field', conflict_handling='replace')
+    assert kg.get_edge_attributes(edges, ['share', 'note']) == [{'share': 'all'}]
+    result = kg.add_relationships([['1', '9', 'more']], columns, 'IN', 'Well', 'well', 'Field', 'field', conflict_handling='add')
+    assert result['created'] == 1 and len(kg.select_edges('IN')) == 2
+
+
+def test_add_relationships_missing_nodes():
+    kg = wells()
+    result = kg.add_relationships([['1', '77']], ['well', 'field'], 'IN', 'Well', 'well', 'Field', 'field', create_missing_nodes=False)
+    assert result['missing'] == [('1', '77')] and kg.get_nodes('Field') == []
+    kg.add_relationships([['1', '77']], ['well', 'field'], 'IN', 'Well', 'well', 'Field', 'field')
+    assert kg.get_node_attributes(kg.get_nodes('Field'), ['unique_id']) == [{'unique_id': '77'}]
+
+
+def test_add_relationships_rejected_placeholder_rolls_back():
+    kg = constrained_wells()
+    kg.add_constraint('Well', 'depth', 'required')
+    try:
+        kg.add_relationships([['2', '1']], ['from', 'to'], 'NEAR', 'Well', 'from', 'Well', 'to')
+    except ValueError as e:
+        assert 'depth is required' in str(e)
+    else:
+        raise AssertionError('the placeholder without depth was not rejected')
+    assert well_values(kg) == [('1', 'gas', 'active', 5)]
+    assert kg.select_edges('NEAR') == []
+
+
+def test_add_relationships_placeholder_takes_defaults():
+    kg = constrained_wells()
+    kg.add_relationships([['2', '1']], ['from', 'to'], 'NEAR', 'Well', 'from', 'Well', 'to')
+    assert well_values(kg) == [('1', 'gas', 'active', 5), ('2', None, 'active', None)]
+
+
+def test_add_nodes_applies_schema():
+    kg = constrained_wells()
+    kg.add_nodes([['2', 'b', 'oil', '7']], WELL_COLUMNS, 'Well', 'id', 'name', column_types=WELL_TYPES)
+    assert_ingested(kg)
+    assert_rejected(lambda kg: kg.add_nodes([['2', 'b', 'oil', '700']], WELL_COLUMNS, 'Well', 'id', 'name', column_types=WELL_TYPES))
+
+
+def test_add_nodes_from_csv_applies_schema():
+    kg = constrained_wells()
+    kg.add_nodes_from_csv(write_csv(7), 'Well', 'id', 'name')
+    assert_ingested(kg)
+    assert_rejected(lambda kg: kg.add_nodes_from_csv(write_csv(700), 'Well', 'id', 'name'))
+
+
+def test_add_nodes_stream_applies_schema():
+    kg = constrained_wells()
+    kg.add_nodes_stream(iter([{'id': '2', 'name': 'b', 'fluid': 'oil', 'depth': 7}]), 'Well', 'id', 'name')
+    assert_ingested(kg)
+    assert_rejected(lambda kg: kg.add_nodes_stream(iter([{'id': '2', 'name': 'b', 'fluid': 'oil', 'depth': 700}]), 'Well', 'id', 'name'))
+
+
+def test_add_nodes_from_sql_applies_schema():
+    kg = constrained_wells()
+    kg.add_nodes_from_sql(write_sqlite(7), 'SELECT * FROM wells', 'Well', 'id', 'name')
+    assert_ingested(kg)
+    assert_rejected(lambda kg: kg.add_nodes_from_sql(write_sqlite(700), 'SELECT * FROM wells', 'Well', 'id', 'name'))
+
+
+def write_links(rows):
+    path = temp_path('links.db')
+    connection = sqlite3.connect(path)
+    connection.execute('CREATE TABLE links (well TEXT, field TEXT, share TEXT)')
+    connection.executemany('INSERT INTO links VALUES (?, ?, ?)', rows)
+    connection.commit()
+    connection.close()
+    return 'sqlite:///' + path
+
+
+def test_add_relationships_from_sql_conflict_handling_and_endpoints():
+    kg = wells()
+    kg.add_nodes([['9', 'F']], ['id', 'name'], 'Field', 'id', 'name')
+    kg.declare_relation('IN', 'Well', 'Field', 'N:1')
+    query = 'SELECT * FROM links'
+    result = kg.add_relationships_from_sql(
+        write_links([('1', '9', '0.5'), ('1', '9', '0.7'), ('1', '8', '0.1')]), query, 'IN', 'Well', 'well', 'Field', 'field',
+        column_types={'share': 'Float'}, enforce_schema=True, conflict_handling='skip_duplicates',
+    )
+    assert result['created'] == 1 and result['skipped'] == 1
+    assert [violation['row'] for violation in result['violations']] == [2]
+    assert kg.get_edge_attributes(kg.select_edges('IN'), ['share']) == [{'share': 0.5}]
+    assert kg.get_relation_schemas()['IN']['attributes'] == {'share': 'Float'}
+
+
+def test_add_relationships_from_sql_bad_cell_adds_nothing():
+    kg = wells()
+    query = 'SELECT * FROM links'
+    try:
+        kg.add_relationships_from_sql(
+            write_links([('1', '9', '0.5'), ('2', '9', 'half')]), query, 'IN', 'Well', 'well', 'Field', 'field', column_types={'share': 'Float'},
+        )
+    except ValueError as e:
+        assert "'half'" in str(e)
+    else:
+        raise AssertionError('the bad share was not reported')
+    assert kg.select_edges('IN') == [] and kg.get_nodes('Field') == []
+    assert well_values(kg) == [('1', 'gas', None, 5)]
+
+
+def test_import_jsonl_applies_schema():
+    kg = constrained_wells()
+    kg.import_jsonl(write_jsonl(7))
+    assert_ingested(kg)
+    assert_rejected(lambda kg: kg.import_jsonl(write_jsonl(700)))
+
+
+if __name__ == '__main__':
+    for name, test in list(globals().items()):
+        if name.startswith('test_'):
+            test()
+            print(f'{name}: ok')
@@ -0,0 +1,173 @@
+import os
+import sqlite3
+import tempfile
+
+from rusty_graph import KnowledgeGraph
+
+ROOT = os.path.dirname(os.path.dirname(os.path.abspath(__file__)))
+
+
+def school_graph():
+    kg = KnowledgeGraph()
+    kg.add_nodes([['Skole A', '0']], ['navn', 'unique_id'], 'Skole', 'unique_id', 'navn')
+    kg.add_nodes(
+        [['Klasse A', '0', '5'], ['Klasse B', '1', '6'], ['Klasse C', '2', '2']],
+        ['navn', 'unique_id', 'elever'], 'Klasse', 'unique_id', 'navn', column_types={'elever': 'Int'},
+    )
+    kg.add_relationships([['0', '0'], ['1', '0'], ['2', '0']], ['klasse_id', 'skole_id'], 'klasse_i', 'Klasse', 'klasse_id', 'Skole', 'skole_id')
+    return kg
+
+
+def test_loads_file_saved_by_first_release():
+    kg = KnowledgeGraph()
+    kg.load_from_file(os.path.join(ROOT, 'test_file'))
+    school = kg.get_nodes(None, [{'title': 'Skole A'}])
+    assert len(school) == 1
+    classes = kg.traverse_incoming(school, 'klasse_i', 'elever')
+    assert len(classes) == 3
+    assert sorted(node['elever'] for node in kg.get_node_attributes(classes, ['elever'])) == [2, 5, 6]
+
+
+def test_save_to_file_round_trip():
+    kg = school_graph()
+    path = os.path.join(tempfile.mkdtemp(), 'graph.bin')
+    kg.save_to_file(path)
+    loaded = KnowledgeGraph()
+    loaded.load_from_file(path)
+    kg.assert_equal(loaded)
+
+
+def test_save_round_trip_keeps_schema():
+    kg = school_graph()
+    kg.add_constraint('Klasse', 'elever', 'range', 0, 40)
+    kg.set_default('Klasse', 'trinn', 1)
+    path = os.path.join(tempfile.mkdtemp(), 'graph.rg')
+    kg.save(path)
+    loaded = KnowledgeGraph.load(path)
+    kg.assert_equal(loaded)
+    schema = loaded.get_node_schemas()['Klasse']
+    assert schema['defaults'] == {'trinn': 1}
+    assert 'elever' in schema['constraints']
+    assert schema['nulls']['unique_id'] == 0 and schema['nulls']['navn'] == 0
+
+
+def test_large_integers_round_trip():
+    kg = KnowledgeGraph()
+    kg.add_nodes([[9007199254740993, 'a', str(2**40)]], ['id', 'name', 'volume'], 'Well', 'id', 'name', column_types={'volume': 'Int'})
+    path = os.path.join(tempfile.mkdtemp(), 'graph.bin')
+    kg.save_to_file(path)
+    loaded = KnowledgeGraph()
+    loaded.load_from_file(path)
+    wells = loaded.get_nodes('Well', [{'volume': {'>': 2**32}}])
+    assert loaded.get_node_attributes(wells, ['unique_id', 'volume']) == [{'unique_id': '9007199254740993', 'volume': 2**40}]
+
+
+
+def test_graphml_round_trip():
+    kg = school_graph()
+    kg.add_nodes([['Skole <B> & C', '1']], ['navn', 'unique_id'], 'Skole', 'unique_id', 'navn')
+    path = os.path.join(tempfile.mkdtemp(), 'graph.graphml')
+    kg.to_graphml(path)
+    kg.assert_equal(KnowledgeGraph.from_graphml(path))
+
+
+def test_export_neo4j_writes_typed_csvs_and_scripts():
+    kg = school_graph()
+    directory = tempfile.mkdtemp()
+    files = kg.export_neo4j(directory)
+    assert sorted(os.path.basename(path) for path in files) == [
+        'import.sh', 'load_csv.cypher', 'nodes_Klasse.csv', 'nodes_Skole.csv', 'relationships_klasse_i_Klasse_Skole.csv',
+    ]
+
+    def read(name):
+        with open(os.path.join(directory, name)) as file:
+            return file.read().splitlines()
+
+    assert read('nodes_Klasse.csv') == [
+        'unique_id:ID(Klasse),title,elever:int,:LABEL', '0,Klasse A,5,Klasse', '1,Klasse B,6,Klasse', '2,Klasse C,2,Klasse',
+    ]
+    assert read('relationships_klasse_i_Klasse_Skole.csv') == [
+        ':START_ID(Klasse),:END_ID(Skole),:TYPE', '0,0,klasse_i', '1,0,klasse_i', '2,0,klasse_i',
+    ]
+    assert '"--relationships=klasse_i=$DIR/relationships_klasse_i_Klasse_Skole.csv"' in '\n'.join(read('import.sh'))
+    assert 'n.`elever` = toInteger(row.`elever:int`)' in '\n'.join(read('load_csv.cypher'))
+
+
+def test_sqlite_round_trip_and_sql_edits():
+    kg = school_graph()
+    kg.add_constraint('Klasse', 'elever', 'range', 0, 40)
+    kg.save_selection('small', kg.get_nodes('Klasse', [{'elever': {'<': 6}}]))
+    path = os.path.join(tempfile.mkdtemp(), 'graph.sqlite')
+    kg.to_sqlite(path)
+    loaded = KnowledgeGraph.from_sqlite(path)
+    kg.assert_equal(loaded)
+    assert 'elever' in loaded.get_node_schemas()['Klasse']['constraints']
+    assert sorted(node['elever'] for node in loaded.get_node_attributes(loaded.load_selection('small'), ['elever'])) == [2, 5]
+
+    with sqlite3.connect(path) as connection:
+        connection.execute("UPDATE node_properties SET value = 7 WHERE value = 6 AND name = 'elever'")
+        connection.execute("INSERT INTO nodes (id, node_type, unique_id, title) VALUES (100, 'Klasse', '3', 'Klasse D')")
+    edited = KnowledgeGraph.from_sqlite(path)
+    classes = edited.get_node_attributes(edited.get_nodes('Klasse'), ['title', 'elever'])
+    assert sorted((node['title'], node.get('elever')) for node in classes) == [
+        ('Klasse A', 5), ('Klasse B', 7), ('Klasse C', 2), ('Klasse D', None),
+    ]
+
+
+def test_deltas_reproduce_later_states():
+    directory = tempfile.mkdtemp()
+    base, first, second = (os.path.join(directory, name) for name in ('graph.rg', '1.delta', '2.delta'))
+    kg = school_graph()
+    kg.save(base)
+    kg.add_nodes(
+        [['Klasse D', '3', '9'], ['Klasse A', '0', '4']],
+        ['navn', 'unique_id', 'elever'], 'Klasse', 'unique_id', 'navn', column_types={'elever': 'Int'},
+    )
+    kg.add_relationships([['3', '0']], ['klasse_id', 'skole_id'], 'klasse_i', 'Klasse', 'klasse_id', 'Skole', 'skole_id')
+    kg.save_delta(base, first)
+    kg.delete_nodes_by_ids('Klasse', ['1'])
+    kg.save_delta(base, second)
+
+    loaded = KnowledgeGraph.load_with_deltas(base, [first, second])
+    kg.assert_equal(loaded)
+    classes = loaded.get_node_attributes(loaded.get_nodes('Klasse'), ['unique_id', 'elever'])
+    assert sorted((node['unique_id'], node['elever']) for node in classes) == [('0', 4), ('2', 2), ('3', 9)]
+    school = loaded.get_nodes('Skole')
+    assert len(loaded.traverse_incoming(school, 'klasse_i')) == 3
+
+    # Deltas apply only in the order they were saved
+    try:
+        KnowledgeGraph.load_with_deltas(base, [second])
+    except ValueError as e:
+        assert 'in the order they were saved' in str(e)
+    else:
+        raise AssertionError('a delta applied out of order should raise')
+
+
+def test_to_rdf_writes_turtle():
+    kg = school_graph()
+    kg.add_nodes([['Skole "B"', 'x y']], ['navn', 'unique_id'], 'Skole', 'unique_id', 'navn')
+    path = os.path.join(tempfile.mkdtemp(), 'graph.ttl')
+    triples = kg.to_rdf(path, base_iri='https://data.example.org/', type_mappings={'Klasse': 'http://example.org/ontology/Class'})
+    # Type, label and unique ID of each node, the size of each class and one triple per relation
+    assert triples == 5 * 3 + 3 + 3
+    with open(path) as file:
+        turtle = file.read()
+    assert '@prefix xsd: <http://www.w3.org/2001/XMLSchema#> .' in turtle
+    assert (
+        '<https://data.example.org/Klasse/0>\n'
+        '    a <http://example.org/ontology/Class> ;\n'
+        '    rdfs:label "Klasse A" ;\n'
+        '    <https://data.example.org/vocab/unique_id> "0" ;\n'
+        '    <https://data.example.org/vocab/elever> "5"^^xsd:integer .'
+    ) in turtle
+    # IRIs are percent-encoded and literals escaped
+    assert '<https://data.example.org/Skole/x%20y>\n    a <https://data.example.org/vocab/Skole> ;\n    rdfs:label "Skole \\"B\\"" ;' in turtle
+    assert '<https://data.example.org/Klasse/2> <https://data.example.org/vocab/klasse_i> <https://data.example.org/Skole/0> .' in turtle
+
+
+if __name__ == '__main__':
+    for name, test in list(globals().items()):
+        if name.startswith('test_'):
+            test()
+            print(f'{name}: ok')
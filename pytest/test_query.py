@@ -0,0 +1,54 @@
+from rusty_graph import KnowledgeGraph
+
+
+def wells():
+    kg = KnowledgeGraph()
+    kg.add_nodes(
+        [['1', 'A', '1200'], ['2', 'B', '3400'], ['3', 'C', '800']],
+        ['id', 'name', 'depth'], 'Well', 'id', 'name', column_types={'depth': 'Int'},
+    )
+    kg.add_nodes([['9', 'F'], ['8', 'G']], ['id', 'name'], 'Field', 'id', 'name')
+    kg.add_relationships([['1', '9'], ['2', '9'], ['3', '8']], ['well', 'field'], 'IN', 'Well', 'well', 'Field', 'field')
+    return kg
+
+
+def test_match_where_return():
+    kg = wells()
+    rows = kg.query("MATCH (w:Well)-[:IN]->(f:Field) WHERE w.depth > 1000 RETURN w.title AS well, f.title AS field ORDER BY well")
+    assert rows == [{'well': 'A', 'field': 'F'}, {'well': 'B', 'field': 'F'}]
+
+
+def test_return_groups_aggregates():
+    kg = wells()
+    rows = kg.query("MATCH (w:Well)-[:IN]->(f:Field) RETURN f.title AS field, count(*) AS wells, sum(w.depth) AS depth ORDER BY field")
+    assert rows == [{'field': 'F', 'wells': 2, 'depth': 4600}, {'field': 'G', 'wells': 1, 'depth': 800}]
+
+
+def test_bare_alias_returns_the_node_index():
+    kg = wells()
+    assert kg.query("MATCH (w:Well {title: 'C'}) RETURN w") == [{'w': kg.get_nodes('Well', [{'title': 'C'}])[0]}]
+    assert kg.query("MATCH (w:Well) RETURN w.title AS title ORDER BY title DESC LIMIT 2") == [{'title': 'C'}, {'title': 'B'}]
+
+
+def test_malformed_queries_raise():
+    kg = wells()
+    for query, message in [
+        ("RETURN 1", "must start with MATCH"),
+        ("MATCH (w:Well)", "needs a RETURN clause"),
+        ("MATCH (w:Well RETURN w", "Expected ')'"),
+        ("MATCH (w:Well) RETURN w LIMIT x", "LIMIT expects a non-negative integer"),
+        ("MATCH (w:Well) RETURN w ORDER BY depth", "must name a RETURN column"),
+    ]:
+        try:
+            kg.query(query)
+        except ValueError as e:
+            assert message in str(e), (query, str(e))
+        else:
+            raise AssertionError(f'{query!r} should raise')
+
+
+if __name__ == '__main__':
+    for name, test in list(globals().items()):
+        if name.startswith('test_'):
+            test()
+            print(f'{name}: ok')
@@ -0,0 +1,40 @@
+from rusty_graph import KnowledgeGraph
+
+
+def wells():
+    kg = KnowledgeGraph()
+    kg.add_nodes([['1', 'a'], ['2', 'b']], ['id', 'name'], 'Well', 'id', 'name')
+    kg.add_relationships([['1', '2', 0.5]], ['from', 'to', 'share'], 'NEAR', 'Well', 'from', 'Well', 'to')
+    return kg
+
+
+def test_relation_schema_records_property_types():
+    kg = wells()
+    schema = kg.get_relation_schemas()['NEAR']
+    assert schema['attributes'] == {'share': 'Float'}
+    assert schema['endpoints'] == [{'source_type': 'Well', 'target_type': 'Well', 'count': 1}]
+
+
+def test_relation_schema_rejects_conflicting_types():
+    kg = wells()
+    try:
+        kg.add_relationships([['2', '1', 'half']], ['from', 'to', 'share'], 'NEAR', 'Well', 'from', 'Well', 'to')
+    except ValueError as e:
+        assert "existing type 'Float', new type 'String'" in str(e)
+    else:
+        raise AssertionError('the String share was not reported')
+    assert len(kg.select_edges('NEAR')) == 1
+
+
+def test_relation_schema_records_aggregated_types():
+    kg = wells()
+    rows = [['1', '2', 2], ['1', '2', 3]]
+    kg.add_relationships(rows, ['from', 'to', 'wells'], 'NEAR', 'Well', 'from', 'Well', 'to', conflict_handling='update', aggregate={'wells': 'sum'})
+    assert kg.get_relation_schemas()['NEAR']['attributes'] == {'share': 'Float', 'wells': 'Int'}
+
+
+if __name__ == '__main__':
+    for name, test in list(globals().items()):
+        if name.startswith('test_'):
+            test()
+            print(f'{name}: ok')
@@ -1,15 +1,278 @@
-use chrono::{NaiveDateTime, NaiveDate, Utc, TimeZone};
+use chrono::{Datelike, FixedOffset, NaiveDateTime, NaiveDate, Timelike, Utc, TimeZone};
 use std::cmp::Ordering;
-use pyo3::{prelude::*, exceptions::PyTypeError};
+use std::fmt;
+use pyo3::{prelude::*, exceptions::{PyTypeError, PyValueError}};
 use pyo3::{PyResult, Python, FromPyObject, PyAny};
-use serde::{Serialize, Deserialize};
+use pyo3::types::{PyBool, PyByteArray, PyBytes, PyDate, PyDateAccess, PyDateTime, PyDelta, PyDeltaAccess, PyDict, PyList, PyTimeAccess, PyTuple, PyTzInfo};
+use serde::{Serialize, Deserialize, Serializer, Deserializer};
+use serde_json::Value as JsonValue;
+use std::collections::HashMap;
+use std::sync::Arc;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub enum AttributeValue {
-    Int(i32),
+    Int(i64),
     Float(f64),
     DateTime(i64), // Timestamp, used for both dates and datetimes
     String(String),
+    Boolean(bool),
+    Null, // A genuinely missing value, such as None or NaN from Python; never stored on nodes
+    List(Vec<AttributeValue>), // Multi-valued property, e.g. tags
+    Map(HashMap<String, AttributeValue>), // Nested properties, reached with dotted paths such as meta.source
+    Decimal(Decimal), // Fixed-point number for amounts that must add up exactly, e.g. money
+    Date(i32), // Calendar date without a time, as days since 1970-01-01
+    Duration(i64), // Length of time in seconds, e.g. downtime or the difference of two dates
+    Uuid(u128),
+    Category(Category), // Label of a low-cardinality column, stored as a code into the column's dictionary
+    Bytes(Vec<u8>), // Binary payload such as a hash, thumbnail or serialized blob
+}
+
+// 1970-01-01 counted in chrono's days from the common era, where 0001-01-01 is day 1
+const EPOCH_DAYS_FROM_CE: i32 = 719_163;
+
+/// Fixed-point number: `units` counted in steps of 10^-`scale`, so 12.30 is 1230 units at scale 2
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Decimal {
+    pub units: i128,
+    pub scale: u32,
+}
+
+// Results are rounded to at most this many decimals, which leaves 20 digits before the point
+pub const MAX_DECIMAL_SCALE: u32 = 18;
+// Quotients keep this many decimals beyond those of their operands
+const DIVISION_EXTRA_SCALE: u32 = 6;
+
+impl Decimal {
+    pub fn new(units: i128, scale: u32) -> Self {
+        Decimal { units, scale }
+    }
+
+    // Parses decimal text such as "-12.30", "1e3" or "1.5E-7", as Python's Decimal prints it
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        let (mantissa, exponent) = match text.find(['e', 'E']) {
+            Some(i) => (&text[..i], text[i + 1..].parse::<i32>().ok()?),
+            None => (text, 0),
+        };
+        let (negative, digits) = match mantissa.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, mantissa.strip_prefix('+').unwrap_or(mantissa)),
+        };
+        let (whole, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+        if whole.is_empty() && fraction.is_empty() || !whole.chars().chain(fraction.chars()).all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        let mut units: i128 = 0;
+        for digit in whole.bytes().chain(fraction.bytes()) {
+            units = units.checked_mul(10)?.checked_add((digit - b'0') as i128)?;
+        }
+        if negative {
+            units = -units;
+        }
+        let scale = fraction.len() as i64 - exponent as i64;
+        if scale < 0 {
+            let factor = 10i128.checked_pow(u32::try_from(-scale).ok()?)?;
+            return Some(Decimal::new(units.checked_mul(factor)?, 0));
+        }
+        let decimal = Decimal::new(units, u32::try_from(scale).ok()?);
+        decimal.rescale(decimal.scale.min(MAX_DECIMAL_SCALE))
+    }
+
+    // The shortest decimal that reads back as the float, so 0.1 becomes 0.1 rather than its binary expansion
+    pub fn from_f64(value: f64) -> Option<Self> {
+        if !value.is_finite() {
+            return None;
+        }
+        Decimal::parse(&value.to_string())
+    }
+
+    pub fn to_f64(self) -> f64 {
+        self.to_string().parse().unwrap_or(f64::NAN)
+    }
+
+    // The same number with `scale` decimals, rounding half away from zero; None on overflow
+    pub fn rescale(self, scale: u32) -> Option<Self> {
+        if scale >= self.scale {
+            let factor = 10i128.checked_pow(scale - self.scale)?;
+            return Some(Decimal::new(self.units.checked_mul(factor)?, scale));
+        }
+        let divisor = 10i128.checked_pow(self.scale - scale)?;
+        Some(Decimal::new(round_div(self.units, divisor), scale))
+    }
+
+    // Both operands' units at their common scale
+    fn aligned(self, other: Decimal) -> Option<(i128, i128, u32)> {
+        let scale = self.scale.max(other.scale);
+        Some((self.rescale(scale)?.units, other.rescale(scale)?.units, scale))
+    }
+
+    pub fn checked_add(self, other: Decimal) -> Option<Self> {
+        let (a, b, scale) = self.aligned(other)?;
+        Some(Decimal::new(a.checked_add(b)?, scale))
+    }
+
+    pub fn checked_sub(self, other: Decimal) -> Option<Self> {
+        let (a, b, scale) = self.aligned(other)?;
+        Some(Decimal::new(a.checked_sub(b)?, scale))
+    }
+
+    pub fn checked_mul(self, other: Decimal) -> Option<Self> {
+        let product = Decimal::new(self.units.checked_mul(other.units)?, self.scale + other.scale);
+        product.rescale(product.scale.min(MAX_DECIMAL_SCALE))
+    }
+
+    // Rounded to `DIVISION_EXTRA_SCALE` decimals beyond the operands'; None when dividing by zero
+    pub fn checked_div(self, other: Decimal) -> Option<Self> {
+        if other.units == 0 {
+            return None;
+        }
+        let scale = (self.scale.max(other.scale) + DIVISION_EXTRA_SCALE).min(MAX_DECIMAL_SCALE);
+        let factor = 10i128.checked_pow(scale + other.scale - self.scale)?;
+        Some(Decimal::new(round_div(self.units.checked_mul(factor)?, other.units), scale))
+    }
+
+    pub fn checked_rem(self, other: Decimal) -> Option<Self> {
+        let (a, b, scale) = self.aligned(other)?;
+        Some(Decimal::new(a.checked_rem(b)?, scale))
+    }
+
+    pub fn abs(self) -> Self {
+        Decimal::new(self.units.abs(), self.scale)
+    }
+
+    pub fn neg(self) -> Self {
+        Decimal::new(-self.units, self.scale)
+    }
+}
+
+// Integer division rounding half away from zero
+fn round_div(numerator: i128, divisor: i128) -> i128 {
+    let (quotient, remainder) = (numerator / divisor, numerator % divisor);
+    if remainder.abs() >= divisor.abs() - remainder.abs() {
+        quotient + if (numerator < 0) == (divisor < 0) { 1 } else { -1 }
+    } else {
+        quotient
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = self.units.unsigned_abs().to_string();
+        let sign = if self.units < 0 { "-" } else { "" };
+        let scale = self.scale as usize;
+        if scale == 0 {
+            return write!(f, "{}{}", sign, digits);
+        }
+        let digits = format!("{:0>width$}", digits, width = scale + 1);
+        let (whole, fraction) = digits.split_at(digits.len() - scale);
+        write!(f, "{}{}.{}", sign, whole, fraction)
+    }
+}
+
+// Equal in value regardless of scale, so 1.5 equals 1.50
+impl PartialEq for Decimal {
+    fn eq(&self, other: &Self) -> bool {
+        self.partial_cmp(other) == Some(Ordering::Equal)
+    }
+}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match self.aligned(*other) {
+            Some((a, b, _)) => Some(a.cmp(&b)),
+            None => self.to_f64().partial_cmp(&other.to_f64()),
+        }
+    }
+}
+
+/// Value of a categorical column: a code into the column's labels, which all its values share, so
+/// each label is stored once however many nodes carry it
+#[derive(Debug, Clone)]
+pub struct Category {
+    code: u32,
+    labels: Arc<Vec<String>>,
+}
+
+impl Category {
+    // A category with a dictionary of its own, until `CategoryDictionary::encode_value` moves it
+    // into its column's
+    pub fn new(label: &str) -> Self {
+        Category { code: 0, labels: Arc::new(vec![label.to_string()]) }
+    }
+
+    pub fn label(&self) -> &str {
+        &self.labels[self.code as usize]
+    }
+}
+
+// Values of the same dictionary compare by code; others by label
+impl PartialEq for Category {
+    fn eq(&self, other: &Self) -> bool {
+        if Arc::ptr_eq(&self.labels, &other.labels) {
+            self.code == other.code
+        } else {
+            self.label() == other.label()
+        }
+    }
+}
+
+// Stored as the label, since codes only hold within the dictionary of a running graph
+impl Serialize for Category {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.label())
+    }
+}
+
+impl<'de> Deserialize<'de> for Category {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Category::new(&String::deserialize(deserializer)?))
+    }
+}
+
+/// Labels of a categorical column in the order of their codes. Codes never change; adding a label
+/// makes a new label list, which values encoded before keep using until they are encoded again
+#[derive(Debug, Clone, Default)]
+pub struct CategoryDictionary {
+    labels: Arc<Vec<String>>,
+    codes: HashMap<String, u32>,
+}
+
+impl CategoryDictionary {
+    // Code of a label, adding it when new
+    pub fn add(&mut self, label: &str) -> u32 {
+        match self.codes.get(label) {
+            Some(&code) => code,
+            None => {
+                let code = self.labels.len() as u32;
+                Arc::make_mut(&mut self.labels).push(label.to_string());
+                self.codes.insert(label.to_string(), code);
+                code
+            },
+        }
+    }
+
+    pub fn encode(&mut self, label: &str) -> Category {
+        let code = self.add(label);
+        Category { code, labels: Arc::clone(&self.labels) }
+    }
+
+    // Turns text into a code and points categories at the current labels; other values stay as they are
+    pub fn encode_value(&mut self, value: &mut AttributeValue) {
+        let category = match value {
+            AttributeValue::String(label) => self.encode(label),
+            AttributeValue::Category(category) if !Arc::ptr_eq(&category.labels, &self.labels) => self.encode(category.label()),
+            _ => return,
+        };
+        *value = AttributeValue::Category(category);
+    }
+
+    pub fn len(&self) -> usize {
+        self.labels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+    }
 }
 
 impl AttributeValue {
@@ -19,8 +282,99 @@ impl AttributeValue {
             AttributeValue::Float(v) => v.to_string(),
             AttributeValue::DateTime(v) => v.to_string(),
             AttributeValue::String(v) => v.clone(),
+            AttributeValue::Boolean(v) => v.to_string(),
+            AttributeValue::Null => String::new(),
+            AttributeValue::List(values) => {
+                format!("[{}]", values.iter().map(AttributeValue::to_string).collect::<Vec<_>>().join(", "))
+            },
+            AttributeValue::Map(entries) => {
+                let mut entries: Vec<String> = entries.iter().map(|(key, value)| format!("{}: {}", key, value.to_string())).collect();
+                entries.sort();
+                format!("{{{}}}", entries.join(", "))
+            },
+            AttributeValue::Decimal(v) => v.to_string(),
+            AttributeValue::Date(v) => AttributeValue::naive_date(*v).map_or_else(|| v.to_string(), |d| d.format("%Y-%m-%d").to_string()),
+            AttributeValue::Duration(v) => AttributeValue::format_iso_duration(*v),
+            AttributeValue::Uuid(v) => format!(
+                "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+                v >> 96, (v >> 80) & 0xffff, (v >> 64) & 0xffff, (v >> 48) & 0xffff, v & 0xffff_ffff_ffff,
+            ),
+            AttributeValue::Category(v) => v.label().to_string(),
+            AttributeValue::Bytes(v) => v.iter().map(|byte| format!("{:02x}", byte)).collect(),
+        }
+    }
+    // Name of the data type as recorded in the schema
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            AttributeValue::Int(_) => "Int",
+            AttributeValue::Float(_) => "Float",
+            AttributeValue::DateTime(_) => "DateTime",
+            AttributeValue::String(_) => "String",
+            AttributeValue::Boolean(_) => "Boolean",
+            AttributeValue::Null => "Null",
+            AttributeValue::List(_) => "List",
+            AttributeValue::Map(_) => "Map",
+            AttributeValue::Decimal(_) => "Decimal",
+            AttributeValue::Date(_) => "Date",
+            AttributeValue::Duration(_) => "Duration",
+            AttributeValue::Uuid(_) => "UUID",
+            AttributeValue::Category(_) => "Category",
+            AttributeValue::Bytes(_) => "Bytes",
+        }
+    }
+    // Text form for file exports, with dates as ISO 8601 (UTC) rather than raw timestamps, list
+    // elements separated by semicolons, as Neo4j's importer expects for arrays, and maps as JSON
+    pub fn to_export_string(&self) -> String {
+        match self {
+            AttributeValue::DateTime(v) => Utc.timestamp_opt(*v, 0).single()
+                .map_or_else(|| v.to_string(), |dt| dt.format("%Y-%m-%dT%H:%M:%S").to_string()),
+            AttributeValue::List(values) => values.iter().map(AttributeValue::to_export_string).collect::<Vec<_>>().join(";"),
+            AttributeValue::Map(_) => self.to_json().to_string(),
+            other => other.to_string(),
         }
     }
+    // JSON form: numbers stay numbers, dates become ISO strings and non-finite floats null
+    pub fn to_json(&self) -> JsonValue {
+        match self {
+            AttributeValue::Int(v) => JsonValue::from(*v),
+            AttributeValue::Float(v) => serde_json::Number::from_f64(*v).map_or(JsonValue::Null, JsonValue::Number),
+            AttributeValue::DateTime(_) => JsonValue::String(self.to_export_string()),
+            AttributeValue::String(v) => JsonValue::String(v.clone()),
+            AttributeValue::Boolean(v) => JsonValue::Bool(*v),
+            AttributeValue::Null => JsonValue::Null,
+            AttributeValue::List(values) => JsonValue::Array(values.iter().map(AttributeValue::to_json).collect()),
+            AttributeValue::Map(entries) => JsonValue::Object(entries.iter().map(|(key, value)| (key.clone(), value.to_json())).collect()),
+            AttributeValue::Decimal(v) => serde_json::Number::from_f64(v.to_f64()).map_or(JsonValue::Null, JsonValue::Number),
+            AttributeValue::Date(_) | AttributeValue::Duration(_) | AttributeValue::Uuid(_) | AttributeValue::Category(_)
+            | AttributeValue::Bytes(_) => JsonValue::String(self.to_string()),
+        }
+    }
+    // Value from JSON: whole numbers that fit become Int, other numbers Float; null is None
+    pub fn from_json(value: &JsonValue) -> Option<Self> {
+        match value {
+            JsonValue::Null => None,
+            JsonValue::Bool(v) => Some(AttributeValue::Boolean(*v)),
+            JsonValue::Number(n) => Some(n.as_i64().map_or_else(
+                || AttributeValue::Float(n.as_f64().unwrap_or(f64::NAN)),
+                AttributeValue::Int,
+            )),
+            JsonValue::String(s) => Some(AttributeValue::String(s.clone())),
+            JsonValue::Array(values) => Some(AttributeValue::List(values.iter().filter_map(AttributeValue::from_json).collect())),
+            JsonValue::Object(entries) => Some(AttributeValue::Map(
+                entries.iter().filter_map(|(key, value)| Some((key.clone(), AttributeValue::from_json(value)?))).collect(),
+            )),
+        }
+    }
+    // Looks up a property, following dotted paths into maps when there is no property by the full
+    // name, e.g. "meta.source.confidence"
+    pub fn lookup<'a>(attributes: &'a HashMap<String, AttributeValue>, path: &str) -> Option<&'a AttributeValue> {
+        attributes.get(path).or_else(|| {
+            path.match_indices('.').find_map(|(i, _)| match attributes.get(&path[..i]) {
+                Some(AttributeValue::Map(inner)) => AttributeValue::lookup(inner, &path[i + 1..]),
+                _ => None,
+            })
+        })
+    }
     pub fn to_python_object(&self, py: Python, data_type: Option<&str>) -> PyResult<PyObject> {
         match self {
             AttributeValue::Int(v) => match data_type {
@@ -31,8 +385,13 @@ impl AttributeValue {
                 Some("Float") | None => Ok(v.into_py(py)),
                 _ => Err(PyTypeError::new_err("Type mismatch for Float value")),
             },
-            AttributeValue::DateTime(v) => match data_type {
-                Some("DateTime") => {
+            AttributeValue::DateTime(v) => match data_type.and_then(AttributeValue::datetime_zone) {
+                // Aware datetime in the column's time zone
+                Some(Some(zone)) => {
+                    let datetime_class = PyModule::import(py, "datetime")?.getattr("datetime")?;
+                    Ok(datetime_class.call_method1("fromtimestamp", (*v, time_zone(py, zone)?))?.into_py(py))
+                },
+                Some(None) => {
                     // Convert the timestamp to a Python datetime object and then to a string
                     let datetime_module = PyModule::import(py, "datetime")?;
                     let datetime_class = datetime_module.getattr("datetime")?;
@@ -41,6 +400,13 @@ impl AttributeValue {
                     let datetime_str = datetime.call_method0("isoformat")?; // Convert datetime to ISO format string
                     Ok(datetime_str.into_py(py))
                 },
+                // Untyped values, such as relation properties and statistics, as naive UTC datetimes
+                None if data_type.is_none() => match Utc.timestamp_opt(*v, 0).single() {
+                    Some(dt) => Ok(PyDateTime::new(
+                        py, dt.year(), dt.month() as u8, dt.day() as u8, dt.hour() as u8, dt.minute() as u8, dt.second() as u8, 0, None,
+                    )?.into_py(py)),
+                    None => Ok(v.into_py(py)),
+                },
                 _ => Err(PyTypeError::new_err("Type mismatch for DateTime value")),
             }
             
@@ -48,6 +414,63 @@ impl AttributeValue {
                 Some("String") | None => Ok(v.into_py(py)),
                 _ => Err(PyTypeError::new_err("Type mismatch for String value")),
             },
+            AttributeValue::Boolean(v) => match data_type {
+                Some("Boolean") | None => Ok(v.into_py(py)),
+                _ => Err(PyTypeError::new_err("Type mismatch for Boolean value")),
+            },
+            AttributeValue::Null => Ok(py.None()),
+            AttributeValue::List(values) => match data_type {
+                Some("List") | None => {
+                    let items = values.iter().map(|value| value.to_python_object(py, None)).collect::<PyResult<Vec<_>>>()?;
+                    Ok(PyList::new(py, items).into_py(py))
+                },
+                _ => Err(PyTypeError::new_err("Type mismatch for List value")),
+            },
+            AttributeValue::Map(entries) => match data_type {
+                Some("Map") | None => {
+                    let dict = PyDict::new(py);
+                    for (key, value) in entries {
+                        dict.set_item(key, value.to_python_object(py, None)?)?;
+                    }
+                    Ok(dict.into_py(py))
+                },
+                _ => Err(PyTypeError::new_err("Type mismatch for Map value")),
+            },
+            AttributeValue::Decimal(v) => match data_type {
+                Some(data_type) if AttributeValue::decimal_scale(data_type).is_none() => {
+                    Err(PyTypeError::new_err("Type mismatch for Decimal value"))
+                },
+                _ => Ok(PyModule::import(py, "decimal")?.getattr("Decimal")?.call1((v.to_string(),))?.into_py(py)),
+            },
+            AttributeValue::Date(v) => match (data_type, AttributeValue::naive_date(*v)) {
+                (Some("Date") | None, Some(date)) => Ok(PyDate::new(py, date.year(), date.month() as u8, date.day() as u8)?.into_py(py)),
+                (Some("Date") | None, None) => Err(PyValueError::new_err(format!("Date {} days from 1970 is out of range", v))),
+                _ => Err(PyTypeError::new_err("Type mismatch for Date value")),
+            },
+            AttributeValue::Duration(v) => match data_type {
+                Some("Duration") | None => {
+                    let days = i32::try_from(v.div_euclid(86_400))
+                        .map_err(|_| PyValueError::new_err(format!("Duration of {} seconds is out of range", v)))?;
+                    Ok(PyDelta::new(py, days, v.rem_euclid(86_400) as i32, 0, true)?.into_py(py))
+                },
+                _ => Err(PyTypeError::new_err("Type mismatch for Duration value")),
+            },
+            AttributeValue::Uuid(v) => match data_type {
+                Some("UUID") | None => {
+                    let kwargs = PyDict::new(py);
+                    kwargs.set_item("int", *v)?;
+                    Ok(py.import("uuid")?.getattr("UUID")?.call((), Some(kwargs))?.into_py(py))
+                },
+                _ => Err(PyTypeError::new_err("Type mismatch for UUID value")),
+            },
+            AttributeValue::Category(v) => match data_type {
+                Some("Category") | None => Ok(v.label().into_py(py)),
+                _ => Err(PyTypeError::new_err("Type mismatch for Category value")),
+            },
+            AttributeValue::Bytes(v) => match data_type {
+                Some("Bytes") | None => Ok(PyBytes::new(py, v).into_py(py)),
+                _ => Err(PyTypeError::new_err("Type mismatch for Bytes value")),
+            },
         }
     }
 
@@ -56,6 +479,46 @@ impl AttributeValue {
         AttributeValue::DateTime(Utc.from_utc_datetime(dt).timestamp())
     }
 
+    // Time zone of a "DateTime(zone)" schema type, e.g. "DateTime(Europe/Oslo)" or "DateTime(+02:00)",
+    // in which naive times are read and values are returned. None for other types; Some(None) for
+    // plain "DateTime", which is UTC
+    pub fn datetime_zone(data_type: &str) -> Option<Option<&str>> {
+        if data_type == "DateTime" {
+            return Some(None);
+        }
+        let zone = data_type.strip_prefix("DateTime(")?.strip_suffix(')')?.trim();
+        (!zone.is_empty()).then_some(Some(zone))
+    }
+
+    // Whether a schema type names a type values can be stored as, e.g. "Int", "Decimal(2)" or "DateTime(UTC)"
+    pub fn is_schema_type(data_type: &str) -> bool {
+        matches!(data_type, "Int" | "Float" | "String" | "Boolean" | "List" | "Map" | "Date" | "Duration" | "UUID" | "Category" | "Bytes")
+            || AttributeValue::datetime_zone(data_type).is_some()
+            || AttributeValue::decimal_scale(data_type).is_some()
+    }
+
+    // Seconds since the epoch of a local time in `zone` (UTC if None); IANA names are resolved by
+    // Python's zoneinfo, which takes the first of two ambiguous times
+    pub fn localize(naive: &NaiveDateTime, zone: Option<&str>) -> PyResult<i64> {
+        let Some(zone) = zone.filter(|zone| *zone != "UTC") else { return Ok(naive.and_utc().timestamp()) };
+        if let Ok(offset) = zone.parse::<FixedOffset>() {
+            return Ok(naive.and_utc().timestamp() - offset.local_minus_utc() as i64);
+        }
+        Python::with_gil(|py| {
+            let local = PyDateTime::new(
+                py, naive.year(), naive.month() as u8, naive.day() as u8,
+                naive.hour() as u8, naive.minute() as u8, naive.second() as u8, 0, Some(time_zone(py, zone)?),
+            )?;
+            Ok(local.call_method0("timestamp")?.extract::<f64>()?.floor() as i64)
+        })
+    }
+
+    // The wall-clock time of a Python datetime, ignoring any tzinfo
+    pub fn naive_datetime(datetime: &PyDateTime) -> Option<NaiveDateTime> {
+        NaiveDate::from_ymd_opt(datetime.get_year(), datetime.get_month() as u32, datetime.get_day() as u32)
+            .and_then(|date| date.and_hms_opt(datetime.get_hour() as u32, datetime.get_minute() as u32, datetime.get_second() as u32))
+    }
+
     // Convert a NaiveDate to AttributeValue::DateTime, setting the time to midnight
     pub fn from_naive_date(d: &NaiveDate) -> PyResult<Self> {
         let datetime_opt = d.and_hms_opt(0, 0, 0); // Returns Option<NaiveDateTime>
@@ -64,6 +527,196 @@ impl AttributeValue {
         })?;
         Ok(AttributeValue::DateTime(Utc.from_utc_datetime(&datetime).timestamp()))
     }
+
+    // Convert a NaiveDate to AttributeValue::Date
+    pub fn from_date(d: &NaiveDate) -> Self {
+        AttributeValue::Date(d.num_days_from_ce() - EPOCH_DAYS_FROM_CE)
+    }
+
+    // The (UTC) date a timestamp falls on
+    pub fn date_from_timestamp(timestamp: i64) -> Self {
+        AttributeValue::Date(timestamp.div_euclid(86_400) as i32)
+    }
+
+    // The calendar date of a Date value's day number
+    pub fn naive_date(days: i32) -> Option<NaiveDate> {
+        NaiveDate::from_num_days_from_ce_opt(days.checked_add(EPOCH_DAYS_FROM_CE)?)
+    }
+
+    // Parse an ISO 8601 date string such as "2020-01-31"
+    pub fn parse_iso_date(value: &str) -> Option<Self> {
+        NaiveDate::parse_from_str(value.trim(), "%Y-%m-%d").ok().map(|d| AttributeValue::from_date(&d))
+    }
+
+    // timedelta in whole seconds; sub-second parts are dropped towards the past, as for datetimes
+    pub fn from_delta(delta: &PyDelta) -> Self {
+        AttributeValue::Duration(delta.get_days() as i64 * 86_400 + delta.get_seconds() as i64)
+    }
+
+    // Parse an ISO 8601 duration in days, hours, minutes and seconds, e.g. "P1DT2H30M" or "-PT90S".
+    // Years and months have no fixed length and are not accepted; fractional seconds are truncated
+    pub fn parse_iso_duration(value: &str) -> Option<Self> {
+        let value = value.trim();
+        let (sign, value) = match value.strip_prefix('-') {
+            Some(rest) => (-1, rest),
+            None => (1, value.strip_prefix('+').unwrap_or(value)),
+        };
+        let value = value.strip_prefix('P').or_else(|| value.strip_prefix('p'))?;
+        let (date_part, time_part) = match value.find(['T', 't']) {
+            Some(i) => (&value[..i], Some(&value[i + 1..])),
+            None => (value, None),
+        };
+
+        let mut seconds: i64 = 0;
+        let mut components = 0;
+        let mut add = |part: &str, units: &[(char, i64)]| -> Option<()> {
+            let mut rest = part;
+            for &(unit, length) in units {
+                let Some(end) = rest.find([unit, unit.to_ascii_lowercase()]) else { continue };
+                let number = &rest[..end];
+                let whole = if unit == 'S' { number.split(['.', ',']).next()? } else { number };
+                if whole.is_empty() || !whole.chars().all(|c| c.is_ascii_digit()) {
+                    return None;
+                }
+                seconds = seconds.checked_add(whole.parse::<i64>().ok()?.checked_mul(length)?)?;
+                components += 1;
+                rest = &rest[end + 1..];
+            }
+            rest.is_empty().then_some(())
+        };
+        add(date_part, &[('W', 604_800), ('D', 86_400)])?;
+        if let Some(time_part) = time_part {
+            add(time_part, &[('H', 3_600), ('M', 60), ('S', 1)])?;
+        }
+        (components > 0).then_some(AttributeValue::Duration(sign * seconds))
+    }
+
+    // Parse a UUID in its usual spellings: hyphenated or not, any case, in braces or as a "urn:uuid:" URN
+    pub fn parse_uuid(value: &str) -> Option<Self> {
+        let value = value.trim();
+        let value = value.strip_prefix("urn:uuid:").unwrap_or(value);
+        let value = value.strip_prefix('{').and_then(|v| v.strip_suffix('}')).unwrap_or(value);
+        let hex: String = match value.len() {
+            32 => value.to_string(),
+            36 if [8, 13, 18, 23].iter().all(|&i| value.as_bytes()[i] == b'-') => value.replace('-', ""),
+            _ => return None,
+        };
+        if hex.len() != 32 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        u128::from_str_radix(&hex, 16).ok().map(AttributeValue::Uuid)
+    }
+
+    // Parse bytes written as hexadecimal text, the text form of Bytes values, e.g. "00ff1a"
+    pub fn parse_hex(value: &str) -> Option<Self> {
+        let value = value.trim();
+        let value = value.strip_prefix("0x").unwrap_or(value);
+        if !value.len().is_multiple_of(2) || !value.is_ascii() {
+            return None;
+        }
+        (0..value.len()).step_by(2)
+            .map(|i| u8::from_str_radix(&value[i..i + 2], 16).ok())
+            .collect::<Option<Vec<u8>>>()
+            .map(AttributeValue::Bytes)
+    }
+
+    // Format seconds as an ISO 8601 duration, e.g. 93784 as "P1DT2H3M4S" and 0 as "PT0S"
+    pub fn format_iso_duration(seconds: i64) -> String {
+        let sign = if seconds < 0 { "-" } else { "" };
+        let total = seconds.unsigned_abs();
+        let (days, hours, minutes, secs) = (total / 86_400, total % 86_400 / 3_600, total % 3_600 / 60, total % 60);
+
+        let mut text = format!("{}P", sign);
+        if days > 0 {
+            text.push_str(&format!("{}D", days));
+        }
+        if hours > 0 || minutes > 0 || secs > 0 || days == 0 {
+            text.push('T');
+            if hours > 0 {
+                text.push_str(&format!("{}H", hours));
+            }
+            if minutes > 0 {
+                text.push_str(&format!("{}M", minutes));
+            }
+            if secs > 0 || (hours == 0 && minutes == 0) {
+                text.push_str(&format!("{}S", secs));
+            }
+        }
+        text
+    }
+
+    // Parse an ISO 8601 date or datetime string (e.g. "2020-01-01" or "2020-01-01T12:00:00") as UTC
+    pub fn parse_iso_datetime(value: &str) -> Option<Self> {
+        AttributeValue::parse_iso_datetime_in(value, None).ok().flatten()
+    }
+
+    // Like `parse_iso_datetime`, with times that carry no offset taken to be local to `zone`
+    pub fn parse_iso_datetime_in(value: &str, zone: Option<&str>) -> PyResult<Option<Self>> {
+        let value = value.trim();
+        if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(value) {
+            return Ok(Some(AttributeValue::DateTime(dt.timestamp())));
+        }
+        let naive = ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M"].iter()
+            .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+            .or_else(|| NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()?.and_hms_opt(0, 0, 0));
+        naive.map(|naive| AttributeValue::localize(&naive, zone).map(AttributeValue::DateTime)).transpose()
+    }
+
+    // Only genuinely missing values are null; 0, 0.0 and "" are values like any other
+    pub fn is_null(&self) -> bool {
+        matches!(self, AttributeValue::Null)
+    }
+
+    // None and NaN (pandas' marker for missing cells) stand for missing values
+    pub fn is_missing(ob: &PyAny) -> bool {
+        ob.is_none() || ob.extract::<f64>().is_ok_and(f64::is_nan)
+    }
+
+    // Schema types "Decimal" and "Decimal(s)", the latter rounding values to s decimals, e.g. "Decimal(2)"
+    // for amounts in cents. None for other types; Some(None) when no scale is declared
+    pub fn decimal_scale(data_type: &str) -> Option<Option<u32>> {
+        if data_type == "Decimal" {
+            return Some(None);
+        }
+        let scale = data_type.strip_prefix("Decimal(")?.strip_suffix(')')?.trim().parse().ok()?;
+        (scale <= MAX_DECIMAL_SCALE).then_some(Some(scale))
+    }
+
+    // Converts numbers and numeric text to a decimal, rounded to `scale` decimals if given
+    pub fn to_decimal(&self, scale: Option<u32>) -> Option<Decimal> {
+        let decimal = match self {
+            AttributeValue::Decimal(v) => *v,
+            AttributeValue::Int(v) => Decimal::new(*v as i128, 0),
+            AttributeValue::Float(v) => Decimal::from_f64(*v)?,
+            AttributeValue::String(v) => Decimal::parse(v)?,
+            _ => return None,
+        };
+        match scale {
+            Some(scale) => decimal.rescale(scale),
+            None => Some(decimal),
+        }
+    }
+
+    // Parse the usual spellings of a flag: true/false, yes/no, y/n, 1/0 (case-insensitive)
+    pub fn parse_bool(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "true" | "t" | "yes" | "y" | "1" => Some(AttributeValue::Boolean(true)),
+            "false" | "f" | "no" | "n" | "0" => Some(AttributeValue::Boolean(false)),
+            _ => None,
+        }
+    }
+}
+
+// tzinfo for "UTC", a fixed offset such as "+02:00" or an IANA name such as "Europe/Oslo"
+fn time_zone<'py>(py: Python<'py>, zone: &str) -> PyResult<&'py PyTzInfo> {
+    let datetime = py.import("datetime")?;
+    let tz = match zone.parse::<FixedOffset>() {
+        _ if zone == "UTC" => datetime.getattr("timezone")?.getattr("utc")?,
+        Ok(offset) => datetime.getattr("timezone")?.call1((datetime.getattr("timedelta")?.call1((0, offset.local_minus_utc()))?,))?,
+        Err(_) => py.import("zoneinfo")?.getattr("ZoneInfo")?.call1((zone,))
+            .map_err(|_| PyValueError::new_err(format!("Unknown time zone '{}'", zone)))?,
+    };
+    Ok(tz.downcast()?)
 }
 
 impl Clone for AttributeValue {
@@ -73,6 +726,16 @@ impl Clone for AttributeValue {
             AttributeValue::Float(v) => AttributeValue::Float(*v),
             AttributeValue::DateTime(v) => AttributeValue::DateTime(*v),
             AttributeValue::String(v) => AttributeValue::String(v.clone()),
+            AttributeValue::Boolean(v) => AttributeValue::Boolean(*v),
+            AttributeValue::Null => AttributeValue::Null,
+            AttributeValue::List(v) => AttributeValue::List(v.clone()),
+            AttributeValue::Map(v) => AttributeValue::Map(v.clone()),
+            AttributeValue::Decimal(v) => AttributeValue::Decimal(*v),
+            AttributeValue::Date(v) => AttributeValue::Date(*v),
+            AttributeValue::Duration(v) => AttributeValue::Duration(*v),
+            AttributeValue::Uuid(v) => AttributeValue::Uuid(*v),
+            AttributeValue::Category(v) => AttributeValue::Category(v.clone()),
+            AttributeValue::Bytes(v) => AttributeValue::Bytes(v.clone()),
         }
     }
 }
@@ -83,6 +746,16 @@ impl PartialEq for AttributeValue {
             (AttributeValue::Float(a), AttributeValue::Float(b)) => a == b,
             (AttributeValue::DateTime(a), AttributeValue::DateTime(b)) => a == b,
             (AttributeValue::String(a), AttributeValue::String(b)) => a == b,
+            (AttributeValue::Boolean(a), AttributeValue::Boolean(b)) => a == b,
+            (AttributeValue::Null, AttributeValue::Null) => true,
+            (AttributeValue::List(a), AttributeValue::List(b)) => a == b,
+            (AttributeValue::Map(a), AttributeValue::Map(b)) => a == b,
+            (AttributeValue::Decimal(a), AttributeValue::Decimal(b)) => a == b,
+            (AttributeValue::Date(a), AttributeValue::Date(b)) => a == b,
+            (AttributeValue::Duration(a), AttributeValue::Duration(b)) => a == b,
+            (AttributeValue::Uuid(a), AttributeValue::Uuid(b)) => a == b,
+            (AttributeValue::Category(a), AttributeValue::Category(b)) => a == b,
+            (AttributeValue::Bytes(a), AttributeValue::Bytes(b)) => a == b,
             _ => false, // Different types are always not equal
         }
     }
@@ -95,6 +768,13 @@ impl PartialOrd for AttributeValue {
             (AttributeValue::DateTime(a), AttributeValue::DateTime(b)) => a.partial_cmp(b),
             // For strings, we'll default to a simple lexicographical comparison
             (AttributeValue::String(a), AttributeValue::String(b)) => a.partial_cmp(b),
+            (AttributeValue::Boolean(a), AttributeValue::Boolean(b)) => a.partial_cmp(b),
+            (AttributeValue::Decimal(a), AttributeValue::Decimal(b)) => a.partial_cmp(b),
+            (AttributeValue::Date(a), AttributeValue::Date(b)) => a.partial_cmp(b),
+            (AttributeValue::Duration(a), AttributeValue::Duration(b)) => a.partial_cmp(b),
+            (AttributeValue::Uuid(a), AttributeValue::Uuid(b)) => a.partial_cmp(b),
+            (AttributeValue::Category(a), AttributeValue::Category(b)) => a.label().partial_cmp(b.label()),
+            (AttributeValue::Bytes(a), AttributeValue::Bytes(b)) => a.partial_cmp(b),
             _ => None, // Comparison between different types is undefined
         }
     }
@@ -102,21 +782,153 @@ impl PartialOrd for AttributeValue {
 
 impl<'source> FromPyObject<'source> for AttributeValue {
     fn extract(ob: &'source PyAny) -> PyResult<Self> {
-        // Try to extract the Python object as different types
-        if let Ok(value) = ob.extract::<i32>() {
+        if AttributeValue::is_missing(ob) {
+            return Ok(AttributeValue::Null);
+        }
+        // Try to extract the Python object as different types; bool first, as it subclasses int
+        if let Ok(value) = ob.downcast::<PyBool>() {
+            return Ok(AttributeValue::Boolean(value.is_true()));
+        }
+        // datetime before date, as it subclasses date; naive datetimes are taken as UTC
+        if let Ok(datetime) = ob.downcast::<PyDateTime>() {
+            if !datetime.getattr("tzinfo")?.is_none() {
+                let timestamp: f64 = datetime.call_method0("timestamp")?.extract()?;
+                return Ok(AttributeValue::DateTime(timestamp.floor() as i64));
+            }
+            if let Some(naive) = AttributeValue::naive_datetime(datetime) {
+                return Ok(AttributeValue::from_naive_datetime(&naive));
+            }
+        } else if let Ok(date) = ob.downcast::<PyDate>() {
+            if let Some(naive) = NaiveDate::from_ymd_opt(date.get_year(), date.get_month() as u32, date.get_day() as u32) {
+                return Ok(AttributeValue::from_date(&naive));
+            }
+        } else if let Ok(delta) = ob.downcast::<PyDelta>() {
+            return Ok(AttributeValue::from_delta(delta));
+        }
+        // bytes and bytearray are binary payloads, not lists of small integers
+        if let Ok(bytes) = ob.downcast::<PyBytes>() {
+            return Ok(AttributeValue::Bytes(bytes.as_bytes().to_vec()));
+        }
+        if let Ok(bytes) = ob.downcast::<PyByteArray>() {
+            return Ok(AttributeValue::Bytes(bytes.to_vec()));
+        }
+        // numpy.bool_ is not a bool subclass but does have __index__
+        if ob.get_type().name()? == "bool_" {
+            return Ok(AttributeValue::Boolean(ob.is_true()?));
+        }
+        // uuid.UUID by its 128-bit value
+        if ob.get_type().name()? == "UUID" {
+            if let Ok(value) = ob.getattr("int").and_then(|value| value.extract::<u128>()) {
+                return Ok(AttributeValue::Uuid(value));
+            }
+        }
+        if let Ok(value) = ob.extract::<i64>() {
             return Ok(AttributeValue::Int(value));
         }
+        // Integers beyond 64 bits would lose digits as floats
+        if ob.hasattr("__index__")? {
+            return Err(PyValueError::new_err(format!("Integer {} does not fit in 64 bits", ob)));
+        }
+        // decimal.Decimal keeps its exact digits; infinities fall through to Float
+        if ob.get_type().name()? == "Decimal" {
+            if let Some(value) = Decimal::parse(&ob.str()?.to_string()) {
+                return Ok(AttributeValue::Decimal(value));
+            }
+        }
         if let Ok(value) = ob.extract::<f64>() {
             return Ok(AttributeValue::Float(value));
         }
         if let Ok(value) = ob.extract::<String>() {
             return Ok(AttributeValue::String(value));
         }
-        if let Ok(value) = ob.extract::<i64>() { // Assuming DateTime is represented as a timestamp
-            return Ok(AttributeValue::DateTime(value));
+        // Dicts become maps with text keys; missing values are left out
+        if let Ok(dict) = ob.downcast::<PyDict>() {
+            let mut entries = HashMap::with_capacity(dict.len());
+            for (key, value) in dict.iter() {
+                let value: AttributeValue = value.extract()?;
+                if !value.is_null() {
+                    entries.insert(key.str()?.to_string(), value);
+                }
+            }
+            return Ok(AttributeValue::Map(entries));
+        }
+        // Lists, tuples and arrays become lists; missing elements are left out
+        if let Ok(items) = ob.extract::<Vec<AttributeValue>>() {
+            return Ok(AttributeValue::List(items.into_iter().filter(|item| !item.is_null()).collect()));
         }
         Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(
             "Could not extract AttributeValue",
         ))
     }
-}
\ No newline at end of file
+}
+
+/// Text form of a unique ID from Python: strings as they are, integers of any size exactly, floats
+/// only when they hold a whole number exactly (as pandas makes of integer columns with gaps),
+/// tuples as composite keys and other objects such as uuid.UUID by their text form
+pub fn unique_id_from_py(ob: &PyAny) -> PyResult<String> {
+    if AttributeValue::is_missing(ob) {
+        return Err(PyValueError::new_err("Unique ID is missing"));
+    }
+    if let Ok(text) = ob.extract::<String>() {
+        return Ok(text);
+    }
+    if let Ok(parts) = ob.downcast::<PyTuple>() {
+        return Ok(composite_id(&parts.iter().map(unique_id_from_py).collect::<PyResult<Vec<_>>>()?));
+    }
+    if ob.downcast::<PyBool>().is_err() && ob.hasattr("__index__")? {
+        return Ok(ob.call_method0("__index__")?.str()?.to_string());
+    }
+    if let Ok(value) = ob.extract::<f64>() {
+        // Above 2^53 floats skip whole numbers, so the ID may already have lost digits
+        if value.fract() == 0.0 && value.abs() <= 9_007_199_254_740_992.0 {
+            return Ok((value as i64).to_string());
+        }
+        return Err(PyValueError::new_err(format!(
+            "Unique ID {} cannot be converted without losing digits; pass IDs as int or str", ob
+        )));
+    }
+    Ok(ob.str()?.to_string())
+}
+
+/// Canonical unique ID of a key made of several fields: the parts joined by '|', with '|' and '\'
+/// inside parts escaped by a backslash so ("a|b", "c") and ("a", "b|c") stay distinct. A single
+/// part is the ID as it is
+pub fn composite_id(parts: &[String]) -> String {
+    if let [part] = parts {
+        return part.clone();
+    }
+    parts.iter()
+        .map(|part| part.replace('\\', "\\\\").replace('|', "\\|"))
+        .collect::<Vec<_>>()
+        .join("|")
+}
+
+/// The parts of a unique ID made by `composite_id`
+pub fn split_composite_id(id: &str) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    let mut chars = id.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => parts.last_mut().unwrap().extend(chars.next()),
+            '|' => parts.push(String::new()),
+            c => parts.last_mut().unwrap().push(c),
+        }
+    }
+    parts
+}
+
+/// Python argument accepting either a single string or a list of strings
+#[derive(FromPyObject)]
+pub enum StringOrList {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl StringOrList {
+    pub fn into_vec(self) -> Vec<String> {
+        match self {
+            StringOrList::One(value) => vec![value],
+            StringOrList::Many(values) => values,
+        }
+    }
+}
@@ -1,78 +1,691 @@
 use pyo3::prelude::*;
-use pyo3::types::{PyList, PyDict};
+use pyo3::types::{PyBytes, PyList, PyDict};
 use pyo3::PyResult;
-use pyo3::exceptions::PyIOError;
-use petgraph::graph::DiGraph;
-use std::collections::HashMap;
+use pyo3::exceptions::{PyIOError, PyKeyError, PyValueError};
+use petgraph::graph::{EdgeIndex, NodeIndex};
+use petgraph::Direction;
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
-use std::io::{BufWriter, BufReader};
+use std::io::{BufWriter, BufReader, Read};
+use std::time::Instant;
 use crate::schema::{Node, Relation};
-use crate::data_types::AttributeValue; 
+use crate::data_types::{unique_id_from_py, AttributeValue, StringOrList};
+use explain::Plan;
+use filters::{Filter, FilterOptions};
+use journal::JournaledGraph;
+pub use as_of::AsOf;
+pub use pagination::NodePages;
+pub use transaction::{StagedCall, Transaction};
 
 mod add_nodes;
 mod add_relationships;
+mod algorithms;
+mod anonymize;
+mod as_of;
+mod calculations;
+mod categories;
+mod centrality;
+mod compare_graphs;
+mod constraints;
+mod defaults;
+mod delta;
+mod edge_selection;
+mod equation_parser;
+mod explain;
+mod filters;
 mod get_attributes;
 mod get_schema;
+mod history;
+mod io;
+mod journal;
+mod maintain_graph;
 mod navigate_graph;
+mod pagination;
+mod pattern;
+mod persistence;
+mod query;
+mod sampling;
+mod subgraph;
+mod synthetic;
+mod transaction;
+mod update_nodes;
 
-#[pyclass]
+const NETWORKX_ATTRIBUTES: io::networkx::AttributeNames = io::networkx::AttributeNames {
+    node_type: "node_type",
+    unique_id: "unique_id",
+    title: "title",
+    relation_type: "relation_type",
+};
+
+#[pyclass(module = "rusty_graph")]
 pub struct KnowledgeGraph {
-    pub graph: DiGraph<Node, Relation>,
+    pub graph: JournaledGraph,
+    track_insertion_order: bool,
+    next_sequence: u64,
+    selections: HashMap<String, Vec<usize>>,
+    plan: Plan,
+    as_of: Option<AttributeValue>,  // Traversals only follow relations valid at this date
+    baseline: Option<delta::Baseline>,  // Graph as of the last save or load, for save_delta
+    merge_log: Vec<(String, String, String)>,  // (node type, duplicate ID, survivor ID) of each node merge_nodes removed
+    change_log: Option<history::ChangeLog>,  // Changes made by each operation since track_changes() was turned on
+    checkpoints: HashMap<String, transaction::SavedState>,  // Points checkpoint() saved for restore()
+    open_transactions: usize,  // Transactions running their staged calls, which are undone if one fails
+}
+
+impl KnowledgeGraph {
+    // Stamp nodes created from `first_new_index` onwards with increasing ingestion sequence numbers
+    fn assign_sequences(&mut self, first_new_index: usize) {
+        if !self.track_insertion_order {
+            return;
+        }
+        for index in first_new_index..self.graph.node_count() {
+            let node = &mut self.graph[NodeIndex::new(index)];
+            if let Node::StandardNode { sequence: sequence @ None, .. } = node {
+                *sequence = Some(self.next_sequence);
+                self.next_sequence += 1;
+            }
+        }
+    }
+
+    // Nodes a graph algorithm runs over: the given indices, all nodes of `node_type`, or the whole graph (None)
+    fn scope(&mut self, indices: Option<Vec<usize>>, node_type: Option<&str>) -> PyResult<Option<Vec<usize>>> {
+        match (indices, node_type) {
+            (None, Some(node_type)) => Ok(Some(navigate_graph::get_nodes(&mut self.graph, Some(node_type), None, FilterOptions::default())?)),
+            (indices, _) => Ok(indices),
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn ingest_nodes(
+        &mut self, operation: &str, table: io::tabular::Table, node_type: &str, unique_id_field: &str, node_title_field: Option<&str>,
+        conflict_handling: Option<&str>, column_types: Option<HashMap<String, String>>,
+    ) -> PyResult<Vec<usize>> {
+        let first_new_index = self.graph.node_count();
+        let snapshot = constraints::snapshot(&self.graph, &[node_type]);
+        let indices = io::tabular::ingest_nodes(
+            &mut self.graph, table, node_type, unique_id_field, node_title_field, conflict_handling.unwrap_or("update"),
+            io::tabular::ColumnTypes::new(column_types),
+        )?;
+        defaults::apply(&mut self.graph, node_type, &indices);
+        if let Some(snapshot) = snapshot {
+            constraints::enforce(&mut self.graph, &indices, snapshot)?;
+        }
+        categories::encode_nodes(&mut self.graph, node_type, &indices);
+        self.assign_sequences(first_new_index);
+        self.log_changes(operation);
+        Ok(indices)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn ingest_relationships(
+        &mut self, py: Python, operation: &str, table: io::tabular::Table, relationship_type: &str, source: (&str, &str), target: (&str, &str),
+        title_fields: (Option<&str>, Option<&str>), column_types: Option<HashMap<String, String>>, enforce_schema: Option<bool>,
+        conflict_handling: Option<&str>,
+    ) -> PyResult<PyObject> {
+        let first_new_index = self.graph.node_count();
+        let snapshot = constraints::snapshot(&self.graph, &[source.0, target.0])
+            .map(|snapshot| snapshot.with_relations(&self.graph, &[relationship_type]));
+        let summary = io::tabular::ingest_relationships(
+            &mut self.graph, table, relationship_type, source.0, source.1, target.0, target.1, title_fields,
+            &io::tabular::ColumnTypes::new(column_types), enforce_schema.unwrap_or(false), conflict_handling.unwrap_or("add"),
+        )?;
+        let new_indices: Vec<usize> = (first_new_index..self.graph.node_count()).collect();
+        defaults::apply(&mut self.graph, source.0, &new_indices);
+        defaults::apply(&mut self.graph, target.0, &new_indices);
+        if let Some(snapshot) = snapshot {
+            constraints::enforce(&mut self.graph, &new_indices, snapshot)?;
+        }
+        self.assign_sequences(first_new_index);
+        self.log_changes(operation);
+        relationship_result(py, summary, enforce_schema.unwrap_or(false), conflict_handling.is_some(), None)
+    }
+
+    // Resolve a single node by type and unique ID
+    fn node_by_id(&self, node_type: &str, id: &PyAny) -> PyResult<NodeIndex> {
+        let id = unique_id_from_py(id)?;
+        navigate_graph::unique_id_lookup(&self.graph, node_type).get(id.as_str())
+            .map(|&index| NodeIndex::new(index))
+            .ok_or_else(|| PyKeyError::new_err(format!("No {} node with unique ID '{}'", node_type, id)))
+    }
+
+    // Relation filter for traversals: the filter dictionaries combined with the validity date set by `as_of`
+    fn edge_filter(&self, relationship_filters: Option<Vec<&PyDict>>) -> PyResult<Option<Filter>> {
+        let mut parts = Vec::new();
+        if let Some(filters) = relationship_filters {
+            parts.push(Filter::from_list(filters, FilterOptions::default())?);
+        }
+        if let Some(at) = &self.as_of {
+            parts.push(Filter::valid_at(at.clone()));
+        }
+        Ok(if parts.len() > 1 { Some(Filter::And(parts)) } else { parts.pop() })
+    }
+
+    // Delete nodes and their relations, renumbering the saved selections to match; returns the
+    // number of nodes and relations removed
+    fn remove_nodes(&mut self, indices: Vec<usize>) -> (usize, usize) {
+        let node_count = self.graph.node_count();
+        let (edges_deleted, original) = maintain_graph::delete_nodes(&mut self.graph, indices);
+        let mut new_index = vec![None; node_count];
+        for (index, &old) in original.iter().enumerate() {
+            new_index[old] = Some(index);
+        }
+        for selection in self.selections.values_mut() {
+            selection.retain_mut(|index| match new_index.get(*index).copied().flatten() {
+                Some(new) => { *index = new; true },
+                None => false,
+            });
+        }
+        (node_count - self.graph.node_count(), edges_deleted)
+    }
+
+    // Add the changes an operation made to the change log, when it is on
+    fn log_changes(&mut self, operation: &str) {
+        if let Some(log) = &mut self.change_log {
+            log.record(operation, self.graph.events_since(log.recorded), &self.graph);
+            log.recorded = self.graph.position();
+        }
+        self.sync_journal();
+    }
+
+    // The graph logs its changes while the change log, a checkpoint or a transaction needs them, and keeps them only
+    // while a checkpoint or transaction may have to undo them
+    fn sync_journal(&mut self) {
+        let undoable = !self.checkpoints.is_empty() || self.open_transactions > 0;
+        if !undoable {
+            self.graph.forget();
+            if let Some(log) = &mut self.change_log {
+                log.recorded = 0;
+            }
+        }
+        self.graph.set_recording(self.change_log.is_some() || undoable);
+    }
+
+    // Add a selecting operation to the plan reported by `explain`
+    fn record<T>(&mut self, operation: String, input: Option<usize>, started: Instant, result: &PyResult<Vec<T>>) {
+        let output = result.as_ref().map(Vec::len).map_err(|e| e.to_string());
+        self.plan.record(operation, input, output, started);
+    }
+}
+
+// What add_relationships and the file imports return: the (source, target) indices of the rows, or with any of the
+// options given a dict that also has what those report
+fn relationship_result(
+    py: Python, summary: add_relationships::RelationSummary, enforce_schema: bool, conflict_handling: bool, create_missing_nodes: Option<bool>,
+) -> PyResult<PyObject> {
+    if !enforce_schema && !conflict_handling && create_missing_nodes.is_none() {
+        return Ok(summary.indices.into_py(py));
+    }
+
+    // Rows breaking the declared endpoints of the relation type are left out and reported
+    let result = PyDict::new(py);
+    result.set_item("indices", summary.indices)?;
+    if enforce_schema {
+        result.set_item("violations", summary.violations.iter().map(|violation| violation.to_py(py)).collect::<PyResult<Vec<_>>>()?)?;
+    }
+    if conflict_handling {
+        result.set_item("created", summary.created)?;
+        result.set_item("updated", summary.updated)?;
+        result.set_item("skipped", summary.skipped)?;
+    }
+    if create_missing_nodes.is_some() {
+        result.set_item("placeholders", summary.placeholders)?;
+        result.set_item("missing", summary.missing)?;
+    }
+    Ok(result.into())
+}
+
+// Unique IDs are stored as strings; accept ints and other Python values by their string form
+fn ids_to_strings(ids: Vec<&PyAny>) -> PyResult<Vec<String>> {
+    ids.into_iter().map(unique_id_from_py).collect()
 }
 
 #[pymethods]
 impl KnowledgeGraph {
     #[new]
-    pub fn new() -> Self {
+    pub fn new(track_insertion_order: Option<bool>) -> Self {
         KnowledgeGraph {
-            graph: DiGraph::new(),
+            graph: JournaledGraph::default(),
+            track_insertion_order: track_insertion_order.unwrap_or(false),
+            next_sequence: 0,
+            selections: HashMap::new(),
+            plan: Plan::default(),
+            as_of: None,
+            baseline: None,
+            merge_log: Vec::new(),
+            change_log: None,
+            checkpoints: HashMap::new(),
+            open_transactions: 0,
         }
     }
 
+    // Build a random graph for benchmarks and reproducible examples
+    #[staticmethod]
+    pub fn generate_synthetic(
+        node_types: HashMap<String, usize>, connections: Option<Vec<(String, String, String)>>,
+        property_distributions: Option<HashMap<String, HashMap<String, &PyDict>>>, seed: Option<u64>,
+    ) -> PyResult<Self> {
+        let mut kg = KnowledgeGraph::new(None);
+        synthetic::generate_synthetic(
+            &mut kg.graph,
+            node_types,
+            connections,
+            property_distributions,
+            seed,
+        )?;
+        Ok(kg)
+    }
+
     // Method to add a single node
     pub fn add_node(
         &mut self, node_type: String, unique_id: String,  attributes: Option<HashMap<String, AttributeValue>>, node_title: Option<String>
     ) -> usize {
         let node = Node::new(&node_type, &unique_id, attributes, node_title.as_deref());
         let index = self.graph.add_node(node);
+        defaults::apply(&mut self.graph, &node_type, &[index.index()]);
+        self.assign_sequences(index.index());
+        self.log_changes("add_node");
         index.index() // Convert NodeIndex to usize before returning
     }
 
-    // Add nodes to graph
+    // Add nodes to graph; with report=True, returns what happened to the rows and cells rather than just the indices
+    #[allow(clippy::too_many_arguments)]
     pub fn add_nodes(
-        &mut self, data: &PyList, columns: Vec<String>, node_type: String, unique_id_field: String, node_title_field: Option<String>, 
-        conflict_handling: Option<String>, column_types: Option<&PyDict>,
-    ) -> PyResult<Vec<usize>> {
-        add_nodes::add_nodes(
+        &mut self, py: Python, data: &PyList, columns: Vec<String>, node_type: String, unique_id_field: StringOrList, node_title_field: Option<String>, 
+        conflict_handling: Option<String>, column_types: Option<&PyDict>, dtypes: Option<HashMap<String, String>>, report: Option<bool>,
+    ) -> PyResult<PyObject> {
+        let first_new_index = self.graph.node_count();
+        let snapshot = constraints::snapshot(&self.graph, &[node_type.as_str()]);
+        let with_report = report.unwrap_or(false);
+        let summary = add_nodes::add_nodes(
             &mut self.graph, 
             data,
             columns,
-            node_type,
-            unique_id_field,
+            node_type.clone(),
+            unique_id_field.into_vec(),
             node_title_field,
             conflict_handling,
             column_types,
-        ) // Call the standalone function
+            dtypes,
+            with_report,
+        )?; // Call the standalone function
+        defaults::apply(&mut self.graph, &node_type, &summary.indices);
+        if let Some(snapshot) = snapshot {
+            constraints::enforce(&mut self.graph, &summary.indices, snapshot)?;
+        }
+        categories::encode_nodes(&mut self.graph, &node_type, &summary.indices);
+        self.assign_sequences(first_new_index);
+        self.log_changes("add_nodes");
+        if !with_report {
+            return Ok(summary.indices.into_py(py));
+        }
+
+        let result = PyDict::new(py);
+        result.set_item("indices", summary.indices)?;
+        result.set_item("rows", summary.rows)?;
+        result.set_item("rows_skipped", summary.rows_skipped)?;
+        result.set_item("values_coerced", summary.values_coerced)?;
+        result.set_item("nulls", summary.nulls)?;
+        result.set_item("created", summary.created)?;
+        result.set_item("updated", summary.updated)?;
+        result.set_item("skipped", summary.skipped)?;
+        Ok(result.into())
     }
 
-    // Add relationships to graph
+    // Add relationships to graph. conflict_handling decides what a row does to a relation of the type already running
+    // between its source and target: "add" another (the default), "skip_duplicates", "update" its properties or "replace" them.
+    // With "update" or "replace", aggregate maps properties to "count", "sum", "avg", "min" or "max" to combine the values of
+    // duplicate rows (and, for "update", the relation's current value) instead of keeping the last. Source and target IDs
+    // without a node get one; create_missing_nodes=True also tags those with "_placeholder": True (until ingested with
+    // "update"), create_missing_nodes=False leaves such rows out and lists them under "missing"
+    #[allow(clippy::too_many_arguments)]
     pub fn add_relationships(
-        &mut self, data: &PyList, columns: Vec<String>, relationship_type: String, source_type: String, source_id_field: String, 
-        target_type: String, target_id_field: String, source_title_field: Option<String>, target_title_field: Option<String>,
-    ) -> PyResult<Vec<(usize, usize)>> {
-        add_relationships::add_relationships(
+        &mut self, py: Python, data: &PyList, columns: Vec<String>, relationship_type: String, source_type: String, source_id_field: StringOrList, 
+        target_type: String, target_id_field: StringOrList, source_title_field: Option<String>, target_title_field: Option<String>,
+        enforce_schema: Option<bool>, conflict_handling: Option<&str>, aggregate: Option<HashMap<String, String>>,
+        create_missing_nodes: Option<bool>,
+    ) -> PyResult<PyObject> {
+        let first_new_index = self.graph.node_count();
+        let enforce_schema = enforce_schema.unwrap_or(false);
+        // Nodes created for missing IDs are checked against the constraints of their types
+        let snapshot = constraints::snapshot(&self.graph, &[source_type.as_str(), target_type.as_str()])
+            .map(|snapshot| snapshot.with_relations(&self.graph, &[relationship_type.as_str()]));
+        let endpoint_types = [source_type.clone(), target_type.clone()];
+        let summary = add_relationships::add_relationships(
             &mut self.graph,
             data,
             columns,
             relationship_type,
             source_type,
-            source_id_field,
+            source_id_field.into_vec(),
             target_type,            
-            target_id_field,
+            target_id_field.into_vec(),
             source_title_field,
             target_title_field,
+            enforce_schema,
+            conflict_handling.unwrap_or("add"),
+            aggregate.unwrap_or_default(),
+            create_missing_nodes,
+        )?;
+        let new_indices: Vec<usize> = (first_new_index..self.graph.node_count()).collect();
+        for node_type in &endpoint_types {
+            defaults::apply(&mut self.graph, node_type, &new_indices);
+        }
+        if let Some(snapshot) = snapshot {
+            constraints::enforce(&mut self.graph, &new_indices, snapshot)?;
+        }
+        self.assign_sequences(first_new_index);
+        self.log_changes("add_relationships");
+        relationship_result(py, summary, enforce_schema, conflict_handling.is_some(), create_missing_nodes)
+    }
+
+    // Declare the node types a relation type runs from and to, and its cardinality ("1:1", "1:N", "N:1" or the default "N:M"),
+    // which add_relationships(enforce_schema=True) checks rows against
+    pub fn declare_relation(&mut self, relation_type: &str, source_type: &str, target_type: &str, cardinality: Option<&str>) -> PyResult<()> {
+        constraints::declare_relation(&mut self.graph, relation_type, source_type, target_type, cardinality.unwrap_or("N:M"))
+    }
+
+    // Set the value node_type nodes take for a property they lack, both on nodes ingested later and on the existing nodes
+    // missing it; returns the number of existing nodes given the default. A value of None removes the default
+    pub fn set_default(&mut self, node_type: &str, property: &str, value: AttributeValue) -> PyResult<usize> {
+        let snapshot = constraints::snapshot(&self.graph, &[node_type]);
+        let filled = defaults::set_default(&mut self.graph, node_type, property, value)?;
+        if let Some(snapshot) = snapshot {
+            constraints::enforce(&mut self.graph, &filled, snapshot)?;
+        }
+        self.log_changes("set_default");
+        Ok(filled.len())
+    }
+
+    // Add nodes straight from a CSV file with a header row, streaming the rows in Rust
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_nodes_from_csv(
+        &mut self, path: &str, node_type: &str, unique_id_field: &str, node_title_field: Option<&str>,
+        conflict_handling: Option<&str>, column_types: Option<HashMap<String, String>>, delimiter: Option<char>,
+    ) -> PyResult<Vec<usize>> {
+        let table = io::tabular::read_csv(path, delimiter)?;
+        self.ingest_nodes("add_nodes_from_csv", table, node_type, unique_id_field, node_title_field, conflict_handling, column_types)
+    }
+
+    // Add nodes straight from a Parquet file, keeping the column types stored in the file
+    pub fn add_nodes_from_parquet(
+        &mut self, path: &str, node_type: &str, unique_id_field: &str, node_title_field: Option<&str>,
+        conflict_handling: Option<&str>, column_types: Option<HashMap<String, String>>,
+    ) -> PyResult<Vec<usize>> {
+        let table = io::tabular::read_parquet(path)?;
+        self.ingest_nodes("add_nodes_from_parquet", table, node_type, unique_id_field, node_title_field, conflict_handling, column_types)
+    }
+
+    // Add nodes from an iterable or generator of dicts, pulled lazily and applied batch_size at a time; returns the number of records
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_nodes_stream(
+        &mut self, iterable: &PyAny, node_type: &str, unique_id_field: &str, node_title_field: Option<&str>,
+        conflict_handling: Option<&str>, column_types: Option<HashMap<String, String>>, batch_size: Option<usize>,
+    ) -> PyResult<usize> {
+        let first_new_index = self.graph.node_count();
+        let snapshot = constraints::snapshot(&self.graph, &[node_type]);
+        let mut processor = io::batch::BatchProcessor::new(
+            &mut self.graph, io::tabular::ColumnTypes::new(column_types), conflict_handling.unwrap_or("update"),
+            batch_size.unwrap_or(10_000),
+        )?;
+        let count = io::stream::ingest_stream(&mut processor, iterable, node_type, unique_id_field, node_title_field)?;
+        let indices = processor.finish()?;
+        let new_indices: Vec<usize> = (first_new_index..self.graph.node_count()).collect();
+        defaults::apply(&mut self.graph, node_type, &new_indices);
+        if let Some(snapshot) = snapshot {
+            constraints::enforce(&mut self.graph, &indices, snapshot)?;
+        }
+        categories::encode_type(&mut self.graph, node_type);
+        self.assign_sequences(first_new_index);
+        self.log_changes("add_nodes_stream");
+        Ok(count)
+    }
+
+    // Add nodes from the rows of an SQL query on SQLite (sqlite:///file.db) or PostgreSQL (postgresql://...). Rows are added as
+    // the query yields them, a batch at a time, so the result set is never held in memory as a whole
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_nodes_from_sql(
+        &mut self, connection: &str, query: &str, node_type: &str, unique_id_field: &str, node_title_field: Option<&str>,
+        conflict_handling: Option<&str>, column_types: Option<HashMap<String, String>>,
+    ) -> PyResult<Vec<usize>> {
+        let table = io::sql::read_sql(connection, query)?;
+        self.ingest_nodes("add_nodes_from_sql", table, node_type, unique_id_field, node_title_field, conflict_handling, column_types)
+    }
+
+    // Add nodes from a pyarrow RecordBatch or Table, read through the Arrow C data interface
+    pub fn add_nodes_arrow(
+        &mut self, data: &PyAny, node_type: &str, unique_id_field: &str, node_title_field: Option<&str>,
+        conflict_handling: Option<&str>, column_types: Option<HashMap<String, String>>,
+    ) -> PyResult<Vec<usize>> {
+        let batches = io::record_batch::import_batches(data)?;
+        // Dictionary columns are stored as Category unless declared or already in the schema otherwise
+        let mut column_types = column_types.unwrap_or_default();
+        let schema = get_schema::find_schema_node(&self.graph, "Node", node_type).map(|index| &self.graph[index]);
+        for column in io::record_batch::dictionary_columns(&batches) {
+            if !matches!(schema, Some(Node::DataTypeNode { attributes, .. }) if attributes.contains_key(&column)) {
+                column_types.entry(column).or_insert_with(|| "Category".to_string());
+            }
+        }
+        let table = io::record_batch::batches_to_table(batches)?;
+        self.ingest_nodes("add_nodes_arrow", table, node_type, unique_id_field, node_title_field, conflict_handling, Some(column_types))
+    }
+
+    // Refresh properties of existing nodes from a pandas or polars DataFrame or pyarrow Table, joined on the unique ID column.
+    // Only the listed columns are set (all but the ID column by default); empty cells keep the current value and IDs that match
+    // no node are returned under "missing" rather than added
+    pub fn update_node_properties_from_df(
+        &mut self, py: Python, df: &PyAny, node_type: &str, unique_id_field: &str, columns: Option<Vec<String>>,
+        column_types: Option<HashMap<String, String>>,
+    ) -> PyResult<PyObject> {
+        let table = io::dataframe::frame_to_table(df)?;
+        let snapshot = constraints::snapshot(&self.graph, &[node_type]);
+        let (updated, missing) = io::tabular::update_nodes(
+            &mut self.graph, table, node_type, unique_id_field, columns, io::tabular::ColumnTypes::new(column_types),
+        )?;
+        if let Some(snapshot) = snapshot {
+            constraints::enforce(&mut self.graph, &updated, snapshot)?;
+        }
+        categories::encode_nodes(&mut self.graph, node_type, &updated);
+        self.log_changes("update_node_properties_from_df");
+        let result = PyDict::new(py);
+        result.set_item("updated", updated.len())?;
+        result.set_item("missing", missing)?;
+        Ok(result.into())
+    }
+
+    // Add relationships straight from a CSV file, creating missing source and target nodes. enforce_schema and
+    // conflict_handling work as in add_relationships, and so does the result
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_relationships_from_csv(
+        &mut self, py: Python, path: &str, relationship_type: &str, source_type: &str, source_id_field: &str, target_type: &str,
+        target_id_field: &str, source_title_field: Option<&str>, target_title_field: Option<&str>,
+        column_types: Option<HashMap<String, String>>, enforce_schema: Option<bool>, conflict_handling: Option<&str>, delimiter: Option<char>,
+    ) -> PyResult<PyObject> {
+        let table = io::tabular::read_csv(path, delimiter)?;
+        self.ingest_relationships(
+            py, "add_relationships_from_csv", table, relationship_type, (source_type, source_id_field), (target_type, target_id_field),
+            (source_title_field, target_title_field), column_types, enforce_schema, conflict_handling,
         )
     }
+
+    // Add relationships straight from a Parquet file, as add_relationships_from_csv does
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_relationships_from_parquet(
+        &mut self, py: Python, path: &str, relationship_type: &str, source_type: &str, source_id_field: &str, target_type: &str,
+        target_id_field: &str, source_title_field: Option<&str>, target_title_field: Option<&str>,
+        column_types: Option<HashMap<String, String>>, enforce_schema: Option<bool>, conflict_handling: Option<&str>,
+    ) -> PyResult<PyObject> {
+        let table = io::tabular::read_parquet(path)?;
+        self.ingest_relationships(
+            py, "add_relationships_from_parquet", table, relationship_type, (source_type, source_id_field), (target_type, target_id_field),
+            (source_title_field, target_title_field), column_types, enforce_schema, conflict_handling,
+        )
+    }
+
+    // Add relationships from the rows of an SQL query, as add_relationships_from_csv does
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_relationships_from_sql(
+        &mut self, py: Python, connection: &str, query: &str, relationship_type: &str, source_type: &str, source_id_field: &str,
+        target_type: &str, target_id_field: &str, source_title_field: Option<&str>, target_title_field: Option<&str>,
+        column_types: Option<HashMap<String, String>>, enforce_schema: Option<bool>, conflict_handling: Option<&str>,
+    ) -> PyResult<PyObject> {
+        let table = io::sql::read_sql(connection, query)?;
+        self.ingest_relationships(
+            py, "add_relationships_from_sql", table, relationship_type, (source_type, source_id_field), (target_type, target_id_field),
+            (source_title_field, target_title_field), column_types, enforce_schema, conflict_handling,
+        )
+    }
+
+    // Reverse the direction of all edges of a relation type, optionally renaming them
+    pub fn invert_connection_type(
+        &mut self, relationship_type: &str, new_type: Option<&str>, keep_original: Option<bool>,
+    ) -> usize {
+        let inverted = maintain_graph::invert_connection_type(
+            &mut self.graph,
+            relationship_type,
+            new_type,
+            keep_original.unwrap_or(false),
+        );
+        self.log_changes("invert_connection_type");
+        inverted
+    }
+
+    // Add derived edges for the transitive closure of a hierarchical relation, e.g. PARENT_OF -> ANCESTOR_OF
+    pub fn materialize_transitive(&mut self, relationship_type: &str, as_type: Option<&str>, max_depth: Option<usize>) -> usize {
+        let added = maintain_graph::materialize_transitive(&mut self.graph, relationship_type, as_type.unwrap_or("ANCESTOR_OF"), max_depth);
+        self.log_changes("materialize_transitive");
+        added
+    }
+
+    // Merge another graph into this one, matching nodes by node type and unique ID
+    pub fn merge(&mut self, py: Python, other: PyRef<KnowledgeGraph>, conflict_handling: Option<&str>) -> PyResult<PyObject> {
+        let first_new_index = self.graph.node_count();
+        let summary = maintain_graph::merge_graphs(&mut self.graph, &other.graph, conflict_handling.unwrap_or("update"))?;
+        categories::encode_all(&mut self.graph);
+        self.assign_sequences(first_new_index);
+        self.log_changes("merge");
+
+        let result = PyDict::new(py);
+        result.set_item("nodes_added", summary.nodes_added)?;
+        result.set_item("nodes_updated", summary.nodes_updated)?;
+        result.set_item("edges_added", summary.edges_added)?;
+        Ok(result.into())
+    }
+
+    // Store calculated values on nodes, guarding ingested attributes from being overwritten
+    pub fn update_node_properties(
+        &mut self, py: Python, property: &str, values: HashMap<usize, AttributeValue>, overwrite: Option<String>,
+    ) -> PyResult<usize> {
+        let updated = update_nodes::update_node_properties(
+            &mut self.graph,
+            py,
+            property,
+            values,
+            overwrite,
+        )?;
+        self.log_changes("update_node_properties");
+        Ok(updated)
+    }
+
+    // Rename a property on every node of a type and in its schema, keeping its type, constraints and default; returns the
+    // number of nodes changed
+    pub fn rename_property(&mut self, node_type: &str, old_name: &str, new_name: &str) -> PyResult<usize> {
+        let renamed = maintain_graph::rename_property(&mut self.graph, node_type, old_name, new_name)?;
+        self.log_changes("rename_property");
+        Ok(renamed)
+    }
+
+    // Remove a property from every node of a type and from its schema; returns the number of nodes changed
+    pub fn drop_property(&mut self, node_type: &str, name: &str) -> PyResult<usize> {
+        let dropped = maintain_graph::drop_property(&mut self.graph, node_type, name)?;
+        self.log_changes("drop_property");
+        Ok(dropped)
+    }
+
+    // Move nodes to another node type, e.g. after a taxonomy change, recording their properties in its schema; returns the
+    // indices moved. Nothing changes if a unique ID is already taken in the new type or a constraint of it is broken
+    pub fn change_node_type(&mut self, indices: Vec<usize>, new_type: &str) -> PyResult<Vec<usize>> {
+        // The snapshot covers the old types too, so the moved nodes can be put back
+        let node_types: HashSet<String> = indices.iter()
+            .filter_map(|&index| match self.graph.node_weight(NodeIndex::new(index)) {
+                Some(Node::StandardNode { node_type, .. }) => Some(node_type.as_str().to_string()),
+                _ => None,
+            })
+            .chain([new_type.to_string()])
+            .collect();
+        let node_types: Vec<&str> = node_types.iter().map(String::as_str).collect();
+        let snapshot = constraints::snapshot(&self.graph, &node_types);
+        let moved = maintain_graph::change_node_type(&mut self.graph, &indices, new_type)?;
+        if let Some(snapshot) = snapshot {
+            constraints::enforce(&mut self.graph, &moved, snapshot)?;
+        }
+        self.log_changes("change_node_type");
+        Ok(moved)
+    }
+
+    // Rename a relation type on every relation and in its schema; returns the number of relations renamed
+    pub fn rename_relation_type(&mut self, old_type: &str, new_type: &str) -> PyResult<usize> {
+        let renamed = maintain_graph::rename_relation_type(&mut self.graph, old_type, new_type)?;
+        self.log_changes("rename_relation_type");
+        Ok(renamed)
+    }
+
+    // Change the type of a property, converting its existing values; on_error="null" drops values that don't convert, "fail" raises
+    pub fn alter_property_type(
+        &mut self, py: Python, node_type: &str, property: &str, new_type: &str, on_error: Option<&str>,
+    ) -> PyResult<PyObject> {
+        let snapshot = constraints::snapshot(&self.graph, &[node_type]);
+        let (indices, removed) = maintain_graph::alter_property_type(&mut self.graph, node_type, property, new_type, on_error.unwrap_or("null"))?;
+        if let Some(snapshot) = snapshot {
+            constraints::enforce(&mut self.graph, &indices, snapshot)?;
+        }
+        self.log_changes("alter_property_type");
+
+        let result = PyDict::new(py);
+        result.set_item("converted", indices.len() - removed)?;
+        result.set_item("removed", removed)?;
+        Ok(result.into())
+    }
+
+    // Get the schema of node types, optionally with value counts for categorical string attributes and
+    // per-attribute statistics (distinct count, min, max, top_k most common values) for profiling
+    pub fn get_node_schemas(
+        &self, py: Python, node_types: Option<Vec<String>>, include_values: Option<bool>, max_distinct: Option<usize>,
+        include_stats: Option<bool>, top_k: Option<usize>,
+    ) -> PyResult<PyObject> {
+        get_schema::get_node_schemas(
+            &self.graph,
+            py,
+            node_types,
+            include_values.unwrap_or(false),
+            max_distinct,
+            include_stats.unwrap_or(false),
+            top_k,
+        )
+    }
+
+    // Get the schema of relation types: edge counts, the node types they connect, property types and null counts
+    pub fn get_relation_schemas(&self, py: Python, relation_types: Option<Vec<String>>) -> PyResult<PyObject> {
+        get_schema::get_relation_schemas(&self.graph, py, relation_types)
+    }
+
+    // Record a constraint on a property of a node type: "unique" (default), "required", or "range"
+    // with inclusive min and/or max. Existing nodes are not checked; see validate()
+    pub fn add_constraint(
+        &mut self, node_type: &str, property: &str, kind: Option<&str>, min: Option<AttributeValue>, max: Option<AttributeValue>,
+    ) -> PyResult<()> {
+        let constraint = constraints::parse_constraint(kind.unwrap_or("unique"), min, max)?;
+        constraints::add_constraint(&mut self.graph, node_type, property, constraint);
+        Ok(())
+    }
+
+    // List the nodes breaking the constraints of their type, optionally of one node type only
+    pub fn validate(&self, py: Python, node_type: Option<&str>) -> PyResult<Vec<PyObject>> {
+        constraints::violations(&self.graph, None).iter()
+            .filter(|violation| node_type.is_none_or(|node_type| violation.node_type == node_type))
+            .map(|violation| violation.to_py(py))
+            .collect()
+    }
+
     // Get attributes from nodes
     pub fn get_node_attributes(
         &mut self, py: Python, indices: Vec<usize>, specified_attributes: Option<Vec<String>>, max_relations: Option<usize>,
@@ -86,15 +699,231 @@ impl KnowledgeGraph {
         )
     }
 
+    // Stream node data for a large selection in pages of `page_size` nodes
+    pub fn iter_pages(
+        slf: PyRef<Self>, indices: Vec<usize>, page_size: Option<usize>, specified_attributes: Option<Vec<String>>,
+    ) -> NodePages {
+        NodePages::new(slf.into(), indices, page_size.unwrap_or(10_000), specified_attributes)
+    }
+
+    // Selected nodes as a pandas (default), polars or pyarrow table, built column by column
+    pub fn to_df(&self, py: Python, indices: Vec<usize>, columns: Option<Vec<String>>, library: Option<&str>) -> PyResult<PyObject> {
+        io::dataframe::nodes_to_frame(&self.graph, py, &indices, columns, io::dataframe::Library::parse(library)?)
+    }
+
+    // All nodes of a type as a DataFrame
+    pub fn nodes_to_df(&mut self, py: Python, node_type: &str, columns: Option<Vec<String>>, library: Option<&str>) -> PyResult<PyObject> {
+        let library = io::dataframe::Library::parse(library)?;
+        let indices = navigate_graph::get_nodes(&mut self.graph, Some(node_type), None, FilterOptions::default())?;
+        io::dataframe::nodes_to_frame(&self.graph, py, &indices, columns, library)
+    }
+
+    // All relations of a type as a DataFrame with their endpoints and properties
+    pub fn edges_to_df(&self, py: Python, relationship_type: &str, columns: Option<Vec<String>>, library: Option<&str>) -> PyResult<PyObject> {
+        let edges: Vec<usize> = self.graph.edge_indices()
+            .filter(|&edge| self.graph[edge].relation_type == relationship_type)
+            .map(|edge| edge.index())
+            .collect();
+        io::dataframe::edges_to_frame(&self.graph, py, &edges, columns, io::dataframe::Library::parse(library)?)
+    }
+
+    // Render nodes as a Markdown table for pasting into issues and docs
+    pub fn to_markdown(&self, indices: Vec<usize>, max_rows: Option<usize>, properties: Option<Vec<String>>) -> String {
+        get_attributes::nodes_to_markdown(&self.graph, &indices, max_rows.unwrap_or(50), properties)
+    }
+
+    // Look up a batch of external IDs, returning node data for the found IDs and a list of missing IDs
+    pub fn resolve_ids(
+        &mut self, py: Python, node_type: &str, ids: Vec<&PyAny>, specified_attributes: Option<Vec<String>>,
+    ) -> PyResult<PyObject> {
+        let (found, missing) = navigate_graph::lookup_ids(&self.graph, node_type, ids_to_strings(ids)?);
+        let indices = found.into_iter().map(|(_, index)| index).collect();
+
+        let result = PyDict::new(py);
+        result.set_item("found", get_attributes::get_node_attributes(&mut self.graph, py, indices, specified_attributes, None)?)?;
+        result.set_item("missing", missing)?;
+        Ok(result.into())
+    }
+
+    // Select nodes of one type by a list of unique IDs, reporting the IDs that were not found
+    pub fn select_by_ids(&mut self, py: Python, node_type: &str, ids: Vec<&PyAny>) -> PyResult<PyObject> {
+        let started = Instant::now();
+        let (found, missing) = navigate_graph::lookup_ids(&self.graph, node_type, ids_to_strings(ids)?);
+        let indices: Vec<usize> = found.into_iter().map(|(_, index)| index).collect();
+        self.plan.record(format!("select_by_ids({:?})", node_type), None, Ok(indices.len()), started);
+        let result = PyDict::new(py);
+        result.set_item("indices", indices)?;
+        result.set_item("missing", missing)?;
+        Ok(result.into())
+    }
+
     // Navigate the graph
+    // String filters can ignore case and compare Unicode text in NFKC normal form
     pub fn get_nodes(
-        &mut self, node_type: Option<&str>, filters: Option<Vec<HashMap<String, String>>>,
-    ) -> Vec<usize> {
-        navigate_graph::get_nodes(
+        &mut self, node_type: Option<&str>, filters: Option<Vec<&PyDict>>, case_insensitive: Option<bool>,
+        normalize: Option<bool>,
+    ) -> PyResult<Vec<usize>> {
+        let options = FilterOptions {
+            case_insensitive: case_insensitive.unwrap_or(false),
+            normalize: normalize.unwrap_or(false),
+            ..FilterOptions::default()
+        };
+        let started = Instant::now();
+        let operation = format!(
+            "get_nodes(node_type={}, {} filters)", node_type.unwrap_or("*"), filters.as_ref().map_or(0, Vec::len)
+        );
+        let result = navigate_graph::get_nodes(
             &mut self.graph, 
             node_type,
-            filters
-        )
+            filters,
+            options,
+        );
+        self.record(operation, None, started, &result);
+        result
+    }
+    pub fn filter_expr(&mut self, indices: Vec<usize>, expression: &str, aliases: Option<HashMap<String, String>>) -> PyResult<Vec<usize>> {
+        let (started, input) = (Instant::now(), indices.len());
+        let result = navigate_graph::filter_expr(&self.graph, indices, expression, aliases.as_ref());
+        self.record(format!("filter_expr({:?})", expression), Some(input), started, &result);
+        result
+    }
+    // Evaluate an expression per node, returning the values or storing them as a calculated property
+    pub fn calculate(
+        &mut self, py: Python, indices: Vec<usize>, expression: &str, aliases: Option<HashMap<String, String>>, store_as: Option<&str>,
+        overwrite: Option<String>,
+    ) -> PyResult<PyObject> {
+        let result = calculations::calculate(&mut self.graph, py, indices, expression, aliases, store_as, overwrite)?;
+        self.log_changes("calculate");
+        Ok(result)
+    }
+    // Keep parents whose related nodes satisfy an aggregate expression such as "count(*) > 5"
+    pub fn filter_by_children(
+        &mut self, indices: Vec<usize>, relationship_types: StringOrList, expression: &str, direction: Option<&str>,
+        aliases: Option<HashMap<String, String>>,
+    ) -> PyResult<Vec<usize>> {
+        let (started, input) = (Instant::now(), indices.len());
+        let directions = navigate_graph::parse_directions(direction)?;
+        let relationship_types = relationship_types.into_vec();
+        let result = self.edge_filter(None).and_then(|edge_filter| calculations::filter_by_children(
+            &self.graph, indices, &relationship_types, &directions, expression, aliases.as_ref(), edge_filter.as_ref(),
+        ));
+        self.record(format!("filter_by_children({:?}, {:?})", relationship_types, expression), Some(input), started, &result);
+        result
+    }
+    // Aggregate related nodes per parent, e.g. "avg(depth)", optionally keeping parents without matching children
+    #[allow(clippy::too_many_arguments)]
+    pub fn aggregate_children(
+        &self, py: Python, indices: Vec<usize>, relationship_types: StringOrList, expression: &str, direction: Option<&str>,
+        filters: Option<Vec<&PyDict>>, keep_empty: Option<bool>, aliases: Option<HashMap<String, String>>,
+    ) -> PyResult<Vec<(usize, PyObject)>> {
+        let directions = navigate_graph::parse_directions(direction)?;
+        let child_filter = filters.map(|filters| Filter::from_list(filters, FilterOptions::default())).transpose()?;
+        let edge_filter = self.edge_filter(None)?;
+        let results = calculations::aggregate_children(
+            &self.graph, indices, &relationship_types.into_vec(), &directions, expression, aliases.as_ref(),
+            child_filter.as_ref(), keep_empty.unwrap_or(false), edge_filter.as_ref(),
+        )?;
+        Ok(results.iter().map(|(index, value)| (*index, calculations::value_to_py(py, value))).collect())
+    }
+    // Aggregate over all nodes within `hops` relations of each node, e.g. "avg(porosity)", returning or storing the values
+    #[allow(clippy::too_many_arguments)]
+    pub fn aggregate_neighborhood(
+        &mut self, py: Python, indices: Vec<usize>, expression: &str, hops: Option<usize>, relationship_types: Option<StringOrList>,
+        direction: Option<&str>, filters: Option<Vec<&PyDict>>, aliases: Option<HashMap<String, String>>, store_as: Option<&str>,
+        overwrite: Option<String>,
+    ) -> PyResult<PyObject> {
+        let directions = navigate_graph::parse_directions(Some(direction.unwrap_or("both")))?;
+        let child_filter = filters.map(|filters| Filter::from_list(filters, FilterOptions::default())).transpose()?;
+        let edge_filter = self.edge_filter(None)?;
+        let results = calculations::aggregate_neighborhood(
+            &self.graph, indices, hops.unwrap_or(2), relationship_types.map(StringOrList::into_vec).as_deref(), &directions,
+            expression, aliases.as_ref(), child_filter.as_ref(), edge_filter.as_ref(),
+        )?;
+        let Some(property) = store_as else {
+            return Ok(results.iter().map(|(index, value)| (*index, calculations::value_to_py(py, value))).collect::<Vec<_>>().into_py(py));
+        };
+        let stored = calculations::store_results(&mut self.graph, py, property, results, overwrite)?;
+        self.log_changes("aggregate_neighborhood");
+        Ok(stored.into_py(py))
+    }
+    // Select hubs or isolated nodes by their number of (matching) edges
+    pub fn filter_by_degree(
+        &mut self, indices: Vec<usize>, min_degree: Option<usize>, max_degree: Option<usize>, direction: Option<&str>,
+        relationship_types: Option<StringOrList>,
+    ) -> PyResult<Vec<usize>> {
+        let (started, input) = (Instant::now(), indices.len());
+        let directions = navigate_graph::parse_directions(direction)?;
+        let min_degree = min_degree.unwrap_or(1);
+        let edge_filter = self.edge_filter(None)?;
+        let filtered = navigate_graph::filter_by_degree(
+            &self.graph, indices, min_degree, max_degree, &directions, relationship_types.map(StringOrList::into_vec).as_deref(),
+            edge_filter.as_ref(),
+        );
+        self.plan.record(format!("filter_by_degree(min={}, max={:?})", min_degree, max_degree), Some(input), Ok(filtered.len()), started);
+        Ok(filtered)
+    }
+    pub fn sample(&mut self, indices: Vec<usize>, n: usize, seed: Option<u64>) -> Vec<usize> {
+        let (started, input) = (Instant::now(), indices.len());
+        let sampled = sampling::sample(indices, n, seed);
+        self.plan.record(format!("sample(n={})", n), Some(input), Ok(sampled.len()), started);
+        sampled
+    }
+    pub fn sample_frac(&mut self, indices: Vec<usize>, frac: f64, seed: Option<u64>) -> PyResult<Vec<usize>> {
+        let (started, input) = (Instant::now(), indices.len());
+        let result = sampling::sample_frac(indices, frac, seed);
+        self.record(format!("sample_frac(frac={})", frac), Some(input), started, &result);
+        result
+    }
+    // Named selections kept on the graph so pipelines can resume from a saved set of nodes
+    pub fn save_selection(&mut self, name: String, indices: Vec<usize>) {
+        self.selections.insert(name, indices);
+    }
+    pub fn load_selection(&self, name: &str) -> PyResult<Vec<usize>> {
+        self.selections.get(name).cloned()
+            .ok_or_else(|| PyKeyError::new_err(format!("No saved selection named '{}'", name)))
+    }
+    pub fn list_selections(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.selections.keys().cloned().collect();
+        names.sort();
+        names
+    }
+    pub fn delete_selection(&mut self, name: &str) -> bool {
+        self.selections.remove(name).is_some()
+    }
+    // Named points the graph, with its saved selections and merge log, can be put back to, so exploratory calculations and
+    // deletes can be undone without reloading from disk. The graph logs its changes from the first checkpoint on and
+    // restore undoes them; it keeps the checkpoint for reuse and drops the ones taken after it
+    pub fn checkpoint(&mut self, name: String) {
+        self.graph.set_recording(true);
+        let state = transaction::SavedState::capture(self);
+        self.checkpoints.insert(name, state);
+    }
+    pub fn restore(&mut self, name: &str) -> PyResult<()> {
+        let state = self.checkpoints.get(name).cloned()
+            .ok_or_else(|| PyKeyError::new_err(format!("No checkpoint named '{}'", name)))?;
+        state.restore(self, "restore");
+        Ok(())
+    }
+    pub fn list_checkpoints(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.checkpoints.keys().cloned().collect();
+        names.sort();
+        names
+    }
+    pub fn delete_checkpoint(&mut self, name: &str) -> bool {
+        let deleted = self.checkpoints.remove(name).is_some();
+        self.sync_journal();
+        deleted
+    }
+    // Randomly sample children per parent (or overall), optionally weighted by an attribute
+    #[allow(clippy::too_many_arguments)]
+    pub fn children_sample(
+        &self, indices: Vec<usize>, relationship_types: StringOrList, n: usize, direction: Option<&str>, per_parent: Option<bool>,
+        weight_property: Option<&str>, seed: Option<u64>,
+    ) -> PyResult<Vec<(usize, usize)>> {
+        let directions = navigate_graph::parse_directions(direction)?;
+        Ok(sampling::children_sample(
+            &self.graph, indices, &relationship_types.into_vec(), &directions, n, per_parent.unwrap_or(true), weight_property, seed,
+        ))
     }
     pub fn get_relationships(
         &mut self, py: Python, indices: Vec<usize>,
@@ -105,13 +934,799 @@ impl KnowledgeGraph {
             indices
         )
     }
-    pub fn traverse_incoming(&self, indices: Vec<usize>, relationship_type: String, sort_attribute: Option<&str>, ascending: Option<bool>, max_relations: Option<usize>) -> Vec<usize> {
-        navigate_graph::traverse_nodes(&self.graph, indices, relationship_type, true, sort_attribute, ascending, max_relations)
+    #[allow(clippy::too_many_arguments)]
+    pub fn traverse_incoming(
+        &mut self, indices: Vec<usize>, relationship_type: String, sort_attribute: Option<&str>, ascending: Option<bool>, max_relations: Option<usize>,
+        offset: Option<usize>, relationship_filters: Option<Vec<&PyDict>>,
+    ) -> PyResult<Vec<usize>> {
+        let (started, input) = (Instant::now(), indices.len());
+        let operation = format!("traverse_incoming({:?})", relationship_type);
+        let result = self.edge_filter(relationship_filters).map(|filter| navigate_graph::traverse_nodes(
+            &self.graph, indices, &[relationship_type], &[Direction::Incoming], sort_attribute, ascending, max_relations, offset, filter.as_ref(),
+        ));
+        self.record(operation, Some(input), started, &result);
+        result
+    }
+    #[allow(clippy::too_many_arguments)]
+    pub fn traverse_outgoing(
+        &mut self, indices: Vec<usize>, relationship_type: String, sort_attribute: Option<&str>, ascending: Option<bool>, max_relations: Option<usize>,
+        offset: Option<usize>, relationship_filters: Option<Vec<&PyDict>>,
+    ) -> PyResult<Vec<usize>> {
+        let (started, input) = (Instant::now(), indices.len());
+        let operation = format!("traverse_outgoing({:?})", relationship_type);
+        let result = self.edge_filter(relationship_filters).map(|filter| navigate_graph::traverse_nodes(
+            &self.graph, indices, &[relationship_type], &[Direction::Outgoing], sort_attribute, ascending, max_relations, offset, filter.as_ref(),
+        ));
+        self.record(operation, Some(input), started, &result);
+        result
+    }
+    // Follow one or several relation types in one or both directions, returning the union per node
+    #[allow(clippy::too_many_arguments)]
+    pub fn traverse(
+        &mut self, indices: Vec<usize>, relationship_types: StringOrList, direction: Option<&str>, sort_attribute: Option<&str>, ascending: Option<bool>,
+        max_relations: Option<usize>, offset: Option<usize>, relationship_filters: Option<Vec<&PyDict>>,
+    ) -> PyResult<Vec<usize>> {
+        let (started, input) = (Instant::now(), indices.len());
+        let directions = navigate_graph::parse_directions(direction)?;
+        let relationship_types = relationship_types.into_vec();
+        let operation = format!("traverse({:?}, direction={:?})", relationship_types, direction.unwrap_or("outgoing"));
+        let result = self.edge_filter(relationship_filters).map(|filter| navigate_graph::traverse_nodes(
+            &self.graph, indices, &relationship_types, &directions, sort_attribute, ascending, max_relations, offset, filter.as_ref(),
+        ));
+        self.record(operation, Some(input), started, &result);
+        result
+    }
+    // Follow relation types transitively, optionally returning (index, depth) pairs
+    #[allow(clippy::too_many_arguments)]
+    pub fn traverse_deep(
+        &mut self, py: Python, indices: Vec<usize>, relationship_types: StringOrList, min_depth: Option<usize>, max_depth: Option<usize>,
+        direction: Option<&str>, include_depth: Option<bool>,
+    ) -> PyResult<PyObject> {
+        let (started, input) = (Instant::now(), indices.len());
+        let directions = navigate_graph::parse_directions(direction)?;
+        let relationship_types = relationship_types.into_vec();
+        let edge_filter = self.edge_filter(None)?;
+        let reached = navigate_graph::traverse_deep(
+            &self.graph, indices, &relationship_types, &directions, min_depth.unwrap_or(1), Some(max_depth.unwrap_or(5)),
+            edge_filter.as_ref(),
+        );
+        let operation = format!("traverse_deep({:?}, max_depth={})", relationship_types, max_depth.unwrap_or(5));
+        self.plan.record(operation, Some(input), Ok(reached.len()), started);
+        if include_depth.unwrap_or(false) {
+            Ok(reached.into_py(py))
+        } else {
+            Ok(reached.into_iter().map(|(index, _)| index).collect::<Vec<_>>().into_py(py))
+        }
+    }
+    pub fn top_k(
+        &mut self, indices: Vec<usize>, relationship_types: StringOrList, k: usize, sort_attribute: &str, direction: Option<&str>, ascending: Option<bool>,
+    ) -> PyResult<Vec<(usize, usize)>> {
+        let (started, input) = (Instant::now(), indices.len());
+        let directions = navigate_graph::parse_directions(direction)?;
+        let pairs = navigate_graph::top_k(&self.graph, indices, &relationship_types.into_vec(), k, sort_attribute, &directions, ascending);
+        self.plan.record(format!("top_k(k={}, sort_attribute={:?})", k, sort_attribute), Some(input), Ok(pairs.len()), started);
+        Ok(pairs)
+    }
+    pub fn sort_nodes(&mut self, indices: Vec<usize>, sort_attribute: &str, ascending: Option<bool>, limit: Option<usize>, offset: Option<usize>) -> Vec<usize> {
+        let (started, input) = (Instant::now(), indices.len());
+        let sorted = navigate_graph::sort_nodes(&self.graph, indices, sort_attribute, ascending, limit, offset);
+        self.plan.record(format!("sort_nodes({:?})", sort_attribute), Some(input), Ok(sorted.len()), started);
+        sorted
+    }
+
+    // Cheapest path between two nodes, by hop count or a numeric edge property; None when unreachable
+    #[allow(clippy::too_many_arguments)]
+    pub fn shortest_path(
+        &self, py: Python, source_type: &str, source_id: &PyAny, target_type: &str, target_id: &PyAny,
+        weight_property: Option<&str>, relationship_types: Option<StringOrList>, direction: Option<&str>,
+    ) -> PyResult<PyObject> {
+        let source = self.node_by_id(source_type, source_id)?;
+        let target = self.node_by_id(target_type, target_id)?;
+        let directions = navigate_graph::parse_directions(direction)?;
+        let relationship_types = relationship_types.map(StringOrList::into_vec);
+        let edge_filter = self.edge_filter(None)?;
+        let path = algorithms::shortest_path(
+            &self.graph, source, target, relationship_types.as_deref(), &directions, weight_property, edge_filter.as_ref(),
+        )?;
+        let Some(path) = path else { return Ok(py.None()) };
+
+        let node_ids: Vec<Option<&str>> = path.nodes.iter().map(|&index| match &self.graph[NodeIndex::new(index)] {
+            Node::StandardNode { unique_id, .. } => Some(unique_id.as_str()),
+            Node::DataTypeNode { .. } => None,
+        }).collect();
+        let result = PyDict::new(py);
+        result.set_item("cost", path.cost)?;
+        result.set_item("nodes", path.nodes)?;
+        result.set_item("node_ids", node_ids)?;
+        result.set_item("edges", path.edges)?;
+        Ok(result.into())
+    }
+
+    // Union of nodes reachable from the selection, or (source, reach count) pairs with `counts`
+    pub fn reachable_from(
+        &mut self, py: Python, indices: Vec<usize>, relationship_types: Option<StringOrList>, max_depth: Option<usize>,
+        direction: Option<&str>, counts: Option<bool>,
+    ) -> PyResult<PyObject> {
+        let (started, input) = (Instant::now(), indices.len());
+        let directions = navigate_graph::parse_directions(direction)?;
+        let relationship_types = relationship_types.map(StringOrList::into_vec);
+        let edge_filter = self.edge_filter(None)?;
+        if counts.unwrap_or(false) {
+            let reach = algorithms::reach_counts(
+                &self.graph, &indices, relationship_types.as_deref(), &directions, max_depth, edge_filter.as_ref(),
+            );
+            self.plan.record(format!("reachable_from(max_depth={:?}, counts=True)", max_depth), Some(input), Ok(reach.len()), started);
+            return Ok(reach.into_py(py));
+        }
+        let reached = algorithms::reachable_from(
+            &self.graph, &indices, relationship_types.as_deref(), &directions, max_depth, edge_filter.as_ref(),
+        );
+        self.plan.record(format!("reachable_from(max_depth={:?})", max_depth), Some(input), Ok(reached.len()), started);
+        Ok(reached.into_py(py))
+    }
+
+    // Simple paths from one selection to another, each a list of (node, edge used to reach it) records
+    #[allow(clippy::too_many_arguments)]
+    pub fn paths_between(
+        &self, from_indices: Vec<usize>, to_indices: Vec<usize>, max_depth: Option<usize>, relationship_types: Option<StringOrList>,
+        direction: Option<&str>, max_paths: Option<usize>,
+    ) -> PyResult<Vec<algorithms::PathRecords>> {
+        let directions = navigate_graph::parse_directions(direction)?;
+        let edge_filter = self.edge_filter(None)?;
+        Ok(algorithms::paths_between(
+            &self.graph, &from_indices, &to_indices, relationship_types.map(StringOrList::into_vec).as_deref(), &directions,
+            max_depth.unwrap_or(5), max_paths.unwrap_or(10_000), edge_filter.as_ref(),
+        ))
+    }
+
+    // Bindings of a small pattern of typed, filtered nodes and relations, as {alias: node index} dictionaries
+    pub fn match_pattern(&self, py: Python, nodes: &PyDict, edges: Option<&PyList>, max_matches: Option<usize>) -> PyResult<PyObject> {
+        let mut pattern = pattern::Pattern::from_py(nodes, edges)?;
+        if let Some(at) = &self.as_of {
+            pattern.valid_at(at);
+        }
+        let matches = pattern::match_pattern(&self.graph, &pattern, max_matches.unwrap_or(10_000));
+        let result = PyList::empty(py);
+        for binding in matches {
+            let dict = PyDict::new(py);
+            for (node, index) in pattern.nodes.iter().zip(binding) {
+                dict.set_item(&node.alias, index)?;
+            }
+            result.append(dict)?;
+        }
+        Ok(result.into())
+    }
+
+    // Run a query in a small Cypher subset (MATCH, WHERE, RETURN, ORDER BY, LIMIT), returning one dict per row
+    pub fn query(&self, py: Python, query: &str) -> PyResult<PyObject> {
+        query::run_query(&self.graph, py, query, self.as_of.as_ref())
+    }
+
+    // Nodes within `radius` hops of one node (itself included), as a selection or an independent subgraph
+    #[allow(clippy::too_many_arguments)]
+    pub fn ego_graph(
+        &self, py: Python, node_type: &str, node_id: &PyAny, radius: Option<usize>, relationship_types: Option<StringOrList>,
+        direction: Option<&str>, as_subgraph: Option<bool>,
+    ) -> PyResult<PyObject> {
+        let center = self.node_by_id(node_type, node_id)?.index();
+        let directions = navigate_graph::parse_directions(Some(direction.unwrap_or("both")))?;
+        let relationship_types = relationship_types.map(StringOrList::into_vec);
+        let edge_filter = self.edge_filter(None)?;
+        let mut indices = vec![center];
+        indices.extend(
+            algorithms::reachable_from(
+                &self.graph, &[center], relationship_types.as_deref(), &directions, Some(radius.unwrap_or(2)), edge_filter.as_ref(),
+            )
+                .into_iter()
+                .filter(|&index| index != center),
+        );
+        if !as_subgraph.unwrap_or(false) {
+            return Ok(indices.into_py(py));
+        }
+        let mut kg = KnowledgeGraph::new(Some(self.track_insertion_order));
+        kg.graph = subgraph::extract_subgraph(&self.graph, &indices, relationship_types.as_deref()).into();
+        kg.next_sequence = self.next_sequence;
+        Ok(kg.into_py(py))
+    }
+
+    // Pairwise neighborhood similarity ("jaccard" or "overlap") between two selections, as (a, b, score) candidates
+    pub fn similarity(
+        &self, indices_a: Vec<usize>, indices_b: Vec<usize>, method: Option<&str>, relationship_types: Option<StringOrList>,
+        direction: Option<&str>, threshold: Option<f64>,
+    ) -> PyResult<Vec<(usize, usize, f64)>> {
+        let directions = navigate_graph::parse_directions(Some(direction.unwrap_or("both")))?;
+        let relationship_types = relationship_types.map(StringOrList::into_vec);
+        algorithms::similarity(
+            &self.graph, &indices_a, &indices_b, method.unwrap_or("jaccard"), relationship_types.as_deref(),
+            &directions, threshold.unwrap_or(0.0),
+        )
+    }
+
+    // Minimum spanning forest by an edge weight, as edge records or materialized under a new relation type
+    pub fn minimum_spanning_tree(
+        &mut self, py: Python, weight_property: &str, indices: Option<Vec<usize>>, node_type: Option<&str>,
+        relationship_types: Option<StringOrList>, store_as: Option<&str>,
+    ) -> PyResult<PyObject> {
+        let indices = self.scope(indices, node_type)?;
+        let edges = algorithms::minimum_spanning_tree(
+            &self.graph, indices, relationship_types.map(StringOrList::into_vec).as_deref(), weight_property,
+        );
+        let Some(relation_type) = store_as else {
+            return edge_selection::get_edge_attributes(&self.graph, py, edges, None);
+        };
+        let edges: Vec<EdgeIndex> = edges.into_iter().map(EdgeIndex::new).collect();
+        // Declared endpoints are checked before anything is stored
+        if let Some(mut check) = constraints::EndpointCheck::new(&self.graph, relation_type) {
+            for &edge in &edges {
+                let (source, target) = self.graph.edge_endpoints(edge).expect("Edge must exist");
+                let node_type = |index| match &self.graph[index] {
+                    Node::StandardNode { node_type, .. } => node_type.as_str(),
+                    Node::DataTypeNode { .. } => "",
+                };
+                if let Some((_, message)) = check.check(node_type(source), node_type(target), Some(source), Some(target)) {
+                    return Err(PyValueError::new_err(format!("The tree was not stored: {}", message)));
+                }
+                check.record(source, target);
+            }
+        }
+        get_schema::record_copied_relations(&mut self.graph, relation_type, &edges)?;
+        for &edge in &edges {
+            let (source, target) = self.graph.edge_endpoints(edge).expect("Edge must exist");
+            let relation = Relation::new(relation_type, self.graph[edge].attributes.clone());
+            self.graph.add_edge(source, target, relation);
+        }
+        self.log_changes("minimum_spanning_tree");
+        Ok(edges.len().into_py(py))
+    }
+
+    // Maximum flow between two node sets by an edge capacity, with the flow per edge and the minimum cut
+    pub fn max_flow(
+        &self, py: Python, sources: Vec<usize>, sinks: Vec<usize>, capacity_property: &str, indices: Option<Vec<usize>>,
+        relationship_types: Option<StringOrList>,
+    ) -> PyResult<PyObject> {
+        let flow = algorithms::max_flow(
+            &self.graph, &sources, &sinks, capacity_property, indices, relationship_types.map(StringOrList::into_vec).as_deref(),
+        )?;
+        let result = PyDict::new(py);
+        result.set_item("value", flow.value)?;
+        result.set_item("flows", flow.flows)?;
+        result.set_item("min_cut", flow.min_cut)?;
+        Ok(result.into())
+    }
+
+    // Weakly connected component per node, returned as (index, component) pairs or stored as a property
+    pub fn connected_components(
+        &mut self, py: Python, indices: Option<Vec<usize>>, node_type: Option<&str>, relationship_types: Option<StringOrList>,
+        store_as: Option<&str>, overwrite: Option<String>,
+    ) -> PyResult<PyObject> {
+        let indices = self.scope(indices, node_type)?;
+        let labels = algorithms::connected_components(
+            &self.graph, indices, relationship_types.map(StringOrList::into_vec).as_deref(),
+        );
+        let Some(property) = store_as else { return Ok(labels.into_py(py)) };
+
+        // Component ids are stored as ints so they can be filtered on exactly
+        let values = labels.into_iter().map(|(index, id)| (index, AttributeValue::Int(id as i64))).collect();
+        let updated = update_nodes::update_node_properties(&mut self.graph, py, property, values, overwrite)?;
+        self.log_changes("connected_components");
+        Ok(updated.into_py(py))
+    }
+
+    // Strongly connected components as lists of unique IDs (largest first), or component ids stored as a property
+    pub fn strongly_connected_components(
+        &mut self, py: Python, indices: Option<Vec<usize>>, node_type: Option<&str>, relationship_types: Option<StringOrList>,
+        store_as: Option<&str>, overwrite: Option<String>,
+    ) -> PyResult<PyObject> {
+        let indices = self.scope(indices, node_type)?;
+        let components = algorithms::strongly_connected_components(
+            &self.graph, indices, relationship_types.map(StringOrList::into_vec).as_deref(),
+        );
+        let Some(property) = store_as else {
+            let ids: Vec<Vec<Option<&str>>> = components.iter()
+                .map(|members| members.iter().map(|&index| match &self.graph[NodeIndex::new(index)] {
+                    Node::StandardNode { unique_id, .. } => Some(unique_id.as_str()),
+                    Node::DataTypeNode { .. } => None,
+                }).collect())
+                .collect();
+            return Ok(ids.into_py(py));
+        };
+        let values = components.iter().enumerate()
+            .flat_map(|(id, members)| members.iter().map(move |&index| (index, AttributeValue::Int(id as i64))))
+            .collect();
+        let updated = update_nodes::update_node_properties(&mut self.graph, py, property, values, overwrite)?;
+        self.log_changes("strongly_connected_components");
+        Ok(updated.into_py(py))
+    }
+
+    // The DAG of strongly connected components as a new graph, one `component_type` node per component
+    pub fn condensation(
+        &mut self, indices: Option<Vec<usize>>, node_type: Option<&str>, relationship_types: Option<StringOrList>,
+        component_type: Option<&str>,
+    ) -> PyResult<Self> {
+        let indices = self.scope(indices, node_type)?;
+        let relationship_types = relationship_types.map(StringOrList::into_vec);
+        let components = algorithms::strongly_connected_components(&self.graph, indices, relationship_types.as_deref());
+        let mut kg = KnowledgeGraph::new(Some(self.track_insertion_order));
+        kg.graph = subgraph::condense(&self.graph, &components, relationship_types.as_deref(), component_type.unwrap_or("Component"))?;
+        kg.assign_sequences(0);
+        Ok(kg)
+    }
+
+    // PageRank, betweenness, closeness or degree centrality per node, returned as pairs or stored as a property
+    #[allow(clippy::too_many_arguments)]
+    pub fn centrality(
+        &mut self, py: Python, measure: &str, indices: Option<Vec<usize>>, node_type: Option<&str>,
+        relationship_types: Option<StringOrList>, store_as: Option<&str>, overwrite: Option<String>, damping: Option<f64>,
+    ) -> PyResult<PyObject> {
+        let indices = self.scope(indices, node_type)?;
+        let edge_filter = self.edge_filter(None)?;
+        let scores = centrality::centrality(
+            &self.graph, measure, indices, relationship_types.map(StringOrList::into_vec).as_deref(), damping.unwrap_or(0.85),
+            edge_filter.as_ref(),
+        )?;
+        let Some(property) = store_as else { return Ok(scores.into_py(py)) };
+
+        let values = scores.into_iter().map(|(index, score)| (index, AttributeValue::Float(score))).collect();
+        let updated = update_nodes::update_node_properties(&mut self.graph, py, property, values, overwrite)?;
+        self.log_changes("centrality");
+        Ok(updated.into_py(py))
+    }
+
+    // Edge selections hold edge indices rather than node indices; deleting edges may renumber the remaining ones
+    pub fn select_edges(
+        &mut self, relationship_types: Option<StringOrList>, filters: Option<Vec<&PyDict>>, indices: Option<Vec<usize>>,
+        direction: Option<&str>,
+    ) -> PyResult<Vec<usize>> {
+        let started = Instant::now();
+        let directions = navigate_graph::parse_directions(direction)?;
+        let relationship_types = relationship_types.map(StringOrList::into_vec);
+        let filter = self.edge_filter(filters)?;
+        let input = indices.as_ref().map(Vec::len);
+        let edges = edge_selection::select_edges(&self.graph, relationship_types.as_deref(), indices, &directions, filter.as_ref());
+        self.plan.record(format!("select_edges({:?})", relationship_types.unwrap_or_default()), input, Ok(edges.len()), started);
+        Ok(edges)
+    }
+    pub fn filter_edges(&mut self, edges: Vec<usize>, filters: Vec<&PyDict>) -> PyResult<Vec<usize>> {
+        let (started, input) = (Instant::now(), edges.len());
+        let result = Filter::from_list(filters, FilterOptions::default())
+            .map(|filter| edge_selection::filter_edges(&self.graph, edges, &filter));
+        self.record("filter_edges".to_string(), Some(input), started, &result);
+        result
+    }
+    // Nodes at the "source" or "target" end of the edges
+    pub fn edge_nodes(&self, edges: Vec<usize>, end: Option<&str>) -> PyResult<Vec<usize>> {
+        edge_selection::edge_nodes(&self.graph, &edges, end.unwrap_or("target"))
+    }
+    pub fn get_edge_attributes(&self, py: Python, edges: Vec<usize>, specified_attributes: Option<Vec<String>>) -> PyResult<PyObject> {
+        edge_selection::get_edge_attributes(&self.graph, py, edges, specified_attributes)
+    }
+    // Evaluate an expression per edge, returning the values or storing them as an edge property
+    pub fn calculate_edges(
+        &mut self, py: Python, edges: Vec<usize>, expression: &str, aliases: Option<HashMap<String, String>>, store_as: Option<&str>,
+    ) -> PyResult<PyObject> {
+        let results = edge_selection::evaluate_edges(&self.graph, edges, expression, aliases.as_ref())?;
+        let Some(property) = store_as else {
+            return Ok(results.iter().map(|(_, value)| calculations::value_to_py(py, value)).collect::<Vec<_>>().into_py(py));
+        };
+        let stored = edge_selection::store_edge_values(&mut self.graph, property, results)?;
+        self.log_changes("calculate_edges");
+        Ok(stored.into_py(py))
+    }
+    pub fn delete_edges(&mut self, edges: Vec<usize>) -> usize {
+        let deleted = edge_selection::delete_edges(&mut self.graph, edges);
+        self.log_changes("delete_edges");
+        deleted
     }
-    pub fn traverse_outgoing(&self, indices: Vec<usize>, relationship_type: String, sort_attribute: Option<&str>, ascending: Option<bool>, max_relations: Option<usize>) -> Vec<usize> {
-        navigate_graph::traverse_nodes(&self.graph, indices, relationship_type, false, sort_attribute, ascending, max_relations)
+    // Delete relations of a type between the nodes with the given unique IDs; None for source_ids or target_ids matches
+    // any node, so delete_connections("R") retires the whole type. Returns the number of relations deleted
+    pub fn delete_connections(&mut self, relationship_type: &str, source_ids: Option<Vec<&PyAny>>, target_ids: Option<Vec<&PyAny>>) -> PyResult<usize> {
+        let source_ids = source_ids.map(ids_to_strings).transpose()?.map(|ids| ids.into_iter().collect::<HashSet<_>>());
+        let target_ids = target_ids.map(ids_to_strings).transpose()?.map(|ids| ids.into_iter().collect::<HashSet<_>>());
+        let deleted = maintain_graph::delete_connections(&mut self.graph, relationship_type, source_ids.as_ref(), target_ids.as_ref());
+        self.log_changes("delete_connections");
+        Ok(deleted)
+    }
+    // Set properties of existing relations from rows keyed like add_relationships; returns {"updated", "missing"}, the
+    // latter listing (source_id, target_id) of rows without a relation. Empty cells keep the current value
+    #[allow(clippy::too_many_arguments)]
+    pub fn update_connection_properties(
+        &mut self, py: Python, data: &PyList, columns: Vec<String>, relationship_type: &str, source_type: &str, source_id_field: StringOrList,
+        target_type: &str, target_id_field: StringOrList,
+    ) -> PyResult<PyObject> {
+        let (updated, missing) = maintain_graph::update_connection_properties(
+            &mut self.graph, data, columns, relationship_type, source_type, source_id_field.into_vec(), target_type, target_id_field.into_vec(),
+        )?;
+        self.log_changes("update_connection_properties");
+        let result = PyDict::new(py);
+        result.set_item("updated", updated)?;
+        result.set_item("missing", missing)?;
+        Ok(result.into())
+    }
+    // Delete nodes with all their relations; returns {"nodes_deleted", "edges_deleted"}. Deleting nodes may renumber the
+    // remaining ones, so earlier results go stale; saved selections are updated to match
+    pub fn delete_nodes(&mut self, py: Python, indices: Vec<usize>) -> PyResult<PyObject> {
+        let (nodes_deleted, edges_deleted) = self.remove_nodes(indices);
+        self.log_changes("delete_nodes");
+        let result = PyDict::new(py);
+        result.set_item("nodes_deleted", nodes_deleted)?;
+        result.set_item("edges_deleted", edges_deleted)?;
+        Ok(result.into())
+    }
+    // Delete nodes of a type by unique ID, as delete_nodes; IDs without a node are listed under "missing"
+    pub fn delete_nodes_by_ids(&mut self, py: Python, node_type: &str, ids: Vec<&PyAny>) -> PyResult<PyObject> {
+        let (found, missing) = navigate_graph::lookup_ids(&self.graph, node_type, ids_to_strings(ids)?);
+        let (nodes_deleted, edges_deleted) = self.remove_nodes(found.into_iter().map(|(_, index)| index).collect());
+        self.log_changes("delete_nodes_by_ids");
+        let result = PyDict::new(py);
+        result.set_item("nodes_deleted", nodes_deleted)?;
+        result.set_item("edges_deleted", edges_deleted)?;
+        result.set_item("missing", missing)?;
+        Ok(result.into())
+    }
+
+    // Merge duplicate nodes of a type into a survivor, as the apply step of entity resolution: relations of the duplicates move
+    // to the survivor (without duplicating ones it already has), properties merge by property_strategy ("prefer_survivor",
+    // "prefer_duplicates" or "keep_survivor") and the duplicates are deleted. Returns {"survivor", "merged", "edges_moved",
+    // "edges_dropped", "missing"} with the survivor's index after the deletion; merged IDs are added to get_merge_log()
+    pub fn merge_nodes(
+        &mut self, py: Python, node_type: &str, survivor_id: &PyAny, duplicate_ids: Vec<&PyAny>, property_strategy: Option<&str>,
+    ) -> PyResult<PyObject> {
+        let survivor_id = unique_id_from_py(survivor_id)?;
+        let (survivor, _) = navigate_graph::lookup_ids(&self.graph, node_type, vec![survivor_id.clone()]);
+        let &[(_, survivor)] = survivor.as_slice() else {
+            return Err(PyValueError::new_err(format!("No {} node with unique ID '{}'", node_type, survivor_id)));
+        };
+        let (found, missing) = navigate_graph::lookup_ids(&self.graph, node_type, ids_to_strings(duplicate_ids)?);
+        let duplicates: Vec<(String, usize)> = found.into_iter().filter(|&(_, index)| index != survivor).collect();
+        let indices: Vec<NodeIndex> = duplicates.iter().map(|&(_, index)| NodeIndex::new(index)).collect();
+        let (edges_moved, edges_dropped) = maintain_graph::merge_nodes(
+            &mut self.graph, NodeIndex::new(survivor), &indices, property_strategy.unwrap_or("prefer_survivor"),
+        )?;
+
+        // The survivor keeps its unique ID, so it is found again once the duplicates are gone
+        let survivor_unique_id = match &self.graph[NodeIndex::new(survivor)] {
+            Node::StandardNode { unique_id, .. } => unique_id.clone(),
+            Node::DataTypeNode { .. } => survivor_id,
+        };
+        self.remove_nodes(indices.iter().map(|index| index.index()).collect());
+        let merged: Vec<String> = duplicates.into_iter().map(|(id, _)| id).collect();
+        self.merge_log.extend(merged.iter().map(|id| (node_type.to_string(), id.clone(), survivor_unique_id.clone())));
+        self.log_changes("merge_nodes");
+        let (survivor, _) = navigate_graph::lookup_ids(&self.graph, node_type, vec![survivor_unique_id]);
+
+        let result = PyDict::new(py);
+        result.set_item("survivor", survivor.first().map(|&(_, index)| index))?;
+        result.set_item("merged", merged)?;
+        result.set_item("edges_moved", edges_moved)?;
+        result.set_item("edges_dropped", edges_dropped)?;
+        result.set_item("missing", missing)?;
+        Ok(result.into())
+    }
+    // The nodes merge_nodes folded into survivors so far, as {"node_type", "duplicate_id", "survivor_id"} dicts
+    pub fn get_merge_log(&self, py: Python) -> PyResult<PyObject> {
+        let log = self.merge_log.iter().map(|(node_type, duplicate_id, survivor_id)| {
+            let entry = PyDict::new(py);
+            entry.set_item("node_type", node_type)?;
+            entry.set_item("duplicate_id", duplicate_id)?;
+            entry.set_item("survivor_id", survivor_id)?;
+            Ok(entry.into())
+        }).collect::<PyResult<Vec<PyObject>>>()?;
+        Ok(log.into_py(py))
+    }
+
+    // Turn the change log on (the default) or off; while on, every node and relation an operation creates, updates or
+    // deletes is logged with the operation and the old and new property values. Turning it off discards the log
+    pub fn track_changes(&mut self, enabled: Option<bool>) {
+        match enabled.unwrap_or(true) {
+            true if self.change_log.is_none() => self.change_log = Some(history::ChangeLog::new(self.graph.position())),
+            true => (),
+            false => self.change_log = None,
+        }
+        self.sync_journal();
+    }
+    // Logged changes to the node with unique ID node_id (of node_type if given) and its relations, or all changes, oldest
+    // first as {"timestamp", "operation", "action", "entity", ..., "changes"} dicts with "changes" as {property: (old, new)}
+    pub fn history(&self, py: Python, node_id: Option<&PyAny>, node_type: Option<&str>) -> PyResult<PyObject> {
+        let log = self.change_log.as_ref()
+            .ok_or_else(|| PyValueError::new_err("Change tracking is off; turn it on with track_changes()"))?;
+        let node_id = node_id.map(unique_id_from_py).transpose()?;
+        let changes = log.history(node_id.as_deref(), node_type).into_iter()
+            .map(|change| change.to_py(py))
+            .collect::<PyResult<Vec<_>>>()?;
+        Ok(changes.into_py(py))
+    }
+    // Write the change log as JSON Lines, one change per line; returns the number of changes written
+    pub fn export_history(&self, path: &str) -> PyResult<usize> {
+        let log = self.change_log.as_ref()
+            .ok_or_else(|| PyValueError::new_err("Change tracking is off; turn it on with track_changes()"))?;
+        log.export(path)
+    }
+
+    // Describe the operations of the current pipeline with node counts and timings per step
+    pub fn explain(&mut self, reset: Option<bool>) -> String {
+        let plan = self.plan.format();
+        if reset.unwrap_or(false) {
+            self.plan.clear();
+        }
+        plan
+    }
+
+
+    // Only follow relations whose valid_from/valid_to interval contains `date` (None clears it);
+    // `with kg.as_of(date):` puts back the date set before, if any, when the block ends
+    pub fn as_of(mut slf: PyRefMut<Self>, date: Option<AttributeValue>) -> AsOf {
+        let previous = std::mem::replace(&mut slf.as_of, date);
+        AsOf::new(slf.into(), previous)
+    }
+    // Pickle support (also used by joblib and multiprocessing): the state is the `save` file layout as bytes
+    fn __getstate__(&self, py: Python) -> PyResult<PyObject> {
+        let mut bytes = Vec::new();
+        persistence::write_state(&mut bytes, &persistence::GraphStateRef {
+            graph: &self.graph,
+            track_insertion_order: self.track_insertion_order,
+            next_sequence: self.next_sequence,
+            selections: &self.selections,
+        }, persistence::Codec::Lz4, "pickled graph")?;
+        Ok(PyBytes::new(py, &bytes).into())
+    }
+
+    fn __setstate__(&mut self, state: &PyBytes) -> PyResult<()> {
+        let state = persistence::read_state(state.as_bytes(), "pickled graph")?;
+        *self = KnowledgeGraph::new(Some(state.track_insertion_order));
+        self.graph = state.graph;
+        self.next_sequence = state.next_sequence;
+        self.selections = state.selections;
+        Ok(())
+    }
+
+    // `with kg.transaction() as tx:` makes the calls staged on tx in the block all-or-nothing: they run when the block
+    // ends, and if one raises, such as a row failing validation halfway through a file, the changes of those before it are
+    // undone. If the block raises or tx.rollback() is called, the staged calls are dropped
+    pub fn transaction(slf: PyRef<Self>) -> Transaction {
+        Transaction::new(slf.into())
     }
     
+    // Copy the selected nodes, the edges between them and their schema into a new, independent graph
+    pub fn to_subgraph(&self, indices: Vec<usize>, relationship_types: Option<StringOrList>) -> Self {
+        let mut kg = KnowledgeGraph::new(Some(self.track_insertion_order));
+        kg.graph = subgraph::extract_subgraph(&self.graph, &indices, relationship_types.map(StringOrList::into_vec).as_deref()).into();
+        kg.next_sequence = self.next_sequence;
+        kg
+    }
+
+    // Deep-compare against another graph, raising an AssertionError describing the differences
+    pub fn assert_equal(
+        &self, other: PyRef<KnowledgeGraph>, ignore_properties: Option<Vec<String>>, tolerance: Option<f64>, max_differences: Option<usize>,
+    ) -> PyResult<()> {
+        compare_graphs::assert_equal(
+            &self.graph,
+            &other.graph,
+            ignore_properties,
+            tolerance,
+            max_differences,
+        )
+    }
+
+    // Report nodes and connections added, removed or changed in `other` relative to this graph
+    pub fn diff(
+        &self, py: Python, other: PyRef<KnowledgeGraph>, ignore_properties: Option<Vec<String>>, tolerance: Option<f64>,
+    ) -> PyResult<PyObject> {
+        compare_graphs::diff(&self.graph, &other.graph, py, ignore_properties, tolerance)
+    }
+
+    // Save a de-identified copy of the graph for sharing bug reproductions and demos
+    pub fn export_anonymized(
+        &self, file_path: &str, hash_ids: Option<bool>, scramble: Option<Vec<String>>, noise: Option<HashMap<String, f64>>, seed: Option<u64>,
+    ) -> PyResult<()> {
+        let noise_rate = noise.and_then(|n| n.get("rate").copied());
+        let anonymized = KnowledgeGraph {
+            graph: anonymize::anonymize(&self.graph, hash_ids.unwrap_or(true), &scramble.unwrap_or_default(), noise_rate, seed)?.into(),
+            track_insertion_order: self.track_insertion_order,
+            next_sequence: self.next_sequence,
+            selections: HashMap::new(),
+            plan: Plan::default(),
+            as_of: None,
+            baseline: None,
+            merge_log: Vec::new(),
+            change_log: None,
+            checkpoints: HashMap::new(),
+            open_transactions: 0,
+        };
+        anonymized.save_to_file(file_path)
+    }
+
+    // Save the whole graph, its ingestion sequence and saved selections to a compact binary file, compressed with codec 'zstd' or 'lz4'
+    pub fn save(&mut self, path: &str, codec: Option<&str>) -> PyResult<()> {
+        persistence::save(path, &persistence::GraphStateRef {
+            graph: &self.graph,
+            track_insertion_order: self.track_insertion_order,
+            next_sequence: self.next_sequence,
+            selections: &self.selections,
+        }, persistence::Codec::parse(codec)?)?;
+        self.baseline = Some(delta::Baseline::new(path, &self.graph));
+        Ok(())
+    }
+
+    // Restore a graph written by `save`
+    #[staticmethod]
+    pub fn load(path: &str) -> PyResult<Self> {
+        let state = persistence::load(path)?;
+        let mut kg = KnowledgeGraph::new(Some(state.track_insertion_order));
+        kg.baseline = Some(delta::Baseline::new(path, &state.graph));
+        kg.graph = state.graph;
+        kg.next_sequence = state.next_sequence;
+        kg.selections = state.selections;
+        Ok(kg)
+    }
+
+    // Write only what changed since this graph was saved to or loaded from `base_snapshot` (or since the previous delta)
+    pub fn save_delta(&mut self, base_snapshot: &str, path: &str) -> PyResult<()> {
+        let baseline = self.baseline.as_mut().filter(|baseline| baseline.path == base_snapshot).ok_or_else(|| PyValueError::new_err(format!(
+            "Changes are tracked from the last save or load; save the graph to or load it from '{}' first", base_snapshot
+        )))?;
+        delta::save_delta(path, baseline, &persistence::GraphStateRef {
+            graph: &self.graph,
+            track_insertion_order: self.track_insertion_order,
+            next_sequence: self.next_sequence,
+            selections: &self.selections,
+        })
+    }
+
+    // Restore a graph written by `save` and apply the delta files saved after it, in order
+    #[staticmethod]
+    pub fn load_with_deltas(base_snapshot: &str, deltas: Vec<&str>) -> PyResult<Self> {
+        let mut state = persistence::load(base_snapshot)?;
+        let mut baseline = delta::Baseline::new(base_snapshot, &state.graph);
+        for path in deltas {
+            delta::apply_delta(path, &mut state, &mut baseline)?;
+        }
+        let mut kg = KnowledgeGraph::new(Some(state.track_insertion_order));
+        kg.graph = state.graph;
+        kg.next_sequence = state.next_sequence;
+        kg.selections = state.selections;
+        kg.baseline = Some(baseline);
+        Ok(kg)
+    }
+
+    // Store the graph in an SQLite database (tables nodes, node_properties, edges, edge_properties, schemas, ...) that any SQL tool can read and edit
+    pub fn to_sqlite(&self, path: &str) -> PyResult<()> {
+        io::sqlite::write_sqlite(path, &persistence::GraphStateRef {
+            graph: &self.graph,
+            track_insertion_order: self.track_insertion_order,
+            next_sequence: self.next_sequence,
+            selections: &self.selections,
+        })
+    }
+
+    // Restore a graph from a database written by `to_sqlite`, including rows edited with SQL since
+    #[staticmethod]
+    pub fn from_sqlite(path: &str) -> PyResult<Self> {
+        let state = io::sqlite::read_sqlite(path)?;
+        let mut kg = KnowledgeGraph::new(Some(state.track_insertion_order));
+        kg.graph = state.graph;
+        kg.next_sequence = state.next_sequence;
+        kg.selections = state.selections;
+        kg.assign_sequences(0);
+        Ok(kg)
+    }
+
+    // Export the graph, or only the selected nodes and the relations between them, as GraphML
+    pub fn to_graphml(&self, path: &str, indices: Option<Vec<usize>>) -> PyResult<()> {
+        match indices {
+            Some(indices) => io::graphml::write_graphml(&subgraph::extract_subgraph(&self.graph, &indices, None), path),
+            None => io::graphml::write_graphml(&self.graph, path),
+        }
+    }
+
+    // Build a graph from a GraphML file, e.g. one written by `to_graphml` or exported from Gephi/yEd
+    #[staticmethod]
+    pub fn from_graphml(path: &str) -> PyResult<Self> {
+        let mut kg = KnowledgeGraph::new(None);
+        kg.graph = io::graphml::read_graphml(path)?;
+        kg.assign_sequences(0);
+        Ok(kg)
+    }
+
+    // Cytoscape.js elements JSON for the selected nodes and the relations between them, returned or written to `path`
+    pub fn to_cytoscape_json(&self, indices: Vec<usize>, path: Option<&str>) -> PyResult<Option<String>> {
+        let json = io::cytoscape::to_cytoscape(&self.graph, &indices).to_string();
+        match path {
+            Some(path) => std::fs::write(path, json).map(|_| None)
+                .map_err(|e| PyIOError::new_err(format!("Cannot write '{}': {}", path, e))),
+            None => Ok(Some(json)),
+        }
+    }
+
+    // Render nodes and their relations as Graphviz DOT, written to `path` or returned as text; color_by="" turns coloring off
+    pub fn to_dot(
+        &self, path: Option<&str>, indices: Option<Vec<usize>>, node_label: Option<&str>, color_by: Option<&str>,
+        max_nodes: Option<usize>, collapse_degree: Option<usize>,
+    ) -> PyResult<Option<String>> {
+        let options = io::dot::DotOptions {
+            node_label: node_label.unwrap_or("title"),
+            color_by: Some(color_by.unwrap_or("node_type")).filter(|field| !field.is_empty()),
+            max_nodes,
+            collapse_degree,
+        };
+        match path {
+            Some(path) => io::dot::write_dot(&self.graph, path, indices, &options).map(|_| None),
+            None => Ok(Some(io::dot::to_dot(&self.graph, indices, &options))),
+        }
+    }
+
+    // Write the selected nodes (all by default) and their relations as JSON Lines, one object per line; returns (nodes, edges) written
+    pub fn export_jsonl(&self, nodes_path: &str, edges_path: &str, indices: Option<Vec<usize>>) -> PyResult<(usize, usize)> {
+        io::jsonl::export_jsonl(&self.graph, nodes_path, edges_path, indices.as_deref())
+    }
+
+    // Read export_jsonl files batch_size lines at a time; returns the number of node and edge records imported
+    pub fn import_jsonl(
+        &mut self, py: Python, nodes_path: &str, edges_path: Option<&str>, batch_size: Option<usize>,
+        conflict_handling: Option<&str>, column_types: Option<HashMap<String, String>>,
+    ) -> PyResult<PyObject> {
+        let first_new_index = self.graph.node_count();
+        // The files may hold nodes and relations of any type
+        let snapshot = constraints::snapshot_all(&self.graph);
+        let mut processor = io::batch::BatchProcessor::new(
+            &mut self.graph, io::tabular::ColumnTypes::new(column_types), conflict_handling.unwrap_or("update"),
+            batch_size.unwrap_or(10_000),
+        )?;
+        let nodes = io::jsonl::import_nodes(&mut processor, nodes_path)?;
+        let edges = edges_path.map(|path| io::jsonl::import_edges(&mut processor, path)).transpose()?.unwrap_or(0);
+        let indices = processor.finish()?;
+        defaults::apply_each(&mut self.graph, &indices);
+        if let Some(snapshot) = snapshot {
+            constraints::enforce(&mut self.graph, &indices, snapshot)?;
+        }
+        categories::encode_each(&mut self.graph, &indices);
+        self.assign_sequences(first_new_index);
+        self.log_changes("import_jsonl");
+        let result = PyDict::new(py);
+        result.set_item("nodes", nodes)?;
+        result.set_item("edges", edges)?;
+        Ok(result.into())
+    }
+
+    // Write the selected nodes (all by default) and their relations as RDF Turtle; returns the number of triples
+    pub fn to_rdf(
+        &self, path: &str, base_iri: Option<&str>, type_mappings: Option<HashMap<String, String>>, iri_template: Option<&str>,
+        indices: Option<Vec<usize>>,
+    ) -> PyResult<usize> {
+        let options = io::rdf::IriOptions {
+            base_iri: base_iri.unwrap_or(io::rdf::DEFAULT_BASE_IRI),
+            iri_template: iri_template.unwrap_or(io::rdf::DEFAULT_IRI_TEMPLATE),
+            type_mappings: type_mappings.unwrap_or_default(),
+        };
+        io::rdf::write_rdf(&self.graph, path, indices.as_deref(), &options)
+    }
+
+    // Write node and relationship CSVs plus neo4j-admin and LOAD CSV scripts for seeding a Neo4j database
+    pub fn export_neo4j(&self, dir: &str) -> PyResult<Vec<String>> {
+        io::neo4j::export_neo4j(&self.graph, dir)
+    }
+
+    // A networkx.DiGraph (or MultiDiGraph, keeping parallel relations of different types) keyed by node index
+    pub fn to_networkx(&self, py: Python, indices: Option<Vec<usize>>, multigraph: Option<bool>) -> PyResult<PyObject> {
+        io::networkx::to_networkx(&self.graph, py, indices.as_deref(), multigraph.unwrap_or(false), &NETWORKX_ATTRIBUTES)
+    }
+
+    // Build a graph from a NetworkX graph, reading node types, unique IDs, titles and relation types from the named attributes
+    #[staticmethod]
+    pub fn from_networkx(
+        graph: &PyAny, type_attr: Option<&str>, id_attr: Option<&str>, title_attr: Option<&str>, relation_attr: Option<&str>,
+    ) -> PyResult<Self> {
+        let names = io::networkx::AttributeNames {
+            node_type: type_attr.unwrap_or(NETWORKX_ATTRIBUTES.node_type),
+            unique_id: id_attr.unwrap_or(NETWORKX_ATTRIBUTES.unique_id),
+            title: title_attr.unwrap_or(NETWORKX_ATTRIBUTES.title),
+            relation_type: relation_attr.unwrap_or(NETWORKX_ATTRIBUTES.relation_type),
+        };
+        let mut kg = KnowledgeGraph::new(None);
+        kg.graph = io::networkx::from_networkx(graph, &names)?;
+        kg.assign_sequences(0);
+        Ok(kg)
+    }
+
     fn save_to_file(&self, file_path: &str) -> PyResult<()> {
         // Open a file in write mode
         let file = File::create(file_path)
@@ -133,12 +1748,18 @@ impl KnowledgeGraph {
             Err(e) => return Err(PyIOError::new_err(e.to_string())), // Convert std::io::Error to PyO3's PyIOError
         };
         
-        let reader = BufReader::new(file);
+        let mut bytes = Vec::new();
+        BufReader::new(file).read_to_end(&mut bytes).map_err(|e| PyIOError::new_err(e.to_string()))?;
     
-        // Attempt to deserialize the graph from the file
-        match bincode::deserialize_from(reader) {
+        // Attempt to deserialize the graph from the file, including files written by earlier layouts
+        match persistence::read_unversioned(&bytes) {
             Ok(graph) => {
-                self.graph = graph; // Assign the deserialized graph to self.graph
+                self.graph.replace(graph); // Assign the deserialized graph to self.graph
+                // Resume the ingestion sequence if the stored graph tracked insertion order
+                let last_sequence = self.graph.node_weights().filter_map(Node::sequence).max();
+                self.track_insertion_order = self.track_insertion_order || last_sequence.is_some();
+                self.next_sequence = last_sequence.map_or(0, |seq| seq + 1);
+                self.log_changes("load_from_file");
                 Ok(()) // Return Ok(()) on success
             },
             Err(e) => {
@@ -1,76 +1,199 @@
 use pyo3::prelude::*;
-use pyo3::types::{PyList, PyDict};
-use petgraph::graph::DiGraph;
+use pyo3::types::{PyByteArray, PyBytes, PyDateTime, PyList, PyDict};
+use pyo3::exceptions::PyValueError;
+use petgraph::graph::{DiGraph, EdgeIndex, NodeIndex};
 use std::collections::HashMap;
-use chrono::NaiveDateTime;
-use crate::graph::get_schema::update_or_retrieve_schema;
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use crate::graph::journal::JournaledGraph;
+use crate::graph::add_relationships::PLACEHOLDER_PROPERTY;
+use crate::graph::get_schema::{find_schema_node, record_key_columns, update_or_retrieve_schema};
 use crate::schema::{Node, Relation};
-use crate::data_types::AttributeValue; 
+use crate::data_types::{composite_id, unique_id_from_py, AttributeValue};
+
+/// Ways of handling rows whose unique ID matches an existing node: "update" merges the row's
+/// properties into the node, "replace" swaps the node's title and properties for the row's, "skip"
+/// leaves the node as it is and "error" fails
+pub const CONFLICT_HANDLING: [&str; 4] = ["update", "replace", "skip", "error"];
+
+pub fn check_conflict_handling(conflict_handling: &str) -> PyResult<()> {
+    if CONFLICT_HANDLING.contains(&conflict_handling) {
+        return Ok(());
+    }
+    Err(PyValueError::new_err(format!(
+        "Invalid conflict_handling '{}', expected 'update', 'replace', 'skip' or 'error'", conflict_handling
+    )))
+}
+
+/// What ingesting a row did to the graph
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NodeAction {
+    Created,
+    Updated,
+    Skipped,
+}
+
+// Removes the nodes and relations added since the graph had `node_count` nodes and `edge_count`
+// relations. Ingestion with conflict_handling "error" only adds, so this undoes it when it fails
+pub fn remove_added(graph: &mut JournaledGraph, node_count: usize, edge_count: usize) {
+    // Removing the last one leaves the indices of the others as they are
+    while graph.edge_count() > edge_count {
+        graph.remove_edge(EdgeIndex::new(graph.edge_count() - 1));
+    }
+    while graph.node_count() > node_count {
+        graph.remove_node(NodeIndex::new(graph.node_count() - 1));
+    }
+}
 
 // Function to handle node updating or creation based on conflict handling strategy
 fn update_or_create_node(
-    graph: &mut DiGraph<Node, Relation>,
+    graph: &mut JournaledGraph,
     node_type: &String,
     unique_id: String,
     node_title: Option<String>,
     attributes: Option<HashMap<String, AttributeValue>>, // Now an Option
     conflict_handling: &String,
-) -> usize {
-    let existing_node_index = graph.node_indices().find(|&i| match &graph[i] {
-        Node::StandardNode {
-            node_type: nt,
-            unique_id: uid,
-            ..
-        } => nt == node_type && uid == &unique_id,
-        _ => false,
-    });
-
-    match existing_node_index {
-        Some(node_index) => {
-            match conflict_handling.as_str() {
-                "replace" => {
-                    // If replacing, create a new node with the provided attributes (which may be None)
-                    graph[node_index] = Node::new(&node_type, &unique_id, attributes, node_title.as_deref());
-                },
-                "update" => {
-                    if let Some(attrs) = attributes {
-                        if let Node::StandardNode {
-                            attributes: node_attrs,
-                            ..
-                        } = &mut graph[node_index]
-                        {
-                            for (key, value) in attrs {
-                                node_attrs.insert(key, value);
-                            }
-                        }
-                    }
-                },
-                "skip" => (),
-                _ => panic!("Invalid conflict_handling value"),
-            }
-            node_index.index()
+    lookup: &mut HashMap<String, NodeIndex>,  // Nodes of node_type by unique ID
+) -> PyResult<(usize, NodeAction)> {
+    match lookup.get(&unique_id) {
+        Some(&node_index) => {
+            let action = update_existing_node(graph, node_index, node_type, &unique_id, node_title, attributes, conflict_handling)?;
+            Ok((node_index.index(), action))
         },
         None => {
             // Create a new node with the provided attributes, which may be None
             let node = Node::new(&node_type, &unique_id, attributes, node_title.as_deref());
-            graph.add_node(node).index()
+            let node_index = graph.add_node(node);
+            lookup.insert(unique_id, node_index);
+            Ok((node_index.index(), NodeAction::Created))
         },
     }
 }
 
+// Nodes of `node_type` by unique ID, built once per call rather than searching the graph for each
+// row. The first node with an ID is the one rows match
+fn nodes_by_id(graph: &DiGraph<Node, Relation>, node_type: &str) -> HashMap<String, NodeIndex> {
+    let mut lookup = HashMap::new();
+    for index in graph.node_indices() {
+        if let Node::StandardNode { node_type: nt, unique_id, .. } = &graph[index] {
+            if nt == node_type {
+                lookup.entry(unique_id.clone()).or_insert(index);
+            }
+        }
+    }
+    lookup
+}
+
+// Applies the conflict handling strategy to a node that already exists
+pub fn update_existing_node(
+    graph: &mut JournaledGraph,
+    node_index: NodeIndex,
+    node_type: &str,
+    unique_id: &str,
+    node_title: Option<String>,
+    attributes: Option<HashMap<String, AttributeValue>>,
+    conflict_handling: &str,
+) -> PyResult<NodeAction> {
+    match conflict_handling {
+        "replace" => {
+            // If replacing, create a new node with the provided attributes (which may be None)
+            let sequence = graph[node_index].sequence();
+            graph[node_index] = Node::new(node_type, unique_id, attributes, node_title.as_deref());
+            graph[node_index].set_sequence(sequence);
+        },
+        "update" => {
+            if let Node::StandardNode { attributes: node_attrs, title, .. } = &mut graph[node_index] {
+                // Properties not in the row, and missing values, leave the current value in place
+                for (key, value) in attributes.into_iter().flatten().filter(|(_, value)| !value.is_null()) {
+                    node_attrs.insert(key, value);
+                }
+                // The node is no longer only referred to by relations
+                node_attrs.remove(PLACEHOLDER_PROPERTY);
+                if node_title.is_some() {
+                    *title = node_title;
+                }
+            }
+        },
+        "skip" => return Ok(NodeAction::Skipped),
+        "error" => return Err(PyValueError::new_err(format!(
+            "{} node '{}' already exists; with conflict_handling='error' no nodes were added", node_type, unique_id
+        ))),
+        other => return Err(PyValueError::new_err(format!("Invalid conflict_handling '{}'", other))),
+    }
+    Ok(NodeAction::Updated)
+}
+
+// Undeclared columns are read as String, which binary payloads are not: a column whose first value
+// is bytes or bytearray, or that the schema already records as Bytes, is read as Bytes instead
+fn infer_bytes_columns(
+    graph: &DiGraph<Node, Relation>,
+    data: &PyList,
+    columns: &[String],
+    node_type: &str,
+    column_types_map: &mut HashMap<String, String>,
+) -> PyResult<()> {
+    let recorded = match find_schema_node(graph, "Node", node_type).map(|index| &graph[index]) {
+        Some(Node::DataTypeNode { attributes, .. }) => Some(attributes),
+        _ => None,
+    };
+    for (col_index, column) in columns.iter().enumerate() {
+        if column_types_map.contains_key(column) {
+            continue;
+        }
+        let mut is_bytes = recorded.is_some_and(|attributes| attributes.get(column).is_some_and(|t| t == "Bytes"));
+        if !is_bytes {
+            for row in data.iter() {
+                let item = row.get_item(col_index)?;
+                if !AttributeValue::is_missing(item) {
+                    is_bytes = item.downcast::<PyBytes>().is_ok() || item.downcast::<PyByteArray>().is_ok();
+                    break;
+                }
+            }
+        }
+        if is_bytes {
+            column_types_map.insert(column.clone(), "Bytes".to_string());
+        }
+    }
+    Ok(())
+}
+
+/// What an `add_nodes` call did with its rows and cells
+#[derive(Default)]
+pub struct IngestReport {
+    pub indices: Vec<usize>,
+    pub rows: usize,
+    pub rows_skipped: usize,  // Rows without a unique ID
+    pub values_coerced: HashMap<String, usize>,  // Cells stored as another type than passed, or dropped as unreadable
+    pub nulls: HashMap<String, usize>,  // Cells left unset, missing or unreadable
+    pub created: usize,
+    pub updated: usize,
+    pub skipped: usize,  // Rows matching an existing node that conflict_handling "skip" left as it was
+}
+
 // The simplified main function
+#[allow(clippy::too_many_arguments)]
 pub fn add_nodes(
-    graph: &mut DiGraph<Node, Relation>,
+    graph: &mut JournaledGraph,
     data: &PyList, // Each item in this list is a sublist representing a single node's attributes
     columns: Vec<String>,
     node_type: String,
-    unique_id_field: String,
+    unique_id_fields: Vec<String>, // Several fields form a composite key
     node_title_field: Option<String>,
     conflict_handling: Option<String>,
     column_types: Option<&PyDict>,
-) -> PyResult<Vec<usize>> {
+    dtypes: Option<HashMap<String, String>>, // Override column_types; unreadable values are left unset rather than failing
+    report: bool, // Skip rows without a unique ID and count coerced values instead of failing
+) -> PyResult<IngestReport> {
+    if let Some(field) = unique_id_fields.iter().find(|field| !columns.contains(field)) {
+        return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Unique ID field '{}' is not among the columns", field)));
+    }
     let conflict_handling = conflict_handling.unwrap_or_else(|| "update".to_string());
+    check_conflict_handling(&conflict_handling)?;
+    let (node_count, edge_count) = (graph.node_count(), graph.edge_count());
+    let (mut created, mut updated, mut skipped) = (0, 0, 0);
     let mut indices = Vec::new();
+    let mut rows_skipped = 0;
+    let mut coerced = vec![0; columns.len()];
+    let mut nulls = vec![0; columns.len()];
     let default_datetime_format = "%Y-%m-%d %H:%M:%S".to_string();
 
     // Initialize column_types_map based on whether column_types is Some or None
@@ -84,6 +207,9 @@ pub fn add_nodes(
             HashMap::new()
         }
     };
+    let overridden: Vec<bool> = columns.iter().map(|column| dtypes.as_ref().is_some_and(|d| d.contains_key(column))).collect();
+    column_types_map.extend(dtypes.unwrap_or_default());
+    infer_bytes_columns(graph, data, &columns, &node_type, &mut column_types_map)?;
 
     // Extract datetime formats if column_types_map is not empty
     let datetime_formats = if !column_types_map.is_empty() {
@@ -102,36 +228,57 @@ pub fn add_nodes(
         Some(columns.clone()),
         Some(column_types_map.clone())
     )?;
+    record_key_columns(graph, &node_type, &unique_id_fields, node_title_field.as_deref());
+    let mut lookup = nodes_by_id(graph, &node_type);
 
-    
-    for row in data.iter() {
+    'rows: for row in data.iter() {
         let row: Vec<&PyAny> = row.extract()?; // Extract the row as a list of PyAny references
         let mut attributes: HashMap<String, AttributeValue> = HashMap::new();
-        let mut unique_id = String::new();
+        let mut id_parts = vec![String::new(); unique_id_fields.len()];
         let mut node_title: Option<String> = None;
 
         for (col_index, column_name) in columns.iter().enumerate() {
             let item = row.get(col_index).unwrap(); // Safe to use unwrap() due to the structure of the data
 
-            if column_name == &unique_id_field {
-                unique_id = item.extract()?;
+            if let Some(position) = unique_id_fields.iter().position(|field| field == column_name) {
+                if report && AttributeValue::is_missing(item) {
+                    rows_skipped += 1;
+                    continue 'rows;
+                }
+                let mut unique_id = unique_id_from_py(item)?;
+                // IDs declared as UUID are matched in their canonical form, however the source spells them
+                if schema.get(column_name).is_some_and(|t| t == "UUID") {
+                    unique_id = AttributeValue::parse_uuid(&unique_id)
+                        .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Unique ID '{}' is not a UUID", unique_id)))?
+                        .to_string();
+                }
+                id_parts[position] = unique_id;
+                continue;
+            }
+
+            // Missing cells leave the attribute unset instead of failing the type conversion
+            if AttributeValue::is_missing(item) {
+                nulls[col_index] += 1;
                 continue;
             }
 
             if node_title_field.as_deref() == Some(column_name.as_str()) {
-                node_title = Some(item.extract()?);
+                node_title = item.extract()?;
                 continue;
             }
 
             // Determine the attribute's data type from the schema and extract value accordingly
             let data_type = schema.get(column_name).map_or("String", String::as_str);
             let attribute_value = match data_type {
-                "Int" => match item.extract::<i32>() {
+                "Int" => match item.extract::<i64>() {
                     Ok(value) => Ok(AttributeValue::Int(value)),
+                    Err(_) if item.hasattr("__index__")? => {
+                        Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Integer {} does not fit in 64 bits", item)))
+                    },
                     Err(_) => {
                         // Attempt to parse from String if direct extraction fails
                         item.extract::<String>()
-                            .and_then(|s| s.parse::<i32>().map_err(|_| PyErr::new::<pyo3::exceptions::PyTypeError, _>("Failed to parse Int from String")))
+                            .and_then(|s| s.parse::<i64>().map_err(|_| PyErr::new::<pyo3::exceptions::PyTypeError, _>("Failed to parse Int from String")))
                             .map(AttributeValue::Int)
                     }
                 },
@@ -144,71 +291,199 @@ pub fn add_nodes(
                             .map(AttributeValue::Float)
                     }
                 },
-                "DateTime" => {
+                // "DateTime" or "DateTime(zone)"; naive times are taken to be in the column's zone
+                data_type if AttributeValue::datetime_zone(data_type).is_some() => {
+                    let zone = AttributeValue::datetime_zone(data_type).flatten();
                     let format = datetime_formats.get(column_name).unwrap_or(&default_datetime_format);
                     // Attempt to directly extract a timestamp (i64)
                     if let Ok(timestamp) = item.extract::<i64>() {
                         Ok(AttributeValue::DateTime(timestamp))
+                    } else if let Ok(datetime) = item.downcast::<PyDateTime>() {
+                        // Aware datetimes, such as tz-aware pandas timestamps, are exact instants
+                        match AttributeValue::naive_datetime(datetime) {
+                            Some(naive) if datetime.getattr("tzinfo")?.is_none() => AttributeValue::localize(&naive, zone).map(AttributeValue::DateTime),
+                            _ => item.extract::<AttributeValue>(),
+                        }
                     } else {
                         // If direct extraction fails, try parsing from a string representation
                         let datetime_str: String = item.extract()?;
                         // Here you'll need to parse the string into a datetime
                         // The exact method depends on the format of your datetime strings
                         // For example, using chrono::NaiveDateTime for "YYYY-MM-DD HH:MM:SS" format:
-                        match NaiveDateTime::parse_from_str(&datetime_str, format) {
-                            Ok(naive_datetime) => {
-                                // Convert NaiveDateTime to a timestamp
-                                let timestamp = naive_datetime.and_utc().timestamp();
-                                Ok(AttributeValue::DateTime(timestamp))
-                            },
-                            Err(_) => Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>("Failed to parse DateTime")),
+                        // Formats with an offset (%z) give the instant directly
+                        if let Ok(datetime) = DateTime::parse_from_str(&datetime_str, format) {
+                            Ok(AttributeValue::DateTime(datetime.timestamp()))
+                        } else {
+                            match NaiveDateTime::parse_from_str(&datetime_str, format) {
+                                Ok(naive_datetime) => {
+                                    // Convert NaiveDateTime to a timestamp
+                                    AttributeValue::localize(&naive_datetime, zone).map(AttributeValue::DateTime)
+                                },
+                                Err(_) => Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>("Failed to parse DateTime")),
+                            }
                         }
                     }
                 },
+                "Date" => {
+                    let format = datetime_formats.get(column_name).map_or("%Y-%m-%d", String::as_str);
+                    match item.extract::<AttributeValue>() {
+                        Ok(value @ AttributeValue::Date(_)) => Ok(value),
+                        // datetime objects keep their (UTC) date
+                        Ok(AttributeValue::DateTime(timestamp)) => Ok(AttributeValue::date_from_timestamp(timestamp)),
+                        Ok(AttributeValue::String(text)) => NaiveDate::parse_from_str(text.trim(), format)
+                            .map(|date| AttributeValue::from_date(&date))
+                            .map_err(|_| PyErr::new::<pyo3::exceptions::PyTypeError, _>("Failed to parse Date")),
+                        _ => Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>("Failed to parse Date, expected a date or text")),
+                    }
+                },
+                // timedelta objects, numbers of seconds or ISO 8601 text such as "PT2H30M"
+                "Duration" => match item.extract::<AttributeValue>() {
+                    Ok(value @ AttributeValue::Duration(_)) => Ok(value),
+                    Ok(AttributeValue::Int(seconds)) => Ok(AttributeValue::Duration(seconds)),
+                    Ok(AttributeValue::Float(seconds)) if seconds.is_finite() => Ok(AttributeValue::Duration(seconds.floor() as i64)),
+                    Ok(AttributeValue::String(text)) => AttributeValue::parse_iso_duration(&text)
+                        .or_else(|| text.trim().parse().ok().map(AttributeValue::Duration))
+                        .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyTypeError, _>("Failed to parse Duration")),
+                    _ => Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>("Failed to parse Duration, expected a timedelta, seconds or text")),
+                },
+                // uuid.UUID objects or text in any of the usual spellings
+                "UUID" => match item.extract::<AttributeValue>() {
+                    Ok(value @ AttributeValue::Uuid(_)) => Ok(value),
+                    Ok(AttributeValue::String(text)) => AttributeValue::parse_uuid(&text)
+                        .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyTypeError, _>("Failed to parse UUID")),
+                    _ => Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>("Failed to parse UUID, expected a uuid.UUID or text")),
+                },
+                "Boolean" => match item.extract::<bool>() {
+                    Ok(value) => Ok(AttributeValue::Boolean(value)),
+                    Err(_) => {
+                        // Attempt to parse 0/1 or strings such as "true" and "no"
+                        AttributeValue::parse_bool(&item.str()?.to_string())
+                            .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyTypeError, _>("Failed to parse Boolean"))
+                    }
+                },
+                "List" => match item.extract::<AttributeValue>() {
+                    Ok(value @ AttributeValue::List(_)) => Ok(value),
+                    _ => Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>("Failed to parse List, expected a list or tuple")),
+                },
+                "Map" => match item.extract::<AttributeValue>() {
+                    Ok(value @ AttributeValue::Map(_)) => Ok(value),
+                    // JSON text, as read from files or databases
+                    Ok(AttributeValue::String(text)) => serde_json::from_str::<serde_json::Value>(&text).ok()
+                        .filter(serde_json::Value::is_object)
+                        .and_then(|json| AttributeValue::from_json(&json))
+                        .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyTypeError, _>("Failed to parse Map from JSON text")),
+                    _ => Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>("Failed to parse Map, expected a dict")),
+                },
                 "String" => item.extract::<String>().map(AttributeValue::String),
-                // Extend cases for other data types like 'DateTime', 'Date', etc.
-                _ => Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>("Unsupported data type")),
-            }?;
+                // bytes and bytearray, or hexadecimal text
+                "Bytes" => match item.extract::<AttributeValue>() {
+                    Ok(value @ AttributeValue::Bytes(_)) => Ok(value),
+                    Ok(AttributeValue::String(text)) => AttributeValue::parse_hex(&text)
+                        .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyTypeError, _>("Failed to parse Bytes from hexadecimal text")),
+                    _ => Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>("Failed to parse Bytes, expected bytes or hexadecimal text")),
+                },
+                // Kept as text here and turned into codes once the batch is in
+                "Category" => match item.extract::<AttributeValue>() {
+                    Ok(AttributeValue::List(_) | AttributeValue::Map(_)) | Err(_) => {
+                        Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>("Failed to parse Category, expected a single value"))
+                    },
+                    Ok(value) => Ok(AttributeValue::String(value.to_string())),
+                },
+                // "Decimal" or "Decimal(2)": decimal.Decimal, numbers and numeric text, rounded to the declared scale
+                data_type => match AttributeValue::decimal_scale(data_type) {
+                    Some(scale) => item.extract::<AttributeValue>().ok()
+                        .and_then(|value| value.to_decimal(scale))
+                        .map(AttributeValue::Decimal)
+                        .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyTypeError, _>("Failed to parse Decimal")),
+                    None => Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>("Unsupported data type")),
+                },
+            };
+            let attribute_value = match attribute_value {
+                Ok(value) => value,
+                Err(_) if overridden[col_index] => {
+                    coerced[col_index] += 1;
+                    nulls[col_index] += 1;
+                    continue;
+                },
+                Err(e) => return Err(e),
+            };
+            // Only worth extracting twice when the caller asked for the counts
+            if report && item.extract::<AttributeValue>().map_or(true, |passed| passed.type_name() != attribute_value.type_name()) {
+                coerced[col_index] += 1;
+            }
 
             attributes.insert(column_name.clone(), attribute_value);
         }
 
         // Create or update the node in the graph based on the conflict handling strategy
-        let index = update_or_create_node(
+        let (index, action) = match update_or_create_node(
             graph,
             &node_type,
-            unique_id,
+            composite_id(&id_parts),
             node_title,
             Some(attributes),
             &conflict_handling,
-        );
+            &mut lookup,
+        ) {
+            Ok(result) => result,
+            Err(e) => {
+                remove_added(graph, node_count, edge_count);
+                return Err(e);
+            },
+        };
+        match action {
+            NodeAction::Created => created += 1,
+            NodeAction::Updated => updated += 1,
+            NodeAction::Skipped => skipped += 1,
+        }
 
         indices.push(index);
     }
 
-    Ok(indices)
+    // Counted per column other than the unique ID
+    let per_column = |counts: Vec<usize>| -> HashMap<String, usize> {
+        columns.iter().zip(counts)
+            .filter(|(column, _)| !unique_id_fields.contains(column))
+            .map(|(column, count)| (column.clone(), count))
+            .collect()
+    };
+    Ok(IngestReport {
+        indices,
+        rows: data.len(),
+        rows_skipped,
+        values_coerced: per_column(coerced),
+        nulls: per_column(nulls),
+        created,
+        updated,
+        skipped,
+    })
 }
 
-fn extract_datetime_formats(column_types_map: &mut HashMap<String, String>, default_datetime_format: &str) -> HashMap<String, String> {
+pub fn extract_datetime_formats(column_types_map: &mut HashMap<String, String>, default_datetime_format: &str) -> HashMap<String, String> {
     
     let mut datetime_formats: HashMap<String, String> = HashMap::new();
 
-    // Iterate through the map to find and process "DateTime" types
+    // Iterate through the map to find and process "DateTime" and "Date" types
     for (column, data_type) in column_types_map.iter() {
-        // Split the data_type into two parts, expecting "DateTime" and an optional format
+        // Split the data_type into two parts, expecting "DateTime" or "Date" and an optional format
         let parts: Vec<&str> = data_type.splitn(2, ' ').collect();
 
-        if parts[0] == "DateTime" {
+        if AttributeValue::datetime_zone(parts[0]).is_some() {
             // Check if a custom format is provided; otherwise, use the default format
             let format = parts.get(1).unwrap_or(&default_datetime_format);
             datetime_formats.insert(column.clone(), format.to_string());
+        } else if let ("Date", Some(format)) = (parts[0], parts.get(1)) {
+            // Dates without a format are read as ISO dates
+            datetime_formats.insert(column.clone(), format.to_string());
         }
     }
 
-    // Update column_types_map to remove the format from "DateTime" entries
+    // Update column_types_map to remove the format from "DateTime" and "Date" entries
     for (_column, data_type) in column_types_map.iter_mut() {
-        if data_type.starts_with("DateTime") {
-            *data_type = "DateTime".to_string();  // Set to "DateTime" only, removing any format
+        if let Some((base, _format)) = data_type.split_once(' ') {
+            if base == "Date" || AttributeValue::datetime_zone(base).is_some() {
+                *data_type = base.to_string();  // Keep the type (and time zone) only, removing any format
+            }
         }
     }
 
@@ -1,22 +1,134 @@
 use pyo3::prelude::*;
+use pyo3::exceptions::PyValueError;
 use pyo3::types::PyList;
-use petgraph::graph::DiGraph;
-use std::collections::HashMap;
+use petgraph::graph::{EdgeIndex, NodeIndex};
+use petgraph::visit::EdgeRef;
+use std::collections::{HashMap, HashSet};
+use crate::graph::journal::JournaledGraph;
+use crate::graph::calculations::aggregate_values;
+use crate::graph::constraints::{EndpointCheck, RelationViolation};
+use crate::graph::equation_parser::Value;
+use crate::graph::get_schema::update_or_retrieve_schema;
 use crate::schema::{Node, Relation};
+use crate::data_types::{composite_id, unique_id_from_py, AttributeValue};
 
+/// Ways of handling rows whose source and target already share a relation of the type: "add" adds
+/// another (the default), "skip_duplicates" leaves the existing one, "update" merges the row's
+/// properties into it and "replace" swaps its properties for the row's
+pub const CONFLICT_HANDLING: [&str; 4] = ["add", "skip_duplicates", "update", "replace"];
+
+/// Functions combining the values duplicate rows give an aggregated property
+pub const AGGREGATES: [&str; 6] = ["count", "sum", "avg", "mean", "min", "max"];
+
+/// Boolean property marking nodes add_relationships created for IDs it did not find, so they can
+/// be found and filled in later; ingesting the node with conflict handling "update" clears it
+pub const PLACEHOLDER_PROPERTY: &str = "_placeholder";
+
+/// What add_relationships did with the rows
+#[derive(Default)]
+pub struct RelationSummary {
+    pub indices: Vec<(usize, usize)>,
+    pub violations: Vec<RelationViolation>,
+    pub created: usize,
+    pub updated: usize,
+    pub skipped: usize,
+    pub placeholders: usize,
+    pub missing: Vec<(String, String)>,  // (source ID, target ID) of rows left out for lack of a node
+}
+
+/// One row of relationships: the IDs and titles of its source and target, and its relation properties
+pub struct RelationRow {
+    pub source_id: String,
+    pub target_id: String,
+    pub source_title: Option<String>,
+    pub target_title: Option<String>,
+    pub attributes: HashMap<String, AttributeValue>,
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn add_relationships(
-    graph: &mut DiGraph<Node, Relation>,
+    graph: &mut JournaledGraph,
     data: &PyList,  // 2D list where each inner list represents a row
     columns: Vec<String>,  // Column header names
     relationship_type: String,  // Configuration items directly in the function call
     source_type: String,
-    source_id_field: String,
+    source_id_fields: Vec<String>,  // Several fields match a composite key
     target_type: String,
-    target_id_field: String,
+    target_id_fields: Vec<String>,
     source_title_field: Option<String>,
     target_title_field: Option<String>,
-) -> PyResult<Vec<(usize, usize)>> {
-    let mut indices = Vec::new();
+    enforce_schema: bool,  // Reject rows breaking the declared endpoints of the relation type
+    conflict_handling: &str,  // One of CONFLICT_HANDLING
+    aggregate: HashMap<String, String>,  // Property -> function of AGGREGATES, for "update" and "replace"
+    create_missing_nodes: Option<bool>,  // None creates missing nodes, true also tags them as placeholders, false leaves the row out
+) -> PyResult<RelationSummary> {
+    // Every row is read before the graph changes, so a row missing an ID leaves it as it was
+    let mut rows = Vec::with_capacity(data.len());
+    for row in data.iter() {
+        let row: Vec<&PyAny> = row.extract()?;
+        let row_data: HashMap<_, _> = columns.iter().zip(row.iter()).collect();
+        let title = |field: &Option<String>| field.as_ref().and_then(|field| row_data.get(field).and_then(|&item| item.extract::<String>().ok()));
+
+        // Remaining columns become relation attributes
+        let mut attributes = HashMap::new();
+        for (column, item) in &row_data {
+            let is_key_column = source_id_fields.contains(column) || target_id_fields.contains(column)
+                || source_title_field.as_ref() == Some(*column) || target_title_field.as_ref() == Some(*column);
+            if !is_key_column {
+                attributes.insert((*column).clone(), item.extract::<AttributeValue>()?);
+            }
+        }
+        rows.push(RelationRow {
+            source_id: row_id(&row_data, &source_id_fields, "Source")?,
+            target_id: row_id(&row_data, &target_id_fields, "Target")?,
+            source_title: title(&source_title_field),
+            target_title: title(&target_title_field),
+            attributes,
+        });
+    }
+    add_relation_rows(
+        graph, rows, &relationship_type, &source_type, &target_type, enforce_schema, conflict_handling, aggregate, create_missing_nodes,
+    )
+}
+
+/// Adds the relations of rows already read, as `add_relationships` describes. The relation schema
+/// records the type of each property and is checked before any node or relation changes.
+#[allow(clippy::too_many_arguments)]
+pub fn add_relation_rows(
+    graph: &mut JournaledGraph,
+    rows: Vec<RelationRow>,
+    relationship_type: &str,
+    source_type: &str,
+    target_type: &str,
+    enforce_schema: bool,
+    conflict_handling: &str,
+    aggregate: HashMap<String, String>,
+    create_missing_nodes: Option<bool>,
+) -> PyResult<RelationSummary> {
+    if !CONFLICT_HANDLING.contains(&conflict_handling) {
+        return Err(PyValueError::new_err(format!(
+            "Invalid conflict_handling '{}', expected 'add', 'skip_duplicates', 'update' or 'replace'", conflict_handling
+        )));
+    }
+    if let Some((property, function)) = aggregate.iter().find(|(_, function)| !AGGREGATES.contains(&function.as_str())) {
+        return Err(PyValueError::new_err(format!(
+            "Invalid aggregate '{}' for '{}', expected 'count', 'sum', 'avg', 'min' or 'max'", function, property
+        )));
+    }
+    if !aggregate.is_empty() && !["update", "replace"].contains(&conflict_handling) {
+        return Err(PyValueError::new_err("aggregate requires conflict_handling 'update' or 'replace'"));
+    }
+    // Aggregated properties take the type of their combined value, recorded once it is known
+    let mut found_types: HashMap<String, String> = HashMap::new();
+    for (name, value) in rows.iter().flat_map(|row| &row.attributes) {
+        if !value.is_null() && !aggregate.contains_key(name) {
+            found_types.entry(name.clone()).or_insert_with(|| value.type_name().to_string());
+        }
+    }
+    let schema = update_or_retrieve_schema(graph, "Relation", relationship_type, Some(found_types.keys().cloned().collect()), Some(found_types))?;
+    let first_new_index = graph.node_count();
+    let mut summary = RelationSummary::default();
+    let mut endpoint_check = enforce_schema.then(|| EndpointCheck::new(graph, relationship_type)).flatten();
 
     // Create lookup tables for source and target nodes
     let mut source_node_lookup = HashMap::new();
@@ -27,9 +139,10 @@ pub fn add_relationships(
         if let Some(node) = graph.node_weight(index) {
             match node {
                 Node::StandardNode { node_type, unique_id, .. } => {
-                    if node_type == &source_type {
+                    if node_type == source_type {
                         source_node_lookup.insert(unique_id.clone(), index);
-                    } else if node_type == &target_type {
+                    }
+                    if node_type == target_type {
                         target_node_lookup.insert(unique_id.clone(), index);
                     }
                 },
@@ -39,48 +152,188 @@ pub fn add_relationships(
         }
     }
 
-    // Iterate over each row in the data
-    for row in data.iter() {
-        let row: Vec<&PyAny> = row.extract()?;
-        let row_data: HashMap<_, _> = columns.iter().zip(row.iter()).collect();
+    // Existing relations of the type by (source, target), for the modes that do not always add
+    let mut existing_edges: HashMap<(NodeIndex, NodeIndex), EdgeIndex> = HashMap::new();
+    if conflict_handling != "add" {
+        for edge in graph.edge_references().filter(|edge| edge.weight().relation_type == relationship_type) {
+            existing_edges.entry((edge.source(), edge.target())).or_insert(edge.id());
+        }
+    }
+    // Relations the rows have reached so far, and the values of their aggregated properties
+    let mut touched: HashSet<EdgeIndex> = HashSet::new();
+    let mut aggregated: HashMap<EdgeIndex, HashMap<String, Vec<AttributeValue>>> = HashMap::new();
 
-        let source_unique_id = row_data.get(&source_id_field)
-            .and_then(|&item| item.extract::<String>().ok())
-            .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Source ID column '{}' value missing", source_id_field)))?;
+    // Iterate over each row in the data
+    for (row_number, row) in rows.into_iter().enumerate() {
+        let RelationRow { source_id: source_unique_id, target_id: target_unique_id, source_title, target_title, mut attributes } = row;
 
-        let target_unique_id = row_data.get(&target_id_field)
-            .and_then(|&item| item.extract::<String>().ok())
-            .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Target ID column '{}' value missing", target_id_field)))?;
+        // Rejected rows create no nodes, so they are checked before the lookups below. A row matching a
+        // relation that is already there adds none, so it cannot break the cardinality
+        if let Some(check) = &endpoint_check {
+            let target_lookup = if source_type == target_type { &source_node_lookup } else { &target_node_lookup };
+            let (source, target) = (lookup_node(&source_node_lookup, &source_unique_id), lookup_node(target_lookup, &target_unique_id));
+            let is_duplicate = source.zip(target).is_some_and(|key| existing_edges.contains_key(&key));
+            if let Some((rule, message)) = check.check(source_type, target_type, source, target).filter(|_| !is_duplicate) {
+                summary.violations.push(RelationViolation { row: row_number, source_id: source_unique_id, target_id: target_unique_id, rule, message });
+                continue;
+            }
+        }
 
-        // Optionally extract source and target titles
-        let source_title = source_title_field.as_ref().and_then(|field| row_data.get(field).and_then(|&item| item.extract::<String>().ok()));
-        let target_title = target_title_field.as_ref().and_then(|field| row_data.get(field).and_then(|&item| item.extract::<String>().ok()));
+        if create_missing_nodes == Some(false) {
+            let target_lookup = if source_type == target_type { &source_node_lookup } else { &target_node_lookup };
+            if lookup_node(&source_node_lookup, &source_unique_id).is_none() || lookup_node(target_lookup, &target_unique_id).is_none() {
+                summary.missing.push((source_unique_id, target_unique_id));
+                continue;
+            }
+        }
 
         // Find or create source and target nodes
-        let source_node_index = find_or_create_node(graph, &source_type, &source_unique_id, source_title.clone(), &mut source_node_lookup);
-        let target_node_index = find_or_create_node(graph, &target_type, &target_unique_id, target_title.clone(), &mut target_node_lookup);
+        let source_node_index = find_or_create_node(graph, source_type, &source_unique_id, source_title, &mut source_node_lookup);
+        // Relations within one node type must share a lookup so nodes created as sources are found as targets
+        let target_lookup = if source_type == target_type { &mut source_node_lookup } else { &mut target_node_lookup };
+        let target_node_index = find_or_create_node(graph, target_type, &target_unique_id, target_title, target_lookup);
+        summary.indices.push((source_node_index.index(), target_node_index.index()));
+
+        let key = (source_node_index, target_node_index);
+        let Some(&edge) = existing_edges.get(&key) else {
+            // Aggregated properties are set once every row has been read
+            let values: HashMap<String, Vec<AttributeValue>> = aggregate.keys()
+                .filter_map(|property| Some((property.clone(), vec![attributes.remove(property).filter(|value| !value.is_null())?])))
+                .collect();
+            let relation = Relation::new(relationship_type, (!attributes.is_empty()).then_some(attributes));
+            let edge = graph.add_edge(source_node_index, target_node_index, relation);
+            if let Some(check) = &mut endpoint_check {
+                check.record(source_node_index, target_node_index);
+            }
+            if conflict_handling != "add" {
+                existing_edges.insert(key, edge);
+                touched.insert(edge);
+                aggregated.insert(edge, values);
+            }
+            summary.created += 1;
+            continue;
+        };
+
+        if conflict_handling == "skip_duplicates" {
+            summary.skipped += 1;
+            continue;
+        }
+        let first_touch = touched.insert(edge);
+        let values = aggregated.entry(edge).or_default();
+        for property in aggregate.keys() {
+            let current = graph[edge].attributes.as_ref().and_then(|attributes| attributes.get(property));
+            if first_touch && conflict_handling == "update" {
+                values.entry(property.clone()).or_default().extend(current.filter(|value| !value.is_null()).cloned());
+            }
+            if let Some(value) = attributes.remove(property).filter(|value| !value.is_null()) {
+                values.entry(property.clone()).or_default().push(value);
+            }
+        }
+        attributes.retain(|_, value| !value.is_null());
+        let relation = &mut graph[edge];
+        if first_touch && conflict_handling == "replace" {
+            relation.attributes = (!attributes.is_empty()).then_some(attributes);
+        } else if !attributes.is_empty() {
+            relation.attributes.get_or_insert_with(HashMap::new).extend(attributes);
+        }
+        summary.updated += 1;
+    }
+
+    let mut aggregated_types: HashMap<String, String> = HashMap::new();
+    for (edge, values) in aggregated {
+        for (property, values) in values.into_iter().filter(|(_, values)| !values.is_empty()) {
+            let value = aggregate_relation_values(&aggregate[&property], values)?;
+            let relation = &mut graph[edge];
+            match value {
+                Some(value) => {
+                    if !schema.contains_key(&property) {
+                        aggregated_types.entry(property.clone()).or_insert_with(|| value.type_name().to_string());
+                    }
+                    relation.attributes.get_or_insert_with(HashMap::new).insert(property, value);
+                },
+                None => if let Some(attributes) = &mut relation.attributes {
+                    attributes.remove(&property);
+                },
+            }
+        }
+    }
+    // Only properties the schema did not list yet are added, so this cannot conflict
+    if !aggregated_types.is_empty() {
+        update_or_retrieve_schema(graph, "Relation", relationship_type, Some(aggregated_types.keys().cloned().collect()), Some(aggregated_types))?;
+    }
 
-        // Construct and add the relationship
-        let relation = Relation::new(&relationship_type, None);  // Construct a Relation instance, attributes can be added as needed
-        let _edge = graph.add_edge(source_node_index, target_node_index, relation);
+    // Every node the rows added is one they referred to without it existing
+    if create_missing_nodes == Some(true) {
+        for index in first_new_index..graph.node_count() {
+            if let Node::StandardNode { attributes, .. } = &mut graph[NodeIndex::new(index)] {
+                attributes.insert(PLACEHOLDER_PROPERTY.to_string(), AttributeValue::Boolean(true));
+            }
+        }
+        summary.placeholders = graph.node_count() - first_new_index;
+    }
+    record_created_node_types(graph, first_new_index, create_missing_nodes == Some(true))?;
 
-        indices.push((source_node_index.index(), target_node_index.index()));
+    Ok(summary)
+}
+
+/// Records a schema for the types of the nodes added since the graph had `first_new_index` nodes,
+/// as types first seen as relation endpoints have none, listing the placeholder flag when set
+pub fn record_created_node_types(graph: &mut JournaledGraph, first_new_index: usize, placeholders: bool) -> PyResult<()> {
+    let node_types: HashSet<String> = (first_new_index..graph.node_count())
+        .filter_map(|index| match &graph[NodeIndex::new(index)] {
+            Node::StandardNode { node_type, .. } => Some(node_type.to_string()),
+            Node::DataTypeNode { .. } => None,
+        })
+        .collect();
+    let columns = placeholders.then(|| vec![PLACEHOLDER_PROPERTY.to_string()]);
+    let column_types = HashMap::from([(PLACEHOLDER_PROPERTY.to_string(), "Boolean".to_string())]);
+    for node_type in node_types {
+        update_or_retrieve_schema(graph, "Node", &node_type, columns.clone(), Some(column_types.clone()))?;
     }
+    Ok(())
+}
+
+// Combines the values of an aggregated property; counts and the sum, minimum or maximum of whole
+// numbers stay Int
+fn aggregate_relation_values(function: &str, values: Vec<AttributeValue>) -> PyResult<Option<AttributeValue>> {
+    let all_int = values.iter().all(|value| matches!(value, AttributeValue::Int(_)));
+    let value = aggregate_values(function, values.iter().map(Value::from_attribute).collect())
+        .map_err(PyValueError::new_err)?;
+    Ok(match value {
+        Value::Number(v) if function == "count" || (all_int && ["sum", "min", "max"].contains(&function)) => {
+            Some(AttributeValue::Int(v as i64))
+        },
+        other => other.into_attribute(),
+    })
+}
+
+// The unique ID a row refers to, joining the fields of a composite key
+pub fn row_id(row_data: &HashMap<&String, &&PyAny>, fields: &[String], role: &str) -> PyResult<String> {
+    let parts = fields.iter()
+        .map(|field| row_data.get(field)
+            .and_then(|&item| unique_id_from_py(item).ok())
+            .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("{} ID column '{}' value missing", role, field))))
+        .collect::<PyResult<Vec<_>>>()?;
+    Ok(composite_id(&parts))
+}
 
-    Ok(indices)
+// The node a unique ID refers to, finding UUIDs in any spelling
+pub fn lookup_node(node_lookup: &HashMap<String, NodeIndex>, unique_id: &str) -> Option<NodeIndex> {
+    let canonical = || AttributeValue::parse_uuid(unique_id).map(|uuid| uuid.to_string());
+    node_lookup.get(unique_id).or_else(|| node_lookup.get(&canonical()?)).copied()
 }
 
 // Helper function to find or create a node
-fn find_or_create_node(
-    graph: &mut DiGraph<Node, Relation>,
+pub fn find_or_create_node(
+    graph: &mut JournaledGraph,
     node_type: &str,
     unique_id: &str,
     title: Option<String>,
-    node_lookup: &mut HashMap<String, petgraph::graph::NodeIndex>,  // Note: Changed to mutable reference
-) -> petgraph::graph::NodeIndex {
+    node_lookup: &mut HashMap<String, NodeIndex>,  // Note: Changed to mutable reference
+) -> NodeIndex {
     // Try to get the node index from the lookup table
-    if let Some(index) = node_lookup.get(unique_id) {
-        *index  // If found, return a cloned value of the reference
+    if let Some(index) = lookup_node(node_lookup, unique_id) {
+        index  // If found, return it
     } else {
         // If not found, create a new node and add it to the graph
         let new_node = Node::new(node_type, unique_id, None, title.as_deref());  // Ensure this matches your Node creation logic
@@ -0,0 +1,608 @@
+use pyo3::prelude::*;
+use pyo3::exceptions::PyValueError;
+use petgraph::graph::{DiGraph, EdgeIndex, NodeIndex};
+use petgraph::visit::EdgeRef;
+use petgraph::algo::tarjan_scc;
+use petgraph::unionfind::UnionFind;
+use petgraph::Direction;
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use crate::graph::filters::Filter;
+use crate::schema::{Node, Relation};
+use crate::data_types::AttributeValue;
+
+/// The edges of `node` in the given directions whose relation type is in `relationship_types`
+/// (all types when `None`) and that pass `edge_filter`, as (edge, neighbor) pairs
+pub fn matching_edges<'a>(
+    graph: &'a DiGraph<Node, Relation>,
+    node: NodeIndex,
+    relationship_types: Option<&'a [String]>,
+    directions: &'a [Direction],
+    edge_filter: Option<&'a Filter>,
+) -> impl Iterator<Item = (EdgeIndex, NodeIndex)> + 'a {
+    directions.iter().flat_map(move |&direction| {
+        graph.edges_directed(node, direction)
+            .filter(move |edge| relationship_types.is_none_or(|types| edge.weight().relation_type.is_in(types)))
+            .filter(move |edge| edge_filter.is_none_or(|filter| filter.matches_relation(edge.weight())))
+            .map(move |edge| {
+                let neighbor = if direction == Direction::Outgoing { edge.target() } else { edge.source() };
+                (edge.id(), neighbor)
+            })
+    })
+}
+
+/// Numeric edge weight from a relation attribute; `None` when missing or not numeric
+pub fn edge_weight(relation: &Relation, weight_property: &str) -> Option<f64> {
+    match relation.get_field(weight_property).as_deref() {
+        Some(AttributeValue::Int(v)) => Some(*v as f64),
+        Some(AttributeValue::Float(v)) => Some(*v),
+        _ => None,
+    }
+}
+
+// Min-heap entry for Dijkstra
+struct State {
+    cost: f64,
+    node: NodeIndex,
+}
+
+impl PartialEq for State {
+    fn eq(&self, other: &Self) -> bool {
+        self.cost == other.cost && self.node == other.node
+    }
+}
+
+impl Eq for State {}
+
+impl Ord for State {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.cost.total_cmp(&self.cost).then_with(|| other.node.cmp(&self.node))
+    }
+}
+
+impl PartialOrd for State {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// A path as its total cost, the visited node indices and the traversed edge indices
+pub struct Path {
+    pub cost: f64,
+    pub nodes: Vec<usize>,
+    pub edges: Vec<usize>,
+}
+
+/// Finds the cheapest path from `source` to `target` with Dijkstra's algorithm.
+///
+/// Without `weight_property` every edge costs 1, giving the path with the fewest hops; with it,
+/// edges lacking a numeric weight are not traversed and negative weights are rejected.
+pub fn shortest_path(
+    graph: &DiGraph<Node, Relation>,
+    source: NodeIndex,
+    target: NodeIndex,
+    relationship_types: Option<&[String]>,
+    directions: &[Direction],
+    weight_property: Option<&str>,
+    edge_filter: Option<&Filter>,
+) -> PyResult<Option<Path>> {
+    let mut costs: HashMap<NodeIndex, f64> = HashMap::from([(source, 0.0)]);
+    let mut previous: HashMap<NodeIndex, (NodeIndex, EdgeIndex)> = HashMap::new();
+    let mut heap = BinaryHeap::from([State { cost: 0.0, node: source }]);
+
+    while let Some(State { cost, node }) = heap.pop() {
+        if node == target {
+            break;
+        }
+        if costs.get(&node).is_some_and(|&best| cost > best) {
+            continue;
+        }
+        for (edge, neighbor) in matching_edges(graph, node, relationship_types, directions, edge_filter) {
+            let weight = match weight_property {
+                None => 1.0,
+                Some(property) => match edge_weight(&graph[edge], property) {
+                    Some(w) if w < 0.0 => return Err(PyValueError::new_err(format!(
+                        "Negative weight {} in '{}' on edge {}", w, property, edge.index()
+                    ))),
+                    Some(w) => w,
+                    None => continue,
+                },
+            };
+            let next = cost + weight;
+            if costs.get(&neighbor).is_none_or(|&best| next < best) {
+                costs.insert(neighbor, next);
+                previous.insert(neighbor, (node, edge));
+                heap.push(State { cost: next, node: neighbor });
+            }
+        }
+    }
+
+    let Some(&cost) = costs.get(&target) else { return Ok(None) };
+    let mut nodes = vec![target.index()];
+    let mut edges = Vec::new();
+    let mut current = target;
+    while let Some(&(prev, edge)) = previous.get(&current) {
+        nodes.push(prev.index());
+        edges.push(edge.index());
+        current = prev;
+    }
+    nodes.reverse();
+    edges.reverse();
+    Ok(Some(Path { cost, nodes, edges }))
+}
+
+/// Nodes reachable from any of `sources` within `max_depth` hops, in discovery order.
+///
+/// Sources only appear in the result when reached from a source through at least one edge.
+pub fn reachable_from(
+    graph: &DiGraph<Node, Relation>,
+    sources: &[usize],
+    relationship_types: Option<&[String]>,
+    directions: &[Direction],
+    max_depth: Option<usize>,
+    edge_filter: Option<&Filter>,
+) -> Vec<usize> {
+    let mut visited: HashSet<NodeIndex> = HashSet::new();
+    let mut frontier: Vec<NodeIndex> = sources.iter()
+        .map(|&index| NodeIndex::new(index))
+        .filter(|&node| graph.node_weight(node).is_some())
+        .collect();
+    let mut reached = Vec::new();
+
+    let mut depth = 0;
+    while !frontier.is_empty() && max_depth.is_none_or(|max| depth < max) {
+        depth += 1;
+        let mut next_frontier = Vec::new();
+        for node in frontier {
+            for (_, neighbor) in matching_edges(graph, node, relationship_types, directions, edge_filter) {
+                if visited.insert(neighbor) {
+                    reached.push(neighbor.index());
+                    next_frontier.push(neighbor);
+                }
+            }
+        }
+        frontier = next_frontier;
+    }
+    reached
+}
+
+// Sources whose reach one sweep of `reach_counts` tracks together, bounding the bits kept per node
+const REACH_BLOCK: usize = 4096;
+
+/// Number of nodes each of `sources` reaches within `max_depth` hops, counted as by `reachable_from`
+/// for that source alone, as (source, count) pairs in the order given.
+///
+/// One breadth-first sweep covers a block of up to 4096 sources: each node keeps the set of sources
+/// that have reached it, and a level passes on only the sources new to a node, so every source
+/// crosses each edge at most once.
+pub fn reach_counts(
+    graph: &DiGraph<Node, Relation>,
+    sources: &[usize],
+    relationship_types: Option<&[String]>,
+    directions: &[Direction],
+    max_depth: Option<usize>,
+    edge_filter: Option<&Filter>,
+) -> Vec<(usize, usize)> {
+    let mut distinct: Vec<usize> = sources.iter().copied().filter(|&index| graph.node_weight(NodeIndex::new(index)).is_some()).collect();
+    distinct.sort_unstable();
+    distinct.dedup();
+    let mut counts: HashMap<usize, usize> = HashMap::new();
+    for block in distinct.chunks(REACH_BLOCK) {
+        let words = block.len().div_ceil(64);
+        let bit = |position: usize| {
+            let mut bits = vec![0u64; words];
+            bits[position / 64] |= 1 << (position % 64);
+            bits
+        };
+        let mut reached: HashMap<NodeIndex, Vec<u64>> = HashMap::new();
+        let mut frontier: HashMap<NodeIndex, Vec<u64>> = block.iter().enumerate()
+            .map(|(position, &index)| (NodeIndex::new(index), bit(position)))
+            .collect();
+        let mut depth = 0;
+        while !frontier.is_empty() && max_depth.is_none_or(|max| depth < max) {
+            depth += 1;
+            let mut next_frontier: HashMap<NodeIndex, Vec<u64>> = HashMap::new();
+            for (node, bits) in frontier {
+                for (_, neighbor) in matching_edges(graph, node, relationship_types, directions, edge_filter) {
+                    let known = reached.entry(neighbor).or_insert_with(|| vec![0; words]);
+                    let new: Vec<u64> = bits.iter().zip(known.iter()).map(|(bits, known)| bits & !known).collect();
+                    if new.iter().all(|&word| word == 0) {
+                        continue;
+                    }
+                    let next = next_frontier.entry(neighbor).or_insert_with(|| vec![0; words]);
+                    for ((known, next), new) in known.iter_mut().zip(next.iter_mut()).zip(new) {
+                        *known |= new;
+                        *next |= new;
+                    }
+                }
+            }
+            frontier = next_frontier;
+        }
+        // Each node counts once for every source that reached it
+        let mut block_counts = vec![0; block.len()];
+        for bits in reached.values() {
+            for (word_index, &word) in bits.iter().enumerate() {
+                let mut word = word;
+                while word != 0 {
+                    block_counts[word_index * 64 + word.trailing_zeros() as usize] += 1;
+                    word &= word - 1;
+                }
+            }
+        }
+        counts.extend(block.iter().copied().zip(block_counts));
+    }
+    sources.iter().map(|&index| (index, counts.get(&index).copied().unwrap_or(0))).collect()
+}
+
+/// Labels nodes with the id of their weakly connected component, numbering components by
+/// decreasing size so component 0 is the largest.
+///
+/// With `indices`, components are computed over the subgraph induced by those nodes; otherwise
+/// over all nodes. Only edges of `relationship_types` (all types when `None`) connect nodes.
+pub fn connected_components(
+    graph: &DiGraph<Node, Relation>,
+    indices: Option<Vec<usize>>,
+    relationship_types: Option<&[String]>,
+) -> Vec<(usize, usize)> {
+    let nodes = scope_nodes(graph, indices);
+    let position: HashMap<NodeIndex, usize> = nodes.iter().enumerate().map(|(i, &node)| (node, i)).collect();
+
+    let mut components = UnionFind::new(nodes.len());
+    for (i, &node) in nodes.iter().enumerate() {
+        for (_, neighbor) in matching_edges(graph, node, relationship_types, &[Direction::Outgoing], None) {
+            if let Some(&j) = position.get(&neighbor) {
+                components.union(i, j);
+            }
+        }
+    }
+
+    // Order components by size, then by their first node, for stable ids
+    let roots = components.into_labeling();
+    let mut sizes: HashMap<usize, (usize, usize)> = HashMap::new();
+    for (i, &root) in roots.iter().enumerate() {
+        let entry = sizes.entry(root).or_insert((0, i));
+        entry.0 += 1;
+    }
+    let mut ordered: Vec<(usize, (usize, usize))> = sizes.into_iter().collect();
+    ordered.sort_by(|a, b| b.1.0.cmp(&a.1.0).then(a.1.1.cmp(&b.1.1)));
+    let ids: HashMap<usize, usize> = ordered.into_iter().enumerate().map(|(id, (root, _))| (root, id)).collect();
+
+    nodes.iter().zip(roots).map(|(node, root)| (node.index(), ids[&root])).collect()
+}
+
+/// Strongly connected components (Tarjan) of the subgraph induced by `indices` (all nodes when
+/// `None`) over edges of `relationship_types`, largest first, each listing its node indices in order
+pub fn strongly_connected_components(
+    graph: &DiGraph<Node, Relation>,
+    indices: Option<Vec<usize>>,
+    relationship_types: Option<&[String]>,
+) -> Vec<Vec<usize>> {
+    let nodes = scope_nodes(graph, indices);
+    let mut subgraph: DiGraph<NodeIndex, ()> = DiGraph::with_capacity(nodes.len(), 0);
+    let position: HashMap<NodeIndex, NodeIndex> = nodes.iter().map(|&node| (node, subgraph.add_node(node))).collect();
+    for &node in &nodes {
+        for (_, neighbor) in matching_edges(graph, node, relationship_types, &[Direction::Outgoing], None) {
+            if let Some(&target) = position.get(&neighbor) {
+                subgraph.add_edge(position[&node], target, ());
+            }
+        }
+    }
+
+    let mut components: Vec<Vec<usize>> = tarjan_scc(&subgraph).into_iter()
+        .map(|component| {
+            let mut members: Vec<usize> = component.into_iter().map(|node| subgraph[node].index()).collect();
+            members.sort_unstable();
+            members
+        })
+        .collect();
+    components.sort_by(|a, b| b.len().cmp(&a.len()).then(a[0].cmp(&b[0])));
+    components
+}
+
+// The standard nodes among `indices`, or all standard nodes when `None`
+fn scope_nodes(graph: &DiGraph<Node, Relation>, indices: Option<Vec<usize>>) -> Vec<NodeIndex> {
+    let nodes: Vec<NodeIndex> = match indices {
+        Some(indices) => indices.into_iter().map(NodeIndex::new).collect(),
+        None => graph.node_indices().collect(),
+    };
+    nodes.into_iter()
+        .filter(|&node| matches!(graph.node_weight(node), Some(Node::StandardNode { .. })))
+        .collect()
+}
+
+/// A path as (node, edge used to reach the node) records
+pub type PathRecords = Vec<(usize, Option<usize>)>;
+
+/// Enumerates simple paths of at most `max_depth` edges from any node in `sources` to any node in
+/// `targets`, stopping after `max_paths` paths.
+///
+/// Each path is a list of (node, edge) records, where the edge is the one used to reach the node
+/// (`None` for the starting node).
+#[allow(clippy::too_many_arguments)]
+pub fn paths_between(
+    graph: &DiGraph<Node, Relation>,
+    sources: &[usize],
+    targets: &[usize],
+    relationship_types: Option<&[String]>,
+    directions: &[Direction],
+    max_depth: usize,
+    max_paths: usize,
+    edge_filter: Option<&Filter>,
+) -> Vec<PathRecords> {
+    let targets: HashSet<NodeIndex> = targets.iter().map(|&index| NodeIndex::new(index)).collect();
+    let mut paths = Vec::new();
+
+    for &source in sources {
+        let source = NodeIndex::new(source);
+        if graph.node_weight(source).is_none() {
+            continue;
+        }
+        let mut path = vec![(source, None)];
+        let mut on_path = HashSet::from([source]);
+        extend_paths(graph, &targets, relationship_types, directions, edge_filter, max_depth, max_paths, &mut path, &mut on_path, &mut paths);
+        if paths.len() >= max_paths {
+            break;
+        }
+    }
+    paths
+}
+
+#[allow(clippy::too_many_arguments)]
+fn extend_paths(
+    graph: &DiGraph<Node, Relation>,
+    targets: &HashSet<NodeIndex>,
+    relationship_types: Option<&[String]>,
+    directions: &[Direction],
+    edge_filter: Option<&Filter>,
+    max_depth: usize,
+    max_paths: usize,
+    path: &mut Vec<(NodeIndex, Option<EdgeIndex>)>,
+    on_path: &mut HashSet<NodeIndex>,
+    paths: &mut Vec<PathRecords>,
+) {
+    if path.len() > max_depth {
+        return;
+    }
+    let (node, _) = path[path.len() - 1];
+    for (edge, neighbor) in matching_edges(graph, node, relationship_types, directions, edge_filter) {
+        if paths.len() >= max_paths {
+            return;
+        }
+        if !on_path.insert(neighbor) {
+            continue;
+        }
+        path.push((neighbor, Some(edge)));
+        if targets.contains(&neighbor) {
+            paths.push(path.iter().map(|(n, e)| (n.index(), e.map(|e| e.index()))).collect());
+        }
+        extend_paths(graph, targets, relationship_types, directions, edge_filter, max_depth, max_paths, path, on_path, paths);
+        path.pop();
+        on_path.remove(&neighbor);
+    }
+}
+
+/// Kruskal's minimum spanning forest over the subgraph induced by `indices` (all nodes when `None`),
+/// treating relations as undirected. Edges without a numeric `weight_property` are ignored.
+/// Returns the edge indices of the forest in order of increasing weight.
+pub fn minimum_spanning_tree(
+    graph: &DiGraph<Node, Relation>,
+    indices: Option<Vec<usize>>,
+    relationship_types: Option<&[String]>,
+    weight_property: &str,
+) -> Vec<usize> {
+    let in_scope: Option<HashSet<NodeIndex>> = indices.map(|indices| indices.into_iter().map(NodeIndex::new).collect());
+    let mut candidates: Vec<(f64, EdgeIndex)> = graph.edge_references()
+        .filter(|edge| in_scope.as_ref().is_none_or(|nodes| nodes.contains(&edge.source()) && nodes.contains(&edge.target())))
+        .filter(|edge| relationship_types.is_none_or(|types| edge.weight().relation_type.is_in(types)))
+        .filter_map(|edge| edge_weight(edge.weight(), weight_property).map(|weight| (weight, edge.id())))
+        .collect();
+    candidates.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+
+    let mut components = UnionFind::new(graph.node_count());
+    candidates.into_iter()
+        .filter(|&(_, edge)| {
+            let (source, target) = graph.edge_endpoints(edge).expect("Edge must exist");
+            components.union(source.index(), target.index())
+        })
+        .map(|(_, edge)| edge.index())
+        .collect()
+}
+
+/// Neighborhood similarity between nodes of `left` and `right`, as (left, right, score) pairs with
+/// `score >= threshold`, highest scores first.
+///
+/// `method` is "jaccard" (shared / combined neighbors) or "overlap" (shared / smaller neighborhood).
+/// Only pairs sharing at least one neighbor are scored, found through an index of `right` by
+/// neighbor, so the full cartesian product is never built. A node is never paired with itself.
+pub fn similarity(
+    graph: &DiGraph<Node, Relation>,
+    left: &[usize],
+    right: &[usize],
+    method: &str,
+    relationship_types: Option<&[String]>,
+    directions: &[Direction],
+    threshold: f64,
+) -> PyResult<Vec<(usize, usize, f64)>> {
+    if !matches!(method, "jaccard" | "overlap") {
+        return Err(PyValueError::new_err(format!(
+            "Unknown similarity method '{}', expected 'jaccard' or 'overlap'", method
+        )));
+    }
+    let neighborhood = |index: usize| -> HashSet<NodeIndex> {
+        let node = NodeIndex::new(index);
+        if graph.node_weight(node).is_none() {
+            return HashSet::new();
+        }
+        matching_edges(graph, node, relationship_types, directions, None).map(|(_, neighbor)| neighbor).collect()
+    };
+
+    let mut right_sizes: HashMap<usize, usize> = HashMap::new();
+    let mut by_neighbor: HashMap<NodeIndex, Vec<usize>> = HashMap::new();
+    for &index in right {
+        if right_sizes.contains_key(&index) {
+            continue;
+        }
+        let neighbors = neighborhood(index);
+        right_sizes.insert(index, neighbors.len());
+        for neighbor in neighbors {
+            by_neighbor.entry(neighbor).or_default().push(index);
+        }
+    }
+
+    let mut pairs = Vec::new();
+    let mut seen = HashSet::new();
+    for &a in left {
+        if !seen.insert(a) {
+            continue;
+        }
+        let neighbors = neighborhood(a);
+        let mut shared: HashMap<usize, usize> = HashMap::new();
+        for neighbor in &neighbors {
+            for &b in by_neighbor.get(neighbor).into_iter().flatten() {
+                if b != a {
+                    *shared.entry(b).or_insert(0) += 1;
+                }
+            }
+        }
+        for (b, count) in shared {
+            let denominator = match method {
+                "jaccard" => neighbors.len() + right_sizes[&b] - count,
+                _ => neighbors.len().min(right_sizes[&b]),
+            };
+            let score = count as f64 / denominator as f64;
+            if score >= threshold {
+                pairs.push((a, b, score));
+            }
+        }
+    }
+    pairs.sort_by(|x, y| y.2.total_cmp(&x.2).then(x.0.cmp(&y.0)).then(x.1.cmp(&y.1)));
+    Ok(pairs)
+}
+
+/// Result of a maximum flow computation
+pub struct MaxFlow {
+    pub value: f64,
+    pub flows: Vec<(usize, f64)>,  // (edge, flow) for edges carrying flow
+    pub min_cut: Vec<usize>,  // Saturated edges separating the sources from the sinks
+}
+
+/// Maximum flow from `sources` to `sinks` (Edmonds-Karp), using a numeric edge property as capacity.
+///
+/// Edges are directed and limited to those between nodes of `indices` (all nodes when `None`)
+/// with a relation type in `relationship_types`; edges without a numeric capacity are ignored.
+pub fn max_flow(
+    graph: &DiGraph<Node, Relation>,
+    sources: &[usize],
+    sinks: &[usize],
+    capacity_property: &str,
+    indices: Option<Vec<usize>>,
+    relationship_types: Option<&[String]>,
+) -> PyResult<MaxFlow> {
+    const EPSILON: f64 = 1e-12;
+    if let Some(node) = sources.iter().find(|node| sinks.contains(node)) {
+        return Err(PyValueError::new_err(format!("Node {} cannot be both a source and a sink", node)));
+    }
+    let in_scope: Option<HashSet<NodeIndex>> = indices.map(|indices| indices.into_iter().map(NodeIndex::new).collect());
+
+    // Residual network: arcs come in (forward, reverse) pairs; positions 0 and 1 are the super source and sink
+    let mut position: HashMap<NodeIndex, usize> = HashMap::new();
+    let mut adjacency: Vec<Vec<usize>> = vec![Vec::new(), Vec::new()];
+    let mut heads: Vec<usize> = Vec::new();
+    let mut residual: Vec<f64> = Vec::new();
+    let mut arc_edges: Vec<Option<EdgeIndex>> = Vec::new();
+    let mut node_position = |node: NodeIndex, adjacency: &mut Vec<Vec<usize>>| *position.entry(node).or_insert_with(|| {
+        adjacency.push(Vec::new());
+        adjacency.len() - 1
+    });
+    let mut add_arc = |from: usize, to: usize, capacity: f64, edge: Option<EdgeIndex>, adjacency: &mut Vec<Vec<usize>>| {
+        for (tail, head, capacity) in [(from, to, capacity), (to, from, 0.0)] {
+            adjacency[tail].push(heads.len());
+            heads.push(head);
+            residual.push(capacity);
+            arc_edges.push(edge);
+        }
+    };
+
+    for edge in graph.edge_references() {
+        let in_subgraph = in_scope.as_ref().is_none_or(|nodes| nodes.contains(&edge.source()) && nodes.contains(&edge.target()));
+        if !in_subgraph || relationship_types.is_some_and(|types| !edge.weight().relation_type.is_in(types)) {
+            continue;
+        }
+        let Some(capacity) = edge_weight(edge.weight(), capacity_property) else { continue };
+        if capacity < 0.0 {
+            return Err(PyValueError::new_err(format!(
+                "Negative capacity {} in '{}' on edge {}", capacity, capacity_property, edge.id().index()
+            )));
+        }
+        let from = node_position(edge.source(), &mut adjacency);
+        let to = node_position(edge.target(), &mut adjacency);
+        add_arc(from, to, capacity, Some(edge.id()), &mut adjacency);
+    }
+    for &source in sources {
+        let node = node_position(NodeIndex::new(source), &mut adjacency);
+        add_arc(0, node, f64::INFINITY, None, &mut adjacency);
+    }
+    for &sink in sinks {
+        let node = node_position(NodeIndex::new(sink), &mut adjacency);
+        add_arc(node, 1, f64::INFINITY, None, &mut adjacency);
+    }
+
+    // Breadth-first search for the shortest augmenting path, returning the arc used to reach each position
+    let find_path = |residual: &[f64]| -> Vec<Option<usize>> {
+        let mut via: Vec<Option<usize>> = vec![None; adjacency.len()];
+        let mut visited = vec![false; adjacency.len()];
+        let mut queue = std::collections::VecDeque::from([0]);
+        visited[0] = true;
+        while let Some(tail) = queue.pop_front() {
+            for &arc in &adjacency[tail] {
+                let head = heads[arc];
+                if !visited[head] && residual[arc] > EPSILON {
+                    visited[head] = true;
+                    via[head] = Some(arc);
+                    queue.push_back(head);
+                }
+            }
+        }
+        via
+    };
+
+    let mut value = 0.0;
+    loop {
+        let via = find_path(&residual);
+        if via[1].is_none() {
+            break;
+        }
+        let mut path = Vec::new();
+        let mut current = 1;
+        while let Some(arc) = via[current] {
+            path.push(arc);
+            current = heads[arc ^ 1];
+        }
+        let bottleneck = path.iter().map(|&arc| residual[arc]).fold(f64::INFINITY, f64::min);
+        if bottleneck.is_infinite() {
+            return Err(PyValueError::new_err("Unbounded flow: a path from the sources to the sinks has infinite capacity"));
+        }
+        for arc in path {
+            residual[arc] -= bottleneck;
+            residual[arc ^ 1] += bottleneck;
+        }
+        value += bottleneck;
+    }
+
+    // Edges leaving the part of the residual network still reachable from the sources form the minimum cut
+    let via = find_path(&residual);
+    let reachable = |position: usize| position == 0 || via[position].is_some();
+    let mut flows = Vec::new();
+    let mut min_cut = Vec::new();
+    for arc in (0..heads.len()).step_by(2) {
+        let Some(edge) = arc_edges[arc] else { continue };
+        let flow = residual[arc ^ 1];
+        if flow > EPSILON {
+            flows.push((edge.index(), flow));
+        }
+        if reachable(heads[arc ^ 1]) && !reachable(heads[arc]) {
+            min_cut.push(edge.index());
+        }
+    }
+    Ok(MaxFlow { value, flows, min_cut })
+}
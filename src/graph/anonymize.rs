@@ -0,0 +1,112 @@
+use pyo3::prelude::*;
+use pyo3::exceptions::PyValueError;
+use petgraph::graph::{DiGraph, NodeIndex};
+use rand::prelude::*;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use crate::graph::sampling::rng_from_seed;
+use crate::schema::{Node, Relation, Symbol};
+use crate::data_types::AttributeValue;
+
+/// Returns a structurally identical copy of the graph with identifying data removed.
+///
+/// # Arguments
+///
+/// * `graph` - The graph to anonymize (left untouched)
+/// * `hash_ids` - Replace unique IDs with salted hashes
+/// * `scramble` - Properties (including "title") whose values are replaced: strings by consistent
+///   pseudonyms, numbers by a random permutation of the values within each node type
+/// * `noise_rate` - Relative noise applied to all other numeric values, e.g. 0.05 for ±5%
+/// * `seed` - Optional seed making the output reproducible
+pub fn anonymize(
+    graph: &DiGraph<Node, Relation>,
+    hash_ids: bool,
+    scramble: &[String],
+    noise_rate: Option<f64>,
+    seed: Option<u64>,
+) -> PyResult<DiGraph<Node, Relation>> {
+    if noise_rate.is_some_and(|rate| !(0.0..1.0).contains(&rate)) {
+        return Err(PyValueError::new_err("Noise rate must be in the range [0, 1)"));
+    }
+    let mut rng = rng_from_seed(seed);
+    let salt: u64 = rng.gen();
+    let mut anonymized = graph.clone();
+    let mut pseudonyms: HashMap<String, HashMap<String, String>> = HashMap::new();
+
+    for node in anonymized.node_weights_mut() {
+        let Node::StandardNode { node_type, unique_id, title, attributes, .. } = node else { continue };
+
+        if hash_ids {
+            let mut hasher = DefaultHasher::new();
+            (salt, &*node_type, &*unique_id).hash(&mut hasher);
+            *unique_id = format!("{:016x}", hasher.finish());
+        }
+
+        if let Some(t) = title.as_mut() {
+            if scramble.iter().any(|p| p == "title") {
+                *t = pseudonym(&mut pseudonyms, "title", t);
+            }
+        }
+
+        // Visit attributes in a stable order so a seed reproduces the same noise
+        let mut keys: Vec<String> = attributes.keys().cloned().collect();
+        keys.sort();
+        for key in &keys {
+            let Some(value) = attributes.get_mut(key) else { continue };
+            match value {
+                AttributeValue::String(v) if scramble.contains(key) => *v = pseudonym(&mut pseudonyms, key, v),
+                AttributeValue::Int(v) if !scramble.contains(key) => {
+                    if let Some(rate) = noise_rate {
+                        *v = (*v as f64 * (1.0 + rng.gen_range(-rate..=rate))).round() as i64;
+                    }
+                },
+                AttributeValue::Float(v) if !scramble.contains(key) => {
+                    if let Some(rate) = noise_rate {
+                        *v *= 1.0 + rng.gen_range(-rate..=rate);
+                    }
+                },
+                _ => (),
+            }
+        }
+    }
+
+    // Shuffle scrambled numeric values among the nodes of each type
+    for property in scramble {
+        let mut by_type: HashMap<Symbol, Vec<NodeIndex>> = HashMap::new();
+        for index in anonymized.node_indices() {
+            if let Node::StandardNode { node_type, attributes, .. } = &anonymized[index] {
+                if matches!(attributes.get(property), Some(AttributeValue::Int(_) | AttributeValue::Float(_) | AttributeValue::DateTime(_) | AttributeValue::Date(_) | AttributeValue::Duration(_))) {
+                    by_type.entry(node_type.clone()).or_default().push(index);
+                }
+            }
+        }
+        let mut node_types: Vec<_> = by_type.into_iter().collect();
+        node_types.sort_by(|a, b| a.0.cmp(&b.0));
+        for (_, indices) in node_types {
+            let mut values: Vec<AttributeValue> = indices.iter()
+                .filter_map(|&i| match &anonymized[i] {
+                    Node::StandardNode { attributes, .. } => attributes.get(property).cloned(),
+                    Node::DataTypeNode { .. } => None,
+                })
+                .collect();
+            values.shuffle(&mut rng);
+            for (index, value) in indices.into_iter().zip(values) {
+                if let Node::StandardNode { attributes, .. } = &mut anonymized[index] {
+                    attributes.insert(property.clone(), value);
+                }
+            }
+        }
+    }
+
+    Ok(anonymized)
+}
+
+// The same source value always maps to the same pseudonym, so equality and cardinality are preserved
+fn pseudonym(pseudonyms: &mut HashMap<String, HashMap<String, String>>, property: &str, value: &str) -> String {
+    let values = pseudonyms.entry(property.to_string()).or_default();
+    let next = values.len() + 1;
+    values.entry(value.to_string())
+        .or_insert_with(|| format!("{}_{}", property, next))
+        .clone()
+}
@@ -0,0 +1,29 @@
+use pyo3::prelude::*;
+use crate::graph::KnowledgeGraph;
+use crate::data_types::AttributeValue;
+
+/// Returned by `as_of`, which has already set the date: used as a context manager it puts back the
+/// date that was set before when the block ends, so nested blocks restore the outer date
+#[pyclass]
+pub struct AsOf {
+    graph: Py<KnowledgeGraph>,
+    previous: Option<AttributeValue>,
+}
+
+impl AsOf {
+    pub fn new(graph: Py<KnowledgeGraph>, previous: Option<AttributeValue>) -> Self {
+        AsOf { graph, previous }
+    }
+}
+
+#[pymethods]
+impl AsOf {
+    fn __enter__(&self, py: Python) -> Py<KnowledgeGraph> {
+        self.graph.clone_ref(py)
+    }
+
+    fn __exit__(&mut self, py: Python, _exc_type: &PyAny, _exc_value: &PyAny, _traceback: &PyAny) -> bool {
+        self.graph.borrow_mut(py).as_of = self.previous.take();
+        false
+    }
+}
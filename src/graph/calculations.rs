@@ -0,0 +1,336 @@
+use pyo3::prelude::*;
+use pyo3::exceptions::PyValueError;
+use petgraph::graph::{DiGraph, NodeIndex};
+use petgraph::Direction;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use crate::graph::journal::JournaledGraph;
+use crate::graph::algorithms::reachable_from;
+use crate::graph::equation_parser::{Evaluator, Expr, Parser, Value};
+use crate::graph::filters::Filter;
+use crate::graph::navigate_graph::related_nodes;
+use crate::graph::update_nodes::update_node_properties;
+use crate::schema::{Node, Relation};
+use crate::data_types::{AttributeValue, Decimal};
+
+pub fn parse_expression(expression: &str) -> PyResult<Expr> {
+    Parser::new(expression)
+        .and_then(|mut parser| parser.parse())
+        .map_err(|e| PyValueError::new_err(format!("Failed to parse '{}': {}", expression, e)))
+}
+
+/// Evaluates an expression for every node in `indices`.
+///
+/// Variables resolve to node attributes (or "title"/"unique_id"); `aliases` maps short names
+/// used in the expression to the attribute names they stand for, e.g. {"ntg": "net_to_gross"}.
+pub fn evaluate_nodes(
+    graph: &DiGraph<Node, Relation>,
+    indices: Vec<usize>,
+    expression: &str,
+    aliases: Option<&HashMap<String, String>>,
+) -> PyResult<Vec<(usize, Value)>> {
+    let expr = parse_expression(expression)?;
+
+    let mut results = Vec::with_capacity(indices.len());
+    for index in indices {
+        let Some(node) = graph.node_weight(NodeIndex::new(index)) else { continue };
+        let evaluator = Evaluator::new(|field: &str| {
+            let field = aliases.and_then(|a| a.get(field)).map_or(field, String::as_str);
+            node.get_field(field)
+        });
+        let result = evaluator.evaluate(&expr)
+            .map_err(|e| PyValueError::new_err(format!("Failed to evaluate '{}' for node {}: {}", expression, index, e)))?;
+        results.push((index, result));
+    }
+    Ok(results)
+}
+
+/// Evaluates an expression per node and either returns the values or stores them under `store_as`
+pub fn calculate(
+    graph: &mut JournaledGraph,
+    py: Python,
+    indices: Vec<usize>,
+    expression: &str,
+    aliases: Option<HashMap<String, String>>,
+    store_as: Option<&str>,
+    overwrite: Option<String>,
+) -> PyResult<PyObject> {
+    let results = evaluate_nodes(graph, indices, expression, aliases.as_ref())?;
+
+    let Some(property) = store_as else {
+        let values: Vec<PyObject> = results.iter().map(|(_, value)| value_to_py(py, value)).collect();
+        return Ok(values.into_py(py));
+    };
+    Ok(store_results(graph, py, property, results, overwrite)?.into_py(py))
+}
+
+/// Stores per-node results as a calculated property, returning the number of nodes updated.
+/// Null results leave the node without the property.
+pub fn store_results(
+    graph: &mut JournaledGraph,
+    py: Python,
+    property: &str,
+    results: Vec<(usize, Value)>,
+    overwrite: Option<String>,
+) -> PyResult<usize> {
+    let mut values = HashMap::new();
+    for (index, value) in results {
+        if let Some(value) = value.into_attribute() {
+            values.insert(index, value);
+        }
+    }
+    update_node_properties(graph, py, property, values, overwrite)
+}
+
+/// Keeps the nodes in `indices` whose children satisfy an aggregate expression, e.g.
+/// `count(*) > 5 and avg(depth) > 2000`.
+///
+/// `count`, `sum`, `avg`/`mean` and single-argument `min`/`max` aggregate their argument over the
+/// nodes related through `relationship_types` (and `edge_filter`); other variables resolve against
+/// the parent node.
+pub fn filter_by_children(
+    graph: &DiGraph<Node, Relation>,
+    indices: Vec<usize>,
+    relationship_types: &[String],
+    directions: &[Direction],
+    expression: &str,
+    aliases: Option<&HashMap<String, String>>,
+    edge_filter: Option<&Filter>,
+) -> PyResult<Vec<usize>> {
+    let results = aggregate_children(graph, indices, relationship_types, directions, expression, aliases, None, true, edge_filter)?;
+    Ok(results.into_iter().filter(|(_, value)| value.is_true()).map(|(index, _)| index).collect())
+}
+
+/// Evaluates an aggregate expression over the children of each parent in `indices`, returning
+/// (parent, value) pairs.
+///
+/// Children can be narrowed with `child_filter`; parents left without matching children are
+/// dropped unless `keep_empty` is set, in which case they are kept with `count` 0 and null
+/// for the other aggregates.
+#[allow(clippy::too_many_arguments)]
+pub fn aggregate_children(
+    graph: &DiGraph<Node, Relation>,
+    indices: Vec<usize>,
+    relationship_types: &[String],
+    directions: &[Direction],
+    expression: &str,
+    aliases: Option<&HashMap<String, String>>,
+    child_filter: Option<&Filter>,
+    keep_empty: bool,
+    edge_filter: Option<&Filter>,
+) -> PyResult<Vec<(usize, Value)>> {
+    aggregate_related(graph, indices, expression, aliases, child_filter, keep_empty, |node| {
+        related_nodes(graph, node, relationship_types, directions, edge_filter, None, None)
+    })
+}
+
+/// Like `aggregate_children`, but aggregating over every node within `hops` relations of each
+/// parent (excluding the parent itself) rather than over its direct children
+#[allow(clippy::too_many_arguments)]
+pub fn aggregate_neighborhood(
+    graph: &DiGraph<Node, Relation>,
+    indices: Vec<usize>,
+    hops: usize,
+    relationship_types: Option<&[String]>,
+    directions: &[Direction],
+    expression: &str,
+    aliases: Option<&HashMap<String, String>>,
+    child_filter: Option<&Filter>,
+    edge_filter: Option<&Filter>,
+) -> PyResult<Vec<(usize, Value)>> {
+    aggregate_related(graph, indices, expression, aliases, child_filter, true, |node| {
+        reachable_from(graph, &[node.index()], relationship_types, directions, Some(hops), edge_filter)
+            .into_iter()
+            .filter(|&index| index != node.index())
+            .collect()
+    })
+}
+
+// Evaluates an aggregate expression per parent over the nodes returned by `related`
+fn aggregate_related<F>(
+    graph: &DiGraph<Node, Relation>,
+    indices: Vec<usize>,
+    expression: &str,
+    aliases: Option<&HashMap<String, String>>,
+    child_filter: Option<&Filter>,
+    keep_empty: bool,
+    related: F,
+) -> PyResult<Vec<(usize, Value)>>
+where
+    F: Fn(NodeIndex) -> Vec<usize>,
+{
+    let mut aggregates = Vec::new();
+    let expr = extract_aggregates(parse_expression(expression)?, &mut aggregates);
+    let resolve = |field: &str| aliases.and_then(|a| a.get(field)).map_or(field, String::as_str).to_string();
+    let error = |index: usize, e: String| {
+        PyValueError::new_err(format!("Failed to evaluate '{}' for node {}: {}", expression, index, e))
+    };
+
+    let mut results = Vec::new();
+    for index in indices {
+        let Some(parent) = graph.node_weight(NodeIndex::new(index)) else { continue };
+        let children: Vec<&Node> = related(NodeIndex::new(index))
+            .into_iter()
+            .map(|child| &graph[NodeIndex::new(child)])
+            .filter(|child| child_filter.is_none_or(|filter| filter.matches_node(child)))
+            .collect();
+        if children.is_empty() && !keep_empty {
+            continue;
+        }
+
+        let mut values = HashMap::new();
+        for (i, (function, argument)) in aggregates.iter().enumerate() {
+            let value = aggregate(function, argument.as_ref(), &children, &resolve).map_err(|e| error(index, e))?;
+            if let Some(value) = value.into_attribute() {
+                values.insert(aggregate_placeholder(i), value);
+            }
+        }
+
+        let evaluator = Evaluator::new(|field: &str| match values.get(field) {
+            Some(value) => Some(Cow::Borrowed(value)),
+            None => parent.get_field(&resolve(field)),
+        });
+        results.push((index, evaluator.evaluate(&expr).map_err(|e| error(index, e))?));
+    }
+    Ok(results)
+}
+
+// Aggregate results are referenced by names the parser can never produce
+pub fn aggregate_placeholder(i: usize) -> String {
+    format!("#aggregate{}", i)
+}
+
+/// Replaces aggregate calls by placeholder variables, collecting (function, argument) pairs; `None` stands for `*`
+pub fn extract_aggregates(expr: Expr, aggregates: &mut Vec<(String, Option<Expr>)>) -> Expr {
+    match expr {
+        Expr::Function(name, mut args) if is_aggregate(&name, args.len()) => {
+            let argument = args.pop().filter(|arg| *arg != Expr::Variable("*".to_string()));
+            aggregates.push((name, argument));
+            Expr::Variable(aggregate_placeholder(aggregates.len() - 1))
+        },
+        Expr::Function(name, args) => {
+            Expr::Function(name, args.into_iter().map(|arg| extract_aggregates(arg, aggregates)).collect())
+        },
+        Expr::Unary(op, operand) => Expr::Unary(op, Box::new(extract_aggregates(*operand, aggregates))),
+        Expr::Binary(op, lhs, rhs) => Expr::Binary(
+            op, Box::new(extract_aggregates(*lhs, aggregates)), Box::new(extract_aggregates(*rhs, aggregates)),
+        ),
+        other => other,
+    }
+}
+
+fn is_aggregate(name: &str, arity: usize) -> bool {
+    arity == 1 && matches!(name, "count" | "sum" | "avg" | "mean" | "min" | "max")
+}
+
+fn aggregate<F>(function: &str, argument: Option<&Expr>, children: &[&Node], resolve: &F) -> Result<Value, String>
+where
+    F: Fn(&str) -> String,
+{
+    let Some(argument) = argument else {
+        return match function {
+            "count" => Ok(Value::Number(children.len() as f64)),
+            _ => Err(format!("{}(*) is not supported, only count(*)", function)),
+        };
+    };
+
+    let values = children.iter()
+        .map(|child| Evaluator::new(|field: &str| child.get_field(&resolve(field))).evaluate(argument))
+        .collect::<Result<Vec<_>, _>>()?;
+    aggregate_values(function, values)
+}
+
+/// Applies an aggregate function ("count", "sum", "avg"/"mean", "min" or "max") to evaluated values.
+/// Nulls are skipped; `count` counts the remaining values and the others require numbers.
+/// Decimals aggregate exactly and durations stay durations unless mixed with other numbers.
+pub fn aggregate_values(function: &str, values: Vec<Value>) -> Result<Value, String> {
+    let mut numbers = Vec::new();
+    let mut decimals = Vec::new();
+    let mut durations = Vec::new();
+    let mut count = 0;
+    for value in values {
+        match value {
+            Value::Null => (),
+            Value::Number(v) => {
+                count += 1;
+                numbers.push(v);
+            },
+            Value::Decimal(v) => {
+                count += 1;
+                decimals.push(v);
+            },
+            Value::Duration(v) => {
+                count += 1;
+                durations.push(v);
+            },
+            // Flags count as 1 and 0, so sum() counts the true ones and avg() gives their share
+            Value::Bool(v) => {
+                count += 1;
+                numbers.push(v as u8 as f64);
+            },
+            _ if function == "count" => count += 1,
+            other => return Err(format!("{}() expects numbers, got {}", function, other)),
+        }
+    }
+
+    if function != "count" && numbers.is_empty() && durations.is_empty() && !decimals.is_empty() {
+        if let Some(result) = aggregate_decimals(function, &decimals) {
+            return Ok(result);
+        }
+    }
+    if function != "count" && numbers.is_empty() && decimals.is_empty() && !durations.is_empty() {
+        if let Some(result) = aggregate_durations(function, &durations) {
+            return Ok(result);
+        }
+    }
+    numbers.extend(decimals.iter().map(|v| v.to_f64()));
+    numbers.extend(durations.iter().map(|v| *v as f64));
+
+    Ok(match function {
+        "count" => Value::Number(count as f64),
+        "sum" => Value::Number(numbers.iter().sum()),
+        "avg" | "mean" if numbers.is_empty() => Value::Null,
+        "avg" | "mean" => Value::Number(numbers.iter().sum::<f64>() / numbers.len() as f64),
+        "min" => numbers.into_iter().reduce(f64::min).map_or(Value::Null, Value::Number),
+        _ => numbers.into_iter().reduce(f64::max).map_or(Value::Null, Value::Number),
+    })
+}
+
+// None when the sum overflows the fixed-point range, leaving the aggregate to floats
+fn aggregate_decimals(function: &str, decimals: &[Decimal]) -> Option<Value> {
+    let sum = || decimals.iter().try_fold(Decimal::new(0, 0), |sum, v| sum.checked_add(*v));
+    let value = match function {
+        "sum" => sum()?,
+        "avg" | "mean" => sum()?.checked_div(Decimal::new(decimals.len() as i128, 0))?,
+        "min" => decimals.iter().copied().reduce(|a, b| if b < a { b } else { a })?,
+        _ => decimals.iter().copied().reduce(|a, b| if b > a { b } else { a })?,
+    };
+    Some(Value::Decimal(value))
+}
+
+// None when the sum overflows, leaving the aggregate to floats; averages round to whole seconds
+fn aggregate_durations(function: &str, durations: &[i64]) -> Option<Value> {
+    let sum = || durations.iter().try_fold(0i64, |sum, v| sum.checked_add(*v));
+    let value = match function {
+        "sum" => sum()?,
+        "avg" | "mean" => (sum()? as f64 / durations.len() as f64).round() as i64,
+        "min" => *durations.iter().min()?,
+        _ => *durations.iter().max()?,
+    };
+    Some(Value::Duration(value))
+}
+
+pub fn value_to_py(py: Python, value: &Value) -> PyObject {
+    match value {
+        Value::Number(v) => v.into_py(py),
+        Value::Str(v) => v.into_py(py),
+        Value::Bool(v) => v.into_py(py),
+        Value::Null => py.None(),
+        Value::List(values) => values.iter().map(|value| value_to_py(py, value)).collect::<Vec<_>>().into_py(py),
+        Value::Map(entries) => entries.iter().map(|(key, value)| (key, value_to_py(py, value))).collect::<HashMap<_, _>>().into_py(py),
+        // As decimal.Decimal, or a float should the decimal module be unavailable
+        Value::Decimal(v) => AttributeValue::Decimal(*v).to_python_object(py, None).unwrap_or_else(|_| v.to_f64().into_py(py)),
+        // As datetime.timedelta, or seconds beyond its range
+        Value::Duration(v) => AttributeValue::Duration(*v).to_python_object(py, None).unwrap_or_else(|_| v.into_py(py)),
+    }
+}
@@ -0,0 +1,101 @@
+use petgraph::graph::{DiGraph, NodeIndex};
+use std::collections::HashMap;
+use crate::graph::journal::JournaledGraph;
+use crate::graph::get_schema::find_schema_node;
+use crate::schema::{Node, Relation};
+use crate::data_types::{AttributeValue, CategoryDictionary};
+
+/// Stores the values of a node type's Category columns on the given nodes as codes into the column
+/// dictionaries kept on its schema node. Values arrive as text from ingestion, or as labels from a
+/// graph just read.
+pub fn encode_nodes(graph: &mut JournaledGraph, node_type: &str, indices: &[usize]) {
+    let Some(schema_index) = find_schema_node(graph, "Node", node_type) else { return };
+    let Node::DataTypeNode { attributes, categories, .. } = &mut graph[schema_index] else { return };
+    let mut dictionaries: Vec<(String, CategoryDictionary)> = attributes.iter()
+        .filter(|(_, data_type)| data_type.as_str() == "Category")
+        .map(|(column, _)| (column.clone(), categories.remove(column).unwrap_or_default()))
+        .collect();
+    if dictionaries.is_empty() {
+        return;
+    }
+    let sizes: Vec<usize> = dictionaries.iter().map(|(_, dictionary)| dictionary.len()).collect();
+
+    // New labels first, so the label list is copied at most once for the whole batch
+    for &index in indices {
+        let Some(Node::StandardNode { attributes, .. }) = graph.node_weight(NodeIndex::new(index)) else { continue };
+        for (column, dictionary) in dictionaries.iter_mut() {
+            match attributes.get(column) {
+                Some(AttributeValue::String(label)) => { dictionary.add(label); },
+                Some(AttributeValue::Category(category)) => { dictionary.add(category.label()); },
+                _ => (),
+            }
+        }
+    }
+    // A dictionary that gained labels has a new label list; all nodes of the type move to it, so
+    // the old list is freed
+    let grown = dictionaries.iter().zip(&sizes).any(|((_, dictionary), &size)| dictionary.len() > size);
+    let targets = if grown { nodes_of_type(graph, node_type) } else { indices.to_vec() };
+    for index in targets {
+        let Some(Node::StandardNode { attributes, .. }) = graph.node_weight_mut(NodeIndex::new(index)) else { continue };
+        for (column, dictionary) in dictionaries.iter_mut() {
+            if let Some(value) = attributes.get_mut(column) {
+                dictionary.encode_value(value);
+            }
+        }
+    }
+    if let Node::DataTypeNode { categories, .. } = &mut graph[schema_index] {
+        categories.extend(dictionaries);
+    }
+}
+
+/// Encodes the Category columns of every node of a type
+pub fn encode_type(graph: &mut JournaledGraph, node_type: &str) {
+    let indices = nodes_of_type(graph, node_type);
+    encode_nodes(graph, node_type, &indices);
+}
+
+/// Encodes the Category columns of the given nodes of any type, as after an import mixing node
+/// types
+pub fn encode_each(graph: &mut JournaledGraph, indices: &[usize]) {
+    let mut by_type: HashMap<String, Vec<usize>> = HashMap::new();
+    for &index in indices {
+        if let Some(Node::StandardNode { node_type, .. }) = graph.node_weight(NodeIndex::new(index)) {
+            by_type.entry(node_type.to_string()).or_default().push(index);
+        }
+    }
+    for (node_type, indices) in by_type {
+        encode_nodes(graph, &node_type, &indices);
+    }
+}
+
+/// Encodes the Category columns of every node type, as needed after reading a graph, where values
+/// come back as labels rather than codes
+pub fn encode_all(graph: &mut JournaledGraph) {
+    let mut by_type: HashMap<String, Vec<usize>> = graph.node_weights()
+        .filter_map(|node| match node {
+            Node::DataTypeNode { data_type, name, attributes, .. }
+                if data_type == "Node" && attributes.values().any(|t| t == "Category") => Some((name.clone(), Vec::new())),
+            _ => None,
+        })
+        .collect();
+    if by_type.is_empty() {
+        return;
+    }
+    for index in graph.node_indices() {
+        if let Node::StandardNode { node_type, .. } = &graph[index] {
+            if let Some(indices) = by_type.get_mut(node_type.as_str()) {
+                indices.push(index.index());
+            }
+        }
+    }
+    for (node_type, indices) in by_type {
+        encode_nodes(graph, &node_type, &indices);
+    }
+}
+
+fn nodes_of_type(graph: &DiGraph<Node, Relation>, node_type: &str) -> Vec<usize> {
+    graph.node_indices()
+        .filter(|&index| matches!(&graph[index], Node::StandardNode { node_type: t, .. } if t == node_type))
+        .map(NodeIndex::index)
+        .collect()
+}
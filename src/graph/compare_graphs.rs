@@ -0,0 +1,234 @@
+use petgraph::graph::DiGraph;
+use petgraph::visit::EdgeRef;
+use std::collections::{BTreeMap, HashMap};
+use pyo3::prelude::*;
+use pyo3::exceptions::PyAssertionError;
+use pyo3::types::PyDict;
+use crate::schema::{Node, Relation};
+use crate::data_types::AttributeValue;
+
+pub type NodeKey = (String, String);  // (node_type, unique_id)
+type EdgeKey = (NodeKey, String, NodeKey);  // (source, relation_type, target)
+
+/// Indexes the standard nodes of a graph by (node_type, unique_id)
+pub fn nodes_by_key(graph: &DiGraph<Node, Relation>) -> BTreeMap<NodeKey, &Node> {
+    graph.node_weights().filter_map(|node| match node {
+        Node::StandardNode { node_type, unique_id, .. } => Some(((node_type.to_string(), unique_id.clone()), node)),
+        Node::DataTypeNode { .. } => None,
+    }).collect()
+}
+
+/// Groups the relations of a graph by (source key, relation_type, target key)
+pub fn edges_by_key(graph: &DiGraph<Node, Relation>) -> BTreeMap<EdgeKey, Vec<&Relation>> {
+    let mut edges: BTreeMap<EdgeKey, Vec<&Relation>> = BTreeMap::new();
+    for edge in graph.edge_references() {
+        let key_of = |node: &Node| match node {
+            Node::StandardNode { node_type, unique_id, .. } => Some((node_type.to_string(), unique_id.clone())),
+            Node::DataTypeNode { .. } => None,
+        };
+        if let (Some(source), Some(target)) = (key_of(&graph[edge.source()]), key_of(&graph[edge.target()])) {
+            edges.entry((source, edge.weight().relation_type.to_string(), target)).or_default().push(edge.weight());
+        }
+    }
+    edges
+}
+
+/// Compares two attribute values, allowing `tolerance` between floats
+pub fn values_equal(a: &AttributeValue, b: &AttributeValue, tolerance: f64) -> bool {
+    match (a, b) {
+        (AttributeValue::Float(x), AttributeValue::Float(y)) => x == y || (x - y).abs() <= tolerance,
+        _ => a == b,
+    }
+}
+
+/// A property whose value differs between two attribute maps, as (name, left value, right value)
+pub type AttributeChange<'a> = (&'a String, Option<&'a AttributeValue>, Option<&'a AttributeValue>);
+
+/// Lists the properties that differ between two attribute maps, sorted by name
+pub fn changed_attributes<'a>(
+    left: &'a HashMap<String, AttributeValue>,
+    right: &'a HashMap<String, AttributeValue>,
+    ignore_properties: &[String],
+    tolerance: f64,
+) -> Vec<AttributeChange<'a>> {
+    let mut keys: Vec<&String> = left.keys().chain(right.keys()).collect();
+    keys.sort();
+    keys.dedup();
+
+    keys.into_iter()
+        .filter(|key| !ignore_properties.contains(key))
+        .map(|key| (key, left.get(key), right.get(key)))
+        .filter(|(_, a, b)| match (a, b) {
+            (Some(a), Some(b)) => !values_equal(a, b, tolerance),
+            _ => true,
+        })
+        .collect()
+}
+
+/// Lists property-level differences between two attribute maps
+pub fn attribute_differences(
+    left: &HashMap<String, AttributeValue>,
+    right: &HashMap<String, AttributeValue>,
+    ignore_properties: &[String],
+    tolerance: f64,
+) -> Vec<String> {
+    // Values as exported, e.g. dates as ISO strings, with strings quoted
+    let describe = |value: Option<&AttributeValue>| match value {
+        None => "<missing>".to_string(),
+        Some(AttributeValue::String(v)) => format!("'{}'", v),
+        Some(v) => v.to_export_string(),
+    };
+    changed_attributes(left, right, ignore_properties, tolerance).into_iter()
+        .map(|(key, a, b)| format!("'{}': {} != {}", key, describe(a), describe(b)))
+        .collect()
+}
+
+/// Deep-compares two graphs by node keys, titles, attributes and relations,
+/// raising an AssertionError listing the first `max_differences` discrepancies.
+pub fn assert_equal(
+    left: &DiGraph<Node, Relation>,
+    right: &DiGraph<Node, Relation>,
+    ignore_properties: Option<Vec<String>>,
+    tolerance: Option<f64>,
+    max_differences: Option<usize>,
+) -> PyResult<()> {
+    let ignore_properties = ignore_properties.unwrap_or_default();
+    let tolerance = tolerance.unwrap_or(1e-9);
+    let max_differences = max_differences.unwrap_or(10);
+    let mut differences = Vec::new();
+
+    let left_nodes = nodes_by_key(left);
+    let right_nodes = nodes_by_key(right);
+
+    for (key, left_node) in &left_nodes {
+        let Some(right_node) = right_nodes.get(key) else {
+            differences.push(format!("Node {:?} only in left graph", key));
+            continue;
+        };
+        if let (
+            Node::StandardNode { title: left_title, attributes: left_attrs, .. },
+            Node::StandardNode { title: right_title, attributes: right_attrs, .. },
+        ) = (left_node, right_node) {
+            if left_title != right_title && !ignore_properties.iter().any(|p| p == "title") {
+                differences.push(format!("Node {:?}: title {:?} != {:?}", key, left_title, right_title));
+            }
+            for difference in attribute_differences(left_attrs, right_attrs, &ignore_properties, tolerance) {
+                differences.push(format!("Node {:?}: {}", key, difference));
+            }
+        }
+    }
+    for key in right_nodes.keys().filter(|key| !left_nodes.contains_key(*key)) {
+        differences.push(format!("Node {:?} only in right graph", key));
+    }
+
+    let left_edges = edges_by_key(left);
+    let right_edges = edges_by_key(right);
+    let empty = HashMap::new();
+
+    for (key, left_relations) in &left_edges {
+        let right_relations = right_edges.get(key).map_or(&[][..], |r| r.as_slice());
+        if left_relations.len() != right_relations.len() {
+            differences.push(format!(
+                "Relation {:?}: {} edge(s) != {} edge(s)", key, left_relations.len(), right_relations.len()
+            ));
+            continue;
+        }
+        for (a, b) in left_relations.iter().zip(right_relations) {
+            let a_attrs = a.attributes.as_ref().unwrap_or(&empty);
+            let b_attrs = b.attributes.as_ref().unwrap_or(&empty);
+            for difference in attribute_differences(a_attrs, b_attrs, &ignore_properties, tolerance) {
+                differences.push(format!("Relation {:?}: {}", key, difference));
+            }
+        }
+    }
+    for (key, right_relations) in right_edges.iter().filter(|(key, _)| !left_edges.contains_key(*key)) {
+        differences.push(format!("Relation {:?}: 0 edge(s) != {} edge(s)", key, right_relations.len()));
+    }
+
+    if differences.is_empty() {
+        return Ok(());
+    }
+
+    let total = differences.len();
+    let mut message = format!("Graphs differ ({} discrepancies):", total);
+    for difference in differences.iter().take(max_differences) {
+        message.push_str("\n  - ");
+        message.push_str(difference);
+    }
+    if total > max_differences {
+        message.push_str(&format!("\n  ... and {} more", total - max_differences));
+    }
+    Err(PyAssertionError::new_err(message))
+}
+
+/// Reports how `right` differs from `left` as a Python dict with "nodes_added", "nodes_removed",
+/// "nodes_changed" (with {property: (old, new)} per node) and "connections_added"/"connections_removed".
+pub fn diff(
+    left: &DiGraph<Node, Relation>,
+    right: &DiGraph<Node, Relation>,
+    py: Python,
+    ignore_properties: Option<Vec<String>>,
+    tolerance: Option<f64>,
+) -> PyResult<PyObject> {
+    let ignore_properties = ignore_properties.unwrap_or_default();
+    let tolerance = tolerance.unwrap_or(1e-9);
+    let to_py = |value: Option<&AttributeValue>| match value {
+        Some(v) => v.to_python_object(py, None),
+        None => Ok(py.None()),
+    };
+
+    let left_nodes = nodes_by_key(left);
+    let right_nodes = nodes_by_key(right);
+    let nodes_added: Vec<&NodeKey> = right_nodes.keys().filter(|key| !left_nodes.contains_key(*key)).collect();
+    let nodes_removed: Vec<&NodeKey> = left_nodes.keys().filter(|key| !right_nodes.contains_key(*key)).collect();
+
+    let mut nodes_changed = Vec::new();
+    for (key, left_node) in &left_nodes {
+        let Some(right_node) = right_nodes.get(key) else { continue };
+        let (
+            Node::StandardNode { title: left_title, attributes: left_attrs, .. },
+            Node::StandardNode { title: right_title, attributes: right_attrs, .. },
+        ) = (left_node, right_node) else { continue };
+
+        let changes = PyDict::new(py);
+        if left_title != right_title && !ignore_properties.iter().any(|p| p == "title") {
+            changes.set_item("title", (left_title, right_title))?;
+        }
+        for (property, old, new) in changed_attributes(left_attrs, right_attrs, &ignore_properties, tolerance) {
+            changes.set_item(property, (to_py(old)?, to_py(new)?))?;
+        }
+        if !changes.is_empty() {
+            let change = PyDict::new(py);
+            change.set_item("node_type", &key.0)?;
+            change.set_item("unique_id", &key.1)?;
+            change.set_item("changes", changes)?;
+            nodes_changed.push(change);
+        }
+    }
+
+    // Parallel relations of the same type count individually
+    let left_edges = edges_by_key(left);
+    let right_edges = edges_by_key(right);
+    let result = PyDict::new(py);
+    result.set_item("nodes_added", nodes_added)?;
+    result.set_item("nodes_removed", nodes_removed)?;
+    result.set_item("nodes_changed", nodes_changed)?;
+    result.set_item("connections_added", surplus_edges(&right_edges, &left_edges))?;
+    result.set_item("connections_removed", surplus_edges(&left_edges, &right_edges))?;
+    Ok(result.into())
+}
+
+// Relations in `from` beyond the number of matching relations in `than`, as
+// (source_type, source_id, relation_type, target_type, target_id)
+fn surplus_edges<'a>(
+    from: &'a BTreeMap<EdgeKey, Vec<&Relation>>,
+    than: &BTreeMap<EdgeKey, Vec<&Relation>>,
+) -> Vec<(&'a String, &'a String, &'a String, &'a String, &'a String)> {
+    from.iter()
+        .flat_map(|(key, relations)| {
+            let extra = relations.len().saturating_sub(than.get(key).map_or(0, Vec::len));
+            std::iter::repeat_n(key, extra)
+        })
+        .map(|(source, relation_type, target)| (&source.0, &source.1, relation_type, &target.0, &target.1))
+        .collect()
+}
@@ -0,0 +1,330 @@
+use pyo3::prelude::*;
+use pyo3::exceptions::PyValueError;
+use pyo3::types::PyDict;
+use petgraph::graph::{DiGraph, EdgeIndex, NodeIndex};
+use petgraph::visit::EdgeRef;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use crate::graph::journal::JournaledGraph;
+use crate::graph::add_nodes::remove_added;
+use crate::graph::filters::compare_values;
+use crate::graph::get_schema::find_schema_node;
+use crate::schema::{Cardinality, Constraint, Endpoints, Node, Relation};
+use crate::data_types::AttributeValue;
+
+// Violations named in the error when a change is rejected
+const REPORTED_VIOLATIONS: usize = 5;
+
+/// Builds a constraint from its kind ("unique", "required" or "range") and, for ranges, the
+/// inclusive bounds
+pub fn parse_constraint(kind: &str, min: Option<AttributeValue>, max: Option<AttributeValue>) -> PyResult<Constraint> {
+    let (min, max) = (min.filter(|v| !v.is_null()), max.filter(|v| !v.is_null()));
+    match kind {
+        "unique" | "required" if min.is_some() || max.is_some() => {
+            Err(PyValueError::new_err(format!("min and max only apply to 'range' constraints, not '{}'", kind)))
+        },
+        "unique" => Ok(Constraint::Unique),
+        "required" => Ok(Constraint::Required),
+        "range" if min.is_none() && max.is_none() => Err(PyValueError::new_err("A 'range' constraint needs min, max or both")),
+        "range" => Ok(Constraint::Range(min, max)),
+        other => Err(PyValueError::new_err(format!(
+            "Unknown constraint '{}', expected 'unique', 'required' or 'range'", other
+        ))),
+    }
+}
+
+/// Records a constraint on a property of a node type, replacing one of the same kind
+pub fn add_constraint(graph: &mut JournaledGraph, node_type: &str, property: &str, constraint: Constraint) {
+    let schema_index = match find_schema_node(graph, "Node", node_type) {
+        Some(index) => index,
+        None => graph.add_node(Node::new_data_type("Node", node_type, HashMap::new())),
+    };
+    if let Node::DataTypeNode { constraints, .. } = &mut graph[schema_index] {
+        set_constraint(constraints.entry(property.to_string()).or_default(), constraint);
+    }
+}
+
+// A property holds at most one constraint of each kind
+pub fn set_constraint(rules: &mut Vec<Constraint>, constraint: Constraint) {
+    match rules.iter_mut().find(|rule| rule.kind() == constraint.kind()) {
+        Some(rule) => *rule = constraint,
+        None => rules.push(constraint),
+    }
+}
+
+/// A node breaking a constraint of its type
+pub struct Violation {
+    pub index: usize,
+    pub node_type: String,
+    pub unique_id: String,
+    pub property: String,
+    pub constraint: &'static str,
+    pub message: String,
+}
+
+impl Violation {
+    pub fn to_py(&self, py: Python) -> PyResult<PyObject> {
+        let dict = PyDict::new(py);
+        dict.set_item("graph_id", self.index)?;
+        dict.set_item("node_type", &self.node_type)?;
+        dict.set_item("unique_id", &self.unique_id)?;
+        dict.set_item("property", &self.property)?;
+        dict.set_item("constraint", self.constraint)?;
+        dict.set_item("message", &self.message)?;
+        Ok(dict.into())
+    }
+}
+
+/// Checks nodes against the constraints of their types: those in `indices`, or all nodes. Values
+/// of unique properties are compared with every node of the type, checked or not.
+pub fn violations(graph: &DiGraph<Node, Relation>, indices: Option<&[usize]>) -> Vec<Violation> {
+    let rules: HashMap<&str, &HashMap<String, Vec<Constraint>>> = graph.node_weights().filter_map(|node| match node {
+        Node::DataTypeNode { data_type, name, constraints, .. } if data_type == "Node" && !constraints.is_empty() => {
+            Some((name.as_str(), constraints))
+        },
+        _ => None,
+    }).collect();
+    if rules.is_empty() {
+        return Vec::new();
+    }
+    let checked: Option<HashSet<usize>> = indices.map(|indices| indices.iter().copied().collect());
+    let is_checked = |index: NodeIndex| checked.as_ref().is_none_or(|checked| checked.contains(&index.index()));
+
+    let mut found = Vec::new();
+    let mut report = |index: NodeIndex, property: &str, constraint: &Constraint, message: String| {
+        if let Node::StandardNode { node_type, unique_id, .. } = &graph[index] {
+            found.push(Violation {
+                index: index.index(),
+                node_type: node_type.to_string(),
+                unique_id: unique_id.clone(),
+                property: property.to_string(),
+                constraint: constraint.kind(),
+                message,
+            });
+        }
+    };
+    // Nodes by the value of each unique property, keyed by type and text so 1 and 1.0 stay apart
+    let mut shared: HashMap<(&str, &str, &str, String), Vec<NodeIndex>> = HashMap::new();
+    for index in graph.node_indices() {
+        let Node::StandardNode { node_type, .. } = &graph[index] else { continue };
+        let Some(properties) = rules.get(node_type.as_str()) else { continue };
+        for (property, constraints) in properties.iter() {
+            let value = graph[index].get_field(property).filter(|value| !value.is_null());
+            for constraint in constraints {
+                match (constraint, &value) {
+                    (Constraint::Unique, Some(value)) => {
+                        shared.entry((node_type, property, value.type_name(), value.to_string())).or_default().push(index);
+                    },
+                    (Constraint::Required, None) if is_checked(index) => {
+                        report(index, property, constraint, "is required but missing".to_string());
+                    },
+                    (Constraint::Range(min, max), Some(value)) if is_checked(index) => {
+                        let below = min.as_ref().is_some_and(|min| compare_values(value, min).is_none_or(|o| o == Ordering::Less));
+                        let above = max.as_ref().is_some_and(|max| compare_values(value, max).is_none_or(|o| o == Ordering::Greater));
+                        if below || above {
+                            report(index, property, constraint, format!("value {} is not {}", value.to_string(), constraint.describe()));
+                        }
+                    },
+                    _ => (),
+                }
+            }
+        }
+    }
+    for ((_, property, _, value), nodes) in shared {
+        if nodes.len() < 2 {
+            continue;
+        }
+        for &index in nodes.iter().filter(|&&index| is_checked(index)) {
+            let message = format!("value {} is not unique ({} other node(s) share it)", value, nodes.len() - 1);
+            report(index, property, &Constraint::Unique, message);
+        }
+    }
+    found.sort_by(|a, b| a.index.cmp(&b.index).then_with(|| a.property.cmp(&b.property)));
+    found
+}
+
+/// Node states to return to when a change breaks a constraint: the node and relation counts
+/// before the change, so nodes and relations added since can be removed again, the nodes and
+/// schema nodes of the changed types, and the relations of any changed relation types
+pub struct Snapshot {
+    node_count: usize,
+    edge_count: usize,
+    nodes: Vec<(NodeIndex, Node)>,
+    edges: Vec<(EdgeIndex, Relation)>,
+}
+
+/// Copies the nodes of `node_types` before they change; None when none of the types has
+/// constraints, so unconstrained changes cost nothing
+pub fn snapshot(graph: &DiGraph<Node, Relation>, node_types: &[&str]) -> Option<Snapshot> {
+    let constrained = graph.node_weights().any(|node| matches!(node,
+        Node::DataTypeNode { data_type, name, constraints, .. }
+            if data_type == "Node" && node_types.contains(&name.as_str()) && !constraints.is_empty()
+    ));
+    if !constrained {
+        return None;
+    }
+    let nodes = graph.node_indices().filter(|&index| match &graph[index] {
+        Node::StandardNode { node_type, .. } => node_types.contains(&node_type.as_str()),
+        Node::DataTypeNode { data_type, name, .. } => data_type == "Node" && node_types.contains(&name.as_str()),
+    }).map(|index| (index, graph[index].clone())).collect();
+    Some(Snapshot { node_count: graph.node_count(), edge_count: graph.edge_count(), nodes, edges: Vec::new() })
+}
+
+/// Copies every node and relation before a change that may touch any type, such as an import;
+/// None when no node type has constraints
+pub fn snapshot_all(graph: &DiGraph<Node, Relation>) -> Option<Snapshot> {
+    let constrained = graph.node_weights().any(|node| matches!(node,
+        Node::DataTypeNode { data_type, constraints, .. } if data_type == "Node" && !constraints.is_empty()
+    ));
+    if !constrained {
+        return None;
+    }
+    Some(Snapshot {
+        node_count: graph.node_count(),
+        edge_count: graph.edge_count(),
+        nodes: graph.node_indices().map(|index| (index, graph[index].clone())).collect(),
+        edges: graph.edge_indices().map(|index| (index, graph[index].clone())).collect(),
+    })
+}
+
+impl Snapshot {
+    /// Also copies the relations of `relation_types`, for changes that update existing relations
+    pub fn with_relations(mut self, graph: &DiGraph<Node, Relation>, relation_types: &[&str]) -> Self {
+        self.edges = graph.edge_indices()
+            .filter(|&index| relation_types.contains(&graph[index].relation_type.as_str()))
+            .map(|index| (index, graph[index].clone()))
+            .collect();
+        self
+    }
+
+    fn restore(self, graph: &mut JournaledGraph) {
+        remove_added(graph, self.node_count, self.edge_count);
+        for (index, node) in self.nodes {
+            graph[index] = node;
+        }
+        for (index, relation) in self.edges {
+            graph[index] = relation;
+        }
+    }
+}
+
+/// Checks the changed nodes in `indices`, undoing the whole change and failing when any breaks a
+/// constraint of its type
+pub fn enforce(graph: &mut JournaledGraph, indices: &[usize], snapshot: Snapshot) -> PyResult<()> {
+    let found = violations(graph, Some(indices));
+    if found.is_empty() {
+        return Ok(());
+    }
+    snapshot.restore(graph);
+    let reported: Vec<String> = found.iter().take(REPORTED_VIOLATIONS)
+        .map(|v| format!("{} '{}': {} {}", v.node_type, v.unique_id, v.property, v.message))
+        .collect();
+    Err(PyValueError::new_err(format!(
+        "{} constraint violation(s), no changes were made: {}{}",
+        found.len(), reported.join("; "), if found.len() > REPORTED_VIOLATIONS { "; ..." } else { "" }
+    )))
+}
+
+/// Records the node types a relation type runs from and to, and its cardinality ("1:1", "1:N",
+/// "N:1" or "N:M"), replacing an earlier declaration
+pub fn declare_relation(
+    graph: &mut JournaledGraph,
+    relation_type: &str,
+    source_type: &str,
+    target_type: &str,
+    cardinality: &str,
+) -> PyResult<()> {
+    let cardinality = Cardinality::parse(cardinality).ok_or_else(|| PyValueError::new_err(format!(
+        "Unknown cardinality '{}', expected '1:1', '1:N', 'N:1' or 'N:M'", cardinality
+    )))?;
+    let schema_index = match find_schema_node(graph, "Relation", relation_type) {
+        Some(index) => index,
+        None => graph.add_node(Node::new_data_type("Relation", relation_type, HashMap::new())),
+    };
+    if let Node::DataTypeNode { endpoints, .. } = &mut graph[schema_index] {
+        *endpoints = Some(Endpoints { source_type: source_type.to_string(), target_type: target_type.to_string(), cardinality });
+    }
+    Ok(())
+}
+
+/// A row rejected for breaking the declared endpoints of its relation type
+pub struct RelationViolation {
+    pub row: usize,
+    pub source_id: String,
+    pub target_id: String,
+    pub rule: &'static str,
+    pub message: String,
+}
+
+impl RelationViolation {
+    pub fn to_py(&self, py: Python) -> PyResult<PyObject> {
+        let dict = PyDict::new(py);
+        dict.set_item("row", self.row)?;
+        dict.set_item("source_id", &self.source_id)?;
+        dict.set_item("target_id", &self.target_id)?;
+        dict.set_item("rule", self.rule)?;
+        dict.set_item("message", &self.message)?;
+        Ok(dict.into())
+    }
+}
+
+/// Checks new relations of one type against its declared endpoints, keeping count of the
+/// relations of the type each node has so far
+pub struct EndpointCheck {
+    relation_type: String,
+    endpoints: Endpoints,
+    from_source: HashMap<NodeIndex, usize>,
+    into_target: HashMap<NodeIndex, usize>,
+}
+
+impl EndpointCheck {
+    /// None when the relation type has no declared endpoints
+    pub fn new(graph: &DiGraph<Node, Relation>, relation_type: &str) -> Option<Self> {
+        let endpoints = find_schema_node(graph, "Relation", relation_type).and_then(|index| match &graph[index] {
+            Node::DataTypeNode { endpoints, .. } => endpoints.clone(),
+            Node::StandardNode { .. } => None,
+        })?;
+        let mut check = EndpointCheck {
+            relation_type: relation_type.to_string(), endpoints, from_source: HashMap::new(), into_target: HashMap::new(),
+        };
+        for edge in graph.edge_references().filter(|edge| edge.weight().relation_type == relation_type) {
+            check.record(edge.source(), edge.target());
+        }
+        Some(check)
+    }
+
+    /// The rule a relation between nodes of the given types would break, and why. Nodes not yet
+    /// in the graph are None and have no relations.
+    pub fn check(
+        &self, source_type: &str, target_type: &str, source: Option<NodeIndex>, target: Option<NodeIndex>,
+    ) -> Option<(&'static str, String)> {
+        let Endpoints { source_type: from, target_type: to, cardinality } = &self.endpoints;
+        if source_type == to && target_type == from && from != to {
+            return Some(("direction", format!("{} runs from {} to {}, not the other way", self.relation_type, from, to)));
+        }
+        if source_type != from {
+            return Some(("source_type", format!("{} runs from {} nodes, not {}", self.relation_type, from, source_type)));
+        }
+        if target_type != to {
+            return Some(("target_type", format!("{} runs to {} nodes, not {}", self.relation_type, to, target_type)));
+        }
+        let count = |counts: &HashMap<NodeIndex, usize>, node: Option<NodeIndex>| node.and_then(|node| counts.get(&node)).copied().unwrap_or(0);
+        if !cardinality.many_targets() && count(&self.from_source, source) > 0 {
+            return Some(("cardinality", format!(
+                "{} is {}, and the source already has a {} relation", self.relation_type, cardinality.as_str(), self.relation_type
+            )));
+        }
+        if !cardinality.many_sources() && count(&self.into_target, target) > 0 {
+            return Some(("cardinality", format!(
+                "{} is {}, and the target already has a {} relation", self.relation_type, cardinality.as_str(), self.relation_type
+            )));
+        }
+        None
+    }
+
+    /// Counts a relation that was added
+    pub fn record(&mut self, source: NodeIndex, target: NodeIndex) {
+        *self.from_source.entry(source).or_insert(0) += 1;
+        *self.into_target.entry(target).or_insert(0) += 1;
+    }
+}
@@ -0,0 +1,97 @@
+use pyo3::prelude::*;
+use pyo3::exceptions::PyValueError;
+use petgraph::graph::NodeIndex;
+use std::collections::HashMap;
+use crate::graph::journal::JournaledGraph;
+use crate::graph::categories;
+use crate::graph::get_schema::find_schema_node;
+use crate::graph::io::tabular::ColumnTypes;
+use crate::schema::Node;
+use crate::data_types::AttributeValue;
+
+/// Records the value `node_type` nodes take for `property` when they have none, and sets it on the
+/// existing nodes of the type that lack the property. The value is converted to the property's
+/// schema type; a property not in the schema yet is added with the type of the value. A null value
+/// removes the default.
+///
+/// Returns the nodes the default was set on.
+pub fn set_default(
+    graph: &mut JournaledGraph,
+    node_type: &str,
+    property: &str,
+    value: AttributeValue,
+) -> PyResult<Vec<usize>> {
+    let schema_index = match find_schema_node(graph, "Node", node_type) {
+        Some(index) => index,
+        None => graph.add_node(Node::new_data_type("Node", node_type, HashMap::new())),
+    };
+    let Node::DataTypeNode { attributes, defaults, .. } = &mut graph[schema_index] else { return Ok(Vec::new()) };
+    if value.is_null() {
+        defaults.remove(property);
+        return Ok(Vec::new());
+    }
+    let value = match attributes.get(property) {
+        Some(data_type) => {
+            let column_types = ColumnTypes::new(Some(HashMap::from([(property.to_string(), data_type.clone())])));
+            column_types.convert(property, value).map_err(|_| PyValueError::new_err(format!(
+                "The default for '{}' on {} nodes cannot be read as {}", property, node_type, data_type
+            )))?
+        },
+        None => {
+            attributes.insert(property.to_string(), value.type_name().to_string());
+            value
+        },
+    };
+    let is_category = attributes.get(property).is_some_and(|data_type| data_type == "Category");
+    defaults.insert(property.to_string(), value.clone());
+
+    let mut filled = Vec::new();
+    for index in graph.node_indices() {
+        if let Node::StandardNode { node_type: nt, attributes, .. } = &mut graph[index] {
+            if nt == node_type && !attributes.contains_key(property) {
+                attributes.insert(property.to_string(), value.clone());
+                filled.push(index.index());
+            }
+        }
+    }
+    if is_category {
+        categories::encode_nodes(graph, node_type, &filled);
+    }
+    Ok(filled)
+}
+
+/// Sets the defaults of `node_type` on the given nodes for the properties they lack, as after
+/// ingesting rows that left those columns empty or out
+pub fn apply(graph: &mut JournaledGraph, node_type: &str, indices: &[usize]) {
+    let Some(schema_index) = find_schema_node(graph, "Node", node_type) else { return };
+    let Node::DataTypeNode { defaults, .. } = &graph[schema_index] else { return };
+    if defaults.is_empty() {
+        return;
+    }
+    let defaults = defaults.clone();
+    for &index in indices {
+        let Some(Node::StandardNode { node_type: nt, attributes, .. }) = graph.node_weight_mut(NodeIndex::new(index)) else { continue };
+        if nt != node_type {
+            continue;
+        }
+        for (property, value) in &defaults {
+            if !attributes.contains_key(property) {
+                attributes.insert(property.clone(), value.clone());
+            }
+        }
+    }
+}
+
+/// Sets the defaults on nodes of any type, each taking those of its own type, as after an import
+/// mixing node types
+pub fn apply_each(graph: &mut JournaledGraph, indices: &[usize]) {
+    let mut by_type: HashMap<String, Vec<usize>> = HashMap::new();
+    for &index in indices {
+        if let Some(Node::StandardNode { node_type, .. }) = graph.node_weight(NodeIndex::new(index)) {
+            by_type.entry(node_type.to_string()).or_default().push(index);
+        }
+    }
+    for (node_type, indices) in by_type {
+        apply(graph, &node_type, &indices);
+    }
+}
@@ -0,0 +1,327 @@
+use pyo3::prelude::*;
+use pyo3::exceptions::{PyIOError, PyValueError};
+use petgraph::graph::{DiGraph, EdgeIndex, NodeIndex};
+use petgraph::visit::EdgeRef;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use crate::graph::categories;
+use crate::graph::persistence::{GraphState, GraphStateRef};
+use crate::schema::{Constraint, Node, Relation};
+use crate::data_types::AttributeValue;
+
+const MAGIC: &[u8; 7] = b"RGDELTA";
+// Version 2 fingerprints and stores Int as 64-bit; version 3 stores schema constraints, version 4
+// relation endpoints, version 5 property defaults and version 6 key columns
+const FORMAT_VERSION: u32 = 6;
+
+// FNV-1a over explicitly encoded fields, so fingerprints stay the same across Rust versions and
+// platforms and delta files remain readable
+struct Fingerprint(u64);
+
+impl Fingerprint {
+    fn new(tag: u8) -> Self {
+        let mut fingerprint = Fingerprint(0xcbf2_9ce4_8422_2325);
+        fingerprint.bytes(&[tag]);
+        fingerprint
+    }
+
+    fn bytes(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ byte as u64).wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn text(&mut self, text: &str) {
+        self.bytes(&(text.len() as u64).to_le_bytes());
+        self.bytes(text.as_bytes());
+    }
+
+    fn value(&mut self, value: &AttributeValue) {
+        match value {
+            AttributeValue::Int(v) => { self.bytes(&[0]); self.bytes(&v.to_le_bytes()) },
+            AttributeValue::Float(v) => { self.bytes(&[1]); self.bytes(&v.to_bits().to_le_bytes()) },
+            AttributeValue::DateTime(v) => { self.bytes(&[2]); self.bytes(&v.to_le_bytes()) },
+            AttributeValue::String(v) => { self.bytes(&[3]); self.text(v) },
+            AttributeValue::Boolean(v) => self.bytes(&[4, *v as u8]),
+            AttributeValue::Null => self.bytes(&[5]),
+            AttributeValue::List(values) => {
+                self.bytes(&[6]);
+                self.bytes(&values.len().to_le_bytes());
+                values.iter().for_each(|value| self.value(value));
+            },
+            AttributeValue::Map(entries) => {
+                self.bytes(&[7]);
+                self.bytes(&entries.len().to_le_bytes());
+                let mut entries: Vec<_> = entries.iter().collect();
+                entries.sort_by(|a, b| a.0.cmp(b.0));
+                for (key, value) in entries {
+                    self.text(key);
+                    self.value(value);
+                }
+            },
+            AttributeValue::Decimal(v) => { self.bytes(&[8]); self.bytes(&v.units.to_le_bytes()); self.bytes(&v.scale.to_le_bytes()) },
+            AttributeValue::Date(v) => { self.bytes(&[9]); self.bytes(&v.to_le_bytes()) },
+            AttributeValue::Duration(v) => { self.bytes(&[10]); self.bytes(&v.to_le_bytes()) },
+            AttributeValue::Uuid(v) => { self.bytes(&[11]); self.bytes(&v.to_le_bytes()) },
+            AttributeValue::Category(v) => { self.bytes(&[12]); self.text(v.label()) },
+            AttributeValue::Bytes(v) => { self.bytes(&[13]); self.bytes(&v.len().to_le_bytes()); self.bytes(v) },
+        }
+    }
+
+    // Properties in name order, since map order differs between runs
+    fn attributes(&mut self, attributes: Option<&HashMap<String, AttributeValue>>) {
+        let mut sorted: Vec<_> = attributes.into_iter().flatten().collect();
+        sorted.sort_by(|a, b| a.0.cmp(b.0));
+        self.bytes(&(sorted.len() as u64).to_le_bytes());
+        for (name, value) in sorted {
+            self.text(name);
+            self.value(value);
+        }
+    }
+}
+
+// Nodes are identified by node type and unique ID, schema nodes by data type and name
+fn node_key(node: &Node) -> u64 {
+    let (mut fingerprint, first, second) = match node {
+        Node::StandardNode { node_type, unique_id, .. } => (Fingerprint::new(b'n'), node_type.as_str(), unique_id),
+        Node::DataTypeNode { data_type, name, .. } => (Fingerprint::new(b's'), data_type.as_str(), name),
+    };
+    fingerprint.text(first);
+    fingerprint.text(second);
+    fingerprint.0
+}
+
+fn node_content(node: &Node) -> u64 {
+    let mut fingerprint = Fingerprint::new(b'c');
+    match node {
+        Node::StandardNode { title, attributes, sequence, .. } => {
+            fingerprint.text(title.as_deref().unwrap_or("\0"));
+            fingerprint.bytes(&sequence.map_or(u64::MAX, |s| s).to_le_bytes());
+            fingerprint.attributes(Some(attributes));
+        },
+        Node::DataTypeNode { attributes, calculated, constraints, endpoints, defaults, keys, .. } => {
+            let mut sorted: Vec<_> = attributes.iter().collect();
+            sorted.sort();
+            for (name, data_type) in sorted {
+                fingerprint.text(name);
+                fingerprint.text(data_type);
+                fingerprint.bytes(&[calculated.contains(name) as u8]);
+            }
+            let mut sorted: Vec<_> = constraints.iter().collect();
+            sorted.sort_by_key(|(name, _)| *name);
+            for (name, rules) in sorted {
+                fingerprint.text(name);
+                for rule in rules {
+                    fingerprint.text(rule.kind());
+                    if let Constraint::Range(min, max) = rule {
+                        for bound in [min, max] {
+                            fingerprint.value(bound.as_ref().unwrap_or(&AttributeValue::Null));
+                        }
+                    }
+                }
+            }
+            if let Some(endpoints) = endpoints {
+                fingerprint.text(&endpoints.source_type);
+                fingerprint.text(&endpoints.target_type);
+                fingerprint.text(endpoints.cardinality.as_str());
+            }
+            let mut sorted: Vec<_> = defaults.iter().collect();
+            sorted.sort_by_key(|(name, _)| *name);
+            for (name, value) in sorted {
+                fingerprint.text(name);
+                fingerprint.value(value);
+            }
+            fingerprint.bytes(&(keys.unique_id.len() as u64).to_le_bytes());
+            keys.unique_id.iter().for_each(|field| fingerprint.text(field));
+            fingerprint.text(keys.title.as_deref().unwrap_or("\0"));
+        },
+    }
+    fingerprint.0
+}
+
+fn edge_fingerprint(source: u64, target: u64, relation: &Relation) -> u64 {
+    let mut fingerprint = Fingerprint::new(b'e');
+    fingerprint.bytes(&source.to_le_bytes());
+    fingerprint.bytes(&target.to_le_bytes());
+    fingerprint.text(&relation.relation_type);
+    fingerprint.attributes(relation.attributes.as_ref());
+    fingerprint.0
+}
+
+/// Fingerprints of every node (by key) and edge as of the last full save or load, which
+/// `save_delta` compares the current graph against
+pub struct Baseline {
+    pub path: String,
+    nodes: HashMap<u64, u64>,
+    edges: HashMap<u64, usize>,
+}
+
+impl Baseline {
+    pub fn new(path: &str, graph: &DiGraph<Node, Relation>) -> Self {
+        let mut baseline = Baseline { path: path.to_string(), nodes: HashMap::new(), edges: HashMap::new() };
+        baseline.refresh(graph);
+        baseline
+    }
+
+    fn refresh(&mut self, graph: &DiGraph<Node, Relation>) {
+        let keys: Vec<u64> = graph.node_weights().map(node_key).collect();
+        self.nodes = graph.node_weights().zip(&keys).map(|(node, &key)| (key, node_content(node))).collect();
+        self.edges.clear();
+        for edge in graph.edge_references() {
+            *self.edges.entry(edge_fingerprint(keys[edge.source().index()], keys[edge.target().index()], edge.weight())).or_insert(0) += 1;
+        }
+    }
+
+    // Order-independent fingerprint of the whole graph, used to check deltas are applied in order
+    fn state(&self) -> u64 {
+        let nodes = self.nodes.iter().fold(0u64, |sum, (&key, &content)| sum.wrapping_add(key.rotate_left(17) ^ content));
+        self.edges.iter().fold(nodes, |sum, (&edge, &count)| sum.wrapping_add(edge.wrapping_mul(count as u64)))
+    }
+}
+
+/// Changes between two saves. Nodes and edges refer to nodes by key fingerprint.
+#[derive(Serialize, Deserialize)]
+struct Delta {
+    base_state: u64,
+    state: u64,
+    track_insertion_order: bool,
+    next_sequence: u64,
+    removed_nodes: Vec<u64>,
+    nodes: Vec<Node>,
+    removed_edges: Vec<u64>,
+    edges: Vec<(u64, u64, Relation)>,
+    selections: HashMap<String, Vec<u64>>,
+}
+
+/// Writes the nodes and edges added, changed or removed since the baseline to `path`, and moves
+/// the baseline to the current graph so the next delta continues from this one
+pub fn save_delta(path: &str, baseline: &mut Baseline, state: &GraphStateRef) -> PyResult<()> {
+    let graph = state.graph;
+    let keys: Vec<u64> = graph.node_weights().map(node_key).collect();
+    let mut current = Baseline { path: baseline.path.clone(), nodes: HashMap::new(), edges: HashMap::new() };
+    let mut nodes = Vec::new();
+    for (node, &key) in graph.node_weights().zip(&keys) {
+        let content = node_content(node);
+        if baseline.nodes.get(&key) != Some(&content) {
+            nodes.push(node.clone());
+        }
+        current.nodes.insert(key, content);
+    }
+    let removed_nodes = baseline.nodes.keys().filter(|key| !current.nodes.contains_key(key)).copied().collect();
+
+    let mut edges = Vec::new();
+    let mut unmatched = baseline.edges.clone();
+    for edge in graph.edge_references() {
+        let (source, target) = (keys[edge.source().index()], keys[edge.target().index()]);
+        let fingerprint = edge_fingerprint(source, target, edge.weight());
+        *current.edges.entry(fingerprint).or_insert(0) += 1;
+        match unmatched.get_mut(&fingerprint) {
+            Some(count) if *count > 0 => *count -= 1,
+            _ => edges.push((source, target, edge.weight().clone())),
+        }
+    }
+    let removed_edges = unmatched.into_iter().flat_map(|(fingerprint, count)| std::iter::repeat_n(fingerprint, count)).collect();
+
+    let delta = Delta {
+        base_state: baseline.state(),
+        state: current.state(),
+        track_insertion_order: state.track_insertion_order,
+        next_sequence: state.next_sequence,
+        removed_nodes,
+        nodes,
+        removed_edges,
+        edges,
+        selections: state.selections.iter()
+            .map(|(name, indices)| (name.clone(), indices.iter().filter_map(|&index| keys.get(index).copied()).collect()))
+            .collect(),
+    };
+    let file = File::create(path).map_err(|e| PyIOError::new_err(format!("Cannot create '{}': {}", path, e)))?;
+    let mut writer = BufWriter::new(file);
+    writer.write_all(MAGIC)
+        .and_then(|_| writer.write_all(&FORMAT_VERSION.to_le_bytes()))
+        .map_err(|e| PyIOError::new_err(format!("Cannot write '{}': {}", path, e)))?;
+    bincode::serialize_into(&mut writer, &delta)
+        .map_err(|e| PyValueError::new_err(format!("Cannot serialize delta: {}", e)))?;
+    writer.flush().map_err(|e| PyIOError::new_err(format!("Cannot write '{}': {}", path, e)))?;
+    *baseline = current;
+    Ok(())
+}
+
+fn read_delta(path: &str) -> PyResult<Delta> {
+    let file = File::open(path).map_err(|e| PyIOError::new_err(format!("Cannot open '{}': {}", path, e)))?;
+    let mut reader = BufReader::new(file);
+    let mut header = [0u8; 11];
+    reader.read_exact(&mut header)
+        .map_err(|_| PyValueError::new_err(format!("'{}' is not a delta file", path)))?;
+    if &header[..7] != MAGIC {
+        return Err(PyValueError::new_err(format!("'{}' is not a delta file written by save_delta", path)));
+    }
+    let version = u32::from_le_bytes([header[7], header[8], header[9], header[10]]);
+    if version != FORMAT_VERSION {
+        return Err(PyValueError::new_err(format!(
+            "'{}' uses delta format version {}, but this version of rusty_graph reads version {}", path, version, FORMAT_VERSION
+        )));
+    }
+    bincode::deserialize_from(reader).map_err(|e| PyValueError::new_err(format!("Cannot read delta from '{}': {}", path, e)))
+}
+
+/// Applies a delta file to a loaded snapshot. Deltas must be applied in the order they were
+/// saved; node indices can change where nodes were removed.
+pub fn apply_delta(path: &str, state: &mut GraphState, baseline: &mut Baseline) -> PyResult<()> {
+    let delta = read_delta(path)?;
+    if delta.base_state != baseline.state() {
+        return Err(PyValueError::new_err(format!(
+            "'{}' was not saved against this graph; apply deltas to the snapshot they were saved from, in the order they were saved", path
+        )));
+    }
+    let graph = &mut state.graph;
+
+    // Removing an edge or node moves the last one into its place, so remove from the back
+    let keys: Vec<u64> = graph.node_weights().map(node_key).collect();
+    let mut edges_by_fingerprint: HashMap<u64, Vec<EdgeIndex>> = HashMap::new();
+    for edge in graph.edge_references() {
+        let fingerprint = edge_fingerprint(keys[edge.source().index()], keys[edge.target().index()], edge.weight());
+        edges_by_fingerprint.entry(fingerprint).or_default().push(edge.id());
+    }
+    let mut removed_edges: Vec<EdgeIndex> = delta.removed_edges.iter()
+        .filter_map(|fingerprint| edges_by_fingerprint.get_mut(fingerprint).and_then(Vec::pop))
+        .collect();
+    removed_edges.sort_unstable_by(|a, b| b.cmp(a));
+    for edge in removed_edges {
+        graph.remove_edge(edge);
+    }
+    let index_of: HashMap<u64, NodeIndex> = keys.iter().enumerate().map(|(index, &key)| (key, NodeIndex::new(index))).collect();
+    let mut removed_nodes: Vec<NodeIndex> = delta.removed_nodes.iter().filter_map(|key| index_of.get(key).copied()).collect();
+    removed_nodes.sort_unstable_by(|a, b| b.cmp(a));
+    for node in removed_nodes {
+        graph.remove_node(node);
+    }
+
+    let mut index_of: HashMap<u64, NodeIndex> = graph.node_indices().map(|index| (node_key(&graph[index]), index)).collect();
+    for node in delta.nodes {
+        let key = node_key(&node);
+        match index_of.get(&key) {
+            Some(&index) => graph[index] = node,
+            None => { index_of.insert(key, graph.add_node(node)); },
+        }
+    }
+    for (source, target, relation) in delta.edges {
+        if let (Some(&source), Some(&target)) = (index_of.get(&source), index_of.get(&target)) {
+            graph.add_edge(source, target, relation);
+        }
+    }
+
+    categories::encode_all(graph);
+    state.track_insertion_order = delta.track_insertion_order;
+    state.next_sequence = delta.next_sequence;
+    state.selections = delta.selections.into_iter()
+        .map(|(name, keys)| (name, keys.iter().filter_map(|key| index_of.get(key).map(|index| index.index())).collect()))
+        .collect();
+    baseline.refresh(graph);
+    if baseline.state() != delta.state {
+        return Err(PyValueError::new_err(format!("Applying '{}' did not reproduce the saved graph", path)));
+    }
+    Ok(())
+}
@@ -0,0 +1,161 @@
+use pyo3::prelude::*;
+use pyo3::exceptions::PyValueError;
+use pyo3::types::{PyDict, PyList};
+use petgraph::graph::{DiGraph, EdgeIndex, NodeIndex};
+use petgraph::visit::EdgeRef;
+use petgraph::Direction;
+use std::collections::HashMap;
+use crate::graph::journal::JournaledGraph;
+use crate::graph::calculations::parse_expression;
+use crate::graph::equation_parser::{Evaluator, Value};
+use crate::graph::filters::Filter;
+use crate::schema::{Node, Relation};
+
+/// Selects edges by relation type and filter, optionally limited to the edges of the given nodes.
+///
+/// Edge indices stay valid until edges are deleted; deleting edges may renumber the remaining ones.
+pub fn select_edges(
+    graph: &DiGraph<Node, Relation>,
+    relationship_types: Option<&[String]>,
+    node_indices: Option<Vec<usize>>,
+    directions: &[Direction],
+    filter: Option<&Filter>,
+) -> Vec<usize> {
+    let is_selected = |relation: &Relation| {
+        relationship_types.is_none_or(|types| relation.relation_type.is_in(types))
+            && filter.is_none_or(|f| f.matches_relation(relation))
+    };
+
+    let mut edges: Vec<usize> = match node_indices {
+        Some(indices) => indices.into_iter()
+            .flat_map(|index| directions.iter().flat_map(move |&direction| {
+                graph.edges_directed(NodeIndex::new(index), direction)
+            }))
+            .filter(|edge| is_selected(edge.weight()))
+            .map(|edge| edge.id().index())
+            .collect(),
+        None => graph.edge_references()
+            .filter(|edge| is_selected(edge.weight()))
+            .map(|edge| edge.id().index())
+            .collect(),
+    };
+    edges.sort_unstable();
+    edges.dedup();
+    edges
+}
+
+/// Keeps the edges whose relation matches the filter
+pub fn filter_edges(graph: &DiGraph<Node, Relation>, edges: Vec<usize>, filter: &Filter) -> Vec<usize> {
+    edges.into_iter()
+        .filter(|&edge| graph.edge_weight(EdgeIndex::new(edge)).is_some_and(|relation| filter.matches_relation(relation)))
+        .collect()
+}
+
+/// Returns the source or target node of each edge, without duplicates, in edge order
+pub fn edge_nodes(graph: &DiGraph<Node, Relation>, edges: &[usize], end: &str) -> PyResult<Vec<usize>> {
+    let use_source = match end {
+        "source" => true,
+        "target" => false,
+        _ => return Err(PyValueError::new_err(format!("Invalid edge end '{}', expected 'source' or 'target'", end))),
+    };
+    let mut seen = std::collections::HashSet::new();
+    Ok(edges.iter()
+        .filter_map(|&edge| graph.edge_endpoints(EdgeIndex::new(edge)))
+        .map(|(source, target)| if use_source { source.index() } else { target.index() })
+        .filter(|index| seen.insert(*index))
+        .collect())
+}
+
+/// Exports edges as dictionaries with their endpoints, relation type and attributes
+pub fn get_edge_attributes(
+    graph: &DiGraph<Node, Relation>,
+    py: Python,
+    edges: Vec<usize>,
+    specified_attributes: Option<Vec<String>>,
+) -> PyResult<PyObject> {
+    let include = |name: &str| specified_attributes.as_ref().is_none_or(|attrs| attrs.iter().any(|a| a == name));
+    let unique_id = |index: NodeIndex| match &graph[index] {
+        Node::StandardNode { unique_id, .. } => Some(unique_id.clone()),
+        Node::DataTypeNode { .. } => None,
+    };
+
+    let mut result_list = Vec::new();
+    for edge in edges {
+        let edge_index = EdgeIndex::new(edge);
+        let (Some(relation), Some((source, target))) = (graph.edge_weight(edge_index), graph.edge_endpoints(edge_index)) else {
+            continue;
+        };
+        let dict = PyDict::new(py);
+        if include("edge_id") {
+            dict.set_item("edge_id", edge)?;
+        }
+        if include("relation_type") {
+            dict.set_item("relation_type", &relation.relation_type)?;
+        }
+        if include("source_index") {
+            dict.set_item("source_index", source.index())?;
+        }
+        if include("source_id") {
+            dict.set_item("source_id", unique_id(source))?;
+        }
+        if include("target_index") {
+            dict.set_item("target_index", target.index())?;
+        }
+        if include("target_id") {
+            dict.set_item("target_id", unique_id(target))?;
+        }
+        for (key, value) in relation.attributes.iter().flatten() {
+            if include(key) {
+                dict.set_item(key, value.to_python_object(py, None)?)?;
+            }
+        }
+        result_list.push(dict);
+    }
+    Ok(PyList::new(py, &result_list).into())
+}
+
+/// Evaluates an expression over the attributes of each edge
+pub fn evaluate_edges(
+    graph: &DiGraph<Node, Relation>,
+    edges: Vec<usize>,
+    expression: &str,
+    aliases: Option<&HashMap<String, String>>,
+) -> PyResult<Vec<(usize, Value)>> {
+    let expr = parse_expression(expression)?;
+
+    let mut results = Vec::with_capacity(edges.len());
+    for edge in edges {
+        let Some(relation) = graph.edge_weight(EdgeIndex::new(edge)) else { continue };
+        let evaluator = Evaluator::new(|field: &str| {
+            let field = aliases.and_then(|a| a.get(field)).map_or(field, String::as_str);
+            relation.get_field(field)
+        });
+        let result = evaluator.evaluate(&expr)
+            .map_err(|e| PyValueError::new_err(format!("Failed to evaluate '{}' for edge {}: {}", expression, edge, e)))?;
+        results.push((edge, result));
+    }
+    Ok(results)
+}
+
+/// Stores calculated values as edge attributes, returning the number of edges updated
+pub fn store_edge_values(graph: &mut JournaledGraph, property: &str, values: Vec<(usize, Value)>) -> PyResult<usize> {
+    let mut updated = 0;
+    for (edge, value) in values {
+        let Some(value) = value.into_attribute() else { continue };
+        if let Some(relation) = graph.edge_weight_mut(EdgeIndex::new(edge)) {
+            relation.attributes.get_or_insert_with(HashMap::new).insert(property.to_string(), value);
+            updated += 1;
+        }
+    }
+    Ok(updated)
+}
+
+/// Removes the given edges, returning how many were removed
+pub fn delete_edges(graph: &mut JournaledGraph, mut edges: Vec<usize>) -> usize {
+    // Remove from the highest index down, since petgraph moves the last edge into each freed slot
+    edges.sort_unstable_by(|a, b| b.cmp(a));
+    edges.dedup();
+    edges.into_iter()
+        .filter(|&edge| graph.remove_edge(EdgeIndex::new(edge)).is_some())
+        .count()
+}
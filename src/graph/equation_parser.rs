@@ -0,0 +1,650 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+use crate::data_types::{AttributeValue, Decimal, MAX_DECIMAL_SCALE};
+
+/// A value produced while evaluating an expression
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Number(f64),
+    Str(String),
+    Bool(bool),
+    Null,
+    List(Vec<Value>),
+    Map(HashMap<String, Value>),
+    Decimal(Decimal),
+    Duration(i64), // Seconds
+}
+
+impl Value {
+    pub fn from_attribute(value: &AttributeValue) -> Self {
+        match value {
+            AttributeValue::Int(v) => Value::Number(*v as f64),
+            AttributeValue::Float(v) => Value::Number(*v),
+            AttributeValue::DateTime(v) => Value::Number(*v as f64),
+            // Midnight UTC, so dates compare with datetimes
+            AttributeValue::Date(v) => Value::Number(*v as f64 * 86_400.0),
+            AttributeValue::String(v) => Value::Str(v.clone()),
+            AttributeValue::Boolean(v) => Value::Bool(*v),
+            AttributeValue::Null => Value::Null,
+            AttributeValue::List(values) => Value::List(values.iter().map(Value::from_attribute).collect()),
+            AttributeValue::Map(entries) => {
+                Value::Map(entries.iter().map(|(key, value)| (key.clone(), Value::from_attribute(value))).collect())
+            },
+            AttributeValue::Decimal(v) => Value::Decimal(*v),
+            AttributeValue::Duration(v) => Value::Duration(*v),
+            // Canonical text, so UUIDs compare with string literals and unique IDs
+            AttributeValue::Uuid(_) => Value::Str(value.to_string()),
+            AttributeValue::Category(v) => Value::Str(v.label().to_string()),
+            AttributeValue::Bytes(_) => Value::Str(value.to_string()),
+        }
+    }
+
+    // The property value a result is stored as; None for null, which leaves the property unset
+    pub fn into_attribute(self) -> Option<AttributeValue> {
+        match self {
+            Value::Number(v) => Some(AttributeValue::Float(v)),
+            Value::Str(v) => Some(AttributeValue::String(v)),
+            Value::Bool(v) => Some(AttributeValue::Boolean(v)),
+            Value::Null => None,
+            Value::List(values) => Some(AttributeValue::List(values.into_iter().filter_map(Value::into_attribute).collect())),
+            Value::Map(entries) => Some(AttributeValue::Map(
+                entries.into_iter().filter_map(|(key, value)| Some((key, value.into_attribute()?))).collect(),
+            )),
+            Value::Decimal(v) => Some(AttributeValue::Decimal(v)),
+            Value::Duration(v) => Some(AttributeValue::Duration(v)),
+        }
+    }
+
+    pub fn as_number(&self) -> Option<f64> {
+        match self {
+            Value::Number(v) => Some(*v),
+            Value::Decimal(v) => Some(v.to_f64()),
+            Value::Duration(v) => Some(*v as f64),
+            _ => None,
+        }
+    }
+
+    // Numbers join decimal arithmetic by their shortest decimal form, so a literal 1.1 is exactly 1.1
+    fn as_decimal(&self) -> Option<Decimal> {
+        match self {
+            Value::Number(v) => Decimal::from_f64(*v),
+            Value::Decimal(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    pub fn is_true(&self) -> bool {
+        matches!(self, Value::Bool(true))
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Number(v) => write!(f, "{}", v),
+            Value::Str(v) => write!(f, "'{}'", v),
+            Value::Bool(v) => write!(f, "{}", v),
+            Value::Null => write!(f, "null"),
+            Value::List(values) => {
+                write!(f, "[")?;
+                for (i, value) in values.iter().enumerate() {
+                    write!(f, "{}{}", if i == 0 { "" } else { ", " }, value)?;
+                }
+                write!(f, "]")
+            },
+            Value::Map(entries) => {
+                let mut entries: Vec<_> = entries.iter().collect();
+                entries.sort_by(|a, b| a.0.cmp(b.0));
+                write!(f, "{{")?;
+                for (i, (key, value)) in entries.into_iter().enumerate() {
+                    write!(f, "{}{}: {}", if i == 0 { "" } else { ", " }, key, value)?;
+                }
+                write!(f, "}}")
+            },
+            Value::Decimal(v) => write!(f, "{}", v),
+            Value::Duration(v) => write!(f, "{}", AttributeValue::format_iso_duration(*v)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinaryOp {
+    Add, Subtract, Multiply, Divide, Modulo, Power,
+    Equal, NotEqual, Greater, GreaterEqual, Less, LessEqual,
+    And, Or,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UnaryOp {
+    Negate,
+    Not,
+}
+
+/// Parsed expression tree
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Literal(Value),
+    Variable(String),
+    Unary(UnaryOp, Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    Function(String, Vec<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Str(String),
+    Ident(String),
+    Op(String),
+    LParen,
+    RParen,
+    Comma,
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            _ if c.is_whitespace() => i += 1,
+            '(' => { tokens.push(Token::LParen); i += 1; },
+            ')' => { tokens.push(Token::RParen); i += 1; },
+            ',' => { tokens.push(Token::Comma); i += 1; },
+            '0'..='9' | '.' => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                    i += 1;
+                }
+                // Scientific notation, e.g. 1e-6
+                if i < chars.len() && (chars[i] == 'e' || chars[i] == 'E') {
+                    let mut j = i + 1;
+                    if j < chars.len() && (chars[j] == '+' || chars[j] == '-') {
+                        j += 1;
+                    }
+                    if j < chars.len() && chars[j].is_ascii_digit() {
+                        i = j;
+                        while i < chars.len() && chars[i].is_ascii_digit() {
+                            i += 1;
+                        }
+                    }
+                }
+                let text: String = chars[start..i].iter().collect();
+                let number = text.parse::<f64>().map_err(|_| format!("Invalid number '{}'", text))?;
+                tokens.push(Token::Number(number));
+            },
+            '\'' | '"' => {
+                let quote = c;
+                let start = i + 1;
+                i += 1;
+                while i < chars.len() && chars[i] != quote {
+                    i += 1;
+                }
+                if i >= chars.len() {
+                    return Err("Unterminated string literal".to_string());
+                }
+                tokens.push(Token::Str(chars[start..i].iter().collect()));
+                i += 1;
+            },
+            _ if c.is_alphabetic() || c == '_' => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_' || chars[i] == '.') {
+                    i += 1;
+                }
+                tokens.push(Token::Ident(chars[start..i].iter().collect()));
+            },
+            _ => {
+                let two: String = chars[i..(i + 2).min(chars.len())].iter().collect();
+                if ["==", "!=", ">=", "<=", "&&", "||"].contains(&two.as_str()) {
+                    tokens.push(Token::Op(two));
+                    i += 2;
+                } else if "+-*/%^<>=!".contains(c) {
+                    // A single '=' is accepted as equality
+                    tokens.push(Token::Op(if c == '=' { "==".to_string() } else { c.to_string() }));
+                    i += 1;
+                } else {
+                    return Err(format!("Unexpected character '{}' at position {}", c, i));
+                }
+            },
+        }
+    }
+    Ok(tokens)
+}
+
+/// Recursive descent parser for calculation and predicate expressions, e.g. `profit / revenue > 0.2`
+pub struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    pub fn new(expression: &str) -> Result<Self, String> {
+        Ok(Parser { tokens: tokenize(expression)?, position: 0 })
+    }
+
+    /// Parses the full expression, failing on trailing input
+    pub fn parse(&mut self) -> Result<Expr, String> {
+        let expr = self.parse_or()?;
+        match self.peek() {
+            None => Ok(expr),
+            Some(token) => Err(format!("Unexpected token {:?}", token)),
+        }
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    // Consumes the next token if it is one of the given operators or keywords
+    fn take_operator(&mut self, operators: &[&str]) -> Option<String> {
+        let matched = match self.peek() {
+            Some(Token::Op(op)) if operators.contains(&op.as_str()) => Some(op.clone()),
+            Some(Token::Ident(word)) if operators.contains(&word.to_lowercase().as_str()) => Some(word.to_lowercase()),
+            _ => None,
+        };
+        if matched.is_some() {
+            self.position += 1;
+        }
+        matched
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.parse_and()?;
+        while self.take_operator(&["or", "||"]).is_some() {
+            let rhs = self.parse_and()?;
+            lhs = Expr::Binary(BinaryOp::Or, Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.parse_not()?;
+        while self.take_operator(&["and", "&&"]).is_some() {
+            let rhs = self.parse_not()?;
+            lhs = Expr::Binary(BinaryOp::And, Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_not(&mut self) -> Result<Expr, String> {
+        if self.take_operator(&["not", "!"]).is_some() {
+            let operand = self.parse_not()?;
+            return Ok(Expr::Unary(UnaryOp::Not, Box::new(operand)));
+        }
+        self.parse_comparison()
+    }
+
+    fn parse_comparison(&mut self) -> Result<Expr, String> {
+        let lhs = self.parse_additive()?;
+        let op = match self.take_operator(&["==", "!=", ">", ">=", "<", "<="]) {
+            Some(op) => op,
+            None => return Ok(lhs),
+        };
+        let rhs = self.parse_additive()?;
+        let op = match op.as_str() {
+            "==" => BinaryOp::Equal,
+            "!=" => BinaryOp::NotEqual,
+            ">" => BinaryOp::Greater,
+            ">=" => BinaryOp::GreaterEqual,
+            "<" => BinaryOp::Less,
+            _ => BinaryOp::LessEqual,
+        };
+        Ok(Expr::Binary(op, Box::new(lhs), Box::new(rhs)))
+    }
+
+    fn parse_additive(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.parse_multiplicative()?;
+        while let Some(op) = self.take_operator(&["+", "-"]) {
+            let rhs = self.parse_multiplicative()?;
+            let op = if op == "+" { BinaryOp::Add } else { BinaryOp::Subtract };
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_multiplicative(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.parse_unary()?;
+        while let Some(op) = self.take_operator(&["*", "/", "%"]) {
+            let rhs = self.parse_unary()?;
+            let op = match op.as_str() {
+                "*" => BinaryOp::Multiply,
+                "/" => BinaryOp::Divide,
+                _ => BinaryOp::Modulo,
+            };
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, String> {
+        if self.take_operator(&["-"]).is_some() {
+            let operand = self.parse_unary()?;
+            return Ok(Expr::Unary(UnaryOp::Negate, Box::new(operand)));
+        }
+        self.parse_power()
+    }
+
+    fn parse_power(&mut self) -> Result<Expr, String> {
+        let base = self.parse_primary()?;
+        if self.take_operator(&["^"]).is_some() {
+            // Right associative: 2^3^2 == 2^(3^2)
+            let exponent = self.parse_unary()?;
+            return Ok(Expr::Binary(BinaryOp::Power, Box::new(base), Box::new(exponent)));
+        }
+        Ok(base)
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, String> {
+        match self.next() {
+            Some(Token::Number(v)) => Ok(Expr::Literal(Value::Number(v))),
+            Some(Token::Str(v)) => Ok(Expr::Literal(Value::Str(v))),
+            Some(Token::LParen) => {
+                let expr = self.parse_or()?;
+                match self.next() {
+                    Some(Token::RParen) => Ok(expr),
+                    _ => Err("Expected ')'".to_string()),
+                }
+            },
+            Some(Token::Ident(name)) => {
+                match name.to_lowercase().as_str() {
+                    "true" => return Ok(Expr::Literal(Value::Bool(true))),
+                    "false" => return Ok(Expr::Literal(Value::Bool(false))),
+                    "null" | "none" => return Ok(Expr::Literal(Value::Null)),
+                    _ => (),
+                }
+                if self.peek() != Some(&Token::LParen) {
+                    return Ok(Expr::Variable(name));
+                }
+                self.position += 1;
+                let mut args = Vec::new();
+                if self.peek() == Some(&Token::RParen) {
+                    self.position += 1;
+                    return Ok(Expr::Function(name.to_lowercase(), args));
+                }
+                loop {
+                    // `count(*)` style wildcard arguments
+                    if self.peek() == Some(&Token::Op("*".to_string())) {
+                        self.position += 1;
+                        args.push(Expr::Variable("*".to_string()));
+                    } else {
+                        args.push(self.parse_or()?);
+                    }
+                    match self.next() {
+                        Some(Token::Comma) => continue,
+                        Some(Token::RParen) => break,
+                        _ => return Err(format!("Expected ',' or ')' in call to '{}'", name)),
+                    }
+                }
+                Ok(Expr::Function(name.to_lowercase(), args))
+            },
+            Some(token) => Err(format!("Unexpected token {:?}", token)),
+            None => Err("Unexpected end of expression".to_string()),
+        }
+    }
+}
+
+/// Evaluates parsed expressions, resolving variables through a lookup function
+pub struct Evaluator<F> {
+    lookup: F,
+}
+
+impl<'a, F> Evaluator<F>
+where
+    F: Fn(&str) -> Option<Cow<'a, AttributeValue>>,
+{
+    pub fn new(lookup: F) -> Self {
+        Evaluator { lookup }
+    }
+
+    pub fn evaluate(&self, expr: &Expr) -> Result<Value, String> {
+        match expr {
+            Expr::Literal(value) => Ok(value.clone()),
+            Expr::Variable(name) => Ok((self.lookup)(name)
+                .map_or(Value::Null, |value| Value::from_attribute(&value))),
+            Expr::Unary(op, operand) => {
+                let value = self.evaluate(operand)?;
+                match (op, value) {
+                    (_, Value::Null) => Ok(Value::Null),
+                    (UnaryOp::Negate, Value::Number(v)) => Ok(Value::Number(-v)),
+                    (UnaryOp::Negate, Value::Decimal(v)) => Ok(Value::Decimal(v.neg())),
+                    (UnaryOp::Negate, Value::Duration(v)) => Ok(Value::Duration(v.saturating_neg())),
+                    (UnaryOp::Not, Value::Bool(v)) => Ok(Value::Bool(!v)),
+                    (op, value) => Err(format!("Cannot apply {:?} to {}", op, value)),
+                }
+            },
+            Expr::Binary(BinaryOp::And, lhs, rhs) => {
+                // Short-circuit so guards like `revenue > 0 and profit / revenue > 0.2` are cheap
+                if !self.evaluate(lhs)?.is_true() {
+                    return Ok(Value::Bool(false));
+                }
+                Ok(Value::Bool(self.evaluate(rhs)?.is_true()))
+            },
+            Expr::Binary(BinaryOp::Or, lhs, rhs) => {
+                if self.evaluate(lhs)?.is_true() {
+                    return Ok(Value::Bool(true));
+                }
+                Ok(Value::Bool(self.evaluate(rhs)?.is_true()))
+            },
+            Expr::Binary(op, lhs, rhs) => {
+                let lhs = self.evaluate(lhs)?;
+                let rhs = self.evaluate(rhs)?;
+                apply_binary(*op, lhs, rhs)
+            },
+            Expr::Function(name, args) => {
+                let values = args.iter()
+                    .map(|arg| self.evaluate(arg))
+                    .collect::<Result<Vec<_>, _>>()?;
+                apply_function(name, values)
+            },
+        }
+    }
+}
+
+fn apply_binary(op: BinaryOp, lhs: Value, rhs: Value) -> Result<Value, String> {
+    use BinaryOp::*;
+    match (op, &lhs, &rhs) {
+        // Missing values propagate through arithmetic and never satisfy a comparison
+        (Equal, Value::Null, Value::Null) => Ok(Value::Bool(true)),
+        (NotEqual, Value::Null, Value::Null) => Ok(Value::Bool(false)),
+        (Equal, Value::Null, _) | (Equal, _, Value::Null) => Ok(Value::Bool(false)),
+        (NotEqual, Value::Null, _) | (NotEqual, _, Value::Null) => Ok(Value::Bool(true)),
+        (Greater | GreaterEqual | Less | LessEqual, Value::Null, _)
+        | (Greater | GreaterEqual | Less | LessEqual, _, Value::Null) => Ok(Value::Bool(false)),
+        (_, Value::Null, _) | (_, _, Value::Null) => Ok(Value::Null),
+
+        // Decimals stay exact when combined with decimals or numbers; results beyond the fixed-point
+        // range are computed as floats instead
+        (_, Value::Decimal(_), Value::Number(_) | Value::Decimal(_)) | (_, Value::Number(_), Value::Decimal(_)) => {
+            let (Some(a), Some(b)) = (lhs.as_number(), rhs.as_number()) else { unreachable!("both operands are numeric") };
+            match lhs.as_decimal().zip(rhs.as_decimal()).and_then(|(x, y)| apply_decimal(op, x, y)) {
+                Some(value) => Ok(value),
+                None => apply_binary(op, Value::Number(a), Value::Number(b)),
+            }
+        },
+
+        (_, Value::Duration(_), _) | (_, _, Value::Duration(_)) => apply_duration(op, &lhs, &rhs)
+            .ok_or_else(|| format!("Cannot apply {:?} to {} and {}", op, lhs, rhs)),
+
+        (_, Value::Number(a), Value::Number(b)) => {
+            let (a, b) = (*a, *b);
+            Ok(match op {
+                Add => Value::Number(a + b),
+                Subtract => Value::Number(a - b),
+                Multiply => Value::Number(a * b),
+                Divide | Modulo if b == 0.0 => Value::Null,
+                Divide => Value::Number(a / b),
+                Modulo => Value::Number(a % b),
+                Power => Value::Number(a.powf(b)),
+                Equal => Value::Bool(a == b),
+                NotEqual => Value::Bool(a != b),
+                Greater => Value::Bool(a > b),
+                GreaterEqual => Value::Bool(a >= b),
+                Less => Value::Bool(a < b),
+                LessEqual => Value::Bool(a <= b),
+                And | Or => unreachable!("logical operators are evaluated with short-circuiting"),
+            })
+        },
+        (Add, Value::Str(a), Value::Str(b)) => Ok(Value::Str(format!("{}{}", a, b))),
+        (Equal | NotEqual | Greater | GreaterEqual | Less | LessEqual, Value::Str(a), Value::Str(b)) => {
+            Ok(Value::Bool(match op {
+                Equal => a == b,
+                NotEqual => a != b,
+                Greater => a > b,
+                GreaterEqual => a >= b,
+                Less => a < b,
+                _ => a <= b,
+            }))
+        },
+        (Equal, Value::Bool(a), Value::Bool(b)) => Ok(Value::Bool(a == b)),
+        (NotEqual, Value::Bool(a), Value::Bool(b)) => Ok(Value::Bool(a != b)),
+        (Equal, Value::List(a), Value::List(b)) => Ok(Value::Bool(a == b)),
+        (NotEqual, Value::List(a), Value::List(b)) => Ok(Value::Bool(a != b)),
+        (Equal, Value::Map(a), Value::Map(b)) => Ok(Value::Bool(a == b)),
+        (NotEqual, Value::Map(a), Value::Map(b)) => Ok(Value::Bool(a != b)),
+        _ => Err(format!("Cannot apply {:?} to {} and {}", op, lhs, rhs)),
+    }
+}
+
+// None when the operation has no exact decimal result (fractional or large powers) or overflows
+fn apply_decimal(op: BinaryOp, a: Decimal, b: Decimal) -> Option<Value> {
+    use BinaryOp::*;
+    Some(match op {
+        Add => Value::Decimal(a.checked_add(b)?),
+        Subtract => Value::Decimal(a.checked_sub(b)?),
+        Multiply => Value::Decimal(a.checked_mul(b)?),
+        Divide | Modulo if b.units == 0 => Value::Null,
+        Divide => Value::Decimal(a.checked_div(b)?),
+        Modulo => Value::Decimal(a.checked_rem(b)?),
+        Power => {
+            let exponent = u32::try_from(b.rescale(0).filter(|whole| whole == &b)?.units).ok().filter(|e| *e <= 64)?;
+            Value::Decimal((0..exponent).try_fold(Decimal::new(1, 0), |power, _| power.checked_mul(a))?)
+        },
+        Equal => Value::Bool(a == b),
+        NotEqual => Value::Bool(a != b),
+        Greater => Value::Bool(a > b),
+        GreaterEqual => Value::Bool(a >= b),
+        Less => Value::Bool(a < b),
+        LessEqual => Value::Bool(a <= b),
+        And | Or => unreachable!("logical operators are evaluated with short-circuiting"),
+    })
+}
+
+// Durations add up and scale by numbers, and compare with durations or numbers of seconds. Datetimes
+// are numbers of seconds, so a datetime shifted by a duration stays a datetime. None when the
+// operation makes no sense for durations or overflows
+fn apply_duration(op: BinaryOp, lhs: &Value, rhs: &Value) -> Option<Value> {
+    use BinaryOp::*;
+    let (a, b) = (lhs.as_number()?, rhs.as_number()?);
+    let seconds = |v: f64| Value::Duration(v.round() as i64);
+    Some(match (op, lhs, rhs) {
+        (Add, Value::Duration(x), Value::Duration(y)) => Value::Duration(x.checked_add(*y)?),
+        (Subtract, Value::Duration(x), Value::Duration(y)) => Value::Duration(x.checked_sub(*y)?),
+        (Divide | Modulo, _, _) if b == 0.0 => Value::Null,
+        (Divide, Value::Duration(_), Value::Duration(_)) => Value::Number(a / b),
+        (Modulo, Value::Duration(x), Value::Duration(y)) => Value::Duration(x % y),
+        (Add, _, _) => Value::Number(a + b),
+        (Subtract, _, Value::Duration(_)) => Value::Number(a - b),
+        (Multiply, Value::Duration(_), Value::Duration(_)) => return None,
+        (Multiply, _, _) => seconds(a * b),
+        (Divide, Value::Duration(_), _) => seconds(a / b),
+        (Equal, _, _) => Value::Bool(a == b),
+        (NotEqual, _, _) => Value::Bool(a != b),
+        (Greater, _, _) => Value::Bool(a > b),
+        (GreaterEqual, _, _) => Value::Bool(a >= b),
+        (Less, _, _) => Value::Bool(a < b),
+        (LessEqual, _, _) => Value::Bool(a <= b),
+        _ => return None,
+    })
+}
+
+fn apply_function(name: &str, args: Vec<Value>) -> Result<Value, String> {
+    let number = |value: &Value| match value {
+        Value::Number(v) => Ok(Some(*v)),
+        Value::Decimal(v) => Ok(Some(v.to_f64())),
+        Value::Null => Ok(None),
+        other => Err(format!("{}() expects numbers, got {}", name, other)),
+    };
+    match (name, args.as_slice()) {
+        // Time from `start` to `end`, e.g. date_diff(end_date, start_date) for the length of an outage
+        ("date_diff", [end, start]) => {
+            let (Some(end), Some(start)) = (number(end)?, number(start)?) else { return Ok(Value::Null) };
+            Ok(Value::Duration((end - start).round() as i64))
+        },
+        ("abs", [Value::Duration(v)]) => Ok(Value::Duration(v.saturating_abs())),
+        ("min" | "max", values) if values.iter().any(|value| matches!(value, Value::Duration(_)))
+            && values.iter().all(|value| matches!(value, Value::Duration(_) | Value::Null)) => {
+            let durations = values.iter().filter_map(|value| match value {
+                Value::Duration(v) => Some(*v),
+                _ => None,
+            });
+            let result = if name == "min" { durations.min() } else { durations.max() };
+            Ok(result.map_or(Value::Null, Value::Duration))
+        },
+        ("abs", [Value::Decimal(v)]) => Ok(Value::Decimal(v.abs())),
+        ("round", [Value::Decimal(v)]) => Ok(v.rescale(0).map_or(Value::Null, Value::Decimal)),
+        ("round", [Value::Decimal(v), Value::Number(digits)]) if (0.0..=MAX_DECIMAL_SCALE as f64).contains(digits) => {
+            Ok(v.rescale(*digits as u32).map_or(Value::Null, Value::Decimal))
+        },
+        ("min" | "max", values) if values.iter().any(|value| matches!(value, Value::Decimal(_)))
+            && values.iter().all(|value| matches!(value, Value::Decimal(_) | Value::Null)) => {
+            let decimals = values.iter().filter_map(|value| match value {
+                Value::Decimal(v) => Some(*v),
+                _ => None,
+            });
+            let result = if name == "min" {
+                decimals.reduce(|a, b| if b < a { b } else { a })
+            } else {
+                decimals.reduce(|a, b| if b > a { b } else { a })
+            };
+            Ok(result.map_or(Value::Null, Value::Decimal))
+        },
+        ("abs", [value]) => Ok(number(value)?.map_or(Value::Null, |v| Value::Number(v.abs()))),
+        ("round", [value]) => Ok(number(value)?.map_or(Value::Null, |v| Value::Number(v.round()))),
+        ("round", [value, digits]) => {
+            let (Some(v), Some(d)) = (number(value)?, number(digits)?) else { return Ok(Value::Null) };
+            let factor = 10f64.powi(d as i32);
+            Ok(Value::Number((v * factor).round() / factor))
+        },
+        ("sqrt", [value]) => Ok(number(value)?.map_or(Value::Null, |v| Value::Number(v.sqrt()))),
+        ("min" | "max", values) if !values.is_empty() => {
+            let mut numbers = Vec::new();
+            for value in values {
+                if let Some(v) = number(value)? {
+                    numbers.push(v);
+                }
+            }
+            let result = if name == "min" {
+                numbers.into_iter().reduce(f64::min)
+            } else {
+                numbers.into_iter().reduce(f64::max)
+            };
+            Ok(result.map_or(Value::Null, Value::Number))
+        },
+        // Number of elements of a list or characters of a string
+        ("len", [value]) => match value {
+            Value::List(values) => Ok(Value::Number(values.len() as f64)),
+            Value::Str(text) => Ok(Value::Number(text.chars().count() as f64)),
+            Value::Null => Ok(Value::Null),
+            other => Err(format!("len() expects a list or string, got {}", other)),
+        },
+        ("contains", [collection, item]) => match collection {
+            Value::List(values) => Ok(Value::Bool(values.contains(item))),
+            Value::Str(text) => match item {
+                Value::Str(part) => Ok(Value::Bool(text.contains(part.as_str()))),
+                other => Err(format!("contains() on a string expects a string, got {}", other)),
+            },
+            Value::Null => Ok(Value::Null),
+            other => Err(format!("contains() expects a list or string, got {}", other)),
+        },
+        _ => Err(format!("Unknown function {}() with {} argument(s)", name, args.len())),
+    }
+}
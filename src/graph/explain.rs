@@ -0,0 +1,61 @@
+use std::time::{Duration, Instant};
+
+// Upper bound on recorded steps, so long-running sessions don't grow the plan forever
+const MAX_STEPS: usize = 100;
+
+/// One selection operation with its node counts and run time
+pub struct PlanStep {
+    operation: String,
+    input: Option<usize>,  // None for operations starting a new selection
+    output: Result<usize, String>,
+    elapsed: Duration,
+}
+
+/// Records the selecting operations (filters, traversals, sorts) of the current pipeline
+#[derive(Default)]
+pub struct Plan {
+    steps: Vec<PlanStep>,
+}
+
+impl Plan {
+    /// Adds a step; steps without an input count start a new pipeline and clear the previous one
+    pub fn record(&mut self, operation: String, input: Option<usize>, output: Result<usize, String>, started: Instant) {
+        if input.is_none() {
+            self.steps.clear();
+        }
+        if self.steps.len() == MAX_STEPS {
+            self.steps.remove(0);
+        }
+        self.steps.push(PlanStep { operation, input, output, elapsed: started.elapsed() });
+    }
+
+    pub fn clear(&mut self) {
+        self.steps.clear();
+    }
+
+    /// Formats the steps with per-step node counts and timings, marking where the selection became empty
+    pub fn format(&self) -> String {
+        if self.steps.is_empty() {
+            return "No operations recorded".to_string();
+        }
+        let total: Duration = self.steps.iter().map(|step| step.elapsed).sum();
+        let mut lines = vec![format!("Plan ({} steps, {:.3} ms):", self.steps.len(), total.as_secs_f64() * 1000.0)];
+        let mut emptied = false;
+        for (i, step) in self.steps.iter().enumerate() {
+            let counts = match (&step.output, step.input) {
+                (Ok(output), Some(input)) => format!("{} -> {}", input, output),
+                (Ok(output), None) => output.to_string(),
+                (Err(e), _) => format!("failed: {}", e),
+            };
+            let mut line = format!(
+                "  {}. {}  [{}, {:.3} ms]", i + 1, step.operation, counts, step.elapsed.as_secs_f64() * 1000.0
+            );
+            if step.output == Ok(0) && !emptied {
+                line.push_str("  <- selection became empty here");
+                emptied = true;
+            }
+            lines.push(line);
+        }
+        lines.join("\n")
+    }
+}
@@ -0,0 +1,512 @@
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList};
+use pyo3::exceptions::PyValueError;
+use regex::{Regex, RegexBuilder};
+use unicode_normalization::UnicodeNormalization;
+use crate::schema::{Node, Relation};
+use crate::data_types::AttributeValue;
+
+/// A node filter parsed from the Python filter dictionaries.
+///
+/// Every dictionary is a conjunction of its entries. The special keys `$or`, `$and`
+/// and `$not` allow disjunction, explicit conjunction and negation to be nested freely:
+///
+/// `{"$or": [{"title": "A"}, {"title": "B"}], "$not": {"status": "closed"}}`
+///
+/// A field may also map to an operator dictionary, e.g. `{"title": {"regex": "^W-\\d+$"}}`
+/// or `{"title": {"glob": "W-*"}}`. Patterns are compiled once per filter call. Comparisons
+/// use `{"capacity": {">": 100}}` with the operators `==`, `!=`, `>`, `>=`, `<` and `<=`.
+/// List properties take `{"tags": {"contains": "gas"}}` and `{"tags": {"length": {">": 2}}}`.
+///
+/// `{"$valid_at": "2015-06-30"}` keeps relations whose `valid_from`/`valid_to` interval contains
+/// the date; a missing bound leaves the interval open on that side.
+pub enum Filter {
+    And(Vec<Filter>),
+    Or(Vec<Filter>),
+    Not(Box<Filter>),
+    Condition { field: String, condition: Condition, options: FilterOptions },
+    ValidAt(AttributeValue),
+}
+
+/// String matching options applying to every condition of a filter call
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FilterOptions {
+    pub case_insensitive: bool,
+    pub normalize: bool,  // Apply Unicode NFKC normalization before comparing strings
+    pub tolerance: f64,  // Numbers closer than this compare as equal
+}
+
+impl FilterOptions {
+    fn fold<'a>(&self, value: &'a str) -> Cow<'a, str> {
+        let value: Cow<str> = if self.normalize { Cow::Owned(value.nfkc().collect()) } else { Cow::Borrowed(value) };
+        if self.case_insensitive { Cow::Owned(value.to_lowercase()) } else { value }
+    }
+
+    fn fold_value<'a>(&self, value: &'a AttributeValue) -> Cow<'a, AttributeValue> {
+        match value {
+            AttributeValue::String(v) if self.case_insensitive || self.normalize => {
+                Cow::Owned(AttributeValue::String(self.fold(v).into_owned()))
+            },
+            AttributeValue::Category(v) if self.case_insensitive || self.normalize => {
+                Cow::Owned(AttributeValue::String(self.fold(v.label()).into_owned()))
+            },
+            AttributeValue::List(values) if self.case_insensitive || self.normalize => {
+                Cow::Owned(AttributeValue::List(values.iter().map(|v| self.fold_value(v).into_owned()).collect()))
+            },
+            _ => Cow::Borrowed(value),
+        }
+    }
+}
+
+/// The test applied to a single field value
+pub enum Condition {
+    Equals(String),
+    Regex(Regex),
+    Compare(Ordering, bool, AttributeValue),  // Matches when the ordering (or its negation) holds
+    Between(AttributeValue, AttributeValue),  // Inclusive range
+    Fuzzy(String, f64, Similarity),  // Matches when the similarity to the string reaches the threshold
+    Contains(AttributeValue),  // A list element equal to the value, or a substring of a string
+    Length(Vec<Condition>),  // Conditions on the number of list elements or string characters
+}
+
+/// String similarity measure used by fuzzy filters, scored from 0.0 to 1.0
+#[derive(Debug, Clone, Copy)]
+pub enum Similarity {
+    JaroWinkler,
+    Levenshtein,
+}
+
+impl Similarity {
+    fn from_name(name: &str) -> PyResult<Self> {
+        match name {
+            "jaro_winkler" => Ok(Similarity::JaroWinkler),
+            "levenshtein" => Ok(Similarity::Levenshtein),
+            _ => Err(PyValueError::new_err(format!(
+                "Unknown similarity '{}', expected 'jaro_winkler' or 'levenshtein'", name
+            ))),
+        }
+    }
+
+    fn score(&self, a: &str, b: &str) -> f64 {
+        match self {
+            Similarity::JaroWinkler => strsim::jaro_winkler(a, b),
+            Similarity::Levenshtein => strsim::normalized_levenshtein(a, b),
+        }
+    }
+}
+
+impl Filter {
+    /// Parses a list of filter dictionaries, all of which must match
+    pub fn from_list(filters: Vec<&PyDict>, options: FilterOptions) -> PyResult<Self> {
+        let parsed = filters.into_iter()
+            .map(|dict| Filter::from_dict(dict, options))
+            .collect::<PyResult<Vec<_>>>()?;
+        Ok(Filter::And(parsed))
+    }
+
+    /// Parses a single filter dictionary into a conjunction of its entries
+    pub fn from_dict(dict: &PyDict, options: FilterOptions) -> PyResult<Self> {
+        // `__tol` sets the numeric tolerance for the conditions of this dictionary and those nested in it
+        let mut options = options;
+        if let Some(tolerance) = dict.get_item("__tol") {
+            options.tolerance = tolerance.extract()?;
+            if options.tolerance.is_nan() || options.tolerance < 0.0 {
+                return Err(PyValueError::new_err("'__tol' must be a non-negative number"));
+            }
+        }
+
+        let mut parts = Vec::new();
+        for (key, value) in dict.iter() {
+            let key: String = key.extract()?;
+            let part = match key.as_str() {
+                "__tol" => continue,
+                "$or" => Filter::Or(parse_filter_list(&key, value, options)?),
+                "$and" => Filter::And(parse_filter_list(&key, value, options)?),
+                "$not" => {
+                    // Accept either a single dictionary or a list of dictionaries (negated as a whole)
+                    let inner = match value.downcast::<PyDict>() {
+                        Ok(inner) => Filter::from_dict(inner, options)?,
+                        Err(_) => Filter::And(parse_filter_list(&key, value, options)?),
+                    };
+                    Filter::Not(Box::new(inner))
+                },
+                "$valid_at" => Filter::valid_at(value.extract()?),
+                _ => parse_field(key, value, options)?,
+            };
+            parts.push(part);
+        }
+        Ok(Filter::And(parts))
+    }
+
+    /// Matches items valid at `at`: `valid_from <= at < valid_to`, where missing bounds always hold
+    pub fn valid_at(at: AttributeValue) -> Self {
+        Filter::ValidAt(parse_operand(at))
+    }
+
+    /// Evaluates the filter using `lookup` to resolve field names to values
+    pub fn matches<'a, F>(&self, lookup: &F) -> bool
+    where
+        F: Fn(&str) -> Option<Cow<'a, AttributeValue>>,
+    {
+        match self {
+            Filter::And(filters) => filters.iter().all(|f| f.matches(lookup)),
+            Filter::Or(filters) => filters.iter().any(|f| f.matches(lookup)),
+            Filter::Not(filter) => !filter.matches(lookup),
+            Filter::Condition { field, condition, options } => {
+                let value = lookup(field);
+                condition.matches(value.as_deref().map(|v| options.fold_value(v)).as_deref(), options.tolerance)
+            },
+            Filter::ValidAt(at) => {
+                // Empty strings, as left by blank CSV cells, count as missing bounds
+                let bound = |field: &str| lookup(field).filter(|v| !matches!(v.as_ref(), AttributeValue::String(s) if s.trim().is_empty()));
+                let started = bound("valid_from").is_none_or(|from| compare_values(&from, at).is_some_and(|o| o != Ordering::Greater));
+                started && bound("valid_to").is_none_or(|to| compare_values(&to, at) == Some(Ordering::Greater))
+            },
+        }
+    }
+
+    /// Evaluates the filter against a node's unique_id, title and attributes
+    pub fn matches_node(&self, node: &Node) -> bool {
+        self.matches(&|field: &str| node.get_field(field))
+    }
+
+    /// Evaluates the filter against a relation's attributes
+    pub fn matches_relation(&self, relation: &Relation) -> bool {
+        self.matches(&|field: &str| relation.get_field(field))
+    }
+}
+
+impl Condition {
+    fn from_operator(field: &str, operator: &str, value: &PyAny, options: FilterOptions) -> PyResult<Self> {
+        let operand = || -> PyResult<AttributeValue> {
+            Ok(options.fold_value(&parse_operand(value.extract()?)).into_owned())
+        };
+        if let Some((ordering, negate)) = comparison_operator(operator) {
+            return Ok(Condition::Compare(ordering, negate, operand()?));
+        }
+        match operator {
+            "between" => {
+                let bounds: Vec<AttributeValue> = value.extract()?;
+                let [low, high]: [AttributeValue; 2] = bounds.try_into().map_err(|_| PyValueError::new_err(format!(
+                    "'between' for '{}' expects a [low, high] pair", field
+                )))?;
+                let fold = |bound: AttributeValue| options.fold_value(&parse_operand(bound)).into_owned();
+                Ok(Condition::Between(fold(low), fold(high)))
+            },
+            "fuzzy" => {
+                // [text, threshold] with an optional similarity name as third element
+                let invalid = || PyValueError::new_err(format!(
+                    "'fuzzy' for '{}' expects a (text, threshold) pair or (text, threshold, similarity)", field
+                ));
+                let parts: Vec<&PyAny> = value.extract().map_err(|_| invalid())?;
+                let (target, threshold, similarity) = match parts.as_slice() {
+                    [target, threshold] => (target.extract::<String>()?, threshold.extract::<f64>()?, Similarity::JaroWinkler),
+                    [target, threshold, name] => (
+                        target.extract::<String>()?, threshold.extract::<f64>()?, Similarity::from_name(name.extract()?)?,
+                    ),
+                    _ => return Err(invalid()),
+                };
+                Ok(Condition::Fuzzy(options.fold(&target).into_owned(), threshold, similarity))
+            },
+            // Raw, so date-like text still matches as a substring; list elements parse it when matched
+            "contains" => Ok(Condition::Contains(options.fold_value(&value.extract()?).into_owned())),
+            "length" => match value.downcast::<PyDict>() {
+                Ok(operators) => operators.iter()
+                    .map(|(operator, operand)| Condition::from_operator(field, operator.extract()?, operand, options))
+                    .collect::<PyResult<Vec<_>>>()
+                    .map(Condition::Length),
+                Err(_) => Ok(Condition::Length(vec![Condition::Compare(Ordering::Equal, false, value.extract()?)])),
+            },
+            "regex" => {
+                let pattern: String = value.extract()?;
+                compile_pattern(field, &pattern, options).map(Condition::Regex)
+            },
+            "glob" => {
+                let pattern: String = value.extract()?;
+                compile_pattern(field, &glob_to_regex(&pattern), options).map(Condition::Regex)
+            },
+            _ => Err(PyValueError::new_err(format!(
+                "Unsupported filter operator '{}' for '{}'", operator, field
+            ))),
+        }
+    }
+
+    /// A comparison against `operand` for the operators `==`, `!=`, `>`, `>=`, `<` and `<=` (or their
+    /// names); `None` for any other operator
+    pub fn comparison(operator: &str, operand: AttributeValue, options: FilterOptions) -> Option<Self> {
+        let (ordering, negate) = comparison_operator(operator)?;
+        Some(Condition::Compare(ordering, negate, options.fold_value(&parse_operand(operand)).into_owned()))
+    }
+
+    fn matches(&self, value: Option<&AttributeValue>, tolerance: f64) -> bool {
+        match self {
+            Condition::Equals(expected) => value.is_some_and(|v| match v {
+                // UUIDs match text in any spelling
+                AttributeValue::Uuid(_) => AttributeValue::parse_uuid(expected).as_ref() == Some(v),
+                // Compared in place, without building the text of each value
+                AttributeValue::Category(category) => category.label() == expected,
+                AttributeValue::String(text) => text == expected,
+                v => v.to_string() == *expected,
+            }),
+            Condition::Regex(regex) => value.is_some_and(|v| regex.is_match(&v.to_string())),
+            // {"field": None} matches nodes without the field, {"field": {"!=": None}} those with it
+            Condition::Compare(ordering, negate, AttributeValue::Null) => {
+                *ordering == Ordering::Equal && value.is_none_or(AttributeValue::is_null) != *negate
+            },
+            Condition::Compare(ordering, negate, expected) => {
+                // Missing or incomparable values never match, even for negated comparisons
+                match value.and_then(|v| compare_with_tolerance(v, expected, tolerance)) {
+                    Some(actual) => (actual == *ordering) != *negate,
+                    None => false,
+                }
+            },
+            Condition::Fuzzy(target, threshold, similarity) => {
+                value.is_some_and(|v| similarity.score(&v.to_string(), target) >= *threshold)
+            },
+            Condition::Contains(expected) => match value {
+                Some(AttributeValue::List(values)) => {
+                    let expected = parse_operand(expected.clone());
+                    values.iter().any(|v| compare_with_tolerance(v, &expected, tolerance) == Some(Ordering::Equal))
+                },
+                Some(AttributeValue::String(text)) => text.contains(expected.to_string().as_str()),
+                Some(AttributeValue::Category(category)) => category.label().contains(expected.to_string().as_str()),
+                _ => false,
+            },
+            Condition::Length(conditions) => {
+                let length = match value {
+                    Some(AttributeValue::List(values)) => values.len(),
+                    Some(AttributeValue::String(text)) => text.chars().count(),
+                    Some(AttributeValue::Category(category)) => category.label().chars().count(),
+                    _ => return false,
+                };
+                let length = AttributeValue::Int(length as i64);
+                conditions.iter().all(|condition| condition.matches(Some(&length), tolerance))
+            },
+            Condition::Between(low, high) => value.is_some_and(|v| {
+                let above = compare_with_tolerance(v, low, tolerance).is_some_and(|o| o != Ordering::Less);
+                above && compare_with_tolerance(v, high, tolerance).is_some_and(|o| o != Ordering::Greater)
+            }),
+        }
+    }
+}
+
+/// Orders two values, comparing numbers across Int/Float/DateTime, dates with datetimes (at midnight),
+/// durations with numbers of seconds and parsing numeric strings
+pub fn compare_values(a: &AttributeValue, b: &AttributeValue) -> Option<Ordering> {
+    fn as_number(value: &AttributeValue) -> Option<f64> {
+        match value {
+            AttributeValue::Int(v) => Some(*v as f64),
+            AttributeValue::Float(v) => Some(*v),
+            AttributeValue::DateTime(v) => Some(*v as f64),
+            // Midnight UTC, so dates compare with datetimes
+            AttributeValue::Date(v) => Some(*v as f64 * 86_400.0),
+            AttributeValue::String(v) => v.trim().parse().ok(),
+            AttributeValue::Boolean(v) => Some(*v as u8 as f64),
+            AttributeValue::Decimal(v) => Some(v.to_f64()),
+            AttributeValue::Duration(v) => Some(*v as f64),
+            AttributeValue::Null | AttributeValue::Uuid(_) | AttributeValue::List(_) | AttributeValue::Map(_) => None,
+            AttributeValue::Category(_) | AttributeValue::Bytes(_) => None,
+        }
+    }
+    match (a, b) {
+        // Directly, as 64-bit IDs lose digits as floats
+        (AttributeValue::Int(x), AttributeValue::Int(y)) => Some(x.cmp(y)),
+        // Exactly against whole numbers and other decimals, so amounts at the boundary match
+        (AttributeValue::Decimal(x), AttributeValue::Decimal(_) | AttributeValue::Int(_)) => x.partial_cmp(&b.to_decimal(None)?),
+        (AttributeValue::Int(_), AttributeValue::Decimal(y)) => a.to_decimal(None)?.partial_cmp(y),
+        (AttributeValue::String(x), AttributeValue::String(y)) => Some(x.cmp(y)),
+        (AttributeValue::Boolean(x), AttributeValue::Boolean(y)) => Some(x.cmp(y)),
+        (AttributeValue::Date(x), AttributeValue::Date(y)) => Some(x.cmp(y)),
+        (AttributeValue::Duration(x), AttributeValue::Duration(y)) => Some(x.cmp(y)),
+        (AttributeValue::Uuid(x), AttributeValue::Uuid(y)) => Some(x.cmp(y)),
+        (AttributeValue::Bytes(x), AttributeValue::Bytes(y)) => Some(x.cmp(y)),
+        // Categories compare as their labels
+        (AttributeValue::Category(x), AttributeValue::Category(y)) => Some(x.label().cmp(y.label())),
+        (AttributeValue::Category(x), AttributeValue::String(y)) => Some(x.label().cmp(y.as_str())),
+        (AttributeValue::String(x), AttributeValue::Category(y)) => Some(x.as_str().cmp(y.label())),
+        // UUIDs match text in any spelling, including unique IDs given as uuid.UUID
+        (AttributeValue::String(x), AttributeValue::Uuid(y)) => match AttributeValue::parse_uuid(x)? {
+            AttributeValue::Uuid(x) => Some(x.cmp(y)),
+            _ => None,
+        },
+        (AttributeValue::Uuid(x), AttributeValue::String(y)) => match AttributeValue::parse_uuid(y)? {
+            AttributeValue::Uuid(y) => Some(x.cmp(&y)),
+            _ => None,
+        },
+        // Durations stored as ISO text ("PT2H") compare against timedelta operands and vice versa
+        (AttributeValue::String(x), AttributeValue::Duration(y)) => match AttributeValue::parse_iso_duration(x) {
+            Some(AttributeValue::Duration(x)) => Some(x.cmp(y)),
+            _ => as_number(a)?.partial_cmp(&as_number(b)?),
+        },
+        (AttributeValue::Duration(x), AttributeValue::String(y)) => match AttributeValue::parse_iso_duration(y) {
+            Some(AttributeValue::Duration(y)) => Some(x.cmp(&y)),
+            _ => as_number(a)?.partial_cmp(&as_number(b)?),
+        },
+        // Flags stored as text ("true", "no", ...) still compare against boolean operands
+        (AttributeValue::String(x), AttributeValue::Boolean(y)) => match AttributeValue::parse_bool(x)? {
+            AttributeValue::Boolean(x) => Some(x.cmp(y)),
+            _ => None,
+        },
+        (AttributeValue::Boolean(x), AttributeValue::String(y)) => match AttributeValue::parse_bool(y)? {
+            AttributeValue::Boolean(y) => Some(x.cmp(&y)),
+            _ => None,
+        },
+        // Dates stored as strings still compare against parsed date operands
+        (AttributeValue::String(x), AttributeValue::DateTime(y)) => match AttributeValue::parse_iso_datetime(x) {
+            Some(AttributeValue::DateTime(x)) => Some(x.cmp(y)),
+            _ => as_number(a)?.partial_cmp(&as_number(b)?),
+        },
+        (AttributeValue::DateTime(x), AttributeValue::String(y)) => match AttributeValue::parse_iso_datetime(y) {
+            Some(AttributeValue::DateTime(y)) => Some(x.cmp(&y)),
+            _ => as_number(a)?.partial_cmp(&as_number(b)?),
+        },
+        (AttributeValue::String(x), AttributeValue::Date(_)) => match AttributeValue::parse_iso_datetime(x) {
+            Some(x) => compare_values(&x, b),
+            None => as_number(a)?.partial_cmp(&as_number(b)?),
+        },
+        (AttributeValue::Date(_), AttributeValue::String(y)) => match AttributeValue::parse_iso_datetime(y) {
+            Some(y) => compare_values(a, &y),
+            None => as_number(a)?.partial_cmp(&as_number(b)?),
+        },
+        _ => as_number(a)?.partial_cmp(&as_number(b)?),
+    }
+}
+
+// The ordering a comparison operator tests for, and whether the test is negated
+fn comparison_operator(operator: &str) -> Option<(Ordering, bool)> {
+    match operator {
+        "==" | "eq" => Some((Ordering::Equal, false)),
+        "!=" | "ne" => Some((Ordering::Equal, true)),
+        ">" | "gt" => Some((Ordering::Greater, false)),
+        "<=" | "lte" => Some((Ordering::Greater, true)),
+        "<" | "lt" => Some((Ordering::Less, false)),
+        ">=" | "gte" => Some((Ordering::Less, true)),
+        _ => None,
+    }
+}
+
+// ISO date strings become DateTime operands so they compare against stored timestamps
+fn parse_operand(value: AttributeValue) -> AttributeValue {
+    match &value {
+        AttributeValue::String(v) => AttributeValue::parse_iso_datetime(v).unwrap_or(value),
+        _ => value,
+    }
+}
+
+// Like `compare_values`, but numbers within `tolerance` of each other are equal
+fn compare_with_tolerance(a: &AttributeValue, b: &AttributeValue, tolerance: f64) -> Option<Ordering> {
+    let ordering = compare_values(a, b)?;
+    if tolerance > 0.0 && ordering != Ordering::Equal {
+        if let (Some(x), Some(y)) = (as_float(a), as_float(b)) {
+            if (x - y).abs() <= tolerance {
+                return Some(Ordering::Equal);
+            }
+        }
+    }
+    Some(ordering)
+}
+
+fn as_float(value: &AttributeValue) -> Option<f64> {
+    match value {
+        AttributeValue::Int(v) => Some(*v as f64),
+        AttributeValue::Float(v) => Some(*v),
+        AttributeValue::Decimal(v) => Some(v.to_f64()),
+        _ => None,
+    }
+}
+
+// Operator suffixes accepted in `field__op` keys, e.g. {"ratio__eq": 0.3}
+const OPERATOR_SUFFIXES: [&str; 12] = ["eq", "ne", "gt", "gte", "lt", "lte", "between", "fuzzy", "regex", "glob", "contains", "length"];
+
+// A field maps either to a plain value (equality) or to a dictionary of operators, all of which must hold
+fn parse_field(field: String, value: &PyAny, options: FilterOptions) -> PyResult<Filter> {
+    if let Some((name, operator)) = field.rsplit_once("__") {
+        if !name.is_empty() && OPERATOR_SUFFIXES.contains(&operator) {
+            return Ok(Filter::Condition {
+                condition: Condition::from_operator(name, operator, value, options)?,
+                field: name.to_string(),
+                options,
+            });
+        }
+    }
+
+    if let Ok(operators) = value.downcast::<PyDict>() {
+        let mut conditions = Vec::new();
+        for (operator, operand) in operators.iter() {
+            let operator: String = operator.extract()?;
+            conditions.push(Filter::Condition {
+                condition: Condition::from_operator(&field, &operator, operand, options)?,
+                field: field.clone(),
+                options,
+            });
+        }
+        return Ok(Filter::And(conditions));
+    }
+
+    match value.extract::<String>() {
+        Ok(expected) => Ok(Filter::Condition {
+            field,
+            condition: Condition::Equals(options.fold(&expected).into_owned()),
+            options,
+        }),
+        // Numbers compare by value, so 3 matches 3.0 and `__tol` applies
+        Err(_) => match value.extract::<AttributeValue>() {
+            Ok(expected) => Ok(Filter::Condition {
+                field,
+                condition: Condition::Compare(Ordering::Equal, false, expected),
+                options,
+            }),
+            Err(_) => Err(PyValueError::new_err(format!(
+                "Unsupported filter value for '{}': {}", field, value
+            ))),
+        },
+    }
+}
+
+fn compile_pattern(field: &str, pattern: &str, options: FilterOptions) -> PyResult<Regex> {
+    let pattern: Cow<str> = if options.normalize { Cow::Owned(pattern.nfkc().collect()) } else { Cow::Borrowed(pattern) };
+    RegexBuilder::new(&pattern).case_insensitive(options.case_insensitive).build().map_err(|e| {
+        PyValueError::new_err(format!("Invalid pattern for '{}': {}", field, e))
+    })
+}
+
+// Translate a shell-style glob (`*`, `?`, `[...]`) into an anchored regular expression
+fn glob_to_regex(glob: &str) -> String {
+    let mut regex = String::from("^");
+    let mut in_class = false;
+    for c in glob.chars() {
+        match c {
+            '*' if !in_class => regex.push_str(".*"),
+            '?' if !in_class => regex.push('.'),
+            '[' if !in_class => {
+                in_class = true;
+                regex.push('[');
+            },
+            ']' if in_class => {
+                in_class = false;
+                regex.push(']');
+            },
+            '!' if in_class && regex.ends_with('[') => regex.push('^'),
+            _ if in_class => regex.push(c),
+            _ => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex.push('$');
+    regex
+}
+
+fn parse_filter_list(key: &str, value: &PyAny, options: FilterOptions) -> PyResult<Vec<Filter>> {
+    let list = value.downcast::<PyList>().map_err(|_| {
+        PyValueError::new_err(format!("'{}' expects a list of filter dictionaries", key))
+    })?;
+    list.iter()
+        .map(|item| {
+            let dict = item.downcast::<PyDict>().map_err(|_| {
+                PyValueError::new_err(format!("'{}' expects a list of filter dictionaries", key))
+            })?;
+            Filter::from_dict(dict, options)
+        })
+        .collect()
+}
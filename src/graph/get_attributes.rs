@@ -4,12 +4,13 @@ use pyo3::types::{PyDict, PyList};
 use petgraph::graph::{DiGraph, NodeIndex};
 use petgraph::visit::EdgeRef;
 use petgraph::Direction;
+use crate::graph::journal::JournaledGraph;
 use crate::schema::{Node, Relation};
 use crate::data_types::AttributeValue;
 use crate::graph::get_schema::retrieve_schema;
 
 pub fn get_node_attributes(
-    graph: &mut DiGraph<Node, Relation>,
+    graph: &mut JournaledGraph,
     py: Python,
     indices: Vec<usize>,
     specified_attributes: Option<Vec<String>>,
@@ -25,13 +26,13 @@ pub fn get_node_attributes(
     for index in &indices {
         let node_index = NodeIndex::new(*index);
         if let Some(Node::StandardNode { node_type, .. }) = graph.node_weight(node_index) {
-            if !schemas.contains_key(node_type) {
+            if !schemas.contains_key(node_type.as_str()) {
                 let schema = retrieve_schema(
                     graph,
                     "Node",
                     node_type,
                 ).expect("Failed to fetch schema");
-                schemas.insert(node_type.clone(), schema);
+                schemas.insert(node_type.to_string(), schema);
             }
         }
     }
@@ -39,8 +40,8 @@ pub fn get_node_attributes(
     // Main loop: Process each node using the pre-fetched schemas
     for index in indices {
         let node_index = NodeIndex::new(index);
-        if let Some(Node::StandardNode { node_type, unique_id, attributes, title }) = graph.node_weight(node_index) {
-            let schema = schemas.get(node_type).expect("Schema should be present");
+        if let Some(Node::StandardNode { node_type, unique_id, attributes, title, sequence }) = graph.node_weight(node_index) {
+            let schema = schemas.get(node_type.as_str()).expect("Schema should be present");
 
             let return_attributes = PyDict::new(py);
             if specified_attributes.as_ref().map_or(true, |attrs| attrs.contains(&"graph_id".to_string())) {
@@ -54,6 +55,12 @@ pub fn get_node_attributes(
             if specified_attributes.as_ref().map_or(true, |attrs| attrs.contains(&"unique_id".to_string())) {
                 return_attributes.set_item("unique_id", unique_id)?;
             }
+            // Check if "sequence" should be included (only recorded when tracking insertion order)
+            if let Some(seq) = sequence {
+                if specified_attributes.as_ref().is_none_or(|attrs| attrs.contains(&"sequence".to_string())) {
+                    return_attributes.set_item("sequence", seq)?;
+                }
+            }
             // Check if "title" should be included
             if let Some(t) = title {
                 if specified_attributes.as_ref().map_or(true, |attrs| attrs.contains(&"title".to_string())) {
@@ -149,4 +156,51 @@ fn extract_and_set_attributes(
         }
     }
     Ok(())
-}
\ No newline at end of file
+}
+/// Renders nodes as a Markdown table of unique_id, title and the requested (or all) properties,
+/// noting how many rows were left out beyond `max_rows`.
+pub fn nodes_to_markdown(
+    graph: &DiGraph<Node, Relation>,
+    indices: &[usize],
+    max_rows: usize,
+    properties: Option<Vec<String>>,
+) -> String {
+    let nodes: Vec<&Node> = indices.iter()
+        .filter_map(|&index| graph.node_weight(NodeIndex::new(index)))
+        .filter(|node| matches!(node, Node::StandardNode { .. }))
+        .collect();
+    let shown = &nodes[..max_rows.min(nodes.len())];
+
+    let columns = properties.unwrap_or_else(|| {
+        let mut keys: Vec<String> = shown.iter()
+            .flat_map(|node| match node {
+                Node::StandardNode { attributes, .. } => attributes.keys().cloned().collect(),
+                Node::DataTypeNode { .. } => Vec::new(),
+            })
+            .collect();
+        keys.sort();
+        keys.dedup();
+        ["unique_id".to_string(), "title".to_string()].into_iter().chain(keys).collect()
+    });
+
+    let mut table = format!("| {} |\n|{}\n", columns.join(" | "), "---|".repeat(columns.len()));
+    for node in shown {
+        let cells: Vec<String> = columns.iter()
+            .map(|column| node.get_field(column).map_or_else(String::new, |value| markdown_cell(&value)))
+            .collect();
+        table.push_str(&format!("| {} |\n", cells.join(" | ")));
+    }
+    if nodes.len() > shown.len() {
+        table.push_str(&format!("\n_{} more rows not shown_\n", nodes.len() - shown.len()));
+    }
+    table
+}
+
+fn markdown_cell(value: &AttributeValue) -> String {
+    let text = match value {
+        AttributeValue::DateTime(timestamp) => chrono::DateTime::from_timestamp(*timestamp, 0)
+            .map_or_else(|| timestamp.to_string(), |dt| dt.naive_utc().to_string()),
+        _ => value.to_string(),
+    };
+    text.replace('|', "\\|").replace('\n', " ")
+}
@@ -1,7 +1,15 @@
-use petgraph::graph::DiGraph;
-use std::collections::{HashMap, hash_map::Entry};
-use crate::schema::{Node, Relation};  // Import the Node enum
+use petgraph::graph::{DiGraph, EdgeIndex, NodeIndex};
+use petgraph::visit::EdgeRef;
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap, HashSet, hash_map::Entry};
+use crate::graph::journal::JournaledGraph;
+use crate::schema::{Cardinality, Constraint, Endpoints, KeyColumns, Node, Relation};  // Import the Node enum
+use crate::data_types::AttributeValue;
+use crate::graph::filters::compare_values;
+use crate::graph::io::tabular::ColumnTypes;
 use pyo3::prelude::*;
+use pyo3::types::PyDict;
 use pyo3::exceptions::PyValueError;
 
 /// Updates or retrieves the schema (DataTypeNode) from the graph
@@ -14,7 +22,7 @@ use pyo3::exceptions::PyValueError;
 /// * `columns` - Optional list of columns to update in the DataTypeNode
 /// * `column_types` - Optional mapping of column names to their data types
 pub fn update_or_retrieve_schema(
-    graph: &mut DiGraph<Node, Relation>,
+    graph: &mut JournaledGraph,
     data_type: &str,
     name: &str,
     columns: Option<Vec<String>>,
@@ -45,6 +53,8 @@ pub fn update_or_retrieve_schema(
                     .clone();
 
                 match attr.entry(column.clone()) {
+                    // Types read off values ("Decimal", "DateTime") match the type declared with a scale or time zone
+                    Entry::Occupied(entry) if entry.get().strip_prefix(column_data_type.as_str()).is_some_and(|rest| rest.starts_with('(')) => (),
                     Entry::Occupied(entry) if entry.get() != &column_data_type => {
                         return Err(PyErr::new::<PyValueError, _>(format!(
                             "Data type conflict for attribute '{}': existing type '{}', new type '{}'",
@@ -94,4 +104,330 @@ pub fn retrieve_schema(
     } else {
         Err(PyErr::new::<PyValueError, _>("Failed to retrieve DataTypeNode"))
     }
-}
\ No newline at end of file
+}
+/// Finds the DataTypeNode holding the schema for the given data type and name
+pub fn find_schema_node(
+    graph: &DiGraph<Node, Relation>,
+    data_type: &str,
+    name: &str,
+) -> Option<NodeIndex> {
+    graph.node_indices().find(|&i| {
+        matches!(&graph[i], Node::DataTypeNode { data_type: dt, name: n, .. } if dt.as_str() == data_type && n == name)
+    })
+}
+
+/// Records the columns the unique ID and title of `node_type` nodes are ingested from; a later
+/// ingestion without a title column keeps the one recorded
+pub fn record_key_columns(graph: &mut JournaledGraph, node_type: &str, unique_id: &[String], title: Option<&str>) {
+    let Some(index) = find_schema_node(graph, "Node", node_type) else { return };
+    if let Node::DataTypeNode { keys, .. } = &mut graph[index] {
+        keys.unique_id = unique_id.to_vec();
+        if let Some(title) = title {
+            keys.title = Some(title.to_string());
+        }
+    }
+}
+
+/// Records `relation_type` for relations about to be copied from `edges`: the types of their
+/// properties, taken from the schema of the relations copied where listed, and, unless endpoints
+/// are declared already, the node types they run between when all of them join the same pair
+pub fn record_copied_relations(graph: &mut JournaledGraph, relation_type: &str, edges: &[EdgeIndex]) -> PyResult<()> {
+    let mut column_types: HashMap<String, String> = HashMap::new();
+    let mut pairs: HashSet<(&str, &str)> = HashSet::new();
+    for &edge in edges {
+        let relation = &graph[edge];
+        let (source, target) = graph.edge_endpoints(edge).expect("Edge must exist");
+        if let (Node::StandardNode { node_type: from, .. }, Node::StandardNode { node_type: to, .. }) = (&graph[source], &graph[target]) {
+            pairs.insert((from.as_str(), to.as_str()));
+        }
+        let copied = find_schema_node(graph, "Relation", &relation.relation_type).map(|index| &graph[index]);
+        for (property, value) in relation.attributes.iter().flatten().filter(|(_, value)| !value.is_null()) {
+            column_types.entry(property.clone()).or_insert_with(|| match copied {
+                Some(Node::DataTypeNode { attributes, .. }) if attributes.contains_key(property) => attributes[property].clone(),
+                _ => value.type_name().to_string(),
+            });
+        }
+    }
+    let pair = match pairs.len() {
+        1 => pairs.into_iter().next().map(|(from, to)| (from.to_string(), to.to_string())),
+        _ => None,
+    };
+    update_or_retrieve_schema(graph, "Relation", relation_type, Some(column_types.keys().cloned().collect()), Some(column_types))?;
+    let index = find_schema_node(graph, "Relation", relation_type).expect("Schema was just recorded");
+    if let (Node::DataTypeNode { endpoints: endpoints @ None, .. }, Some((source_type, target_type))) = (&mut graph[index], pair) {
+        *endpoints = Some(Endpoints { source_type, target_type, cardinality: Cardinality::ManyToMany });
+    }
+    Ok(())
+}
+
+// Value of a schema attribute on a node, None when missing. Key columns are read from the node's
+// unique ID and title, in the type the schema lists for them where they convert.
+fn property_value<'a>(node: &'a Node, attribute: &str, keys: &KeyColumns, key_types: &ColumnTypes) -> Option<Cow<'a, AttributeValue>> {
+    let Node::StandardNode { unique_id, title, attributes, .. } = node else { return None };
+    if keys.contains(attribute) {
+        let text = keys.value(attribute, unique_id, title.as_deref())?;
+        let value = key_types.convert(attribute, AttributeValue::String(text.clone())).unwrap_or(AttributeValue::String(text));
+        return Some(Cow::Owned(value));
+    }
+    attributes.get(attribute).filter(|value| !value.is_null()).map(Cow::Borrowed)
+}
+
+// Most nodes of one type profiled by `include_stats`; larger types are sampled evenly
+const STATS_SAMPLE: usize = 100_000;
+
+/// Returns the schema of each node type as a Python dict: the node count, attribute types,
+/// per-attribute null counts, calculated attributes, constraints and defaults
+///
+/// # Arguments
+///
+/// * `graph` - The graph object containing all nodes and relations
+/// * `node_types` - Optional list of node types to include, defaults to all
+/// * `include_values` - Include distinct values and counts for low-cardinality String and Category attributes
+/// * `max_distinct` - Cardinality threshold for `include_values`, defaults to 50
+/// * `include_stats` - Include distinct counts, min, max and the most common values of each attribute
+/// * `top_k` - Number of most common values listed by `include_stats`, defaults to 5
+pub fn get_node_schemas(
+    graph: &DiGraph<Node, Relation>,
+    py: Python,
+    node_types: Option<Vec<String>>,
+    include_values: bool,
+    max_distinct: Option<usize>,
+    include_stats: bool,
+    top_k: Option<usize>,
+) -> PyResult<PyObject> {
+    let max_distinct = max_distinct.unwrap_or(50);
+    let result = PyDict::new(py);
+
+    for node in graph.node_weights() {
+        let Node::DataTypeNode { data_type, name, attributes, calculated, constraints, defaults, keys, .. } = node else { continue };
+        if data_type != "Node" || node_types.as_ref().is_some_and(|types| !types.contains(name)) {
+            continue;
+        }
+
+        let string_attributes: HashSet<&String> = attributes.iter()
+            .filter(|(_, t)| matches!(t.as_str(), "String" | "Category"))
+            .map(|(attribute, _)| attribute)
+            .collect();
+        let key_types = ColumnTypes::new(Some(
+            attributes.iter().filter(|(attribute, _)| keys.contains(attribute)).map(|(k, v)| (k.clone(), v.clone())).collect(),
+        ));
+        let mut count = 0;
+        // Nodes without a value for each attribute; 0, 0.0 and "" are values, not nulls
+        let mut nulls: HashMap<&String, usize> = attributes.keys().map(|attribute| (attribute, 0)).collect();
+        let mut value_counts: HashMap<&String, BTreeMap<Cow<str>, usize>> = HashMap::new();
+        let mut members = Vec::new();
+        for other in graph.node_weights() {
+            let Node::StandardNode { node_type, .. } = other else { continue };
+            if node_type != name {
+                continue;
+            }
+            count += 1;
+            if include_stats {
+                members.push(other);
+            }
+            for (attribute, missing) in nulls.iter_mut() {
+                if property_value(other, attribute, keys, &key_types).is_none() {
+                    *missing += 1;
+                }
+            }
+            if !include_values {
+                continue;
+            }
+            for &attribute in &string_attributes {
+                let label = match property_value(other, attribute, keys, &key_types) {
+                    Some(Cow::Borrowed(AttributeValue::String(v))) => Cow::Borrowed(v.as_str()),
+                    Some(Cow::Borrowed(AttributeValue::Category(v))) => Cow::Borrowed(v.label()),
+                    Some(Cow::Owned(AttributeValue::String(v))) => Cow::Owned(v),
+                    Some(Cow::Owned(AttributeValue::Category(v))) => Cow::Owned(v.label().to_string()),
+                    _ => continue,
+                };
+                let counts = value_counts.entry(attribute).or_default();
+                // Stop tracking once the attribute exceeds the threshold; it is not categorical
+                if counts.len() <= max_distinct {
+                    *counts.entry(label).or_insert(0) += 1;
+                }
+            }
+        }
+
+        let schema = PyDict::new(py);
+        schema.set_item("count", count)?;
+        schema.set_item("attributes", attributes.clone())?;
+        schema.set_item("nulls", nulls)?;
+        let mut calculated: Vec<&String> = calculated.iter().collect();
+        calculated.sort();
+        schema.set_item("calculated", calculated)?;
+        let rules = PyDict::new(py);
+        for (attribute, constraints) in constraints {
+            let mut listed = Vec::with_capacity(constraints.len());
+            for constraint in constraints {
+                let rule = PyDict::new(py);
+                rule.set_item("kind", constraint.kind())?;
+                if let Constraint::Range(min, max) = constraint {
+                    for (key, bound) in [("min", min), ("max", max)] {
+                        let bound = bound.as_ref().map(|v| v.to_python_object(py, None)).transpose()?;
+                        rule.set_item(key, bound)?;
+                    }
+                }
+                listed.push(rule);
+            }
+            rules.set_item(attribute, listed)?;
+        }
+        schema.set_item("constraints", rules)?;
+        let default_values = PyDict::new(py);
+        for (attribute, value) in defaults {
+            default_values.set_item(attribute, value.to_python_object(py, None)?)?;
+        }
+        schema.set_item("defaults", default_values)?;
+        if include_values {
+            let values = PyDict::new(py);
+            for (attribute, counts) in value_counts {
+                if counts.len() <= max_distinct {
+                    values.set_item(attribute, counts.into_iter().collect::<HashMap<_, _>>())?;
+                }
+            }
+            schema.set_item("values", values)?;
+        }
+        if include_stats {
+            let step = members.len().div_ceil(STATS_SAMPLE).max(1);
+            let sample: Vec<_> = members.into_iter().step_by(step).collect();
+            schema.set_item("stats", attribute_stats(py, attributes, keys, &key_types, &sample, top_k.unwrap_or(5))?)?;
+            schema.set_item("stats_sample", sample.len())?;
+        }
+        result.set_item(name, schema)?;
+    }
+
+    Ok(result.into())
+}
+
+/// Returns the schema of each relation type as a Python dict: the edge count, the source and target
+/// node types it connects with an edge count for each pair, property types, per-property null
+/// counts and any declared endpoints. Property types come from the recorded relation schema where there is one, otherwise
+/// from the first value seen.
+///
+/// # Arguments
+///
+/// * `graph` - The graph object containing all nodes and relations
+/// * `relation_types` - Optional list of relation types to include, defaults to all
+pub fn get_relation_schemas(
+    graph: &DiGraph<Node, Relation>,
+    py: Python,
+    relation_types: Option<Vec<String>>,
+) -> PyResult<PyObject> {
+    #[derive(Default)]
+    struct Summary<'a> {
+        count: usize,
+        endpoints: BTreeMap<(&'a str, &'a str), usize>,
+        attributes: HashMap<String, String>,
+        present: HashMap<&'a str, usize>,
+        declared: Option<&'a Endpoints>,
+    }
+    let mut summaries: BTreeMap<&str, Summary> = BTreeMap::new();
+    for edge in graph.edge_references() {
+        let relation = edge.weight();
+        if relation_types.as_ref().is_some_and(|types| !relation.relation_type.is_in(types)) {
+            continue;
+        }
+        let (Node::StandardNode { node_type: source, .. }, Node::StandardNode { node_type: target, .. }) =
+            (&graph[edge.source()], &graph[edge.target()]) else { continue };
+        let summary = summaries.entry(relation.relation_type.as_str()).or_default();
+        summary.count += 1;
+        *summary.endpoints.entry((source.as_str(), target.as_str())).or_insert(0) += 1;
+        for (name, value) in relation.attributes.iter().flatten() {
+            // Types read off the values are kept only for properties the schema does not list
+            summary.attributes.entry(name.clone()).or_insert_with(|| value.type_name().to_string());
+            *summary.present.entry(name.as_str()).or_insert(0) += 1;
+        }
+    }
+    // The schema recorded at ingestion gives the property types. Declared relation types are listed
+    // even before they have edges
+    for node in graph.node_weights() {
+        let Node::DataTypeNode { data_type, name, attributes, endpoints, .. } = node else { continue };
+        if data_type != "Relation" || relation_types.as_ref().is_some_and(|types| !types.contains(name)) {
+            continue;
+        }
+        let summary = match summaries.get_mut(name.as_str()) {
+            Some(summary) => summary,
+            None if endpoints.is_some() => summaries.entry(name.as_str()).or_default(),
+            None => continue,
+        };
+        summary.attributes.extend(attributes.iter().map(|(k, v)| (k.clone(), v.clone())));
+        summary.declared = endpoints.as_ref();
+    }
+
+    let result = PyDict::new(py);
+    for (relation_type, summary) in summaries {
+        let schema = PyDict::new(py);
+        schema.set_item("count", summary.count)?;
+        let mut endpoints = Vec::with_capacity(summary.endpoints.len());
+        for ((source, target), count) in summary.endpoints {
+            let endpoint = PyDict::new(py);
+            endpoint.set_item("source_type", source)?;
+            endpoint.set_item("target_type", target)?;
+            endpoint.set_item("count", count)?;
+            endpoints.push(endpoint);
+        }
+        schema.set_item("endpoints", endpoints)?;
+        // Edges without a value for each property, as for node schemas
+        let nulls: HashMap<&String, usize> = summary.attributes.keys()
+            .map(|name| (name, summary.count - summary.present.get(name.as_str()).copied().unwrap_or(0)))
+            .collect();
+        schema.set_item("nulls", nulls)?;
+        schema.set_item("attributes", summary.attributes)?;
+        if let Some(endpoints) = summary.declared {
+            let declared = PyDict::new(py);
+            declared.set_item("source_type", &endpoints.source_type)?;
+            declared.set_item("target_type", &endpoints.target_type)?;
+            declared.set_item("cardinality", endpoints.cardinality.as_str())?;
+            schema.set_item("declared", declared)?;
+        }
+        result.set_item(relation_type, schema)?;
+    }
+    Ok(result.into())
+}
+
+// Distinct count, min, max and most common values of each attribute over the given nodes, with
+// key columns read as for null counts. Values count as the same when their text is; lists and maps
+// have no min or max.
+fn attribute_stats<'py>(
+    py: Python<'py>,
+    attributes: &HashMap<String, String>,
+    keys: &KeyColumns,
+    key_types: &ColumnTypes,
+    sample: &[&Node],
+    top_k: usize,
+) -> PyResult<&'py PyDict> {
+    let stats = PyDict::new(py);
+    for attribute in attributes.keys() {
+        let mut counts: HashMap<String, (usize, Cow<AttributeValue>)> = HashMap::new();
+        for node in sample {
+            let Some(value) = property_value(node, attribute, keys, key_types) else { continue };
+            counts.entry(value.to_export_string()).or_insert((0, value)).0 += 1;
+        }
+        let (mut min, mut max): (Option<&AttributeValue>, Option<&AttributeValue>) = (None, None);
+        for (_, value) in counts.values() {
+            let value = value.as_ref();
+            if matches!(value, AttributeValue::List(_) | AttributeValue::Map(_)) {
+                continue;
+            }
+            if min.is_none_or(|min| compare_values(value, min).is_some_and(Ordering::is_lt)) {
+                min = Some(value);
+            }
+            if max.is_none_or(|max| compare_values(value, max).is_some_and(Ordering::is_gt)) {
+                max = Some(value);
+            }
+        }
+        let mut top: Vec<(&String, &(usize, Cow<AttributeValue>))> = counts.iter().collect();
+        top.sort_unstable_by(|a, b| b.1.0.cmp(&a.1.0).then_with(|| a.0.cmp(b.0)));
+
+        let entry = PyDict::new(py);
+        entry.set_item("distinct", counts.len())?;
+        entry.set_item("min", min.map(|v| v.to_python_object(py, None)).transpose()?)?;
+        entry.set_item("max", max.map(|v| v.to_python_object(py, None)).transpose()?)?;
+        let top = top.into_iter().take(top_k)
+            .map(|(_, (count, value))| Ok((value.to_python_object(py, None)?, *count)))
+            .collect::<PyResult<Vec<_>>>()?;
+        entry.set_item("top", top)?;
+        stats.set_item(attribute, entry)?;
+    }
+    Ok(stats)
+}
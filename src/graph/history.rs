@@ -0,0 +1,285 @@
+use pyo3::prelude::*;
+use pyo3::exceptions::PyIOError;
+use pyo3::types::PyDict;
+use petgraph::graph::{DiGraph, EdgeIndex, NodeIndex};
+use chrono::Utc;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use crate::graph::compare_graphs::{changed_attributes, NodeKey};
+use crate::graph::journal::Event;
+use crate::schema::{Node, Relation};
+use crate::data_types::AttributeValue;
+
+/// A property set, changed or removed, as (name, old value, new value)
+type PropertyChange = (String, Option<AttributeValue>, Option<AttributeValue>);
+
+/// One node or relation created, updated or deleted by an operation
+pub struct Change {
+    timestamp: i64,
+    operation: String,
+    action: &'static str,  // "created", "updated" or "deleted"
+    node: NodeKey,  // The node, or the source of the relation
+    relation: Option<(String, NodeKey)>,  // Relation type and target for relation changes
+    properties: Vec<PropertyChange>,
+}
+
+impl Change {
+    fn new(timestamp: i64, operation: &str, before: Option<Subject>, after: Option<Subject>, properties: Vec<PropertyChange>) -> Self {
+        let action = match (&before, &after) {
+            (None, _) => "created",
+            (_, None) => "deleted",
+            _ => "updated",
+        };
+        let (node, relation) = after.or(before).expect("a change has a subject before or after it");
+        Change { timestamp, operation: operation.to_string(), action, node, relation, properties }
+    }
+
+    fn involves(&self, unique_id: &str, node_type: Option<&str>) -> bool {
+        let matches = |key: &NodeKey| key.1 == unique_id && node_type.is_none_or(|node_type| key.0 == node_type);
+        matches(&self.node) || self.relation.as_ref().is_some_and(|(_, target)| matches(target))
+    }
+
+    pub fn to_py(&self, py: Python) -> PyResult<PyObject> {
+        let change = PyDict::new(py);
+        change.set_item("timestamp", value_to_py(py, &AttributeValue::DateTime(self.timestamp))?)?;
+        change.set_item("operation", &self.operation)?;
+        change.set_item("action", self.action)?;
+        match &self.relation {
+            None => {
+                change.set_item("entity", "node")?;
+                change.set_item("node_type", &self.node.0)?;
+                change.set_item("unique_id", &self.node.1)?;
+            },
+            Some((relation_type, target)) => {
+                change.set_item("entity", "relation")?;
+                change.set_item("relation_type", relation_type)?;
+                change.set_item("source_type", &self.node.0)?;
+                change.set_item("source_id", &self.node.1)?;
+                change.set_item("target_type", &target.0)?;
+                change.set_item("target_id", &target.1)?;
+            },
+        }
+        let to_py = |value: &Option<AttributeValue>| match value {
+            Some(value) => value_to_py(py, value),
+            None => Ok(py.None()),
+        };
+        let properties = PyDict::new(py);
+        for (name, old, new) in &self.properties {
+            properties.set_item(name, (to_py(old)?, to_py(new)?))?;
+        }
+        change.set_item("changes", properties)?;
+        Ok(change.into())
+    }
+
+    fn to_json(&self) -> Value {
+        let to_json = |value: &Option<AttributeValue>| value.as_ref().map_or(Value::Null, AttributeValue::to_json);
+        let properties: Map<String, Value> = self.properties.iter()
+            .map(|(name, old, new)| (name.clone(), json!([to_json(old), to_json(new)])))
+            .collect();
+        let mut record = json!({
+            "timestamp": AttributeValue::DateTime(self.timestamp).to_json(),
+            "operation": self.operation,
+            "action": self.action,
+        });
+        let fields = match &self.relation {
+            None => json!({ "entity": "node", "node_type": self.node.0, "unique_id": self.node.1 }),
+            Some((relation_type, target)) => json!({
+                "entity": "relation", "relation_type": relation_type, "source_type": self.node.0, "source_id": self.node.1,
+                "target_type": target.0, "target_id": target.1,
+            }),
+        };
+        if let (Value::Object(record), Value::Object(fields)) = (&mut record, fields) {
+            record.extend(fields);
+            record.insert("changes".to_string(), Value::Object(properties));
+        }
+        record
+    }
+}
+
+/// The changes made by each operation, found from the events the graph logs at its mutation sites
+/// when the operation ends
+pub struct ChangeLog {
+    changes: Vec<Change>,
+    pub recorded: usize,  // Position in the graph's event log up to which changes are logged
+}
+
+impl ChangeLog {
+    pub fn new(position: usize) -> Self {
+        ChangeLog { changes: Vec::new(), recorded: position }
+    }
+
+    /// Logs the changes made by `events` as the changes of `operation`
+    pub fn record(&mut self, operation: &str, events: &[Event], graph: &DiGraph<Node, Relation>) {
+        let timestamp = Utc::now().timestamp();
+        for Diff { before, after, properties } in diffs(events, graph) {
+            self.changes.push(Change::new(timestamp, operation, before, after, properties));
+        }
+    }
+
+    /// Logs undoing `events` as the changes of `operation`: what they created is deleted, what
+    /// they deleted is created again and updates are reversed. Called before the events are undone.
+    pub fn record_undo(&mut self, operation: &str, events: &[Event], graph: &DiGraph<Node, Relation>) {
+        let timestamp = Utc::now().timestamp();
+        for Diff { before, after, properties } in diffs(events, graph).into_iter().rev() {
+            let properties = properties.into_iter().map(|(name, old, new)| (name, new, old)).collect();
+            self.changes.push(Change::new(timestamp, operation, after, before, properties));
+        }
+    }
+
+    /// The changes to the nodes with `unique_id` (of `node_type` if given) and their relations,
+    /// or all changes, oldest first
+    pub fn history(&self, unique_id: Option<&str>, node_type: Option<&str>) -> Vec<&Change> {
+        self.changes.iter()
+            .filter(|change| unique_id.is_none_or(|unique_id| change.involves(unique_id, node_type)))
+            .collect()
+    }
+
+    /// Writes every change as one JSON object per line; returns the number written
+    pub fn export(&self, path: &str) -> PyResult<usize> {
+        let error = |e: std::io::Error| PyIOError::new_err(format!("Cannot write '{}': {}", path, e));
+        let mut file = BufWriter::new(File::create(path).map_err(error)?);
+        for change in &self.changes {
+            writeln!(file, "{}", change.to_json()).map_err(error)?;
+        }
+        file.flush().map_err(error)?;
+        Ok(self.changes.len())
+    }
+}
+
+// Dates and times are given as UTC datetimes, the other values as their Python equivalents
+fn value_to_py(py: Python, value: &AttributeValue) -> PyResult<PyObject> {
+    match value {
+        AttributeValue::DateTime(_) => value.to_python_object(py, Some("DateTime(UTC)")),
+        _ => value.to_python_object(py, None),
+    }
+}
+
+/// A node, or a relation as (source, (relation type, target))
+type Subject = (NodeKey, Option<(String, NodeKey)>);
+
+// A node or relation as it was before an operation and after it, missing if created or deleted
+struct Diff {
+    before: Option<Subject>,
+    after: Option<Subject>,
+    properties: Vec<PropertyChange>,
+}
+
+// Turns the events of an operation into one change per node and relation it touched. The events
+// are followed in order, keeping for each index touched the value it had before the operation
+// (None if created by it); removals move the last index into the freed one, and the entry with
+// it. What is left is compared with the graph after the operation. Schema nodes are left out.
+fn diffs(events: &[Event], graph: &DiGraph<Node, Relation>) -> Vec<Diff> {
+    let mut deleted = Vec::new();
+    let mut nodes: HashMap<NodeIndex, Option<&Node>> = HashMap::new();
+    let mut edges: HashMap<EdgeIndex, Option<&Relation>> = HashMap::new();
+    for event in events {
+        match event {
+            Event::NodeAdded(index) => { nodes.insert(*index, None); },
+            Event::NodeChanged(index, old) => { nodes.entry(*index).or_insert(Some(old)); },
+            Event::NodeRemoved { index, node, last } => {
+                if let Some(old) = nodes.remove(index).unwrap_or(Some(node)) {
+                    if let Some(key) = node_key(old) {
+                        deleted.push(Diff { before: Some((key, None)), after: None, properties: node_changes(Some(old), None) });
+                    }
+                }
+                if let Some(moved) = nodes.remove(last) {
+                    nodes.insert(*index, moved);
+                }
+            },
+            Event::EdgeAdded(index) => { edges.insert(*index, None); },
+            Event::EdgeChanged(index, old) => { edges.entry(*index).or_insert(Some(old)); },
+            Event::EdgeRemoved { index, ends, relation, last, .. } => {
+                if let Some(old) = edges.remove(index).unwrap_or(Some(relation)) {
+                    let subject = (ends.0.clone(), Some((old.relation_type.to_string(), ends.1.clone())));
+                    deleted.push(Diff { before: Some(subject), after: None, properties: relation_changes(Some(old), None) });
+                }
+                if let Some(moved) = edges.remove(last) {
+                    edges.insert(*index, moved);
+                }
+            },
+        }
+    }
+
+    let mut nodes: Vec<_> = nodes.into_iter().collect();
+    nodes.sort_unstable_by_key(|(index, _)| *index);
+    let mut edges: Vec<_> = edges.into_iter().collect();
+    edges.sort_unstable_by_key(|(index, _)| *index);
+    let mut diffs = deleted;
+    for (index, old) in nodes {
+        let new = &graph[index];
+        let Some(key) = node_key(new) else { continue };
+        let properties = node_changes(old, Some(new));
+        if old.is_none() || !properties.is_empty() {
+            let before = old.and_then(node_key).map(|key| (key, None));
+            diffs.push(Diff { before, after: Some((key, None)), properties });
+        }
+    }
+    for (index, old) in edges {
+        let (source, target) = graph.edge_endpoints(index).expect("relations touched are still in the graph");
+        let (Some(source), Some(target)) = (node_key(&graph[source]), node_key(&graph[target])) else { continue };
+        let new = &graph[index];
+        let properties = relation_changes(old, Some(new));
+        if old.is_none() || !properties.is_empty() {
+            // An existing relation keeps its ends, though they may have been renamed
+            let before = old.map(|old| (source.clone(), Some((old.relation_type.to_string(), target.clone()))));
+            diffs.push(Diff { before, after: Some((source, Some((new.relation_type.to_string(), target)))), properties });
+        }
+    }
+    diffs
+}
+
+fn node_key(node: &Node) -> Option<NodeKey> {
+    match node {
+        Node::StandardNode { node_type, unique_id, .. } => Some((node_type.to_string(), unique_id.clone())),
+        Node::DataTypeNode { .. } => None,
+    }
+}
+
+// Type, unique ID, title and property changes between two versions of a node, either of which may
+// be missing
+fn node_changes(before: Option<&Node>, after: Option<&Node>) -> Vec<PropertyChange> {
+    type Parts<'a> = ([(&'static str, Option<AttributeValue>); 3], Option<&'a HashMap<String, AttributeValue>>);
+    fn parts(node: Option<&Node>) -> Parts<'_> {
+        match node {
+            Some(Node::StandardNode { node_type, unique_id, title, attributes, .. }) => ([
+                ("node_type", Some(AttributeValue::String(node_type.to_string()))),
+                ("unique_id", Some(AttributeValue::String(unique_id.clone()))),
+                ("title", title.clone().map(AttributeValue::String)),
+            ], Some(attributes)),
+            _ => ([("node_type", None), ("unique_id", None), ("title", None)], None),
+        }
+    }
+    let ((old_fields, old_attributes), (new_fields, new_attributes)) = (parts(before), parts(after));
+    // A created or deleted node is already named by its type and unique ID
+    let skip = if before.is_some() && after.is_some() { 0 } else { 2 };
+    let mut properties: Vec<PropertyChange> = old_fields.into_iter().zip(new_fields).skip(skip)
+        .filter(|((_, old), (_, new))| old != new)
+        .map(|((name, old), (_, new))| (name.to_string(), old, new))
+        .collect();
+    let empty = HashMap::new();
+    properties.extend(attribute_changes(old_attributes.unwrap_or(&empty), new_attributes.unwrap_or(&empty)));
+    properties
+}
+
+// Type and property changes between two versions of a relation, either of which may be missing
+fn relation_changes(before: Option<&Relation>, after: Option<&Relation>) -> Vec<PropertyChange> {
+    let mut properties = Vec::new();
+    if let (Some(old), Some(new)) = (before, after) {
+        if old.relation_type != new.relation_type {
+            let name = |relation: &Relation| Some(AttributeValue::String(relation.relation_type.to_string()));
+            properties.push(("relation_type".to_string(), name(old), name(new)));
+        }
+    }
+    let empty = HashMap::new();
+    let (old, new) = (before.and_then(|relation| relation.attributes.as_ref()), after.and_then(|relation| relation.attributes.as_ref()));
+    properties.extend(attribute_changes(old.unwrap_or(&empty), new.unwrap_or(&empty)));
+    properties
+}
+
+fn attribute_changes(before: &HashMap<String, AttributeValue>, after: &HashMap<String, AttributeValue>) -> Vec<PropertyChange> {
+    changed_attributes(before, after, &[], 0.0).into_iter()
+        .map(|(name, old, new)| (name.clone(), old.cloned(), new.cloned()))
+        .collect()
+}
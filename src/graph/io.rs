@@ -0,0 +1,67 @@
+// Formats for exchanging graphs with other tools
+use pyo3::prelude::*;
+use std::collections::{BTreeMap, HashMap};
+use crate::graph::journal::JournaledGraph;
+use crate::graph::get_schema::update_or_retrieve_schema;
+use crate::graph::io::tabular::ColumnTypes;
+use crate::data_types::AttributeValue;
+
+pub mod batch;
+pub mod cytoscape;
+pub mod dataframe;
+pub mod dot;
+pub mod graphml;
+pub mod jsonl;
+pub mod neo4j;
+pub mod networkx;
+pub mod rdf;
+pub mod record_batch;
+pub mod sql;
+pub mod sqlite;
+pub mod stream;
+pub mod tabular;
+
+// Relation type given to imported edges that don't name one
+pub const DEFAULT_RELATION_TYPE: &str = "RELATED_TO";
+
+/// Property types seen while importing a graph, registered as schema nodes once the import is done
+#[derive(Default)]
+pub struct ImportedSchemas {
+    nodes: BTreeMap<String, HashMap<String, String>>,
+    relations: BTreeMap<String, HashMap<String, String>>,
+}
+
+impl ImportedSchemas {
+    pub fn record_node(&mut self, node_type: &str, attributes: &HashMap<String, AttributeValue>) {
+        record(self.nodes.entry(node_type.to_string()).or_default(), attributes, None);
+    }
+
+    // Columns declared as Category are recorded as such, since their values arrive as text and are
+    // encoded once the nodes are in
+    pub fn record_node_typed(&mut self, node_type: &str, attributes: &HashMap<String, AttributeValue>, column_types: &ColumnTypes) {
+        record(self.nodes.entry(node_type.to_string()).or_default(), attributes, Some(column_types));
+    }
+
+    pub fn record_relation(&mut self, relation_type: &str, attributes: &HashMap<String, AttributeValue>) {
+        record(self.relations.entry(relation_type.to_string()).or_default(), attributes, None);
+    }
+
+    pub fn register(self, graph: &mut JournaledGraph) -> PyResult<()> {
+        for (data_type, schemas) in [("Node", self.nodes), ("Relation", self.relations)] {
+            for (name, column_types) in schemas {
+                update_or_retrieve_schema(graph, data_type, &name, Some(column_types.keys().cloned().collect()), Some(column_types))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// The first type seen for a property is the one recorded
+fn record(schema: &mut HashMap<String, String>, attributes: &HashMap<String, AttributeValue>, column_types: Option<&ColumnTypes>) {
+    for (name, value) in attributes {
+        schema.entry(name.clone()).or_insert_with(|| match column_types.and_then(|types| types.data_type(name)) {
+            Some("Category") => "Category".to_string(),
+            _ => value.type_name().to_string(),
+        });
+    }
+}
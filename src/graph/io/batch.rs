@@ -0,0 +1,120 @@
+use pyo3::prelude::*;
+use petgraph::graph::NodeIndex;
+use std::collections::{HashMap, HashSet};
+use crate::graph::journal::JournaledGraph;
+use crate::graph::add_nodes::{check_conflict_handling, remove_added, update_existing_node};
+use crate::graph::io::tabular::ColumnTypes;
+use crate::graph::io::ImportedSchemas;
+use crate::schema::{Node, Relation};
+use crate::data_types::AttributeValue;
+
+/// Applies records of any node and relation type to the graph a batch at a time, for sources
+/// that are read incrementally rather than as one table. Nodes are matched on node type and
+/// unique ID with the same conflict handling as `add_nodes`.
+pub struct BatchProcessor<'a> {
+    pub graph: &'a mut JournaledGraph,
+    pub column_types: ColumnTypes,
+    pub conflict_handling: &'a str,
+    pub batch_size: usize,
+    lookup: HashMap<(String, String), NodeIndex>,
+    // Graph size before the first batch, to return to when conflict_handling "error" fails
+    start: (usize, usize),
+    // Column types per (data type, name) with the existing schema filling in undeclared properties
+    types_by_schema: HashMap<(&'static str, String), ColumnTypes>,
+    schemas: ImportedSchemas,
+    // Nodes added or updated so far, in the order first seen
+    touched: Vec<NodeIndex>,
+    seen: HashSet<NodeIndex>,
+}
+
+impl<'a> BatchProcessor<'a> {
+    pub fn new(graph: &'a mut JournaledGraph, column_types: ColumnTypes, conflict_handling: &'a str, batch_size: usize) -> PyResult<Self> {
+        check_conflict_handling(conflict_handling)?;
+        let lookup = graph.node_indices().filter_map(|index| match &graph[index] {
+            Node::StandardNode { node_type, unique_id, .. } => Some(((node_type.to_string(), unique_id.clone()), index)),
+            Node::DataTypeNode { .. } => None,
+        }).collect();
+        Ok(BatchProcessor {
+            start: (graph.node_count(), graph.edge_count()),
+            graph, column_types, conflict_handling, batch_size: batch_size.max(1), lookup,
+            types_by_schema: HashMap::new(), schemas: ImportedSchemas::default(), touched: Vec::new(), seen: HashSet::new(),
+        })
+    }
+
+    /// Converts property values to the declared type, or the type in the schema of the node or
+    /// relation type
+    pub fn properties(
+        &mut self, data_type: &'static str, type_name: &str, values: impl IntoIterator<Item = (String, AttributeValue)>,
+    ) -> PyResult<HashMap<String, AttributeValue>> {
+        let graph = &*self.graph;
+        let column_types = self.types_by_schema.entry((data_type, type_name.to_string())).or_insert_with(|| {
+            let schema = graph.node_weights().find_map(|node| match node {
+                Node::DataTypeNode { data_type: dt, name, attributes, .. } if dt == data_type && name == type_name => Some(attributes.clone()),
+                _ => None,
+            });
+            self.column_types.clone().with_schema(schema.unwrap_or_default())
+        });
+        values.into_iter().map(|(name, value)| {
+            let value = column_types.convert(&name, value)?;
+            Ok((name, value))
+        }).collect()
+    }
+
+    // Finds a node, or creates it without properties
+    pub fn endpoint(&mut self, node_type: String, unique_id: String) -> NodeIndex {
+        match self.lookup.get(&(node_type.clone(), unique_id.clone())) {
+            Some(&index) => index,
+            None => {
+                // The type may be seen nowhere else, and still needs a schema
+                self.schemas.record_node(&node_type, &HashMap::new());
+                self.insert(node_type, unique_id, None, None)
+            },
+        }
+    }
+
+    fn insert(&mut self, node_type: String, unique_id: String, title: Option<String>, attributes: Option<HashMap<String, AttributeValue>>) -> NodeIndex {
+        let index = self.graph.add_node(Node::new(&node_type, &unique_id, attributes, title.as_deref()));
+        self.lookup.insert((node_type, unique_id), index);
+        self.touch(index);
+        index
+    }
+
+    /// Adds a node or updates the existing one, recording its property types for the schema. With
+    /// conflict_handling "error", an existing node undoes every batch applied so far and fails
+    pub fn node(&mut self, node_type: String, unique_id: String, title: Option<String>, attributes: HashMap<String, AttributeValue>) -> PyResult<NodeIndex> {
+        match self.types_by_schema.get(&("Node", node_type.clone())) {
+            Some(column_types) => self.schemas.record_node_typed(&node_type, &attributes, column_types),
+            None => self.schemas.record_node(&node_type, &attributes),
+        }
+        match self.lookup.get(&(node_type.clone(), unique_id.clone())) {
+            Some(&index) => {
+                if let Err(e) = update_existing_node(self.graph, index, &node_type, &unique_id, title, Some(attributes), self.conflict_handling) {
+                    remove_added(self.graph, self.start.0, self.start.1);
+                    return Err(e);
+                }
+                self.touch(index);
+                Ok(index)
+            },
+            None => Ok(self.insert(node_type, unique_id, title, Some(attributes))),
+        }
+    }
+
+    /// Adds a relation, recording its property types for the schema
+    pub fn relation(&mut self, source: NodeIndex, target: NodeIndex, relation_type: &str, attributes: HashMap<String, AttributeValue>) {
+        self.schemas.record_relation(relation_type, &attributes);
+        self.graph.add_edge(source, target, Relation::new(relation_type, (!attributes.is_empty()).then_some(attributes)));
+    }
+
+    fn touch(&mut self, index: NodeIndex) {
+        if self.seen.insert(index) {
+            self.touched.push(index);
+        }
+    }
+
+    /// Registers the schema of the imported node and relation types; returns the nodes added or
+    /// updated, for the checks and defaults that follow ingestion
+    pub fn finish(self) -> PyResult<Vec<usize>> {
+        self.schemas.register(self.graph)?;
+        Ok(self.touched.into_iter().map(NodeIndex::index).collect())
+    }
+}
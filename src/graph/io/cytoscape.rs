@@ -0,0 +1,45 @@
+use petgraph::graph::{DiGraph, NodeIndex};
+use petgraph::visit::EdgeRef;
+use serde_json::{json, Map, Value};
+use std::collections::{HashMap, HashSet};
+use crate::schema::{Node, Relation};
+use crate::data_types::AttributeValue;
+
+fn data(fields: Vec<(&str, Value)>, attributes: Option<&HashMap<String, AttributeValue>>) -> Value {
+    let mut data: Map<String, Value> = attributes.into_iter().flatten()
+        .map(|(name, value)| (name.clone(), value.to_json()))
+        .collect();
+    data.extend(fields.into_iter().map(|(name, value)| (name.to_string(), value)));
+    json!({ "data": data })
+}
+
+/// Builds the Cytoscape.js `{"elements": {"nodes": [...], "edges": [...]}}` document for the
+/// selected nodes and the relations between them. Element IDs are "n<index>" and "e<index>";
+/// `label` holds the title (or unique ID) of nodes and the type of relations.
+pub fn to_cytoscape(graph: &DiGraph<Node, Relation>, indices: &[usize]) -> Value {
+    let selected: Vec<NodeIndex> = indices.iter().map(|&index| NodeIndex::new(index))
+        .filter(|&node| matches!(graph.node_weight(node), Some(Node::StandardNode { .. })))
+        .collect();
+    let included: HashSet<NodeIndex> = selected.iter().copied().collect();
+
+    let nodes: Vec<Value> = selected.iter().filter_map(|&node| match &graph[node] {
+        Node::StandardNode { node_type, unique_id, title, attributes, .. } => Some(data(vec![
+            ("id", json!(format!("n{}", node.index()))),
+            ("node_type", json!(node_type)),
+            ("unique_id", json!(unique_id)),
+            ("label", json!(title.as_ref().unwrap_or(unique_id))),
+        ], Some(attributes))),
+        Node::DataTypeNode { .. } => None,
+    }).collect();
+    let edges: Vec<Value> = graph.edge_references()
+        .filter(|edge| included.contains(&edge.source()) && included.contains(&edge.target()))
+        .map(|edge| data(vec![
+            ("id", json!(format!("e{}", edge.id().index()))),
+            ("source", json!(format!("n{}", edge.source().index()))),
+            ("target", json!(format!("n{}", edge.target().index()))),
+            ("relation_type", json!(edge.weight().relation_type)),
+            ("label", json!(edge.weight().relation_type)),
+        ], edge.weight().attributes.as_ref()))
+        .collect();
+    json!({ "elements": { "nodes": nodes, "edges": edges } })
+}
@@ -0,0 +1,153 @@
+use pyo3::prelude::*;
+use pyo3::exceptions::{PyTypeError, PyValueError};
+use pyo3::types::{PyDict, PyList};
+use petgraph::graph::{DiGraph, EdgeIndex, NodeIndex};
+use std::collections::BTreeSet;
+use crate::graph::io::record_batch;
+use crate::graph::io::tabular::{Row, Table};
+use crate::schema::{Node, Relation};
+use crate::data_types::AttributeValue;
+
+/// DataFrame library the columns are handed to
+#[derive(Clone, Copy)]
+pub enum Library {
+    Pandas,
+    Polars,
+    PyArrow,
+}
+
+impl Library {
+    pub fn parse(name: Option<&str>) -> PyResult<Self> {
+        match name.unwrap_or("pandas") {
+            "pandas" => Ok(Library::Pandas),
+            "polars" => Ok(Library::Polars),
+            "pyarrow" => Ok(Library::PyArrow),
+            other => Err(PyValueError::new_err(format!(
+                "Unknown DataFrame library '{}', expected 'pandas', 'polars' or 'pyarrow'", other
+            ))),
+        }
+    }
+}
+
+/// Reads a pandas or polars DataFrame, or a pyarrow Table or RecordBatch, as a table. Arrow data is
+/// converted column by column; DataFrame rows are read through Python, with None and NaN cells empty.
+pub fn frame_to_table(frame: &PyAny) -> PyResult<Table> {
+    if frame.hasattr("to_batches")? || frame.hasattr("_export_to_c")? {
+        return record_batch::batches_to_table(record_batch::import_batches(frame)?);
+    }
+    let rows = if frame.hasattr("itertuples")? {
+        let kwargs = PyDict::new(frame.py());
+        kwargs.set_item("index", false)?;
+        kwargs.set_item("name", frame.py().None())?;
+        frame.call_method("itertuples", (), Some(kwargs))?
+    } else if frame.hasattr("iter_rows")? {
+        frame.call_method0("iter_rows")?
+    } else {
+        return Err(PyTypeError::new_err("Expected a pandas or polars DataFrame, or a pyarrow Table"));
+    };
+    let columns: Vec<String> = frame.getattr("columns")?.iter()?
+        .map(|column| Ok(column?.str()?.to_string()))
+        .collect::<PyResult<_>>()?;
+    let rows = rows.iter()?.map(|row| -> PyResult<Row> {
+        row?.iter()?.map(|cell| {
+            let value = cell?.extract::<AttributeValue>()?;
+            Ok((!value.is_null()).then_some(value))
+        }).collect()
+    }).collect::<PyResult<Vec<_>>>()?;
+    Ok(Table { columns, rows: Box::new(rows.into_iter().map(Ok)) })
+}
+
+const NODE_COLUMNS: [&str; 4] = ["graph_id", "node_type", "unique_id", "title"];
+const EDGE_COLUMNS: [&str; 6] = ["edge_id", "relation_type", "source_index", "source_id", "target_index", "target_id"];
+
+// Requested columns, or the built-in columns followed by every property in name order
+fn column_names<'a>(
+    columns: Option<Vec<String>>,
+    builtins: &[&str],
+    properties: impl Iterator<Item = &'a String>,
+) -> Vec<String> {
+    columns.unwrap_or_else(|| {
+        let properties: BTreeSet<&String> = properties.collect();
+        builtins.iter().map(|c| c.to_string()).chain(properties.into_iter().cloned()).collect()
+    })
+}
+
+fn build_frame(py: Python, library: Library, columns: Vec<(String, Vec<PyObject>)>) -> PyResult<PyObject> {
+    let data = PyDict::new(py);
+    for (name, values) in columns {
+        data.set_item(name, PyList::new(py, values))?;
+    }
+    let frame = match library {
+        Library::Pandas => py.import("pandas")?.getattr("DataFrame")?.call1((data,))?,
+        Library::Polars => py.import("polars")?.getattr("DataFrame")?.call1((data,))?,
+        Library::PyArrow => py.import("pyarrow")?.getattr("table")?.call1((data,))?,
+    };
+    Ok(frame.into_py(py))
+}
+
+/// Builds a DataFrame with one row per node, filled column by column. Nodes lacking a property
+/// get a missing value.
+pub fn nodes_to_frame(
+    graph: &DiGraph<Node, Relation>,
+    py: Python,
+    indices: &[usize],
+    columns: Option<Vec<String>>,
+    library: Library,
+) -> PyResult<PyObject> {
+    let nodes: Vec<(usize, &Node)> = indices.iter()
+        .filter_map(|&index| graph.node_weight(NodeIndex::new(index)).map(|node| (index, node)))
+        .filter(|(_, node)| matches!(node, Node::StandardNode { .. }))
+        .collect();
+    let names = column_names(columns, &NODE_COLUMNS, nodes.iter().flat_map(|(_, node)| match node {
+        Node::StandardNode { attributes, .. } => attributes.keys(),
+        Node::DataTypeNode { .. } => unreachable!("Only standard nodes are kept"),
+    }));
+
+    // Dates become naive UTC datetimes so every library infers a datetime column
+    let mut frame_columns = Vec::with_capacity(names.len());
+    for name in names {
+        let values = nodes.iter().map(|(index, node)| match (name.as_str(), node) {
+            ("graph_id", _) => Ok(index.into_py(py)),
+            ("node_type", Node::StandardNode { node_type, .. }) => Ok(node_type.into_py(py)),
+            (_, node) => node.get_field(&name).map_or_else(|| Ok(py.None()), |value| value.to_python_object(py, None)),
+        }).collect::<PyResult<Vec<_>>>()?;
+        frame_columns.push((name, values));
+    }
+    build_frame(py, library, frame_columns)
+}
+
+/// Builds a DataFrame with one row per relation: its edge ID, type, endpoints and properties
+pub fn edges_to_frame(
+    graph: &DiGraph<Node, Relation>,
+    py: Python,
+    edges: &[usize],
+    columns: Option<Vec<String>>,
+    library: Library,
+) -> PyResult<PyObject> {
+    let edges: Vec<(usize, &Relation, NodeIndex, NodeIndex)> = edges.iter().filter_map(|&edge| {
+        let index = EdgeIndex::new(edge);
+        let (source, target) = graph.edge_endpoints(index)?;
+        Some((edge, &graph[index], source, target))
+    }).collect();
+    let names = column_names(columns, &EDGE_COLUMNS, edges.iter().flat_map(|(_, relation, _, _)| relation.attributes.iter().flatten().map(|(k, _)| k)));
+    let unique_id = |index: NodeIndex| match &graph[index] {
+        Node::StandardNode { unique_id, .. } => unique_id.into_py(py),
+        Node::DataTypeNode { .. } => py.None(),
+    };
+
+    let mut frame_columns = Vec::with_capacity(names.len());
+    for name in names {
+        let values = edges.iter().map(|&(edge, relation, source, target)| match name.as_str() {
+            "edge_id" => Ok(edge.into_py(py)),
+            "relation_type" => Ok(relation.relation_type.as_str().into_py(py)),
+            "source_index" => Ok(source.index().into_py(py)),
+            "source_id" => Ok(unique_id(source)),
+            "target_index" => Ok(target.index().into_py(py)),
+            "target_id" => Ok(unique_id(target)),
+            property => relation.attributes.as_ref().and_then(|a| a.get(property))
+                .map_or_else(|| Ok(py.None()), |value| value.to_python_object(py, None)),
+        }).collect::<PyResult<Vec<_>>>()?;
+        frame_columns.push((name, values));
+    }
+    build_frame(py, library, frame_columns)
+}
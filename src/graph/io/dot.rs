@@ -0,0 +1,116 @@
+use pyo3::prelude::*;
+use pyo3::exceptions::PyIOError;
+use petgraph::graph::{DiGraph, NodeIndex};
+use petgraph::visit::EdgeRef;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt::Write;
+use std::fs;
+use crate::schema::{Node, Relation};
+
+// Fill colors handed out to the distinct `color_by` values in sorted order
+const PALETTE: [&str; 10] = [
+    "#8dd3c7", "#ffffb3", "#bebada", "#fb8072", "#80b1d3", "#fdb462", "#b3de69", "#fccde5", "#d9d9d9", "#bc80bd",
+];
+
+/// How nodes are labelled, colored and thinned out in a DOT rendering
+pub struct DotOptions<'a> {
+    pub node_label: &'a str,
+    pub color_by: Option<&'a str>,
+    pub max_nodes: Option<usize>,
+    pub collapse_degree: Option<usize>,
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+fn field_text(node: &Node, field: &str) -> Option<String> {
+    match field {
+        "node_type" => match node {
+            Node::StandardNode { node_type, .. } => Some(node_type.to_string()),
+            Node::DataTypeNode { .. } => None,
+        },
+        _ => node.get_field(field).map(|value| value.to_export_string()),
+    }
+}
+
+/// Renders the selected nodes (at most `max_nodes` of them) and the relations between them as a
+/// Graphviz digraph. With `collapse_degree`, the leaf neighbors of every node with more
+/// relations than that are drawn as one summary node per relation type, direction and node type.
+pub fn to_dot(graph: &DiGraph<Node, Relation>, indices: Option<Vec<usize>>, options: &DotOptions) -> String {
+    let mut selected: Vec<NodeIndex> = match indices {
+        Some(indices) => indices.into_iter().map(NodeIndex::new).collect(),
+        None => graph.node_indices().collect(),
+    };
+    selected.retain(|&node| matches!(graph.node_weight(node), Some(Node::StandardNode { .. })));
+    let left_out = options.max_nodes.map_or(0, |max| selected.len().saturating_sub(max));
+    selected.truncate(selected.len() - left_out);
+    let included: HashSet<NodeIndex> = selected.iter().copied().collect();
+    let edges: Vec<_> = graph.edge_references()
+        .filter(|edge| included.contains(&edge.source()) && included.contains(&edge.target()))
+        .collect();
+
+    let mut degree: HashMap<NodeIndex, usize> = HashMap::new();
+    for edge in &edges {
+        *degree.entry(edge.source()).or_insert(0) += 1;
+        *degree.entry(edge.target()).or_insert(0) += 1;
+    }
+    // Summary groups keyed by (hub, relation type, hub is source, leaf node type)
+    let mut groups: BTreeMap<(NodeIndex, &str, bool, String), usize> = BTreeMap::new();
+    let mut collapsed: HashSet<NodeIndex> = HashSet::new();
+    if let Some(limit) = options.collapse_degree {
+        let is_hub = |node: NodeIndex| degree.get(&node).copied().unwrap_or(0) > limit;
+        for edge in &edges {
+            let (hub, leaf, outgoing) = match (is_hub(edge.source()), is_hub(edge.target())) {
+                (true, false) => (edge.source(), edge.target(), true),
+                (false, true) => (edge.target(), edge.source(), false),
+                _ => continue,
+            };
+            if degree[&leaf] == 1 {
+                let leaf_type = field_text(&graph[leaf], "node_type").unwrap_or_default();
+                *groups.entry((hub, edge.weight().relation_type.as_str(), outgoing, leaf_type)).or_insert(0) += 1;
+                collapsed.insert(leaf);
+            }
+        }
+    }
+
+    let mut colors: BTreeMap<String, &str> = BTreeMap::new();
+    if let Some(color_by) = options.color_by {
+        let values: BTreeSet<String> = selected.iter().filter_map(|&node| field_text(&graph[node], color_by)).collect();
+        colors.extend(values.into_iter().zip(PALETTE.iter().cycle().copied()));
+    }
+
+    let mut dot = String::from("digraph G {\n  node [shape=ellipse, style=filled, fillcolor=\"#ffffff\"];\n");
+    if left_out > 0 {
+        let _ = writeln!(dot, "  // {} nodes left out beyond max_nodes", left_out);
+    }
+    for &node in selected.iter().filter(|node| !collapsed.contains(node)) {
+        let weight = &graph[node];
+        let label = field_text(weight, options.node_label).or_else(|| field_text(weight, "unique_id")).unwrap_or_default();
+        let _ = write!(dot, "  n{} [label=\"{}\"", node.index(), escape(&label));
+        if let Some(color) = options.color_by.and_then(|field| field_text(weight, field)).and_then(|value| colors.get(&value)) {
+            let _ = write!(dot, ", fillcolor=\"{}\"", color);
+        }
+        dot.push_str("];\n");
+    }
+    for edge in edges.iter().filter(|edge| !collapsed.contains(&edge.source()) && !collapsed.contains(&edge.target())) {
+        let _ = writeln!(
+            dot, "  n{} -> n{} [label=\"{}\"];", edge.source().index(), edge.target().index(), escape(&edge.weight().relation_type)
+        );
+    }
+    for (group, ((hub, relation_type, outgoing, leaf_type), count)) in groups.into_iter().enumerate() {
+        let _ = writeln!(dot, "  g{} [label=\"{} {}\", shape=box, style=\"filled,dashed\"];", group, count, escape(&leaf_type));
+        let (source, target) = match outgoing {
+            true => (format!("n{}", hub.index()), format!("g{}", group)),
+            false => (format!("g{}", group), format!("n{}", hub.index())),
+        };
+        let _ = writeln!(dot, "  {} -> {} [label=\"{}\", style=dashed];", source, target, escape(relation_type));
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+/// Writes `to_dot` output to a file
+pub fn write_dot(graph: &DiGraph<Node, Relation>, path: &str, indices: Option<Vec<usize>>, options: &DotOptions) -> PyResult<()> {
+    fs::write(path, to_dot(graph, indices, options)).map_err(|e| PyIOError::new_err(format!("Cannot write '{}': {}", path, e)))
+}
@@ -0,0 +1,241 @@
+use pyo3::prelude::*;
+use pyo3::exceptions::{PyIOError, PyValueError};
+use petgraph::graph::{DiGraph, NodeIndex};
+use petgraph::visit::EdgeRef;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::{BufWriter, Write};
+use crate::graph::journal::JournaledGraph;
+use crate::graph::categories;
+use crate::graph::io::{ImportedSchemas, DEFAULT_RELATION_TYPE};
+use crate::schema::{Node, Relation};
+use crate::data_types::{AttributeValue, Category, Decimal};
+
+// Extra key attribute marking string keys that hold ISO datetimes, dates or exact decimals, so they
+// come back as DateTime, Date or Decimal
+const TYPE_HINT: &str = "rusty_graph.type";
+
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn graphml_type(data_type: &str) -> &'static str {
+    match data_type {
+        "Int" => "int",
+        "Float" => "double",
+        "Boolean" => "boolean",
+        _ => "string",
+    }
+}
+
+// Attribute name to data type over all values, falling back to String when the values disagree
+fn attribute_types<'a>(attributes: impl Iterator<Item = &'a HashMap<String, AttributeValue>>) -> BTreeMap<String, &'static str> {
+    let mut types: BTreeMap<String, &'static str> = BTreeMap::new();
+    for (name, value) in attributes.flatten() {
+        types.entry(name.clone())
+            .and_modify(|t| if *t != value.type_name() { *t = "String" })
+            .or_insert(value.type_name());
+    }
+    types
+}
+
+/// Writes the nodes and relations of the graph as GraphML. Node type, unique ID and title become
+/// the `node_type`, `unique_id` and `title` keys, relation types the `relation_type` key, and
+/// every property gets a typed key (dates are written as ISO strings).
+pub fn write_graphml(graph: &DiGraph<Node, Relation>, path: &str) -> PyResult<()> {
+    let io_error = |e: std::io::Error| PyIOError::new_err(format!("Cannot write '{}': {}", path, e));
+    let node_types = attribute_types(graph.node_weights().filter_map(|node| match node {
+        Node::StandardNode { attributes, .. } => Some(attributes),
+        Node::DataTypeNode { .. } => None,
+    }));
+    let edge_types = attribute_types(graph.edge_weights().filter_map(|relation| relation.attributes.as_ref()));
+
+    let file = fs::File::create(path).map_err(|e| PyIOError::new_err(format!("Cannot create '{}': {}", path, e)))?;
+    let mut out = BufWriter::new(file);
+    writeln!(out, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>").map_err(io_error)?;
+    writeln!(out, "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">").map_err(io_error)?;
+
+    let mut node_keys: HashMap<&str, String> = HashMap::new();
+    let mut edge_keys: HashMap<&str, String> = HashMap::new();
+    let mut key_count = 0;
+    let builtin_node_keys = ["node_type", "unique_id", "title"].map(|name| (name, "String"));
+    let node_key_specs = builtin_node_keys.into_iter().chain(node_types.iter().map(|(name, t)| (name.as_str(), *t)));
+    let edge_key_specs = std::iter::once(("relation_type", "String")).chain(edge_types.iter().map(|(name, t)| (name.as_str(), *t)));
+    for (domain, specs, keys) in [
+        ("node", node_key_specs.collect::<Vec<_>>(), &mut node_keys),
+        ("edge", edge_key_specs.collect::<Vec<_>>(), &mut edge_keys),
+    ] {
+        for (name, data_type) in specs {
+            let id = format!("d{}", key_count);
+            key_count += 1;
+            let hint = match data_type {
+                "DateTime" | "Date" | "Decimal" | "Duration" | "UUID" | "Category" => format!(" {}=\"{}\"", TYPE_HINT, data_type),
+                _ => String::new(),
+            };
+            writeln!(
+                out, "  <key id=\"{}\" for=\"{}\" attr.name=\"{}\" attr.type=\"{}\"{}/>",
+                id, domain, escape(name), graphml_type(data_type), hint
+            ).map_err(io_error)?;
+            keys.insert(name, id);
+        }
+    }
+
+    writeln!(out, "  <graph id=\"G\" edgedefault=\"directed\">").map_err(io_error)?;
+    for index in graph.node_indices() {
+        let Node::StandardNode { node_type, unique_id, title, attributes, .. } = &graph[index] else { continue };
+        writeln!(out, "    <node id=\"n{}\">", index.index()).map_err(io_error)?;
+        let builtins = [("node_type", Some(node_type.as_str())), ("unique_id", Some(unique_id.as_str())), ("title", title.as_deref())];
+        let mut values: Vec<(&str, String)> = builtins.into_iter()
+            .filter_map(|(name, value)| value.map(|v| (name, v.to_string())))
+            .collect();
+        let mut properties: Vec<_> = attributes.iter().collect();
+        properties.sort_unstable_by_key(|(name, _)| *name);
+        values.extend(properties.into_iter().map(|(name, value)| (name.as_str(), value.to_export_string())));
+        for (name, value) in values {
+            writeln!(out, "      <data key=\"{}\">{}</data>", node_keys[name], escape(&value)).map_err(io_error)?;
+        }
+        writeln!(out, "    </node>").map_err(io_error)?;
+    }
+    for edge in graph.edge_references() {
+        let relation = edge.weight();
+        writeln!(
+            out, "    <edge id=\"e{}\" source=\"n{}\" target=\"n{}\">",
+            edge.id().index(), edge.source().index(), edge.target().index()
+        ).map_err(io_error)?;
+        writeln!(out, "      <data key=\"{}\">{}</data>", edge_keys["relation_type"], escape(&relation.relation_type)).map_err(io_error)?;
+        let mut properties: Vec<_> = relation.attributes.iter().flatten().collect();
+        properties.sort_unstable_by_key(|(name, _)| *name);
+        for (name, value) in properties {
+            writeln!(out, "      <data key=\"{}\">{}</data>", edge_keys[name.as_str()], escape(&value.to_export_string())).map_err(io_error)?;
+        }
+        writeln!(out, "    </edge>").map_err(io_error)?;
+    }
+    writeln!(out, "  </graph>").map_err(io_error)?;
+    writeln!(out, "</graphml>").map_err(io_error)?;
+    out.flush().map_err(io_error)
+}
+
+struct Key {
+    name: String,
+    data_type: &'static str,
+    default: Option<String>,
+}
+
+// Converts a GraphML value to the key's data type, keeping the text when it doesn't parse
+fn parse_value(text: &str, data_type: &str) -> AttributeValue {
+    let parsed = match data_type {
+        "Int" => text.trim().parse().ok().map(AttributeValue::Int)
+            .or_else(|| text.trim().parse().ok().map(AttributeValue::Float)),
+        "Float" => text.trim().parse().ok().map(AttributeValue::Float),
+        "DateTime" => AttributeValue::parse_iso_datetime(text),
+        "Date" => AttributeValue::parse_iso_date(text),
+        "Duration" => AttributeValue::parse_iso_duration(text),
+        "UUID" => AttributeValue::parse_uuid(text),
+        "Category" => Some(AttributeValue::Category(Category::new(text))),
+        "Boolean" => AttributeValue::parse_bool(text),
+        "Decimal" => Decimal::parse(text).map(AttributeValue::Decimal),
+        _ => None,
+    };
+    parsed.unwrap_or_else(|| AttributeValue::String(text.to_string()))
+}
+
+// Key values of a node or edge element, starting from the key defaults for that domain
+fn element_values<'a>(element: roxmltree::Node, keys: &'a HashMap<String, Key>, domain: &str, domains: &HashMap<String, String>)
+    -> BTreeMap<&'a str, (String, &'static str)>
+{
+    let mut values: BTreeMap<&str, (String, &'static str)> = keys.iter()
+        .filter(|(id, _)| domains.get(*id).is_some_and(|d| d == domain || d == "all"))
+        .filter_map(|(_, key)| key.default.clone().map(|default| (key.name.as_str(), (default, key.data_type))))
+        .collect();
+    for data in element.children().filter(|child| child.has_tag_name("data")) {
+        if let Some(key) = data.attribute("key").and_then(|id| keys.get(id)) {
+            values.insert(key.name.as_str(), (data.text().unwrap_or("").to_string(), key.data_type));
+        }
+    }
+    values
+}
+
+/// Reads a GraphML file into a new graph. The `node_type`, `unique_id` and `title` keys (or
+/// `label`) fill the built-in node fields, defaulting to "Node" and the GraphML node id, and the
+/// `relation_type` key (or `label`) names each relation. Other keys become typed properties.
+pub fn read_graphml(path: &str) -> PyResult<JournaledGraph> {
+    let text = fs::read_to_string(path).map_err(|e| PyIOError::new_err(format!("Cannot open '{}': {}", path, e)))?;
+    let document = roxmltree::Document::parse(&text)
+        .map_err(|e| PyValueError::new_err(format!("'{}' is not valid GraphML: {}", path, e)))?;
+    let root = document.root_element();
+    if !root.has_tag_name("graphml") {
+        return Err(PyValueError::new_err(format!("'{}' is not a GraphML file", path)));
+    }
+
+    let mut keys: HashMap<String, Key> = HashMap::new();
+    let mut domains: HashMap<String, String> = HashMap::new();
+    for key in root.children().filter(|child| child.has_tag_name("key")) {
+        let Some(id) = key.attribute("id") else { continue };
+        let data_type = match (key.attribute(TYPE_HINT), key.attribute("attr.type")) {
+            (Some("DateTime"), _) => "DateTime",
+            (Some("Date"), _) => "Date",
+            (Some("Duration"), _) => "Duration",
+            (Some("UUID"), _) => "UUID",
+            (Some("Category"), _) => "Category",
+            (Some("Decimal"), _) => "Decimal",
+            (_, Some("int" | "long")) => "Int",
+            (_, Some("float" | "double")) => "Float",
+            (_, Some("boolean")) => "Boolean",
+            _ => "String",
+        };
+        let default = key.children().find(|child| child.has_tag_name("default")).map(|d| d.text().unwrap_or("").to_string());
+        keys.insert(id.to_string(), Key { name: key.attribute("attr.name").unwrap_or(id).to_string(), data_type, default });
+        domains.insert(id.to_string(), key.attribute("for").unwrap_or("all").to_string());
+    }
+
+    let graph_element = root.children().find(|child| child.has_tag_name("graph"))
+        .ok_or_else(|| PyValueError::new_err(format!("'{}' contains no graph", path)))?;
+    let mut graph = JournaledGraph::default();
+    let mut node_ids: HashMap<&str, NodeIndex> = HashMap::new();
+    let mut schemas = ImportedSchemas::default();
+
+    for element in graph_element.children().filter(|child| child.has_tag_name("node")) {
+        let id = element.attribute("id").ok_or_else(|| PyValueError::new_err("GraphML node without an id"))?;
+        let mut values = element_values(element, &keys, "node", &domains);
+        let node_type = values.remove("node_type").map_or_else(|| "Node".to_string(), |(v, _)| v);
+        let unique_id = values.remove("unique_id").map_or_else(|| id.to_string(), |(v, _)| v);
+        let title = values.remove("title").or_else(|| values.remove("label")).map(|(v, _)| v);
+        let attributes: HashMap<String, AttributeValue> = values.into_iter()
+            .map(|(name, (text, data_type))| (name.to_string(), parse_value(&text, data_type)))
+            .collect();
+        schemas.record_node(&node_type, &attributes);
+        let index = graph.add_node(Node::new(&node_type, &unique_id, Some(attributes), title.as_deref()));
+        node_ids.insert(id, index);
+    }
+
+    for element in graph_element.children().filter(|child| child.has_tag_name("edge")) {
+        let endpoint = |name: &str| element.attribute(name)
+            .and_then(|id| node_ids.get(id).copied())
+            .ok_or_else(|| PyValueError::new_err(format!("GraphML edge with a missing or unknown {}", name)));
+        let (source, target) = (endpoint("source")?, endpoint("target")?);
+        let mut values = element_values(element, &keys, "edge", &domains);
+        let relation_type = values.remove("relation_type").or_else(|| values.remove("label"))
+            .map_or_else(|| DEFAULT_RELATION_TYPE.to_string(), |(v, _)| v);
+        let attributes: HashMap<String, AttributeValue> = values.into_iter()
+            .map(|(name, (text, data_type))| (name.to_string(), parse_value(&text, data_type)))
+            .collect();
+        schemas.record_relation(&relation_type, &attributes);
+        let attributes = (!attributes.is_empty()).then_some(attributes);
+        graph.add_edge(source, target, Relation::new(&relation_type, attributes));
+    }
+
+    schemas.register(&mut graph)?;
+    categories::encode_all(&mut graph);
+    Ok(graph)
+}
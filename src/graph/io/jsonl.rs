@@ -0,0 +1,147 @@
+use pyo3::prelude::*;
+use pyo3::exceptions::{PyIOError, PyValueError};
+use petgraph::graph::{DiGraph, NodeIndex};
+use petgraph::visit::EdgeRef;
+use serde_json::{json, Map, Value};
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use crate::graph::io::batch::BatchProcessor;
+use crate::schema::{Node, Relation};
+use crate::data_types::AttributeValue;
+
+fn properties_json(attributes: Option<&HashMap<String, AttributeValue>>) -> Value {
+    Value::Object(attributes.into_iter().flatten().map(|(name, value)| (name.clone(), value.to_json())).collect())
+}
+
+/// Writes the selected nodes (all by default) as one JSON object per line with `node_type`,
+/// `unique_id`, `title` and `properties`, and the relations between them with `relation_type`,
+/// the type and unique ID of both ends, and `properties`. Returns the number of nodes and edges.
+pub fn export_jsonl(
+    graph: &DiGraph<Node, Relation>,
+    nodes_path: &str,
+    edges_path: &str,
+    indices: Option<&[usize]>,
+) -> PyResult<(usize, usize)> {
+    let create = |path: &str| File::create(path).map(BufWriter::new)
+        .map_err(|e| PyIOError::new_err(format!("Cannot create '{}': {}", path, e)));
+    let write_error = |path: &str, e: std::io::Error| PyIOError::new_err(format!("Cannot write '{}': {}", path, e));
+    let selected: Option<HashSet<NodeIndex>> = indices.map(|indices| indices.iter().map(|&i| NodeIndex::new(i)).collect());
+    let keep = |node: NodeIndex| selected.as_ref().is_none_or(|selected| selected.contains(&node));
+
+    let mut nodes = create(nodes_path)?;
+    let mut node_count = 0;
+    for index in graph.node_indices().filter(|&index| keep(index)) {
+        let Node::StandardNode { node_type, unique_id, title, attributes, .. } = &graph[index] else { continue };
+        let record = json!({
+            "node_type": node_type,
+            "unique_id": unique_id,
+            "title": title,
+            "properties": properties_json(Some(attributes)),
+        });
+        writeln!(nodes, "{}", record).map_err(|e| write_error(nodes_path, e))?;
+        node_count += 1;
+    }
+    nodes.flush().map_err(|e| write_error(nodes_path, e))?;
+
+    let mut edges = create(edges_path)?;
+    let mut edge_count = 0;
+    for edge in graph.edge_references().filter(|edge| keep(edge.source()) && keep(edge.target())) {
+        let (Node::StandardNode { node_type: source_type, unique_id: source_id, .. },
+             Node::StandardNode { node_type: target_type, unique_id: target_id, .. }) = (&graph[edge.source()], &graph[edge.target()])
+        else { continue };
+        let record = json!({
+            "relation_type": edge.weight().relation_type,
+            "source_type": source_type,
+            "source_id": source_id,
+            "target_type": target_type,
+            "target_id": target_id,
+            "properties": properties_json(edge.weight().attributes.as_ref()),
+        });
+        writeln!(edges, "{}", record).map_err(|e| write_error(edges_path, e))?;
+        edge_count += 1;
+    }
+    edges.flush().map_err(|e| write_error(edges_path, e))?;
+    Ok((node_count, edge_count))
+}
+
+// Calls `apply` for every batch of `batch_size` parsed records in the file, so only one batch is
+// held in memory
+fn for_each_batch(
+    processor: &mut BatchProcessor, path: &str, mut apply: impl FnMut(&mut BatchProcessor, Vec<(usize, Map<String, Value>)>) -> PyResult<()>,
+) -> PyResult<()> {
+    let file = File::open(path).map_err(|e| PyIOError::new_err(format!("Cannot open '{}': {}", path, e)))?;
+    let mut lines = BufReader::new(file).lines().enumerate();
+    loop {
+        let mut batch = Vec::with_capacity(processor.batch_size);
+        for (number, line) in lines.by_ref() {
+            let line = line.map_err(|e| PyIOError::new_err(format!("Cannot read '{}': {}", path, e)))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let record = match serde_json::from_str(&line) {
+                Ok(Value::Object(record)) => record,
+                _ => return Err(PyValueError::new_err(format!("Line {} of '{}' is not a JSON object", number + 1, path))),
+            };
+            batch.push((number + 1, record));
+            if batch.len() == processor.batch_size {
+                break;
+            }
+        }
+        if batch.is_empty() {
+            return Ok(());
+        }
+        apply(processor, batch)?;
+    }
+}
+
+fn text(record: &Map<String, Value>, field: &str) -> Option<String> {
+    record.get(field).and_then(|v| match v {
+        Value::String(s) => Some(s.clone()),
+        Value::Null => None,
+        other => Some(other.to_string()),
+    })
+}
+
+fn properties(record: &Map<String, Value>) -> impl Iterator<Item = (String, AttributeValue)> + '_ {
+    record.get("properties").and_then(Value::as_object).into_iter().flatten()
+        .filter_map(|(name, value)| AttributeValue::from_json(value).map(|value| (name.clone(), value)))
+}
+
+/// Adds or updates the nodes of a file in the `export_jsonl` layout, returning how many records
+/// were read
+pub fn import_nodes(processor: &mut BatchProcessor, path: &str) -> PyResult<usize> {
+    let mut count = 0;
+    for_each_batch(processor, path, |processor, batch| {
+        for (line, record) in batch {
+            let unique_id = text(&record, "unique_id")
+                .ok_or_else(|| PyValueError::new_err(format!("Line {} of '{}' has no unique_id", line, path)))?;
+            let node_type = text(&record, "node_type").unwrap_or_else(|| "Node".to_string());
+            let attributes = processor.properties("Node", &node_type, properties(&record))?;
+            processor.node(node_type, unique_id, text(&record, "title"), attributes)?;
+            count += 1;
+        }
+        Ok(())
+    })?;
+    Ok(count)
+}
+
+/// Adds the relations of a file in the `export_jsonl` layout, creating nodes missing from the
+/// graph, and returns how many were added
+pub fn import_edges(processor: &mut BatchProcessor, path: &str) -> PyResult<usize> {
+    let mut count = 0;
+    for_each_batch(processor, path, |processor, batch| {
+        for (line, record) in batch {
+            let field = |name: &str| text(&record, name)
+                .ok_or_else(|| PyValueError::new_err(format!("Line {} of '{}' has no {}", line, path, name)));
+            let source = processor.endpoint(field("source_type")?, field("source_id")?);
+            let target = processor.endpoint(field("target_type")?, field("target_id")?);
+            let relation_type = field("relation_type")?;
+            let attributes = processor.properties("Relation", &relation_type, properties(&record))?;
+            processor.relation(source, target, &relation_type, attributes);
+            count += 1;
+        }
+        Ok(())
+    })?;
+    Ok(count)
+}
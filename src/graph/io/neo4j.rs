@@ -0,0 +1,196 @@
+use pyo3::prelude::*;
+use pyo3::exceptions::PyIOError;
+use petgraph::graph::{DiGraph, NodeIndex};
+use petgraph::visit::EdgeRef;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+use crate::schema::{Node, Relation};
+use crate::data_types::AttributeValue;
+
+// Property columns of one CSV file, with the Neo4j type of each, in name order
+type Columns = BTreeMap<String, &'static str>;
+
+fn neo4j_type(data_type: &str) -> &'static str {
+    match data_type {
+        "Int" => "int",
+        "Float" => "float",
+        // Neo4j has no decimal type
+        data_type if AttributeValue::decimal_scale(data_type).is_some() => "float",
+        data_type if AttributeValue::datetime_zone(data_type).is_some() => "datetime",
+        "Date" => "date",
+        "Duration" => "duration",
+        "Boolean" => "boolean",
+        "List" => "string[]",
+        _ => "string",
+    }
+}
+
+// Columns holding values, typed by the schema node of the type where it records them
+fn columns<'a>(
+    graph: &DiGraph<Node, Relation>,
+    data_type: &str,
+    name: &str,
+    attributes: impl Iterator<Item = &'a HashMap<String, AttributeValue>>,
+) -> Columns {
+    let schema = graph.node_weights().find_map(|node| match node {
+        Node::DataTypeNode { data_type: dt, name: n, attributes, .. } if dt == data_type && n == name => Some(attributes),
+        _ => None,
+    });
+    let mut columns = Columns::new();
+    for (column, value) in attributes.flatten() {
+        columns.entry(column.clone()).or_insert_with(|| {
+            neo4j_type(schema.and_then(|schema| schema.get(column)).map_or(value.type_name(), String::as_str))
+        });
+    }
+    columns
+}
+
+// File names keep letters, digits and underscores only
+fn file_name(parts: &[&str]) -> String {
+    let name: String = parts.join("_").chars().map(|c| if c.is_alphanumeric() || c == '_' { c } else { '_' }).collect();
+    format!("{}.csv", name)
+}
+
+fn quote(identifier: &str) -> String {
+    format!("`{}`", identifier.replace('`', "``"))
+}
+
+fn write_csv(path: &Path, header: Vec<String>, rows: Vec<Vec<String>>) -> PyResult<()> {
+    let io_error = |e: csv::Error| PyIOError::new_err(format!("Cannot write '{}': {}", path.display(), e));
+    let mut writer = csv::Writer::from_path(path).map_err(io_error)?;
+    writer.write_record(&header).map_err(io_error)?;
+    for row in rows {
+        writer.write_record(&row).map_err(io_error)?;
+    }
+    writer.flush().map_err(|e| PyIOError::new_err(format!("Cannot write '{}': {}", path.display(), e)))
+}
+
+fn cell(attributes: Option<&HashMap<String, AttributeValue>>, column: &str) -> String {
+    attributes.and_then(|a| a.get(column)).map(AttributeValue::to_export_string).unwrap_or_default()
+}
+
+// Cypher expression converting a LOAD CSV field to its Neo4j type
+fn converted(field: &str, neo4j_type: &str) -> String {
+    let value = format!("row.{}", quote(field));
+    match neo4j_type {
+        "int" => format!("toInteger({})", value),
+        "float" => format!("toFloat({})", value),
+        "datetime" => format!("datetime({})", value),
+        "date" => format!("date({})", value),
+        "duration" => format!("duration({})", value),
+        "boolean" => format!("toBoolean({})", value),
+        "string[]" => format!("split({}, ';')", value),
+        _ => value,
+    }
+}
+
+fn set_clause(variable: &str, columns: &Columns, header_of: impl Fn(&str, &str) -> String) -> String {
+    columns.iter()
+        .map(|(column, t)| format!("{}.{} = {}", variable, quote(column), converted(&header_of(column, t), t)))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Writes one CSV per node type and one per (relation type, source type, target type) using the
+/// `neo4j-admin database import` header format, plus `import.sh` running that import and
+/// `load_csv.cypher` loading the same files into a running instance. Returns the written files.
+pub fn export_neo4j(graph: &DiGraph<Node, Relation>, dir: &str) -> PyResult<Vec<String>> {
+    let dir = Path::new(dir);
+    fs::create_dir_all(dir).map_err(|e| PyIOError::new_err(format!("Cannot create '{}': {}", dir.display(), e)))?;
+    let header_of = |column: &str, t: &str| if t == "string" { column.to_string() } else { format!("{}:{}", column, t) };
+
+    let mut nodes_by_type: BTreeMap<&str, Vec<NodeIndex>> = BTreeMap::new();
+    for index in graph.node_indices() {
+        if let Node::StandardNode { node_type, .. } = &graph[index] {
+            nodes_by_type.entry(node_type.as_str()).or_default().push(index);
+        }
+    }
+    let mut edges_by_type: BTreeMap<(&str, &str, &str), Vec<_>> = BTreeMap::new();
+    for edge in graph.edge_references() {
+        if let (Node::StandardNode { node_type: source, .. }, Node::StandardNode { node_type: target, .. }) = (&graph[edge.source()], &graph[edge.target()]) {
+            edges_by_type.entry((edge.weight().relation_type.as_str(), source.as_str(), target.as_str())).or_default().push(edge);
+        }
+    }
+
+    let mut files = Vec::new();
+    let mut admin_arguments = Vec::new();
+    let mut cypher = Vec::new();
+    for (node_type, indices) in &nodes_by_type {
+        let nodes: Vec<&Node> = indices.iter().map(|&index| &graph[index]).collect();
+        let columns = columns(graph, "Node", node_type, nodes.iter().filter_map(|node| match node {
+            Node::StandardNode { attributes, .. } => Some(attributes),
+            Node::DataTypeNode { .. } => None,
+        }));
+        let id_header = format!("unique_id:ID({})", node_type);
+        let mut header = vec![id_header.clone(), "title".to_string()];
+        header.extend(columns.iter().map(|(column, t)| header_of(column, t)));
+        header.push(":LABEL".to_string());
+        let rows = nodes.iter().filter_map(|node| match node {
+            Node::StandardNode { unique_id, title, attributes, .. } => {
+                let mut row = vec![unique_id.clone(), title.clone().unwrap_or_default()];
+                row.extend(columns.keys().map(|column| cell(Some(attributes), column)));
+                row.push(node_type.to_string());
+                Some(row)
+            },
+            Node::DataTypeNode { .. } => None,
+        }).collect();
+        let name = file_name(&["nodes", node_type]);
+        write_csv(&dir.join(&name), header, rows)?;
+
+        admin_arguments.push(format!("  \"--nodes={}=$DIR/{}\"", node_type, name));
+        let label = quote(node_type);
+        cypher.push(format!("CREATE CONSTRAINT IF NOT EXISTS FOR (n:{}) REQUIRE n.unique_id IS UNIQUE;", label));
+        let mut set = "n.title = row.title".to_string();
+        if !columns.is_empty() {
+            set = format!("{}, {}", set, set_clause("n", &columns, header_of));
+        }
+        cypher.push(format!(
+            "LOAD CSV WITH HEADERS FROM 'file:///{}' AS row\nMERGE (n:{} {{unique_id: row.{}}})\nSET {};",
+            name, label, quote(&id_header), set
+        ));
+        files.push(dir.join(name).display().to_string());
+    }
+
+    for ((relation_type, source_type, target_type), edges) in &edges_by_type {
+        let columns = columns(graph, "Relation", relation_type, edges.iter().filter_map(|edge| edge.weight().attributes.as_ref()));
+        let (start_header, end_header) = (format!(":START_ID({})", source_type), format!(":END_ID({})", target_type));
+        let mut header = vec![start_header.clone(), end_header.clone()];
+        header.extend(columns.iter().map(|(column, t)| header_of(column, t)));
+        header.push(":TYPE".to_string());
+        let unique_id = |index: NodeIndex| match &graph[index] {
+            Node::StandardNode { unique_id, .. } => unique_id.clone(),
+            Node::DataTypeNode { .. } => String::new(),
+        };
+        let rows = edges.iter().map(|edge| {
+            let mut row = vec![unique_id(edge.source()), unique_id(edge.target())];
+            row.extend(columns.keys().map(|column| cell(edge.weight().attributes.as_ref(), column)));
+            row.push(relation_type.to_string());
+            row
+        }).collect();
+        let name = file_name(&["relationships", relation_type, source_type, target_type]);
+        write_csv(&dir.join(&name), header, rows)?;
+
+        admin_arguments.push(format!("  \"--relationships={}=$DIR/{}\"", relation_type, name));
+        let set = match columns.is_empty() {
+            true => String::new(),
+            false => format!("\nSET {}", set_clause("r", &columns, header_of)),
+        };
+        cypher.push(format!(
+            "LOAD CSV WITH HEADERS FROM 'file:///{}' AS row\nMATCH (s:{} {{unique_id: row.{}}}), (t:{} {{unique_id: row.{}}})\nCREATE (s)-[r:{}]->(t){};",
+            name, quote(source_type), quote(&start_header), quote(target_type), quote(&end_header), quote(relation_type), set
+        ));
+        files.push(dir.join(name).display().to_string());
+    }
+
+    let script = format!(
+        "#!/bin/sh\n# Offline import into an empty database: run with Neo4j stopped\nDIR=\"$(cd \"$(dirname \"$0\")\" && pwd)\"\nneo4j-admin database import full neo4j \\\n{}\n",
+        admin_arguments.join(" \\\n")
+    );
+    for (name, content) in [("import.sh", script), ("load_csv.cypher", cypher.join("\n\n") + "\n")] {
+        let path = dir.join(name);
+        fs::write(&path, content).map_err(|e| PyIOError::new_err(format!("Cannot write '{}': {}", path.display(), e)))?;
+        files.push(path.display().to_string());
+    }
+    Ok(files)
+}
@@ -0,0 +1,141 @@
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyTuple};
+use petgraph::graph::{DiGraph, NodeIndex};
+use petgraph::visit::EdgeRef;
+use std::collections::{HashMap, HashSet};
+use crate::graph::journal::JournaledGraph;
+use crate::graph::io::{ImportedSchemas, DEFAULT_RELATION_TYPE};
+use crate::schema::{Node, Relation};
+use crate::data_types::AttributeValue;
+
+/// Names of the NetworkX attributes holding the built-in node and relation fields
+pub struct AttributeNames<'a> {
+    pub node_type: &'a str,
+    pub unique_id: &'a str,
+    pub title: &'a str,
+    pub relation_type: &'a str,
+}
+
+fn attribute_dict<'py>(py: Python<'py>, builtins: &[(&str, Option<&str>)], attributes: Option<&HashMap<String, AttributeValue>>) -> PyResult<&'py PyDict> {
+    let dict = PyDict::new(py);
+    for (name, value) in attributes.into_iter().flatten() {
+        dict.set_item(name, value.to_python_object(py, None)?)?;
+    }
+    for &(name, value) in builtins {
+        if let Some(value) = value {
+            dict.set_item(name, value)?;
+        }
+    }
+    Ok(dict)
+}
+
+/// Builds a `networkx.DiGraph` keyed by node index, or a `MultiDiGraph` keyed additionally by
+/// relation type so parallel relations of different types are kept. Node type, unique ID, title
+/// and relation type are stored as attributes next to the properties.
+pub fn to_networkx(
+    graph: &DiGraph<Node, Relation>,
+    py: Python,
+    indices: Option<&[usize]>,
+    multigraph: bool,
+    names: &AttributeNames,
+) -> PyResult<PyObject> {
+    let selected: Option<HashSet<NodeIndex>> = indices.map(|indices| indices.iter().map(|&i| NodeIndex::new(i)).collect());
+    let keep = |node: NodeIndex| selected.as_ref().is_none_or(|selected| selected.contains(&node))
+        && matches!(graph.node_weight(node), Some(Node::StandardNode { .. }));
+
+    let mut nodes = Vec::new();
+    for index in graph.node_indices().filter(|&index| keep(index)) {
+        let Node::StandardNode { node_type, unique_id, title, attributes, .. } = &graph[index] else { continue };
+        let builtins = [(names.node_type, Some(node_type.as_str())), (names.unique_id, Some(unique_id.as_str())), (names.title, title.as_deref())];
+        nodes.push((index.index(), attribute_dict(py, &builtins, Some(attributes))?).to_object(py));
+    }
+    let mut edges = Vec::new();
+    for edge in graph.edge_references().filter(|edge| keep(edge.source()) && keep(edge.target())) {
+        let relation = edge.weight();
+        let data = attribute_dict(py, &[(names.relation_type, Some(relation.relation_type.as_str()))], relation.attributes.as_ref())?;
+        let (source, target) = (edge.source().index(), edge.target().index());
+        edges.push(match multigraph {
+            true => (source, target, &relation.relation_type, data).to_object(py),
+            false => (source, target, data).to_object(py),
+        });
+    }
+
+    let networkx = py.import("networkx")?;
+    let nx_graph = networkx.getattr(if multigraph { "MultiDiGraph" } else { "DiGraph" })?.call0()?;
+    nx_graph.call_method1("add_nodes_from", (nodes,))?;
+    nx_graph.call_method1("add_edges_from", (edges,))?;
+    Ok(nx_graph.into_py(py))
+}
+
+// Values that aren't numbers, text, dates or collections are stored as their string form
+pub fn py_value(value: &PyAny) -> PyResult<AttributeValue> {
+    Ok(value.extract().unwrap_or_else(|_| AttributeValue::String(value.to_string())))
+}
+
+// Built-in fields as text, in the order asked for, and the remaining properties
+type SplitAttributes = (Vec<Option<String>>, HashMap<String, AttributeValue>);
+
+// Splits an attribute dictionary into the built-in fields and the properties
+fn split_attributes(data: &PyDict, builtins: &[&str]) -> PyResult<SplitAttributes> {
+    let mut fields = vec![None; builtins.len()];
+    let mut attributes = HashMap::new();
+    for (key, value) in data.iter() {
+        let key = key.str()?.to_string();
+        match builtins.iter().position(|&name| name == key) {
+            Some(position) => fields[position] = (!value.is_none()).then(|| value.str().map(|s| s.to_string())).transpose()?,
+            None if value.is_none() => (),
+            None => { attributes.insert(key, py_value(value)?); },
+        }
+    }
+    Ok((fields, attributes))
+}
+
+/// Reads a NetworkX graph into a new graph. Nodes without a node type attribute become "Node",
+/// and nodes without a unique ID attribute use their NetworkX key. Relations take their type from
+/// the relation type attribute, the multigraph key, or fall back to a default.
+pub fn from_networkx(nx_graph: &PyAny, names: &AttributeNames) -> PyResult<JournaledGraph> {
+    let py = nx_graph.py();
+    let mut graph = JournaledGraph::default();
+    let mut schemas = ImportedSchemas::default();
+    let node_indices = PyDict::new(py);
+
+    let data_kwargs = PyDict::new(py);
+    data_kwargs.set_item("data", true)?;
+    for item in nx_graph.call_method("nodes", (), Some(data_kwargs))?.iter()? {
+        let (key, data): (&PyAny, &PyDict) = item?.extract()?;
+        let (fields, attributes) = split_attributes(data, &[names.node_type, names.unique_id, names.title])?;
+        let [node_type, unique_id, title]: [Option<String>; 3] = fields.try_into().expect("Three built-in fields");
+        let node_type = node_type.unwrap_or_else(|| "Node".to_string());
+        let unique_id = match unique_id {
+            Some(unique_id) => unique_id,
+            None => key.str()?.to_string(),
+        };
+        schemas.record_node(&node_type, &attributes);
+        let index = graph.add_node(Node::new(&node_type, &unique_id, Some(attributes), title.as_deref()));
+        node_indices.set_item(key, index.index())?;
+    }
+
+    let multigraph: bool = nx_graph.call_method0("is_multigraph")?.extract()?;
+    if multigraph {
+        data_kwargs.set_item("keys", true)?;
+    }
+    for item in nx_graph.call_method("edges", (), Some(data_kwargs))?.iter()? {
+        let item: &PyTuple = item?.downcast()?;
+        let endpoint = |position: usize| -> PyResult<NodeIndex> {
+            let index: usize = node_indices.get_item(item.get_item(position)?)
+                .expect("NetworkX edges connect nodes of the graph")
+                .extract()?;
+            Ok(NodeIndex::new(index))
+        };
+        let (source, target) = (endpoint(0)?, endpoint(1)?);
+        let data: &PyDict = item.get_item(item.len() - 1)?.downcast()?;
+        let (fields, attributes) = split_attributes(data, &[names.relation_type])?;
+        let key_type = multigraph.then(|| item.get_item(2)).transpose()?.and_then(|key| key.extract::<String>().ok());
+        let relation_type = fields.into_iter().next().flatten().or(key_type)
+            .unwrap_or_else(|| DEFAULT_RELATION_TYPE.to_string());
+        schemas.record_relation(&relation_type, &attributes);
+        graph.add_edge(source, target, Relation::new(&relation_type, (!attributes.is_empty()).then_some(attributes)));
+    }
+    schemas.register(&mut graph)?;
+    Ok(graph)
+}
@@ -0,0 +1,138 @@
+use pyo3::prelude::*;
+use pyo3::exceptions::{PyIOError, PyValueError};
+use petgraph::graph::{DiGraph, NodeIndex};
+use petgraph::visit::EdgeRef;
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use crate::schema::{Node, Relation};
+use crate::data_types::AttributeValue;
+
+pub const DEFAULT_BASE_IRI: &str = "http://example.org/";
+pub const DEFAULT_IRI_TEMPLATE: &str = "{base}{node_type}/{unique_id}";
+
+/// Where the IRIs of nodes, classes and predicates come from
+pub struct IriOptions<'a> {
+    pub base_iri: &'a str,
+    // Node IRIs, with {base}, {node_type} and {unique_id} filled in
+    pub iri_template: &'a str,
+    // IRIs replacing the generated ones for node types, relation types and property names
+    pub type_mappings: HashMap<String, String>,
+}
+
+// Percent-encodes what can't appear in an IRI, so IDs with spaces or slashes stay one segment
+fn iri_segment(value: &str) -> String {
+    let mut encoded = String::new();
+    for c in value.chars() {
+        match c {
+            'A'..='Z' | 'a'..='z' | '0'..='9' | '-' | '.' | '_' | '~' => encoded.push(c),
+            c if !c.is_ascii() && !c.is_control() => encoded.push(c),
+            c => {
+                let mut buffer = [0u8; 4];
+                for byte in c.encode_utf8(&mut buffer).bytes() {
+                    encoded.push_str(&format!("%{:02X}", byte));
+                }
+            },
+        }
+    }
+    encoded
+}
+
+fn literal_text(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len() + 2);
+    escaped.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+fn literal(value: &AttributeValue) -> String {
+    match value {
+        AttributeValue::Int(v) => format!("\"{}\"^^xsd:integer", v),
+        AttributeValue::Float(v) => format!("\"{:e}\"^^xsd:double", v),
+        AttributeValue::DateTime(_) => format!("\"{}Z\"^^xsd:dateTime", value.to_export_string()),
+        AttributeValue::String(v) => literal_text(v),
+        AttributeValue::Boolean(v) => format!("\"{}\"^^xsd:boolean", v),
+        AttributeValue::Null => literal_text(""),
+        // Turtle object list: one triple per element
+        AttributeValue::List(values) => values.iter().map(literal).collect::<Vec<_>>().join(", "),
+        AttributeValue::Map(_) => format!("{}^^rdf:JSON", literal_text(&value.to_export_string())),
+        AttributeValue::Decimal(v) => format!("\"{}\"^^xsd:decimal", v),
+        AttributeValue::Date(_) => format!("\"{}\"^^xsd:date", value.to_string()),
+        AttributeValue::Duration(_) => format!("\"{}\"^^xsd:duration", value.to_string()),
+        AttributeValue::Uuid(_) => literal_text(&value.to_string()),
+        AttributeValue::Category(v) => literal_text(v.label()),
+        AttributeValue::Bytes(_) => format!("\"{}\"^^xsd:hexBinary", value.to_string()),
+    }
+}
+
+impl IriOptions<'_> {
+    fn node(&self, node_type: &str, unique_id: &str) -> String {
+        let iri = self.iri_template
+            .replace("{base}", self.base_iri)
+            .replace("{node_type}", &iri_segment(node_type))
+            .replace("{unique_id}", &iri_segment(unique_id));
+        format!("<{}>", iri)
+    }
+
+    // Classes and predicates live under {base}vocab/ unless mapped
+    fn term(&self, name: &str) -> String {
+        match self.type_mappings.get(name) {
+            Some(iri) => format!("<{}>", iri),
+            None => format!("<{}vocab/{}>", self.base_iri, iri_segment(name)),
+        }
+    }
+}
+
+/// Writes the selected nodes (all by default) and the relations between them as Turtle: one
+/// subject per node with its class, label, unique ID and properties, and one triple per relation.
+/// Relation properties have no place in plain RDF triples and are left out.
+pub fn write_rdf(graph: &DiGraph<Node, Relation>, path: &str, indices: Option<&[usize]>, options: &IriOptions) -> PyResult<usize> {
+    if !options.iri_template.contains("{unique_id}") {
+        return Err(PyValueError::new_err("iri_template must contain {unique_id} so every node gets its own IRI"));
+    }
+    let selected: Option<HashSet<NodeIndex>> = indices.map(|indices| indices.iter().map(|&i| NodeIndex::new(i)).collect());
+    let keep = |node: NodeIndex| selected.as_ref().is_none_or(|selected| selected.contains(&node));
+    let iri_of = |node: NodeIndex| match &graph[node] {
+        Node::StandardNode { node_type, unique_id, .. } => Some(options.node(node_type, unique_id)),
+        Node::DataTypeNode { .. } => None,
+    };
+
+    let file = File::create(path).map_err(|e| PyIOError::new_err(format!("Cannot create '{}': {}", path, e)))?;
+    let mut out = BufWriter::new(file);
+    let write_error = |e: std::io::Error| PyIOError::new_err(format!("Cannot write '{}': {}", path, e));
+    writeln!(out, "@prefix rdf: <http://www.w3.org/1999/02/22-rdf-syntax-ns#> .\n@prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#> .\n@prefix xsd: <http://www.w3.org/2001/XMLSchema#> .\n")
+        .map_err(write_error)?;
+    let mut triples = 0;
+    for index in graph.node_indices().filter(|&index| keep(index)) {
+        let Node::StandardNode { node_type, unique_id, title, attributes, .. } = &graph[index] else { continue };
+        let mut statements = vec![format!("a {}", options.term(node_type))];
+        if let Some(title) = title {
+            statements.push(format!("rdfs:label {}", literal_text(title)));
+        }
+        statements.push(format!("{} {}", options.term("unique_id"), literal_text(unique_id)));
+        // Empty lists have no triples to write
+        let mut properties: Vec<_> = attributes.iter().filter(|(_, value)| !matches!(value, AttributeValue::List(v) if v.is_empty())).collect();
+        properties.sort_by(|a, b| a.0.cmp(b.0));
+        statements.extend(properties.into_iter().map(|(name, value)| format!("{} {}", options.term(name), literal(value))));
+        triples += statements.len();
+        writeln!(out, "{}\n    {} .\n", options.node(node_type, unique_id), statements.join(" ;\n    ")).map_err(write_error)?;
+    }
+    for edge in graph.edge_references().filter(|edge| keep(edge.source()) && keep(edge.target())) {
+        if let (Some(source), Some(target)) = (iri_of(edge.source()), iri_of(edge.target())) {
+            writeln!(out, "{} {} {} .", source, options.term(&edge.weight().relation_type), target).map_err(write_error)?;
+            triples += 1;
+        }
+    }
+    out.flush().map_err(write_error)?;
+    Ok(triples)
+}
@@ -0,0 +1,175 @@
+use pyo3::prelude::*;
+use pyo3::exceptions::{PyTypeError, PyValueError};
+use arrow::array::{Array, ArrayRef, AsArray, StructArray};
+use arrow::compute::cast;
+use arrow::datatypes::{self, DataType, TimeUnit};
+use arrow::ffi::{from_ffi, FFI_ArrowArray, FFI_ArrowSchema};
+use arrow::record_batch::RecordBatch;
+use std::collections::HashMap;
+use std::sync::Arc;
+use crate::graph::io::tabular::{Row, Table};
+use crate::data_types::{AttributeValue, Decimal};
+
+fn arrow_error(e: arrow::error::ArrowError) -> PyErr {
+    PyValueError::new_err(format!("Cannot read Arrow data: {}", e))
+}
+
+// Moves one pyarrow RecordBatch into Rust through the Arrow C data interface, without copying
+// the buffers
+fn import_batch(batch: &PyAny) -> PyResult<RecordBatch> {
+    let mut array = FFI_ArrowArray::empty();
+    let mut schema = FFI_ArrowSchema::empty();
+    batch.call_method1(
+        "_export_to_c",
+        (std::ptr::addr_of_mut!(array) as usize, std::ptr::addr_of_mut!(schema) as usize),
+    )?;
+    // SAFETY: pyarrow filled both structs through the pointers above and handed over ownership
+    let data = unsafe { from_ffi(array, &schema) }.map_err(arrow_error)?;
+    Ok(RecordBatch::from(StructArray::from(data)))
+}
+
+/// Imports a pyarrow RecordBatch, Table or list of RecordBatches
+pub fn import_batches(data: &PyAny) -> PyResult<Vec<RecordBatch>> {
+    if data.hasattr("to_batches")? {
+        return data.call_method0("to_batches")?.iter()?.map(|batch| import_batch(batch?)).collect();
+    }
+    if data.hasattr("_export_to_c")? {
+        return Ok(vec![import_batch(data)?]);
+    }
+    match data.extract::<Vec<&PyAny>>() {
+        Ok(batches) => batches.into_iter().map(import_batch).collect(),
+        Err(_) => Err(PyTypeError::new_err("Expected a pyarrow RecordBatch, Table or list of RecordBatches")),
+    }
+}
+
+// Arrow types without a graph counterpart (dictionaries, maps, unions) are read as text
+fn normalize(column: &ArrayRef) -> PyResult<ArrayRef> {
+    match column.data_type() {
+        DataType::Boolean | DataType::Int8 | DataType::Int16 | DataType::Int32 | DataType::Int64
+        | DataType::UInt8 | DataType::UInt16 | DataType::UInt32 | DataType::UInt64
+        | DataType::Float32 | DataType::Float64 | DataType::Utf8 | DataType::LargeUtf8
+        | DataType::Date32 | DataType::Date64 | DataType::Timestamp(_, _) | DataType::Duration(_)
+        | DataType::Decimal128(_, _) | DataType::List(_) | DataType::LargeList(_)
+        | DataType::Binary | DataType::LargeBinary => Ok(column.clone()),
+        // Struct fields are normalized up front so rows read them as they are
+        DataType::Struct(_) => {
+            let column = column.as_struct();
+            let children = column.columns().iter().map(normalize).collect::<PyResult<Vec<_>>>()?;
+            let fields: Vec<_> = column.fields().iter().zip(&children)
+                .map(|(field, child)| Arc::new(field.as_ref().clone().with_data_type(child.data_type().clone())))
+                .collect();
+            let normalized = StructArray::try_new(fields.into(), children, column.nulls().cloned()).map_err(arrow_error)?;
+            Ok(Arc::new(normalized))
+        },
+        DataType::Float16 | DataType::Decimal256(_, _) => cast(column, &DataType::Float64).map_err(arrow_error),
+        DataType::FixedSizeBinary(_) => cast(column, &DataType::Binary).map_err(arrow_error),
+        _ => cast(column, &DataType::Utf8).map_err(arrow_error),
+    }
+}
+
+fn cell(column: &dyn Array, name: &str, row: usize) -> PyResult<Option<AttributeValue>> {
+    if column.is_null(row) {
+        return Ok(None);
+    }
+    let int = |value: u64| i64::try_from(value).map(AttributeValue::Int).map_err(|_| {
+        PyValueError::new_err(format!("Value {} in column '{}' is out of range for Int", value, name))
+    });
+    let value = match column.data_type() {
+        DataType::Boolean => AttributeValue::Boolean(column.as_boolean().value(row)),
+        DataType::Int8 => AttributeValue::Int(column.as_primitive::<datatypes::Int8Type>().value(row) as i64),
+        DataType::Int16 => AttributeValue::Int(column.as_primitive::<datatypes::Int16Type>().value(row) as i64),
+        DataType::Int32 => AttributeValue::Int(column.as_primitive::<datatypes::Int32Type>().value(row) as i64),
+        DataType::Int64 => AttributeValue::Int(column.as_primitive::<datatypes::Int64Type>().value(row)),
+        DataType::UInt8 => AttributeValue::Int(column.as_primitive::<datatypes::UInt8Type>().value(row) as i64),
+        DataType::UInt16 => AttributeValue::Int(column.as_primitive::<datatypes::UInt16Type>().value(row) as i64),
+        DataType::UInt32 => AttributeValue::Int(column.as_primitive::<datatypes::UInt32Type>().value(row) as i64),
+        DataType::UInt64 => int(column.as_primitive::<datatypes::UInt64Type>().value(row))?,
+        DataType::Float32 => AttributeValue::Float(column.as_primitive::<datatypes::Float32Type>().value(row) as f64),
+        DataType::Float64 => AttributeValue::Float(column.as_primitive::<datatypes::Float64Type>().value(row)),
+        DataType::Decimal128(_, scale) => {
+            let units = column.as_primitive::<datatypes::Decimal128Type>().value(row);
+            Decimal::parse(&format!("{}e{}", units, -(*scale as i32))).map(AttributeValue::Decimal).ok_or_else(|| {
+                PyValueError::new_err(format!("Value in column '{}' is out of range for Decimal", name))
+            })?
+        },
+        DataType::Utf8 => AttributeValue::String(column.as_string::<i32>().value(row).to_string()),
+        DataType::LargeUtf8 => AttributeValue::String(column.as_string::<i64>().value(row).to_string()),
+        DataType::Binary => AttributeValue::Bytes(column.as_binary::<i32>().value(row).to_vec()),
+        DataType::LargeBinary => AttributeValue::Bytes(column.as_binary::<i64>().value(row).to_vec()),
+        DataType::Date32 => AttributeValue::Date(column.as_primitive::<datatypes::Date32Type>().value(row)),
+        DataType::Date64 => AttributeValue::date_from_timestamp(column.as_primitive::<datatypes::Date64Type>().value(row).div_euclid(1_000)),
+        DataType::Timestamp(unit, _) => {
+            let (value, per_second) = match unit {
+                TimeUnit::Second => (column.as_primitive::<datatypes::TimestampSecondType>().value(row), 1),
+                TimeUnit::Millisecond => (column.as_primitive::<datatypes::TimestampMillisecondType>().value(row), 1_000),
+                TimeUnit::Microsecond => (column.as_primitive::<datatypes::TimestampMicrosecondType>().value(row), 1_000_000),
+                TimeUnit::Nanosecond => (column.as_primitive::<datatypes::TimestampNanosecondType>().value(row), 1_000_000_000),
+            };
+            AttributeValue::DateTime(value.div_euclid(per_second))
+        },
+        DataType::Duration(unit) => {
+            let (value, per_second) = match unit {
+                TimeUnit::Second => (column.as_primitive::<datatypes::DurationSecondType>().value(row), 1),
+                TimeUnit::Millisecond => (column.as_primitive::<datatypes::DurationMillisecondType>().value(row), 1_000),
+                TimeUnit::Microsecond => (column.as_primitive::<datatypes::DurationMicrosecondType>().value(row), 1_000_000),
+                TimeUnit::Nanosecond => (column.as_primitive::<datatypes::DurationNanosecondType>().value(row), 1_000_000_000),
+            };
+            AttributeValue::Duration(value.div_euclid(per_second))
+        },
+        DataType::List(_) => list(&column.as_list::<i32>().value(row), name)?,
+        DataType::LargeList(_) => list(&column.as_list::<i64>().value(row), name)?,
+        DataType::Struct(fields) => {
+            let columns = column.as_struct().columns();
+            let mut entries = HashMap::with_capacity(fields.len());
+            for (field, values) in fields.iter().zip(columns) {
+                if let Some(value) = cell(values, name, row)? {
+                    entries.insert(field.name().clone(), value);
+                }
+            }
+            AttributeValue::Map(entries)
+        },
+        other => unreachable!("Column type {} is normalized before reading", other),
+    };
+    Ok(Some(value))
+}
+
+// The elements of one list cell, leaving out nulls
+fn list(values: &ArrayRef, name: &str) -> PyResult<AttributeValue> {
+    let values = normalize(values)?;
+    let elements = (0..values.len()).filter_map(|i| cell(&values, name, i).transpose()).collect::<PyResult<_>>()?;
+    Ok(AttributeValue::List(elements))
+}
+
+/// Columns holding Arrow dictionaries, as pandas category columns arrive
+pub fn dictionary_columns(batches: &[RecordBatch]) -> Vec<String> {
+    batches.first().map_or_else(Vec::new, |batch| batch.schema().fields().iter()
+        .filter(|field| matches!(field.data_type(), DataType::Dictionary(_, _)))
+        .map(|field| field.name().clone())
+        .collect())
+}
+
+/// Reads record batches as a table for `ingest_nodes`, converting values column type by column
+/// type instead of through Python objects
+pub fn batches_to_table(batches: Vec<RecordBatch>) -> PyResult<Table> {
+    let Some(first) = batches.first() else {
+        return Ok(Table { columns: Vec::new(), rows: Box::new(std::iter::empty()) });
+    };
+    let columns: Vec<String> = first.schema().fields().iter().map(|field| field.name().clone()).collect();
+    let mut normalized: Vec<Vec<ArrayRef>> = Vec::with_capacity(batches.len());
+    for batch in &batches {
+        if batch.num_columns() != columns.len() {
+            return Err(PyValueError::new_err("All record batches must have the same columns"));
+        }
+        normalized.push(batch.columns().iter().map(normalize).collect::<PyResult<_>>()?);
+    }
+
+    let names = columns.clone();
+    let rows = normalized.into_iter().flat_map(move |arrays| {
+        let names = names.clone();
+        let length = arrays.first().map_or(0, |array| array.len());
+        (0..length).map(move |row| -> PyResult<Row> {
+            arrays.iter().zip(&names).map(|(array, name)| cell(array.as_ref(), name, row)).collect()
+        })
+    });
+    Ok(Table { columns, rows: Box::new(rows) })
+}
@@ -0,0 +1,474 @@
+use pyo3::prelude::*;
+use pyo3::exceptions::{PyIOError, PyValueError};
+use petgraph::graph::NodeIndex;
+use petgraph::visit::EdgeRef;
+use rusqlite::types::{Value, ValueRef};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::{BTreeMap, HashMap};
+use crate::graph::journal::JournaledGraph;
+use crate::graph::io::ImportedSchemas;
+use crate::graph::persistence::{GraphState, GraphStateRef};
+use crate::graph::categories;
+use crate::graph::constraints::{parse_constraint, set_constraint};
+use crate::schema::{Cardinality, Constraint, Endpoints, Node, Relation};
+use crate::data_types::{AttributeValue, Category};
+
+// Version of the table layout below, kept in the metadata table. Version 2 added
+// schema_constraints, version 3 schema_endpoints, version 4 schema_defaults and version 5
+// schema_keys; databases in earlier versions are still read.
+const LAYOUT_VERSION: i64 = 5;
+
+// Node and edge ids are the graph indices when written. Rows added with SQL may use any unused
+// id: nodes are read back in id order, so new ids only need to sort after the existing ones to
+// keep the indices of existing nodes.
+const LAYOUT: &str = "
+CREATE TABLE IF NOT EXISTS metadata (key TEXT PRIMARY KEY, value TEXT NOT NULL);
+CREATE TABLE IF NOT EXISTS nodes (
+    id INTEGER PRIMARY KEY,
+    node_type TEXT NOT NULL,
+    unique_id TEXT NOT NULL,
+    title TEXT,
+    sequence INTEGER
+);
+CREATE INDEX IF NOT EXISTS nodes_by_type_and_id ON nodes (node_type, unique_id);
+CREATE TABLE IF NOT EXISTS node_properties (
+    node_id INTEGER NOT NULL REFERENCES nodes (id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    value,
+    PRIMARY KEY (node_id, name)
+) WITHOUT ROWID;
+CREATE INDEX IF NOT EXISTS node_properties_by_name ON node_properties (name, value);
+CREATE TABLE IF NOT EXISTS edges (
+    id INTEGER PRIMARY KEY,
+    source_id INTEGER NOT NULL REFERENCES nodes (id) ON DELETE CASCADE,
+    target_id INTEGER NOT NULL REFERENCES nodes (id) ON DELETE CASCADE,
+    relation_type TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS edges_by_source ON edges (source_id, relation_type);
+CREATE INDEX IF NOT EXISTS edges_by_target ON edges (target_id, relation_type);
+CREATE TABLE IF NOT EXISTS edge_properties (
+    edge_id INTEGER NOT NULL REFERENCES edges (id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    value,
+    PRIMARY KEY (edge_id, name)
+) WITHOUT ROWID;
+CREATE TABLE IF NOT EXISTS schemas (
+    id INTEGER PRIMARY KEY,
+    data_type TEXT NOT NULL CHECK (data_type IN ('Node', 'Relation')),
+    name TEXT NOT NULL,
+    UNIQUE (data_type, name)
+);
+CREATE TABLE IF NOT EXISTS schema_properties (
+    schema_id INTEGER NOT NULL REFERENCES schemas (id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    type TEXT NOT NULL,
+    calculated INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (schema_id, name)
+) WITHOUT ROWID;
+CREATE TABLE IF NOT EXISTS schema_constraints (
+    schema_id INTEGER NOT NULL REFERENCES schemas (id) ON DELETE CASCADE,
+    property TEXT NOT NULL,
+    kind TEXT NOT NULL CHECK (kind IN ('unique', 'required', 'range')),
+    min,
+    max,
+    PRIMARY KEY (schema_id, property, kind)
+) WITHOUT ROWID;
+CREATE TABLE IF NOT EXISTS schema_endpoints (
+    schema_id INTEGER PRIMARY KEY REFERENCES schemas (id) ON DELETE CASCADE,
+    source_type TEXT NOT NULL,
+    target_type TEXT NOT NULL,
+    cardinality TEXT NOT NULL CHECK (cardinality IN ('1:1', '1:N', 'N:1', 'N:M'))
+);
+CREATE TABLE IF NOT EXISTS schema_defaults (
+    schema_id INTEGER NOT NULL REFERENCES schemas (id) ON DELETE CASCADE,
+    property TEXT NOT NULL,
+    value,
+    PRIMARY KEY (schema_id, property)
+) WITHOUT ROWID;
+CREATE TABLE IF NOT EXISTS schema_keys (
+    schema_id INTEGER NOT NULL REFERENCES schemas (id) ON DELETE CASCADE,
+    property TEXT NOT NULL,
+    role TEXT NOT NULL CHECK (role IN ('unique_id', 'title')),
+    position INTEGER NOT NULL,
+    PRIMARY KEY (schema_id, role, position)
+) WITHOUT ROWID;
+CREATE TABLE IF NOT EXISTS selections (
+    name TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    node_id INTEGER NOT NULL,
+    PRIMARY KEY (name, position)
+) WITHOUT ROWID;
+";
+
+fn sql_error(path: &str) -> impl Fn(rusqlite::Error) -> PyErr + '_ {
+    move |e| PyIOError::new_err(format!("SQLite error in '{}': {}", path, e))
+}
+
+// DateTime and Date are stored as ISO 8601 text so SQLite's date functions work on them, Duration
+// as seconds so it sums in SQL, Decimal as text so no digits are lost, UUID as its canonical text
+// and Category as its label
+fn sql_value(value: &AttributeValue) -> Value {
+    match value {
+        AttributeValue::Int(v) => Value::Integer(*v),
+        AttributeValue::Float(v) => Value::Real(*v),
+        AttributeValue::DateTime(_) | AttributeValue::Date(_) => Value::Text(value.to_export_string()),
+        AttributeValue::String(v) => Value::Text(v.clone()),
+        AttributeValue::Boolean(v) => Value::Integer(*v as i64),
+        AttributeValue::Null => Value::Null,
+        AttributeValue::List(_) | AttributeValue::Map(_) => Value::Text(value.to_json().to_string()),
+        AttributeValue::Decimal(v) => Value::Text(v.to_string()),
+        AttributeValue::Duration(v) => Value::Integer(*v),
+        AttributeValue::Uuid(_) => Value::Text(value.to_string()),
+        AttributeValue::Category(v) => Value::Text(v.label().to_string()),
+        AttributeValue::Bytes(v) => Value::Blob(v.clone()),
+    }
+}
+
+// Reads a stored value back by its SQLite storage class, or as DateTime, Date, Duration, Boolean,
+// Decimal, UUID, Category, Bytes, List or Map (JSON text) where the schema says so
+pub fn attribute_value(value: ValueRef, schema_type: Option<&str>) -> Option<AttributeValue> {
+    if let Some(scale) = schema_type.and_then(AttributeValue::decimal_scale) {
+        let value = attribute_value(value, None)?;
+        return Some(value.to_decimal(scale).map_or(value, AttributeValue::Decimal));
+    }
+    // Stored instants are UTC whatever the column's time zone
+    let schema_type = schema_type.map(|t| if AttributeValue::datetime_zone(t).is_some() { "DateTime" } else { t });
+    match (value, schema_type) {
+        (ValueRef::Null, _) => None,
+        (ValueRef::Integer(v), Some("DateTime")) => Some(AttributeValue::DateTime(v)),
+        (ValueRef::Text(text), Some("DateTime")) => {
+            let text = String::from_utf8_lossy(text);
+            Some(AttributeValue::parse_iso_datetime(&text).unwrap_or_else(|| AttributeValue::String(text.into_owned())))
+        },
+        (ValueRef::Text(text), Some("Date")) => {
+            let text = String::from_utf8_lossy(text);
+            Some(AttributeValue::parse_iso_date(&text).unwrap_or_else(|| AttributeValue::String(text.into_owned())))
+        },
+        (ValueRef::Text(text), Some("UUID")) => {
+            let text = String::from_utf8_lossy(text);
+            Some(AttributeValue::parse_uuid(&text).unwrap_or_else(|| AttributeValue::String(text.into_owned())))
+        },
+        (ValueRef::Text(text), Some("Category")) => Some(AttributeValue::Category(Category::new(&String::from_utf8_lossy(text)))),
+        (ValueRef::Integer(v), Some("Duration")) => Some(AttributeValue::Duration(v)),
+        (ValueRef::Integer(v), Some("Float")) => Some(AttributeValue::Float(v as f64)),
+        (ValueRef::Integer(v), Some("Boolean")) => Some(AttributeValue::Boolean(v != 0)),
+        (ValueRef::Text(text), Some("List" | "Map")) => serde_json::from_slice(text).ok().as_ref().and_then(AttributeValue::from_json)
+            .or_else(|| Some(AttributeValue::String(String::from_utf8_lossy(text).into_owned()))),
+        (ValueRef::Integer(v), _) => Some(AttributeValue::Int(v)),
+        (ValueRef::Real(v), _) => Some(AttributeValue::Float(v)),
+        (ValueRef::Blob(bytes), None | Some("Bytes")) => Some(AttributeValue::Bytes(bytes.to_vec())),
+        (ValueRef::Text(text), Some("Bytes")) => AttributeValue::parse_hex(&String::from_utf8_lossy(text))
+            .or_else(|| Some(AttributeValue::String(String::from_utf8_lossy(text).into_owned()))),
+        (ValueRef::Text(text), _) | (ValueRef::Blob(text), _) => Some(AttributeValue::String(String::from_utf8_lossy(text).into_owned())),
+    }
+}
+
+/// Writes the graph state to an SQLite database in the layout above, replacing what an earlier
+/// write stored there. Everything is written in one transaction.
+pub fn write_sqlite(path: &str, state: &GraphStateRef) -> PyResult<()> {
+    let error = sql_error(path);
+    let mut connection = Connection::open(path).map_err(&error)?;
+    let transaction = connection.transaction().map_err(&error)?;
+    transaction.execute_batch(LAYOUT).map_err(&error)?;
+    for table in ["selections", "schema_keys", "schema_defaults", "schema_endpoints", "schema_constraints", "schema_properties", "schemas", "edge_properties", "edges", "node_properties", "nodes", "metadata"] {
+        transaction.execute(&format!("DELETE FROM {}", table), []).map_err(&error)?;
+    }
+    {
+        let mut metadata = transaction.prepare("INSERT INTO metadata (key, value) VALUES (?1, ?2)").map_err(&error)?;
+        for (key, value) in [
+            ("layout_version", LAYOUT_VERSION.to_string()),
+            ("track_insertion_order", state.track_insertion_order.to_string()),
+            ("next_sequence", state.next_sequence.to_string()),
+        ] {
+            metadata.execute(params![key, value]).map_err(&error)?;
+        }
+
+        let mut node = transaction.prepare("INSERT INTO nodes (id, node_type, unique_id, title, sequence) VALUES (?1, ?2, ?3, ?4, ?5)").map_err(&error)?;
+        let mut node_property = transaction.prepare("INSERT INTO node_properties (node_id, name, value) VALUES (?1, ?2, ?3)").map_err(&error)?;
+        let mut schema = transaction.prepare("INSERT INTO schemas (id, data_type, name) VALUES (?1, ?2, ?3)").map_err(&error)?;
+        let mut schema_property = transaction.prepare(
+            "INSERT INTO schema_properties (schema_id, name, type, calculated) VALUES (?1, ?2, ?3, ?4)"
+        ).map_err(&error)?;
+        let mut schema_constraint = transaction.prepare(
+            "INSERT INTO schema_constraints (schema_id, property, kind, min, max) VALUES (?1, ?2, ?3, ?4, ?5)"
+        ).map_err(&error)?;
+        let mut schema_endpoints = transaction.prepare(
+            "INSERT INTO schema_endpoints (schema_id, source_type, target_type, cardinality) VALUES (?1, ?2, ?3, ?4)"
+        ).map_err(&error)?;
+        let mut schema_default = transaction.prepare("INSERT INTO schema_defaults (schema_id, property, value) VALUES (?1, ?2, ?3)")
+            .map_err(&error)?;
+        let mut schema_key = transaction.prepare("INSERT INTO schema_keys (schema_id, property, role, position) VALUES (?1, ?2, ?3, ?4)")
+            .map_err(&error)?;
+        for index in state.graph.node_indices() {
+            let id = index.index() as i64;
+            match &state.graph[index] {
+                Node::StandardNode { node_type, unique_id, title, attributes, sequence } => {
+                    node.execute(params![id, node_type.as_str(), unique_id, title, sequence.map(|s| s as i64)]).map_err(&error)?;
+                    for (name, value) in attributes {
+                        node_property.execute(params![id, name, sql_value(value)]).map_err(&error)?;
+                    }
+                },
+                Node::DataTypeNode { data_type, name, attributes, calculated, constraints, endpoints, defaults, keys, .. } => {
+                    schema.execute(params![id, data_type, name]).map_err(&error)?;
+                    if let Some(endpoints) = endpoints {
+                        schema_endpoints.execute(params![id, endpoints.source_type, endpoints.target_type, endpoints.cardinality.as_str()])
+                            .map_err(&error)?;
+                    }
+                    for (property, property_type) in attributes {
+                        schema_property.execute(params![id, property, property_type, calculated.contains(property)]).map_err(&error)?;
+                    }
+                    for (property, rules) in constraints {
+                        for constraint in rules {
+                            let (min, max) = match constraint {
+                                Constraint::Range(min, max) => (min.as_ref().map(sql_value), max.as_ref().map(sql_value)),
+                                _ => (None, None),
+                            };
+                            schema_constraint.execute(params![id, property, constraint.kind(), min, max]).map_err(&error)?;
+                        }
+                    }
+                    for (property, value) in defaults {
+                        schema_default.execute(params![id, property, sql_value(value)]).map_err(&error)?;
+                    }
+                    for (position, property) in keys.unique_id.iter().enumerate() {
+                        schema_key.execute(params![id, property, "unique_id", position as i64]).map_err(&error)?;
+                    }
+                    if let Some(property) = &keys.title {
+                        schema_key.execute(params![id, property, "title", 0]).map_err(&error)?;
+                    }
+                },
+            }
+        }
+
+        let mut edge = transaction.prepare("INSERT INTO edges (id, source_id, target_id, relation_type) VALUES (?1, ?2, ?3, ?4)").map_err(&error)?;
+        let mut edge_property = transaction.prepare("INSERT INTO edge_properties (edge_id, name, value) VALUES (?1, ?2, ?3)").map_err(&error)?;
+        for reference in state.graph.edge_references() {
+            let id = reference.id().index() as i64;
+            let relation = reference.weight();
+            edge.execute(params![id, reference.source().index() as i64, reference.target().index() as i64, relation.relation_type.as_str()])
+                .map_err(&error)?;
+            for (name, value) in relation.attributes.iter().flatten() {
+                edge_property.execute(params![id, name, sql_value(value)]).map_err(&error)?;
+            }
+        }
+
+        let mut selection = transaction.prepare("INSERT INTO selections (name, position, node_id) VALUES (?1, ?2, ?3)").map_err(&error)?;
+        for (name, indices) in state.selections {
+            for (position, &index) in indices.iter().enumerate() {
+                selection.execute(params![name, position as i64, index as i64]).map_err(&error)?;
+            }
+        }
+    }
+    transaction.commit().map_err(&error)
+}
+
+// Property rows grouped by owner id
+fn read_properties(connection: &Connection, path: &str, query: &str, types: &HashMap<i64, HashMap<String, String>>)
+    -> PyResult<HashMap<i64, HashMap<String, AttributeValue>>> {
+    let error = sql_error(path);
+    let mut statement = connection.prepare(query).map_err(&error)?;
+    let mut rows = statement.query([]).map_err(&error)?;
+    let mut properties: HashMap<i64, HashMap<String, AttributeValue>> = HashMap::new();
+    while let Some(row) = rows.next().map_err(&error)? {
+        let owner: i64 = row.get(0).map_err(&error)?;
+        let name: String = row.get(1).map_err(&error)?;
+        let schema_type = types.get(&owner).and_then(|types| types.get(&name)).map(String::as_str);
+        if let Some(value) = attribute_value(row.get_ref(2).map_err(&error)?, schema_type) {
+            properties.entry(owner).or_default().insert(name, value);
+        }
+    }
+    Ok(properties)
+}
+
+/// Reads a database written by `write_sqlite`, including rows added or changed with SQL since.
+/// Edges and selections pointing at missing nodes are dropped, and property types of rows that
+/// have no schema entry are added to the schema.
+pub fn read_sqlite(path: &str) -> PyResult<GraphState> {
+    let error = sql_error(path);
+    let connection = Connection::open_with_flags(path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY).map_err(&error)?;
+    let metadata = |key: &str| -> PyResult<Option<String>> {
+        connection.query_row("SELECT value FROM metadata WHERE key = ?1", [key], |row| row.get(0)).optional()
+            .map_err(|_| PyValueError::new_err(format!("'{}' is not a graph database written by to_sqlite", path)))
+    };
+    let version = metadata("layout_version")?.and_then(|v| v.parse::<i64>().ok())
+        .ok_or_else(|| PyValueError::new_err(format!("'{}' is not a graph database written by to_sqlite", path)))?;
+    if !(1..=LAYOUT_VERSION).contains(&version) {
+        return Err(PyValueError::new_err(format!(
+            "'{}' uses SQLite layout version {}, but this version of rusty_graph reads version {}", path, version, LAYOUT_VERSION
+        )));
+    }
+    let track_insertion_order = metadata("track_insertion_order")?.is_some_and(|v| v == "true");
+    let next_sequence = metadata("next_sequence")?.and_then(|v| v.parse().ok()).unwrap_or(0);
+
+    // Schema nodes, with their property types
+    let mut schema_nodes: BTreeMap<i64, Node> = BTreeMap::new();
+    {
+        let mut statement = connection.prepare("SELECT id, data_type, name FROM schemas").map_err(&error)?;
+        let rows = statement.query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?)))
+            .map_err(&error)?;
+        for row in rows {
+            let (id, data_type, name) = row.map_err(&error)?;
+            schema_nodes.insert(id, Node::new_data_type(&data_type, &name, HashMap::new()));
+        }
+        let mut statement = connection.prepare("SELECT schema_id, name, type, calculated FROM schema_properties").map_err(&error)?;
+        let rows = statement.query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?, row.get::<_, bool>(3)?)))
+            .map_err(&error)?;
+        for row in rows {
+            let (id, property, property_type, is_calculated) = row.map_err(&error)?;
+            if let Some(Node::DataTypeNode { attributes, calculated, .. }) = schema_nodes.get_mut(&id) {
+                if is_calculated {
+                    calculated.insert(property.clone());
+                }
+                attributes.insert(property, property_type);
+            }
+        }
+        if version >= 2 {
+            let mut statement = connection.prepare("SELECT schema_id, property, kind, min, max FROM schema_constraints").map_err(&error)?;
+            let mut rows = statement.query([]).map_err(&error)?;
+            while let Some(row) = rows.next().map_err(&error)? {
+                let id: i64 = row.get(0).map_err(&error)?;
+                let property: String = row.get(1).map_err(&error)?;
+                let kind: String = row.get(2).map_err(&error)?;
+                let Some(Node::DataTypeNode { attributes, constraints, .. }) = schema_nodes.get_mut(&id) else { continue };
+                // Bounds are stored like the property's values and read back by its type
+                let schema_type = attributes.get(&property).map(String::as_str);
+                let min = attribute_value(row.get_ref(3).map_err(&error)?, schema_type);
+                let max = attribute_value(row.get_ref(4).map_err(&error)?, schema_type);
+                set_constraint(constraints.entry(property).or_default(), parse_constraint(&kind, min, max)?);
+            }
+        }
+        if version >= 3 {
+            let mut statement = connection.prepare("SELECT schema_id, source_type, target_type, cardinality FROM schema_endpoints").map_err(&error)?;
+            let rows = statement.query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?, row.get::<_, String>(3)?)))
+                .map_err(&error)?;
+            for row in rows {
+                let (id, source_type, target_type, cardinality) = row.map_err(&error)?;
+                if let Some(Node::DataTypeNode { endpoints, .. }) = schema_nodes.get_mut(&id) {
+                    let cardinality = Cardinality::parse(&cardinality)
+                        .ok_or_else(|| PyValueError::new_err(format!("Unknown cardinality '{}' in '{}'", cardinality, path)))?;
+                    *endpoints = Some(Endpoints { source_type, target_type, cardinality });
+                }
+            }
+        }
+        if version >= 4 {
+            let mut statement = connection.prepare("SELECT schema_id, property, value FROM schema_defaults").map_err(&error)?;
+            let mut rows = statement.query([]).map_err(&error)?;
+            while let Some(row) = rows.next().map_err(&error)? {
+                let id: i64 = row.get(0).map_err(&error)?;
+                let property: String = row.get(1).map_err(&error)?;
+                let Some(Node::DataTypeNode { attributes, defaults, .. }) = schema_nodes.get_mut(&id) else { continue };
+                let schema_type = attributes.get(&property).map(String::as_str);
+                if let Some(value) = attribute_value(row.get_ref(2).map_err(&error)?, schema_type) {
+                    defaults.insert(property, value);
+                }
+            }
+        }
+        if version >= 5 {
+            let mut statement = connection.prepare("SELECT schema_id, property, role FROM schema_keys ORDER BY schema_id, role, position")
+                .map_err(&error)?;
+            let rows = statement.query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?)))
+                .map_err(&error)?;
+            for row in rows {
+                let (id, property, role) = row.map_err(&error)?;
+                if let Some(Node::DataTypeNode { keys, .. }) = schema_nodes.get_mut(&id) {
+                    match role.as_str() {
+                        "title" => keys.title = Some(property),
+                        _ => keys.unique_id.push(property),
+                    }
+                }
+            }
+        }
+    }
+    let schema_types = |data_type: &str| -> HashMap<&str, &HashMap<String, String>> {
+        schema_nodes.values().filter_map(|node| match node {
+            Node::DataTypeNode { data_type: dt, name, attributes, .. } if dt == data_type => Some((name.as_str(), attributes)),
+            _ => None,
+        }).collect()
+    };
+    let (node_schemas, relation_schemas) = (schema_types("Node"), schema_types("Relation"));
+
+    let mut standard_nodes: BTreeMap<i64, (String, String, Option<String>, Option<u64>)> = BTreeMap::new();
+    {
+        let mut statement = connection.prepare("SELECT id, node_type, unique_id, title, sequence FROM nodes").map_err(&error)?;
+        let rows = statement.query_map([], |row| Ok((
+            row.get::<_, i64>(0)?,
+            (row.get(1)?, row.get(2)?, row.get(3)?, row.get::<_, Option<i64>>(4)?.map(|s| s as u64)),
+        ))).map_err(&error)?;
+        for row in rows {
+            let (id, node) = row.map_err(&error)?;
+            standard_nodes.insert(id, node);
+        }
+    }
+    let node_types: HashMap<i64, HashMap<String, String>> = standard_nodes.iter()
+        .filter_map(|(&id, (node_type, ..))| node_schemas.get(node_type.as_str()).map(|&types| (id, types.clone())))
+        .collect();
+    let mut node_properties = read_properties(&connection, path, "SELECT node_id, name, value FROM node_properties", &node_types)?;
+
+    let mut edges: Vec<(i64, i64, i64, String)> = Vec::new();
+    {
+        let mut statement = connection.prepare("SELECT id, source_id, target_id, relation_type FROM edges ORDER BY id").map_err(&error)?;
+        let rows = statement.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))).map_err(&error)?;
+        for row in rows {
+            edges.push(row.map_err(&error)?);
+        }
+    }
+    let edge_types: HashMap<i64, HashMap<String, String>> = edges.iter()
+        .filter_map(|(id, _, _, relation_type)| relation_schemas.get(relation_type.as_str()).map(|&types| (*id, types.clone())))
+        .collect();
+    let mut edge_properties = read_properties(&connection, path, "SELECT edge_id, name, value FROM edge_properties", &edge_types)?;
+
+    // Nodes go back in id order, schema nodes and standard nodes interleaved as they were written
+    let mut graph = JournaledGraph::default();
+    let mut schemas = ImportedSchemas::default();
+    let mut indices: HashMap<i64, NodeIndex> = HashMap::new();
+    let mut standard_nodes = standard_nodes.into_iter().peekable();
+    let mut schema_nodes = schema_nodes.into_iter().peekable();
+    loop {
+        let take_schema = match (standard_nodes.peek(), schema_nodes.peek()) {
+            (Some((node_id, _)), Some((schema_id, _))) => schema_id < node_id,
+            (None, Some(_)) => true,
+            (Some(_), None) => false,
+            (None, None) => break,
+        };
+        if take_schema {
+            let (_, node) = schema_nodes.next().expect("Peeked schema node");
+            graph.add_node(node);
+            continue;
+        }
+        let (id, (node_type, unique_id, title, sequence)) = standard_nodes.next().expect("Peeked node");
+        let attributes = node_properties.remove(&id).unwrap_or_default();
+        schemas.record_node(&node_type, &attributes);
+        let mut node = Node::new(&node_type, &unique_id, Some(attributes), title.as_deref());
+        if let Node::StandardNode { sequence: slot, .. } = &mut node {
+            *slot = sequence;
+        }
+        indices.insert(id, graph.add_node(node));
+    }
+    for (id, source, target, relation_type) in edges {
+        let (Some(&source), Some(&target)) = (indices.get(&source), indices.get(&target)) else { continue };
+        let attributes = edge_properties.remove(&id).filter(|attributes| !attributes.is_empty());
+        if let Some(attributes) = &attributes {
+            schemas.record_relation(&relation_type, attributes);
+        }
+        graph.add_edge(source, target, Relation::new(&relation_type, attributes));
+    }
+    schemas.register(&mut graph)?;
+
+    let mut selections: HashMap<String, Vec<usize>> = HashMap::new();
+    {
+        let mut statement = connection.prepare("SELECT name, node_id FROM selections ORDER BY name, position").map_err(&error)?;
+        let rows = statement.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))).map_err(&error)?;
+        for row in rows {
+            let (name, node_id) = row.map_err(&error)?;
+            let selection = selections.entry(name).or_default();
+            if let Some(index) = indices.get(&node_id) {
+                selection.push(index.index());
+            }
+        }
+    }
+    let next_sequence = graph.node_weights().filter_map(|node| match node {
+        Node::StandardNode { sequence: Some(sequence), .. } => Some(sequence + 1),
+        _ => None,
+    }).fold(next_sequence, u64::max);
+    categories::encode_all(&mut graph);
+    Ok(GraphState { graph, track_insertion_order, next_sequence, selections })
+}
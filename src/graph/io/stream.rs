@@ -0,0 +1,57 @@
+use pyo3::prelude::*;
+use pyo3::exceptions::{PyTypeError, PyValueError};
+use pyo3::types::PyDict;
+use crate::graph::io::batch::BatchProcessor;
+use crate::graph::io::networkx::py_value;
+use crate::data_types::AttributeValue;
+
+type NodeRecord = (String, Option<String>, Vec<(String, AttributeValue)>);
+
+// Unique ID, title and properties of one dict; None values are left out
+fn node_record(item: &PyAny, unique_id_field: &str, node_title_field: Option<&str>) -> PyResult<NodeRecord> {
+    let record: &PyDict = item.downcast().map_err(|_| PyTypeError::new_err(format!(
+        "add_nodes_stream expects dicts, got {}", item.get_type().name().unwrap_or("an unknown type")
+    )))?;
+    let mut unique_id = None;
+    let mut title = None;
+    let mut properties = Vec::with_capacity(record.len());
+    for (key, value) in record.iter() {
+        if value.is_none() {
+            continue;
+        }
+        let key = key.str()?.to_string();
+        if key == unique_id_field {
+            unique_id = Some(value.str()?.to_string());
+        } else if node_title_field == Some(key.as_str()) {
+            title = Some(value.str()?.to_string());
+        } else {
+            properties.push((key, py_value(value)?));
+        }
+    }
+    let unique_id = unique_id.ok_or_else(|| PyValueError::new_err(format!("A record has no '{}' value", unique_id_field)))?;
+    Ok((unique_id, title, properties))
+}
+
+/// Pulls dicts from a Python iterable `batch_size` at a time, so generators and message
+/// consumers are read lazily, and applies each batch as nodes of one type. Returns the number
+/// of records applied.
+pub fn ingest_stream(
+    processor: &mut BatchProcessor, iterable: &PyAny, node_type: &str, unique_id_field: &str, node_title_field: Option<&str>,
+) -> PyResult<usize> {
+    let mut items = iterable.iter()?;
+    let mut count = 0;
+    loop {
+        let mut batch = Vec::with_capacity(processor.batch_size);
+        for item in items.by_ref().take(processor.batch_size) {
+            batch.push(node_record(item?, unique_id_field, node_title_field)?);
+        }
+        if batch.is_empty() {
+            return Ok(count);
+        }
+        count += batch.len();
+        for (unique_id, title, properties) in batch {
+            let attributes = processor.properties("Node", node_type, properties)?;
+            processor.node(node_type.to_string(), unique_id, title, attributes)?;
+        }
+    }
+}
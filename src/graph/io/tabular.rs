@@ -0,0 +1,409 @@
+use pyo3::prelude::*;
+use pyo3::exceptions::{PyIOError, PyValueError};
+use petgraph::graph::NodeIndex;
+use parquet::file::reader::{FileReader, SerializedFileReader};
+use parquet::record::Field;
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use std::collections::HashMap;
+use std::fs::File;
+use crate::graph::journal::JournaledGraph;
+use crate::graph::add_nodes::{check_conflict_handling, extract_datetime_formats, remove_added, update_existing_node};
+use crate::graph::add_relationships::{add_relation_rows, lookup_node, RelationRow, RelationSummary};
+use crate::graph::get_schema::update_or_retrieve_schema;
+use crate::schema::Node;
+use crate::data_types::{AttributeValue, Decimal};
+
+/// One row of a file, `None` for empty or null cells
+pub type Row = Vec<Option<AttributeValue>>;
+
+/// Column names and a lazy row iterator, so files are ingested without loading them whole
+pub struct Table {
+    pub columns: Vec<String>,
+    pub rows: Box<dyn Iterator<Item = PyResult<Row>>>,
+}
+
+/// Opens a delimited text file with a header row. Every cell is read as text and converted to
+/// the column types during ingestion.
+pub fn read_csv(path: &str, delimiter: Option<char>) -> PyResult<Table> {
+    let delimiter = delimiter.unwrap_or(',');
+    if !delimiter.is_ascii() {
+        return Err(PyValueError::new_err(format!("The delimiter must be a single ASCII character, got '{}'", delimiter)));
+    }
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter as u8)
+        .from_path(path)
+        .map_err(|e| PyIOError::new_err(format!("Cannot open '{}': {}", path, e)))?;
+    let columns = reader.headers()
+        .map_err(|e| PyValueError::new_err(format!("Cannot read the header of '{}': {}", path, e)))?
+        .iter().map(str::to_string).collect();
+    let path = path.to_string();
+    let rows = reader.into_records().map(move |record| {
+        let record = record.map_err(|e| PyValueError::new_err(format!("Cannot read '{}': {}", path, e)))?;
+        Ok(record.iter().map(|cell| (!cell.is_empty()).then(|| AttributeValue::String(cell.to_string()))).collect())
+    });
+    Ok(Table { columns, rows: Box::new(rows) })
+}
+
+/// Opens a Parquet file, reading its flat columns with their stored types
+pub fn read_parquet(path: &str) -> PyResult<Table> {
+    let file = File::open(path).map_err(|e| PyIOError::new_err(format!("Cannot open '{}': {}", path, e)))?;
+    let reader = SerializedFileReader::new(file)
+        .map_err(|e| PyValueError::new_err(format!("'{}' is not a readable Parquet file: {}", path, e)))?;
+    let columns = reader.metadata().file_metadata().schema_descr().root_schema().get_fields().iter()
+        .map(|field| field.name().to_string())
+        .collect();
+    let path = path.to_string();
+    let rows = reader.into_iter().map(move |row| {
+        let row = row.map_err(|e| PyValueError::new_err(format!("Cannot read '{}': {}", path, e)))?;
+        row.get_column_iter().map(|(name, field)| field_value(name, field)).collect()
+    });
+    Ok(Table { columns, rows: Box::new(rows) })
+}
+
+// Unsigned 64-bit values beyond the range of Int are an error rather than silently clamped
+fn field_value(column: &str, field: &Field) -> PyResult<Option<AttributeValue>> {
+    let int = |value: u64| i64::try_from(value).map(AttributeValue::Int).map_err(|_| {
+        PyValueError::new_err(format!("Value {} in column '{}' is out of range for Int", value, column))
+    });
+    let value = match field {
+        Field::Null => return Ok(None),
+        Field::Bool(v) => AttributeValue::Boolean(*v),
+        Field::Byte(v) => AttributeValue::Int(*v as i64),
+        Field::Short(v) => AttributeValue::Int(*v as i64),
+        Field::Int(v) => AttributeValue::Int(*v as i64),
+        Field::UByte(v) => AttributeValue::Int(*v as i64),
+        Field::UShort(v) => AttributeValue::Int(*v as i64),
+        Field::UInt(v) => AttributeValue::Int(*v as i64),
+        Field::Long(v) => AttributeValue::Int(*v),
+        Field::ULong(v) => int(*v)?,
+        Field::Float16(v) => AttributeValue::Float(f64::from(*v)),
+        Field::Float(v) => AttributeValue::Float(*v as f64),
+        Field::Double(v) => AttributeValue::Float(*v),
+        Field::Decimal(_) => Decimal::parse(&field.to_string()).map_or_else(
+            || AttributeValue::Float(field.to_string().parse().unwrap_or(f64::NAN)),
+            AttributeValue::Decimal,
+        ),
+        Field::Str(v) => AttributeValue::String(v.clone()),
+        Field::Bytes(v) => AttributeValue::Bytes(v.data().to_vec()),
+        Field::Date(days) => AttributeValue::Date(*days),
+        Field::TimestampMillis(ms) => AttributeValue::DateTime(ms.div_euclid(1_000)),
+        Field::TimestampMicros(us) => AttributeValue::DateTime(us.div_euclid(1_000_000)),
+        Field::ListInternal(list) => AttributeValue::List(
+            list.elements().iter().filter_map(|element| field_value(column, element).transpose()).collect::<PyResult<_>>()?,
+        ),
+        Field::Group(row) => AttributeValue::Map(
+            row.get_column_iter()
+                .filter_map(|(name, field)| field_value(column, field).transpose().map(|value| value.map(|value| (name.clone(), value))))
+                .collect::<PyResult<_>>()?,
+        ),
+        Field::MapInternal(map) => AttributeValue::Map(
+            map.entries().iter()
+                .filter_map(|(key, field)| field_value(column, field).transpose().map(|value| value.map(|value| (key.to_string(), value))))
+                .collect::<PyResult<_>>()?,
+        ),
+    };
+    Ok(Some(value))
+}
+
+/// Declared column types with the DateTime and Date formats split off (as in `add_nodes`)
+#[derive(Clone)]
+pub struct ColumnTypes {
+    types: HashMap<String, String>,
+    datetime_formats: HashMap<String, String>,
+}
+
+impl ColumnTypes {
+    pub fn new(column_types: Option<HashMap<String, String>>) -> Self {
+        let mut types = column_types.unwrap_or_default();
+        let datetime_formats = extract_datetime_formats(&mut types, "");
+        ColumnTypes { types, datetime_formats }
+    }
+
+    // Columns the caller didn't declare keep the type already recorded in the schema
+    pub fn with_schema(mut self, schema: HashMap<String, String>) -> Self {
+        for (column, data_type) in schema {
+            self.types.entry(column).or_insert(data_type);
+        }
+        self
+    }
+
+    // Declared type of a column, without any DateTime or Date format
+    pub fn data_type(&self, column: &str) -> Option<&str> {
+        self.types.get(column).map(String::as_str)
+    }
+
+    // Converts a cell to the declared type of its column; undeclared columns keep the type read
+    // from the file
+    pub fn convert(&self, column: &str, value: AttributeValue) -> PyResult<AttributeValue> {
+        let Some(data_type) = self.types.get(column) else { return Ok(value) };
+        // Naive times in a "DateTime(zone)" column are local to that zone
+        let (target, zone) = match AttributeValue::datetime_zone(data_type) {
+            Some(zone) => ("DateTime", zone),
+            None => (data_type.as_str(), None),
+        };
+        let converted = match (target, &value) {
+            (target, value) if target == value.type_name() => Some(value.clone()),
+            (target, value) if AttributeValue::decimal_scale(target).is_some() => {
+                value.to_decimal(AttributeValue::decimal_scale(target).flatten()).map(AttributeValue::Decimal)
+            },
+            ("Int", AttributeValue::String(text)) => text.trim().parse().ok().map(AttributeValue::Int),
+            ("Float", AttributeValue::String(text)) => text.trim().parse().ok().map(AttributeValue::Float),
+            ("Int", AttributeValue::Float(v)) if v.fract() == 0.0 && v.abs() < 9.2e18 => Some(AttributeValue::Int(*v as i64)),
+            ("Float", AttributeValue::Int(v)) => Some(AttributeValue::Float(*v as f64)),
+            ("Float", AttributeValue::Decimal(v)) => Some(AttributeValue::Float(v.to_f64())),
+            ("DateTime", AttributeValue::Int(v)) => Some(AttributeValue::DateTime(*v)),
+            ("DateTime", AttributeValue::String(text)) => match self.datetime_formats.get(column).filter(|f| !f.is_empty()) {
+                // Formats with an offset (%z) give the instant directly
+                Some(format) => match DateTime::parse_from_str(text, format) {
+                    Ok(datetime) => Some(AttributeValue::DateTime(datetime.timestamp())),
+                    Err(_) => {
+                        let naive = NaiveDateTime::parse_from_str(text, format).ok()
+                            .or_else(|| NaiveDate::parse_from_str(text, format).ok()?.and_hms_opt(0, 0, 0));
+                        naive.map(|naive| AttributeValue::localize(&naive, zone)).transpose()?.map(AttributeValue::DateTime)
+                    },
+                },
+                None => AttributeValue::parse_iso_datetime_in(text, zone)?,
+            },
+            ("DateTime", AttributeValue::Date(v)) => Some(AttributeValue::DateTime(*v as i64 * 86_400)),
+            ("Date", AttributeValue::DateTime(v)) => Some(AttributeValue::date_from_timestamp(*v)),
+            ("Date", AttributeValue::String(text)) => match self.datetime_formats.get(column).filter(|f| !f.is_empty()) {
+                Some(format) => NaiveDate::parse_from_str(text, format).ok().map(|d| AttributeValue::from_date(&d)),
+                None => AttributeValue::parse_iso_date(text).or_else(|| match AttributeValue::parse_iso_datetime(text)? {
+                    AttributeValue::DateTime(v) => Some(AttributeValue::date_from_timestamp(v)),
+                    _ => None,
+                }),
+            },
+            ("Duration", AttributeValue::Int(v)) => Some(AttributeValue::Duration(*v)),
+            ("Duration", AttributeValue::String(text)) => AttributeValue::parse_iso_duration(text)
+                .or_else(|| text.trim().parse().ok().map(AttributeValue::Duration)),
+            ("UUID", AttributeValue::String(text)) => AttributeValue::parse_uuid(text),
+            // Text files hold binary cells as hexadecimal, as written by the exports
+            ("Bytes", AttributeValue::String(text)) => AttributeValue::parse_hex(text),
+            ("Boolean", AttributeValue::String(text)) => AttributeValue::parse_bool(text),
+            ("Boolean", AttributeValue::Int(v @ (0 | 1))) => Some(AttributeValue::Boolean(*v == 1)),
+            // List cells in text files hold semicolon-separated elements, as written by the exports
+            ("List", AttributeValue::String(text)) => Some(AttributeValue::List(
+                text.split(';').map(str::trim).filter(|part| !part.is_empty()).map(|part| AttributeValue::String(part.to_string())).collect(),
+            )),
+            ("List", value) => Some(AttributeValue::List(vec![value.clone()])),
+            ("Map", AttributeValue::String(text)) => serde_json::from_str::<serde_json::Value>(text).ok()
+                .filter(serde_json::Value::is_object)
+                .and_then(|json| AttributeValue::from_json(&json)),
+            ("String", value) => Some(AttributeValue::String(value.to_export_string())),
+            // Kept as text until the rows are in and encoded against the column's dictionary
+            ("Category", AttributeValue::List(_) | AttributeValue::Map(_)) => None,
+            ("Category", value) => Some(AttributeValue::String(value.to_export_string())),
+            _ => None,
+        };
+        converted.ok_or_else(|| PyValueError::new_err(format!(
+            "Cannot read '{}' in column '{}' as {}", value.to_string(), column, data_type
+        )))
+    }
+}
+
+fn column_position(columns: &[String], field: &str) -> PyResult<usize> {
+    columns.iter().position(|c| c == field)
+        .ok_or_else(|| PyValueError::new_err(format!("Column '{}' not found in the file", field)))
+}
+
+fn cell_text(row: &Row, position: usize) -> Option<String> {
+    row.get(position).cloned().flatten().map(|value| value.to_export_string())
+}
+
+/// Adds or updates one node per row, matching existing nodes by unique ID with the same conflict
+/// handling as `add_nodes`. The schema records the declared type of each property column, or the
+/// type found in the file.
+pub fn ingest_nodes(
+    graph: &mut JournaledGraph,
+    table: Table,
+    node_type: &str,
+    unique_id_field: &str,
+    node_title_field: Option<&str>,
+    conflict_handling: &str,
+    column_types: ColumnTypes,
+) -> PyResult<Vec<usize>> {
+    check_conflict_handling(conflict_handling)?;
+    let (node_count, edge_count) = (graph.node_count(), graph.edge_count());
+    let id_position = column_position(&table.columns, unique_id_field)?;
+    let title_position = node_title_field.map(|field| column_position(&table.columns, field)).transpose()?;
+    let properties: Vec<(usize, &String)> = table.columns.iter().enumerate()
+        .filter(|&(position, _)| position != id_position && Some(position) != title_position)
+        .collect();
+    let schema = update_or_retrieve_schema(
+        graph, "Node", node_type, Some(column_types.types.keys().cloned().collect()), Some(column_types.types.clone()),
+    )?;
+    let column_types = column_types.with_schema(schema);
+
+    let mut found_types: HashMap<String, String> = HashMap::new();
+    let mut rows = Vec::new();
+    for row in table.rows {
+        let row = row?;
+        let mut unique_id = cell_text(&row, id_position)
+            .ok_or_else(|| PyValueError::new_err(format!("Row {} has no value in '{}'", rows.len() + 1, unique_id_field)))?;
+        // IDs declared as UUID are matched in their canonical form, however the source spells them
+        if column_types.types.get(unique_id_field).is_some_and(|t| t == "UUID") {
+            unique_id = AttributeValue::parse_uuid(&unique_id)
+                .ok_or_else(|| PyValueError::new_err(format!("Unique ID '{}' is not a UUID", unique_id)))?
+                .to_string();
+        }
+        let title = title_position.and_then(|position| cell_text(&row, position));
+        let mut attributes = HashMap::new();
+        for &(position, column) in &properties {
+            if let Some(value) = row.get(position).cloned().flatten() {
+                let value = column_types.convert(column, value)?;
+                found_types.entry(column.clone()).or_insert_with(|| match column_types.types.get(column) {
+                    Some(data_type) if data_type == "Category" => data_type.clone(),
+                    _ => value.type_name().to_string(),
+                });
+                attributes.insert(column.clone(), value);
+            }
+        }
+        rows.push((unique_id, title, attributes));
+    }
+    update_or_retrieve_schema(graph, "Node", node_type, Some(found_types.keys().cloned().collect()), Some(found_types))?;
+
+    // Every row is read before any node changes, so a bad cell or a failed read leaves the graph as
+    // it was. Only conflict_handling='error' can fail from here, before any existing node is updated
+    let mut lookup: HashMap<String, NodeIndex> = graph.node_indices().filter_map(|index| match &graph[index] {
+        Node::StandardNode { node_type: nt, unique_id, .. } if nt == node_type => Some((unique_id.clone(), index)),
+        _ => None,
+    }).collect();
+    let mut indices = Vec::with_capacity(rows.len());
+    for (unique_id, title, attributes) in rows {
+        let index = match lookup.get(&unique_id) {
+            Some(&index) => {
+                if let Err(e) = update_existing_node(graph, index, node_type, &unique_id, title, Some(attributes), conflict_handling) {
+                    remove_added(graph, node_count, edge_count);
+                    return Err(e);
+                }
+                index
+            },
+            None => {
+                let index = graph.add_node(Node::new(node_type, &unique_id, Some(attributes), title.as_deref()));
+                lookup.insert(unique_id, index);
+                index
+            },
+        };
+        indices.push(index.index());
+    }
+    Ok(indices)
+}
+
+/// Sets the `columns` properties (every column but the ID column when None) of existing nodes
+/// matched by unique ID, leaving their other properties as they are. Empty cells keep the current
+/// value and rows matching no node are skipped. Values take the declared or schema type of their
+/// column, or the type found in the table.
+///
+/// Returns the nodes updated and the unique IDs that matched no node.
+pub fn update_nodes(
+    graph: &mut JournaledGraph,
+    table: Table,
+    node_type: &str,
+    unique_id_field: &str,
+    columns: Option<Vec<String>>,
+    column_types: ColumnTypes,
+) -> PyResult<(Vec<usize>, Vec<String>)> {
+    let id_position = column_position(&table.columns, unique_id_field)?;
+    let properties: Vec<(usize, &String)> = match &columns {
+        Some(columns) => columns.iter()
+            .map(|column| Ok((column_position(&table.columns, column)?, column)))
+            .collect::<PyResult<_>>()?,
+        None => table.columns.iter().enumerate().filter(|&(position, _)| position != id_position).collect(),
+    };
+    let schema = update_or_retrieve_schema(
+        graph, "Node", node_type, Some(column_types.types.keys().cloned().collect()), Some(column_types.types.clone()),
+    )?;
+    let column_types = column_types.with_schema(schema);
+
+    let lookup: HashMap<String, NodeIndex> = graph.node_indices().filter_map(|index| match &graph[index] {
+        Node::StandardNode { node_type: nt, unique_id, .. } if nt == node_type => Some((unique_id.clone(), index)),
+        _ => None,
+    }).collect();
+    let mut found_types: HashMap<String, String> = HashMap::new();
+    let mut updates = Vec::new();
+    let mut missing = Vec::new();
+    for (row_number, row) in table.rows.enumerate() {
+        let row = row?;
+        let unique_id = cell_text(&row, id_position)
+            .ok_or_else(|| PyValueError::new_err(format!("Row {} has no value in '{}'", row_number + 1, unique_id_field)))?;
+        let Some(index) = lookup_node(&lookup, &unique_id) else {
+            missing.push(unique_id);
+            continue;
+        };
+        let mut attributes = HashMap::new();
+        for &(position, column) in &properties {
+            if let Some(value) = row.get(position).cloned().flatten() {
+                let value = column_types.convert(column, value)?;
+                found_types.entry(column.clone()).or_insert_with(|| match column_types.types.get(column) {
+                    Some(data_type) if data_type == "Category" => data_type.clone(),
+                    _ => value.type_name().to_string(),
+                });
+                attributes.insert(column.clone(), value);
+            }
+        }
+        updates.push((index, attributes));
+    }
+
+    // Every row is read before any node changes, so a bad cell leaves the graph as it was
+    let mut updated = Vec::with_capacity(updates.len());
+    for (index, values) in updates {
+        if let Node::StandardNode { attributes, .. } = &mut graph[index] {
+            attributes.extend(values);
+        }
+        updated.push(index.index());
+    }
+    update_or_retrieve_schema(graph, "Node", node_type, Some(found_types.keys().cloned().collect()), Some(found_types))?;
+    Ok((updated, missing))
+}
+
+/// Adds one relation per row through `add_relation_rows`, with the conflict handling and endpoint
+/// checks of `add_relationships`, creating missing source and target nodes. Columns other than the
+/// ID and title columns become relation properties. Every row is read before the graph changes, so
+/// a bad cell or a failed read leaves it as it was.
+#[allow(clippy::too_many_arguments)]
+pub fn ingest_relationships(
+    graph: &mut JournaledGraph,
+    table: Table,
+    relationship_type: &str,
+    source_type: &str,
+    source_id_field: &str,
+    target_type: &str,
+    target_id_field: &str,
+    title_fields: (Option<&str>, Option<&str>),
+    column_types: &ColumnTypes,
+    enforce_schema: bool,
+    conflict_handling: &str,
+) -> PyResult<RelationSummary> {
+    let source_position = column_position(&table.columns, source_id_field)?;
+    let target_position = column_position(&table.columns, target_id_field)?;
+    let source_title = title_fields.0.map(|field| column_position(&table.columns, field)).transpose()?;
+    let target_title = title_fields.1.map(|field| column_position(&table.columns, field)).transpose()?;
+    let key_positions = [Some(source_position), Some(target_position), source_title, target_title];
+    let properties: Vec<(usize, &String)> = table.columns.iter().enumerate()
+        .filter(|&(position, _)| !key_positions.contains(&Some(position)))
+        .collect();
+
+    let mut rows = Vec::new();
+    for row in table.rows {
+        let row = row?;
+        let missing = |field: &str| PyValueError::new_err(format!("Row {} has no value in '{}'", rows.len() + 1, field));
+        let source_id = cell_text(&row, source_position).ok_or_else(|| missing(source_id_field))?;
+        let target_id = cell_text(&row, target_position).ok_or_else(|| missing(target_id_field))?;
+        let mut attributes = HashMap::new();
+        for &(position, column) in &properties {
+            if let Some(value) = row.get(position).cloned().flatten() {
+                attributes.insert(column.clone(), column_types.convert(column, value)?);
+            }
+        }
+        rows.push(RelationRow {
+            source_id,
+            target_id,
+            source_title: source_title.and_then(|position| cell_text(&row, position)),
+            target_title: target_title.and_then(|position| cell_text(&row, position)),
+            attributes,
+        });
+    }
+    add_relation_rows(
+        graph, rows, relationship_type, source_type, target_type, enforce_schema, conflict_handling, HashMap::new(), None,
+    )
+}
@@ -0,0 +1,276 @@
+use petgraph::graph::{DiGraph, EdgeIndex, NodeIndex};
+use petgraph::visit::EdgeRef;
+use petgraph::Direction;
+use serde::{Deserialize, Serialize};
+use std::ops::{Deref, Index, IndexMut};
+use crate::graph::compare_graphs::NodeKey;
+use crate::schema::{Node, Relation};
+
+/// One change to the graph, holding what undoing it needs
+pub enum Event {
+    NodeAdded(NodeIndex),
+    NodeChanged(NodeIndex, Node),  // The node before the change
+    NodeRemoved { index: NodeIndex, node: Node, last: NodeIndex },  // The node at `last` took the freed index
+    EdgeAdded(EdgeIndex),
+    EdgeChanged(EdgeIndex, Relation),  // The relation before the change
+    EdgeRemoved { index: EdgeIndex, source: NodeIndex, target: NodeIndex, ends: (NodeKey, NodeKey), relation: Relation, last: EdgeIndex },
+}
+
+/// The graph of a KnowledgeGraph. Reading goes through `Deref` to the petgraph graph; every change
+/// goes through the methods here (or mutable indexing), which log it while a change log,
+/// checkpoint or transaction needs the changes. Undoing them back to a position in that log is
+/// what puts back a checkpoint or a failed transaction, so neither copies the graph. It is
+/// stored as the bare graph.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct JournaledGraph {
+    graph: DiGraph<Node, Relation>,
+    #[serde(skip)]
+    events: Option<Vec<Event>>,  // None while nothing needs the changes
+}
+
+impl JournaledGraph {
+    pub fn new(graph: DiGraph<Node, Relation>) -> Self {
+        JournaledGraph { graph, events: None }
+    }
+
+    pub fn into_inner(self) -> DiGraph<Node, Relation> {
+        self.graph
+    }
+
+    /// Starts or stops logging changes; stopping drops the changes logged so far
+    pub fn set_recording(&mut self, recording: bool) {
+        match (recording, &self.events) {
+            (true, None) => self.events = Some(Vec::new()),
+            (false, Some(_)) => self.events = None,
+            _ => (),
+        }
+    }
+
+    /// Number of changes logged, the position `undo_to` goes back to
+    pub fn position(&self) -> usize {
+        self.events.as_ref().map_or(0, Vec::len)
+    }
+
+    /// The changes logged since `position`, oldest first
+    pub fn events_since(&self, position: usize) -> &[Event] {
+        self.events.as_deref().map_or(&[], |events| &events[position.min(events.len())..])
+    }
+
+    /// Drops the changes logged so far, keeping on logging
+    pub fn forget(&mut self) {
+        if let Some(events) = &mut self.events {
+            events.clear();
+        }
+    }
+
+    fn log(&mut self, event: impl FnOnce(&DiGraph<Node, Relation>) -> Event) {
+        if let Some(events) = &mut self.events {
+            events.push(event(&self.graph));
+        }
+    }
+
+    pub fn add_node(&mut self, node: Node) -> NodeIndex {
+        let index = self.graph.add_node(node);
+        self.log(|_| Event::NodeAdded(index));
+        index
+    }
+
+    pub fn add_edge(&mut self, source: NodeIndex, target: NodeIndex, relation: Relation) -> EdgeIndex {
+        let index = self.graph.add_edge(source, target, relation);
+        self.log(|_| Event::EdgeAdded(index));
+        index
+    }
+
+    /// Removes a node and its relations as petgraph does: the relations one at a time in the same
+    /// order, then the node, whose index the last node takes
+    pub fn remove_node(&mut self, index: NodeIndex) -> Option<Node> {
+        if self.events.is_none() {
+            return self.graph.remove_node(index);
+        }
+        self.graph.node_weight(index)?;
+        for direction in [Direction::Outgoing, Direction::Incoming] {
+            while let Some(edge) = self.graph.edges_directed(index, direction).next().map(|edge| edge.id()) {
+                self.remove_edge(edge);
+            }
+        }
+        let last = NodeIndex::new(self.graph.node_count() - 1);
+        let node = self.graph.remove_node(index)?;
+        self.log(|_| Event::NodeRemoved { index, node: node.clone(), last });
+        Some(node)
+    }
+
+    pub fn remove_edge(&mut self, index: EdgeIndex) -> Option<Relation> {
+        let (source, target) = self.graph.edge_endpoints(index)?;
+        let ends = self.events.is_some().then(|| (key(&self.graph[source]), key(&self.graph[target])));
+        let last = EdgeIndex::new(self.graph.edge_count() - 1);
+        let relation = self.graph.remove_edge(index)?;
+        if let Some(ends) = ends {
+            self.log(|_| Event::EdgeRemoved { index, source, target, ends, relation: relation.clone(), last });
+        }
+        Some(relation)
+    }
+
+    pub fn node_weight_mut(&mut self, index: NodeIndex) -> Option<&mut Node> {
+        self.graph.node_weight(index)?;
+        Some(&mut self[index])
+    }
+
+    pub fn edge_weight_mut(&mut self, index: EdgeIndex) -> Option<&mut Relation> {
+        self.graph.edge_weight(index)?;
+        Some(&mut self[index])
+    }
+
+    /// Swaps the whole graph for another. While logging, the old graph is taken apart node by
+    /// node and the new one added, so undoing puts back the old one
+    pub fn replace(&mut self, graph: DiGraph<Node, Relation>) {
+        if self.events.is_none() {
+            self.graph = graph;
+            return;
+        }
+        while self.graph.edge_count() > 0 {
+            self.remove_edge(EdgeIndex::new(self.graph.edge_count() - 1));
+        }
+        while self.graph.node_count() > 0 {
+            self.remove_node(NodeIndex::new(self.graph.node_count() - 1));
+        }
+        let (nodes, edges) = graph.into_nodes_edges();
+        for node in nodes {
+            self.add_node(node.weight);
+        }
+        for edge in edges {
+            self.add_edge(edge.source(), edge.target(), edge.weight);
+        }
+    }
+
+    /// Undoes the changes logged since `position`, newest first, leaving the graph, indices
+    /// included, as it was when the log was at `position`
+    pub fn undo_to(&mut self, position: usize) {
+        let Some(events) = &mut self.events else { return };
+        if position >= events.len() {
+            return;
+        }
+        let undone = events.split_off(position);
+        let removals = undone.iter().any(|event| matches!(event, Event::NodeRemoved { .. } | Event::EdgeRemoved { .. }));
+        if removals {
+            self.graph = rebuild(std::mem::take(&mut self.graph), undone);
+            return;
+        }
+        // Without removals every change is undone in place: what was added last is removed, which
+        // moves nothing else
+        for event in undone.into_iter().rev() {
+            match event {
+                Event::NodeAdded(index) => { self.graph.remove_node(index); },
+                Event::NodeChanged(index, node) => self.graph[index] = node,
+                Event::EdgeAdded(index) => { self.graph.remove_edge(index); },
+                Event::EdgeChanged(index, relation) => self.graph[index] = relation,
+                Event::NodeRemoved { .. } | Event::EdgeRemoved { .. } => unreachable!("undone by rebuild"),
+            }
+        }
+    }
+}
+
+// Undoes changes including removals. A removal moved the last node or relation into the freed
+// index, which petgraph has no way to reverse in place, so the graph is taken apart into lists,
+// the changes undone on those, and the graph built again in index order. Nodes carry an id that
+// stays with them as they move, and relations refer to that id until the end.
+fn rebuild(graph: DiGraph<Node, Relation>, undone: Vec<Event>) -> DiGraph<Node, Relation> {
+    let (node_list, edge_list) = graph.into_nodes_edges();
+    let mut nodes: Vec<(usize, Node)> = node_list.into_iter().enumerate().map(|(id, node)| (id, node.weight)).collect();
+    let mut edges: Vec<(usize, usize, Relation)> = edge_list.into_iter()
+        .map(|edge| (edge.source().index(), edge.target().index(), edge.weight))
+        .collect();
+    let mut next_id = nodes.len();
+    for event in undone.into_iter().rev() {
+        match event {
+            Event::NodeAdded(_) => { nodes.pop(); },
+            Event::NodeChanged(index, node) => nodes[index.index()].1 = node,
+            Event::NodeRemoved { index, node, .. } => {
+                let restored = (next_id, node);
+                next_id += 1;
+                put_back(&mut nodes, index.index(), restored);
+            },
+            Event::EdgeAdded(_) => { edges.pop(); },
+            Event::EdgeChanged(index, relation) => edges[index.index()].2 = relation,
+            Event::EdgeRemoved { index, source, target, relation, .. } => {
+                let restored = (nodes[source.index()].0, nodes[target.index()].0, relation);
+                put_back(&mut edges, index.index(), restored);
+            },
+        }
+    }
+
+    let mut position = vec![0; next_id];
+    for (index, (id, _)) in nodes.iter().enumerate() {
+        position[*id] = index;
+    }
+    let mut rebuilt = DiGraph::with_capacity(nodes.len(), edges.len());
+    for (_, node) in nodes {
+        rebuilt.add_node(node);
+    }
+    for (source, target, relation) in edges {
+        rebuilt.add_edge(NodeIndex::new(position[source]), NodeIndex::new(position[target]), relation);
+    }
+    rebuilt
+}
+
+// Reverses a swap removal: the item now at `index` goes back to the end and `item` takes its place
+fn put_back<T>(items: &mut Vec<T>, index: usize, item: T) {
+    if index == items.len() {
+        items.push(item);
+    } else {
+        let moved = std::mem::replace(&mut items[index], item);
+        items.push(moved);
+    }
+}
+
+fn key(node: &Node) -> NodeKey {
+    match node {
+        Node::StandardNode { node_type, unique_id, .. } => (node_type.to_string(), unique_id.clone()),
+        Node::DataTypeNode { data_type, name, .. } => (data_type.clone(), name.clone()),
+    }
+}
+
+impl Deref for JournaledGraph {
+    type Target = DiGraph<Node, Relation>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.graph
+    }
+}
+
+impl From<DiGraph<Node, Relation>> for JournaledGraph {
+    fn from(graph: DiGraph<Node, Relation>) -> Self {
+        JournaledGraph::new(graph)
+    }
+}
+
+impl Index<NodeIndex> for JournaledGraph {
+    type Output = Node;
+
+    fn index(&self, index: NodeIndex) -> &Node {
+        &self.graph[index]
+    }
+}
+
+// Logs the node as it was before handing it out for changing
+impl IndexMut<NodeIndex> for JournaledGraph {
+    fn index_mut(&mut self, index: NodeIndex) -> &mut Node {
+        self.log(|graph| Event::NodeChanged(index, graph[index].clone()));
+        &mut self.graph[index]
+    }
+}
+
+impl Index<EdgeIndex> for JournaledGraph {
+    type Output = Relation;
+
+    fn index(&self, index: EdgeIndex) -> &Relation {
+        &self.graph[index]
+    }
+}
+
+impl IndexMut<EdgeIndex> for JournaledGraph {
+    fn index_mut(&mut self, index: EdgeIndex) -> &mut Relation {
+        self.log(|graph| Event::EdgeChanged(index, graph[index].clone()));
+        &mut self.graph[index]
+    }
+}
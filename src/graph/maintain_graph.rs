@@ -0,0 +1,667 @@
+use pyo3::prelude::*;
+use pyo3::exceptions::PyValueError;
+use pyo3::types::PyList;
+use petgraph::graph::{DiGraph, EdgeIndex, NodeIndex};
+use petgraph::visit::EdgeRef;
+use petgraph::Direction;
+use std::collections::{HashMap, HashSet};
+use crate::graph::journal::JournaledGraph;
+use crate::graph::add_relationships::{lookup_node, row_id};
+use crate::graph::categories;
+use crate::graph::constraints::set_constraint;
+use crate::graph::get_schema::find_schema_node;
+use crate::graph::io::tabular::ColumnTypes;
+use crate::graph::navigate_graph::traverse_deep;
+use crate::schema::{KeyColumns, Node, Relation, Symbol};
+use crate::data_types::AttributeValue;
+
+/// Reverses the direction of every `relationship_type` edge, optionally renaming it.
+///
+/// # Arguments
+///
+/// * `graph` - The graph object containing all nodes and relations
+/// * `relationship_type` - The relation type to invert
+/// * `new_type` - Relation type of the reversed edges, defaults to `relationship_type`
+/// * `keep_original` - Add the reversed edges alongside the originals instead of converting them
+pub fn invert_connection_type(
+    graph: &mut JournaledGraph,
+    relationship_type: &str,
+    new_type: Option<&str>,
+    keep_original: bool,
+) -> usize {
+    let new_type = new_type.unwrap_or(relationship_type);
+    let mut edges: Vec<_> = graph.edge_references()
+        .filter(|edge| edge.weight().relation_type == relationship_type)
+        .map(|edge| edge.id())
+        .collect();
+
+    // Remove from the highest index down, since petgraph moves the last edge into a removed slot
+    edges.sort_unstable_by(|a, b| b.cmp(a));
+
+    let mut inverted = Vec::with_capacity(edges.len());
+    for edge in edges {
+        let (source, target) = graph.edge_endpoints(edge).expect("Edge must exist");
+        let relation = if keep_original {
+            graph[edge].clone()
+        } else {
+            graph.remove_edge(edge).expect("Edge must exist")
+        };
+        inverted.push((target, source, relation));
+    }
+
+    let count = inverted.len();
+    // Re-add in original order so traversal order is preserved
+    for (source, target, mut relation) in inverted.into_iter().rev() {
+        relation.relation_type = Symbol::new(new_type);
+        graph.add_edge(source, target, relation);
+    }
+    count
+}
+
+/// Counts reported by `merge_graphs`
+#[derive(Default)]
+pub struct MergeSummary {
+    pub nodes_added: usize,
+    pub nodes_updated: usize,
+    pub edges_added: usize,
+}
+
+/// Merges `other` into `graph`, matching nodes by (node_type, unique_id).
+///
+/// # Arguments
+///
+/// * `graph` - The graph merged into
+/// * `other` - The graph whose nodes, schemas and relations are copied
+/// * `conflict_handling` - For nodes present in both graphs: "update" merges attributes (the default),
+///   "replace" takes the node from `other` and "skip" keeps the existing node. Relations already
+///   present between the same nodes with the same type are not duplicated.
+pub fn merge_graphs(
+    graph: &mut JournaledGraph,
+    other: &DiGraph<Node, Relation>,
+    conflict_handling: &str,
+) -> PyResult<MergeSummary> {
+    if !["update", "replace", "skip"].contains(&conflict_handling) {
+        return Err(PyValueError::new_err(format!(
+            "Invalid conflict_handling value '{}', expected 'update', 'replace' or 'skip'", conflict_handling
+        )));
+    }
+    let mut summary = MergeSummary::default();
+
+    let mut existing: HashMap<(Symbol, String), NodeIndex> = graph.node_indices().filter_map(|index| match &graph[index] {
+        Node::StandardNode { node_type, unique_id, .. } => Some(((node_type.clone(), unique_id.clone()), index)),
+        Node::DataTypeNode { .. } => None,
+    }).collect();
+    let mut index_map: HashMap<NodeIndex, NodeIndex> = HashMap::new();
+
+    for other_index in other.node_indices() {
+        match &other[other_index] {
+            Node::DataTypeNode { data_type, name, attributes, calculated, constraints, endpoints, defaults, .. } => {
+                let Some(schema_index) = find_schema_node(graph, data_type, name) else {
+                    graph.add_node(other[other_index].clone());
+                    continue;
+                };
+                if let Node::DataTypeNode {
+                    attributes: attrs, calculated: calc, constraints: rules, endpoints: declared, defaults: default_values, ..
+                } = &mut graph[schema_index] {
+                    for (attribute, data_type) in attributes {
+                        if conflict_handling == "skip" {
+                            attrs.entry(attribute.clone()).or_insert_with(|| data_type.clone());
+                        } else {
+                            attrs.insert(attribute.clone(), data_type.clone());
+                        }
+                    }
+                    calc.extend(calculated.iter().cloned());
+                    for (attribute, incoming) in constraints {
+                        let existing = rules.entry(attribute.clone()).or_default();
+                        for constraint in incoming {
+                            if conflict_handling != "skip" || existing.iter().all(|rule| rule.kind() != constraint.kind()) {
+                                set_constraint(existing, constraint.clone());
+                            }
+                        }
+                    }
+                    if endpoints.is_some() && (conflict_handling != "skip" || declared.is_none()) {
+                        declared.clone_from(endpoints);
+                    }
+                    for (attribute, value) in defaults {
+                        if conflict_handling == "skip" {
+                            default_values.entry(attribute.clone()).or_insert_with(|| value.clone());
+                        } else {
+                            default_values.insert(attribute.clone(), value.clone());
+                        }
+                    }
+                }
+            },
+            Node::StandardNode { node_type, unique_id, title, attributes, .. } => {
+                let key = (node_type.clone(), unique_id.clone());
+                let Some(&index) = existing.get(&key) else {
+                    let mut node = other[other_index].clone();
+                    node.set_sequence(None);
+                    let index = graph.add_node(node);
+                    existing.insert(key, index);
+                    index_map.insert(other_index, index);
+                    summary.nodes_added += 1;
+                    continue;
+                };
+                index_map.insert(other_index, index);
+                match conflict_handling {
+                    "replace" => {
+                        let sequence = graph[index].sequence();
+                        graph[index] = other[other_index].clone();
+                        graph[index].set_sequence(sequence);
+                    },
+                    "update" => {
+                        if let Node::StandardNode { title: t, attributes: attrs, .. } = &mut graph[index] {
+                            attrs.extend(attributes.iter().map(|(k, v)| (k.clone(), v.clone())));
+                            if title.is_some() {
+                                t.clone_from(title);
+                            }
+                        }
+                    },
+                    _ => continue,
+                }
+                summary.nodes_updated += 1;
+            },
+        }
+    }
+
+    let mut edges: HashMap<(NodeIndex, NodeIndex, Symbol), EdgeIndex> = graph.edge_references()
+        .map(|edge| ((edge.source(), edge.target(), edge.weight().relation_type.clone()), edge.id()))
+        .collect();
+    for edge in other.edge_references() {
+        let (Some(&source), Some(&target)) = (index_map.get(&edge.source()), index_map.get(&edge.target())) else { continue };
+        let key = (source, target, edge.weight().relation_type.clone());
+        match edges.get(&key) {
+            Some(&existing_edge) => {
+                let relation = &mut graph[existing_edge];
+                match (conflict_handling, &edge.weight().attributes) {
+                    ("replace", _) => relation.attributes.clone_from(&edge.weight().attributes),
+                    ("update", Some(attributes)) => relation.attributes.get_or_insert_with(HashMap::new)
+                        .extend(attributes.iter().map(|(k, v)| (k.clone(), v.clone()))),
+                    _ => (),
+                }
+            },
+            None => {
+                edges.insert(key, graph.add_edge(source, target, edge.weight().clone()));
+                summary.edges_added += 1;
+            },
+        }
+    }
+    Ok(summary)
+}
+
+/// Adds an `as_type` edge from every node to each node it reaches through `relationship_type`
+/// edges (within `max_depth` hops), storing the hop count as a "depth" attribute.
+///
+/// Existing `as_type` edges are not duplicated. Returns the number of edges added.
+pub fn materialize_transitive(
+    graph: &mut JournaledGraph,
+    relationship_type: &str,
+    as_type: &str,
+    max_depth: Option<usize>,
+) -> usize {
+    let relationship_types = [relationship_type.to_string()];
+    let mut existing: HashSet<(NodeIndex, NodeIndex)> = graph.edge_references()
+        .filter(|edge| edge.weight().relation_type == as_type)
+        .map(|edge| (edge.source(), edge.target()))
+        .collect();
+
+    let sources: Vec<NodeIndex> = graph.node_indices()
+        .filter(|&node| graph.edges_directed(node, Direction::Outgoing).any(|edge| edge.weight().relation_type == relationship_type))
+        .collect();
+    let mut derived = Vec::new();
+    for source in sources {
+        for (target, depth) in traverse_deep(graph, vec![source.index()], &relationship_types, &[Direction::Outgoing], 1, max_depth, None) {
+            let target = NodeIndex::new(target);
+            if existing.insert((source, target)) {
+                derived.push((source, target, depth));
+            }
+        }
+    }
+
+    let added = derived.len();
+    for (source, target, depth) in derived {
+        let attributes = HashMap::from([("depth".to_string(), AttributeValue::Int(depth as i64))]);
+        graph.add_edge(source, target, Relation::new(as_type, Some(attributes)));
+    }
+    added
+}
+
+/// Converts the `property` values of every `node_type` node to `new_type`, which may carry a
+/// DateTime or Date format as in `column_types`, and records the new type in the schema.
+///
+/// Values that cannot be converted are removed with `on_error` "null"; with "fail" the first of
+/// them raises and no values are changed. Returns the nodes whose value was converted or removed
+/// and how many were removed. A default for the property is converted too, or dropped if it cannot be.
+pub fn alter_property_type(
+    graph: &mut JournaledGraph,
+    node_type: &str,
+    property: &str,
+    new_type: &str,
+    on_error: &str,
+) -> PyResult<(Vec<usize>, usize)> {
+    if !["null", "fail"].contains(&on_error) {
+        return Err(PyValueError::new_err(format!("Invalid on_error '{}', expected 'null' or 'fail'", on_error)));
+    }
+    let column_types = ColumnTypes::new(Some(HashMap::from([(property.to_string(), new_type.to_string())])));
+    let data_type = column_types.data_type(property).unwrap_or(new_type).to_string();
+    if !AttributeValue::is_schema_type(&data_type) {
+        return Err(PyValueError::new_err(format!("Unknown data type '{}'", new_type)));
+    }
+    let schema_index = property_schema(graph, node_type, property)?;
+
+    // Everything is converted before any value is written, so a failure leaves the graph as it was
+    let mut converted = Vec::new();
+    for index in graph.node_indices() {
+        let Node::StandardNode { node_type: nt, unique_id, attributes, .. } = &graph[index] else { continue };
+        let Some(value) = attributes.get(property).filter(|_| nt == node_type) else { continue };
+        // Category values convert from their label
+        let value = match value {
+            AttributeValue::Category(category) => AttributeValue::String(category.label().to_string()),
+            other => other.clone(),
+        };
+        match column_types.convert(property, value.clone()) {
+            Ok(value) => converted.push((index, Some(value))),
+            Err(_) if on_error == "fail" => return Err(PyValueError::new_err(format!(
+                "Cannot convert '{}' on {} node '{}' to {}; no values were changed", value.to_string(), node_type, unique_id, data_type
+            ))),
+            Err(_) => converted.push((index, None)),
+        }
+    }
+
+    let mut removed = 0;
+    for (index, value) in &mut converted {
+        if let Node::StandardNode { attributes, .. } = &mut graph[*index] {
+            match value.take() {
+                Some(value) => { attributes.insert(property.to_string(), value); },
+                None => {
+                    attributes.remove(property);
+                    removed += 1;
+                },
+            }
+        }
+    }
+    if let Node::DataTypeNode { attributes, categories, defaults, .. } = &mut graph[schema_index] {
+        attributes.insert(property.to_string(), data_type.clone());
+        categories.remove(property);
+        if let Some(value) = defaults.remove(property) {
+            let value = match value {
+                AttributeValue::Category(category) => AttributeValue::String(category.label().to_string()),
+                other => other,
+            };
+            if let Ok(value) = column_types.convert(property, value) {
+                defaults.insert(property.to_string(), value);
+            }
+        }
+    }
+    if data_type == "Category" {
+        categories::encode_type(graph, node_type);
+    }
+    Ok((converted.into_iter().map(|(index, _)| index.index()).collect(), removed))
+}
+
+/// Removes the given nodes and every relation to or from them. Schema nodes and indices not in the
+/// graph are ignored.
+///
+/// petgraph fills each freed slot with the last node, so the remaining nodes may be renumbered.
+/// Returns the number of relations removed and, by new index, the old index of each remaining node.
+pub fn delete_nodes(graph: &mut JournaledGraph, mut indices: Vec<usize>) -> (usize, Vec<usize>) {
+    indices.retain(|&index| matches!(graph.node_weight(NodeIndex::new(index)), Some(Node::StandardNode { .. })));
+    // Remove from the highest index down, so each node moved into a freed slot is one that stays
+    indices.sort_unstable_by(|a, b| b.cmp(a));
+    indices.dedup();
+
+    let edge_count = graph.edge_count();
+    let mut original: Vec<usize> = (0..graph.node_count()).collect();
+    for index in indices {
+        graph.remove_node(NodeIndex::new(index));
+        original.swap_remove(index);
+    }
+    (edge_count - graph.edge_count(), original)
+}
+
+/// Removes the `relationship_type` relations running from a node with one of `source_ids` to a node
+/// with one of `target_ids`, where None matches any node. Returns the number of relations removed.
+pub fn delete_connections(
+    graph: &mut JournaledGraph,
+    relationship_type: &str,
+    source_ids: Option<&HashSet<String>>,
+    target_ids: Option<&HashSet<String>>,
+) -> usize {
+    let matches = |ids: Option<&HashSet<String>>, node: NodeIndex| match (ids, &graph[node]) {
+        (None, _) => true,
+        (Some(ids), Node::StandardNode { unique_id, .. }) => ids.contains(unique_id),
+        (Some(_), Node::DataTypeNode { .. }) => false,
+    };
+    let mut edges: Vec<EdgeIndex> = graph.edge_references()
+        .filter(|edge| edge.weight().relation_type == relationship_type)
+        .filter(|edge| matches(source_ids, edge.source()) && matches(target_ids, edge.target()))
+        .map(|edge| edge.id())
+        .collect();
+    // Remove from the highest index down, since petgraph moves the last edge into a removed slot
+    edges.sort_unstable_by(|a, b| b.cmp(a));
+    for &edge in &edges {
+        graph.remove_edge(edge);
+    }
+    edges.len()
+}
+
+/// Sets the properties of existing `relationship_type` relations from rows naming their source and
+/// target by unique ID; the other columns are the properties. Empty cells leave the current value in
+/// place, and every relation of the type between the two nodes is updated.
+///
+/// Returns the number of relations updated and the (source, target) IDs of rows that matched no relation.
+#[allow(clippy::too_many_arguments)]
+pub fn update_connection_properties(
+    graph: &mut JournaledGraph,
+    data: &PyList,
+    columns: Vec<String>,
+    relationship_type: &str,
+    source_type: &str,
+    source_id_fields: Vec<String>,
+    target_type: &str,
+    target_id_fields: Vec<String>,
+) -> PyResult<(usize, Vec<(String, String)>)> {
+    let lookup = |node_type: &str| -> HashMap<String, NodeIndex> {
+        graph.node_indices().filter_map(|index| match &graph[index] {
+            Node::StandardNode { node_type: nt, unique_id, .. } if nt == node_type => Some((unique_id.clone(), index)),
+            _ => None,
+        }).collect()
+    };
+    let (sources, targets) = (lookup(source_type), lookup(target_type));
+
+    let mut updated = 0;
+    let mut missing = Vec::new();
+    for row in data.iter() {
+        let row: Vec<&PyAny> = row.extract()?;
+        let row_data: HashMap<_, _> = columns.iter().zip(row.iter()).collect();
+        let source_id = row_id(&row_data, &source_id_fields, "Source")?;
+        let target_id = row_id(&row_data, &target_id_fields, "Target")?;
+
+        let mut attributes = HashMap::new();
+        for (column, item) in &row_data {
+            if !source_id_fields.contains(column) && !target_id_fields.contains(column) {
+                let value = item.extract::<AttributeValue>()?;
+                if !value.is_null() {
+                    attributes.insert((*column).clone(), value);
+                }
+            }
+        }
+
+        let edges: Vec<EdgeIndex> = match (lookup_node(&sources, &source_id), lookup_node(&targets, &target_id)) {
+            (Some(source), Some(target)) => graph.edges_connecting(source, target)
+                .filter(|edge| edge.weight().relation_type == relationship_type)
+                .map(|edge| edge.id())
+                .collect(),
+            _ => Vec::new(),
+        };
+        if edges.is_empty() {
+            missing.push((source_id, target_id));
+            continue;
+        }
+        for edge in edges {
+            let relation = &mut graph[edge];
+            if !attributes.is_empty() {
+                relation.attributes.get_or_insert_with(HashMap::new).extend(attributes.iter().map(|(k, v)| (k.clone(), v.clone())));
+            }
+            updated += 1;
+        }
+    }
+    Ok((updated, missing))
+}
+
+/// Renames `property` to `new_name` on every `node_type` node and in the schema, carrying over its
+/// type, constraints, default and calculated flag. Returns the number of nodes changed.
+pub fn rename_property(
+    graph: &mut JournaledGraph,
+    node_type: &str,
+    property: &str,
+    new_name: &str,
+) -> PyResult<usize> {
+    let schema_index = property_schema(graph, node_type, property)?;
+    let Node::DataTypeNode { attributes, calculated, constraints, defaults, keys, categories, .. } = &mut graph[schema_index] else {
+        unreachable!("find_schema_node returns schema nodes")
+    };
+    if attributes.contains_key(new_name) {
+        return Err(PyValueError::new_err(format!("Node type '{}' already has a property '{}'", node_type, new_name)));
+    }
+    if let Some(data_type) = attributes.remove(property) {
+        attributes.insert(new_name.to_string(), data_type);
+    }
+    if calculated.remove(property) {
+        calculated.insert(new_name.to_string());
+    }
+    if let Some(rules) = constraints.remove(property) {
+        constraints.insert(new_name.to_string(), rules);
+    }
+    if let Some(value) = defaults.remove(property) {
+        defaults.insert(new_name.to_string(), value);
+    }
+    if let Some(dictionary) = categories.remove(property) {
+        categories.insert(new_name.to_string(), dictionary);
+    }
+    for field in keys.unique_id.iter_mut().chain(keys.title.as_mut()).filter(|field| *field == property) {
+        *field = new_name.to_string();
+    }
+
+    let mut renamed = 0;
+    for index in graph.node_indices() {
+        if !has_property(&graph[index], node_type, property) {
+            continue;
+        }
+        if let Node::StandardNode { attributes, .. } = &mut graph[index] {
+            if let Some(value) = attributes.remove(property) {
+                attributes.insert(new_name.to_string(), value);
+                renamed += 1;
+            }
+        }
+    }
+    Ok(renamed)
+}
+
+/// Removes `property` from every `node_type` node and from the schema, along with its constraints
+/// and default. Returns the number of nodes changed.
+pub fn drop_property(graph: &mut JournaledGraph, node_type: &str, property: &str) -> PyResult<usize> {
+    let schema_index = property_schema(graph, node_type, property)?;
+    if let Node::DataTypeNode { attributes, calculated, constraints, defaults, categories, .. } = &mut graph[schema_index] {
+        attributes.remove(property);
+        calculated.remove(property);
+        constraints.remove(property);
+        defaults.remove(property);
+        categories.remove(property);
+    }
+
+    let mut dropped = 0;
+    for index in graph.node_indices() {
+        if !has_property(&graph[index], node_type, property) {
+            continue;
+        }
+        if let Node::StandardNode { attributes, .. } = &mut graph[index] {
+            attributes.remove(property);
+            dropped += 1;
+        }
+    }
+    Ok(dropped)
+}
+
+fn has_property(node: &Node, node_type: &str, property: &str) -> bool {
+    matches!(node, Node::StandardNode { node_type: nt, attributes, .. } if nt == node_type && attributes.contains_key(property))
+}
+
+// The schema node of a node type that records `property`
+fn property_schema(graph: &DiGraph<Node, Relation>, node_type: &str, property: &str) -> PyResult<NodeIndex> {
+    find_schema_node(graph, "Node", node_type)
+        .filter(|&index| matches!(&graph[index], Node::DataTypeNode { attributes, .. } if attributes.contains_key(property)))
+        .ok_or_else(|| PyValueError::new_err(format!("Node type '{}' has no property '{}'", node_type, property)))
+}
+
+/// Ways of combining the properties of merged nodes: "prefer_survivor" keeps the survivor's values
+/// and fills the properties it lacks from the duplicates, "prefer_duplicates" lets the duplicates'
+/// values win (later duplicates over earlier ones) and "keep_survivor" ignores them
+pub const PROPERTY_STRATEGIES: [&str; 3] = ["prefer_survivor", "prefer_duplicates", "keep_survivor"];
+
+/// Moves the relations of `duplicates` onto `survivor` and merges their properties into it by
+/// `property_strategy`; the duplicates are left without relations, for the caller to delete.
+/// Relations between the merged nodes, and those the survivor already has with the same type and
+/// other end, are dropped. Returns the number of relations moved and dropped.
+pub fn merge_nodes(
+    graph: &mut JournaledGraph,
+    survivor: NodeIndex,
+    duplicates: &[NodeIndex],
+    property_strategy: &str,
+) -> PyResult<(usize, usize)> {
+    if !PROPERTY_STRATEGIES.contains(&property_strategy) {
+        return Err(PyValueError::new_err(format!(
+            "Invalid property_strategy '{}', expected 'prefer_survivor', 'prefer_duplicates' or 'keep_survivor'", property_strategy
+        )));
+    }
+    let merged: HashSet<NodeIndex> = duplicates.iter().copied().chain([survivor]).collect();
+    let mut existing: HashSet<(NodeIndex, NodeIndex, Symbol)> = graph.edges_directed(survivor, Direction::Outgoing)
+        .chain(graph.edges_directed(survivor, Direction::Incoming))
+        .map(|edge| (edge.source(), edge.target(), edge.weight().relation_type.clone()))
+        .collect();
+
+    let (mut moved, mut dropped) = (0, 0);
+    for &duplicate in duplicates.iter().filter(|&&duplicate| duplicate != survivor) {
+        let mut edges: Vec<EdgeIndex> = graph.edges_directed(duplicate, Direction::Outgoing)
+            .chain(graph.edges_directed(duplicate, Direction::Incoming))
+            .map(|edge| edge.id())
+            .collect();
+        // Remove from the highest index down, since petgraph moves the last edge into a removed slot
+        edges.sort_unstable_by(|a, b| b.cmp(a));
+        edges.dedup();
+        for edge in edges {
+            let (source, target) = graph.edge_endpoints(edge).expect("Edge must exist");
+            let relation = graph.remove_edge(edge).expect("Edge must exist");
+            let source = if merged.contains(&source) { survivor } else { source };
+            let target = if merged.contains(&target) { survivor } else { target };
+            if (source == survivor && target == survivor) || !existing.insert((source, target, relation.relation_type.clone())) {
+                dropped += 1;
+                continue;
+            }
+            graph.add_edge(source, target, relation);
+            moved += 1;
+        }
+
+        if property_strategy == "keep_survivor" {
+            continue;
+        }
+        let Node::StandardNode { title, attributes, .. } = graph[duplicate].clone() else { continue };
+        let Node::StandardNode { title: survivor_title, attributes: survivor_attributes, .. } = &mut graph[survivor] else { continue };
+        for (property, value) in attributes {
+            if property_strategy == "prefer_duplicates" {
+                survivor_attributes.insert(property, value);
+            } else {
+                survivor_attributes.entry(property).or_insert(value);
+            }
+        }
+        if title.is_some() && (property_strategy == "prefer_duplicates" || survivor_title.is_none()) {
+            *survivor_title = title;
+        }
+    }
+    Ok((moved, dropped))
+}
+
+/// Moves the given nodes to `new_type`, recording their properties in its schema with the types
+/// they had, along with the unique ID and title columns of their old type. Schema nodes and nodes already of the type are left out. Fails without changes when a
+/// moved node's unique ID is taken in the new type, or a property has another type there.
+/// Returns the nodes moved.
+pub fn change_node_type(graph: &mut JournaledGraph, indices: &[usize], new_type: &str) -> PyResult<Vec<usize>> {
+    let mut taken: HashSet<String> = graph.node_weights().filter_map(|node| match node {
+        Node::StandardNode { node_type, unique_id, .. } if node_type == new_type => Some(unique_id.clone()),
+        _ => None,
+    }).collect();
+    let schema = |graph: &DiGraph<Node, Relation>, node_type: &str| -> (HashMap<String, String>, KeyColumns) {
+        match find_schema_node(graph, "Node", node_type).map(|index| &graph[index]) {
+            Some(Node::DataTypeNode { attributes, keys, .. }) => (attributes.clone(), keys.clone()),
+            _ => (HashMap::new(), KeyColumns::default()),
+        }
+    };
+    let (target_schema, mut target_keys) = schema(graph, new_type);
+    let mut old_schemas: HashMap<Symbol, (HashMap<String, String>, KeyColumns)> = HashMap::new();
+    let mut added_types: HashMap<String, String> = HashMap::new();
+    let mut moved = Vec::new();
+    for &index in indices {
+        let Some(Node::StandardNode { node_type, unique_id, attributes, .. }) = graph.node_weight(NodeIndex::new(index)) else { continue };
+        if node_type == new_type {
+            continue;
+        }
+        if !taken.insert(unique_id.clone()) {
+            return Err(PyValueError::new_err(format!(
+                "Cannot move {} node '{}': a {} node with that unique ID already exists", node_type, unique_id, new_type
+            )));
+        }
+        let (old_schema, old_keys) = old_schemas.entry(node_type.clone()).or_insert_with(|| schema(graph, node_type));
+        // The key columns live in the unique ID and title rather than among the properties
+        let key_types = old_keys.unique_id.iter().chain(&old_keys.title)
+            .map(|column| (column, old_schema.get(column).cloned().unwrap_or_else(|| "String".to_string())));
+        let property_types = attributes.iter()
+            .map(|(property, value)| (property, old_schema.get(property).cloned().unwrap_or_else(|| value.type_name().to_string())));
+        for (property, data_type) in key_types.chain(property_types) {
+            match target_schema.get(property).or_else(|| added_types.get(property)) {
+                Some(existing) if *existing != data_type => return Err(PyValueError::new_err(format!(
+                    "Property '{}' is {} on {} nodes but {} on {} nodes", property, data_type, node_type, existing, new_type
+                ))),
+                Some(_) => (),
+                None => { added_types.insert(property.clone(), data_type); },
+            }
+        }
+        // Nodes keep their unique ID and title, so the new type takes the key columns they came from
+        if target_keys.unique_id.is_empty() {
+            target_keys.unique_id = old_keys.unique_id.clone();
+        }
+        if target_keys.title.is_none() {
+            target_keys.title = old_keys.title.clone();
+        }
+        moved.push(index);
+    }
+
+    let new_symbol = Symbol::new(new_type);
+    for &index in &moved {
+        if let Node::StandardNode { node_type, attributes, .. } = &mut graph[NodeIndex::new(index)] {
+            *node_type = new_symbol.clone();
+            // Category values hold codes into the old type's dictionaries, so they go back to labels
+            for value in attributes.values_mut() {
+                if let AttributeValue::Category(category) = value {
+                    *value = AttributeValue::String(category.label().to_string());
+                }
+            }
+        }
+    }
+    let schema_index = match find_schema_node(graph, "Node", new_type) {
+        Some(index) => index,
+        None => graph.add_node(Node::new_data_type("Node", new_type, HashMap::new())),
+    };
+    if let Node::DataTypeNode { attributes, keys, .. } = &mut graph[schema_index] {
+        attributes.extend(added_types);
+        *keys = target_keys;
+    }
+    categories::encode_nodes(graph, new_type, &moved);
+    Ok(moved)
+}
+
+/// Renames the relation type `old_type` to `new_type` on every relation and in its schema. Fails
+/// when `new_type` is already in use. Returns the number of relations renamed.
+pub fn rename_relation_type(graph: &mut JournaledGraph, old_type: &str, new_type: &str) -> PyResult<usize> {
+    let in_use = |name: &str| graph.edge_weights().any(|relation| relation.relation_type == name)
+        || find_schema_node(graph, "Relation", name).is_some();
+    if !in_use(old_type) {
+        return Err(PyValueError::new_err(format!("No relation type '{}'", old_type)));
+    }
+    if in_use(new_type) {
+        return Err(PyValueError::new_err(format!("Relation type '{}' already exists", new_type)));
+    }
+    if let Some(schema_index) = find_schema_node(graph, "Relation", old_type) {
+        if let Node::DataTypeNode { name, .. } = &mut graph[schema_index] {
+            *name = new_type.to_string();
+        }
+    }
+    let new_symbol = Symbol::new(new_type);
+    let mut renamed = 0;
+    for index in graph.edge_indices() {
+        if graph[index].relation_type == old_type {
+            graph[index].relation_type = new_symbol.clone();
+            renamed += 1;
+        }
+    }
+    Ok(renamed)
+}
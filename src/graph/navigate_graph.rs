@@ -1,22 +1,31 @@
 use petgraph::graph::{DiGraph, NodeIndex};
 use petgraph::Direction;
 use petgraph::visit::EdgeRef;
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use crate::graph::journal::JournaledGraph;
 use crate::data_types::AttributeValue; 
 use pyo3::prelude::*;
+use pyo3::exceptions::PyValueError;
 use pyo3::types::PyDict;
 use crate::schema::{Node, Relation};
+use crate::graph::filters::{Filter, FilterOptions};
+use crate::graph::calculations::evaluate_nodes;
 
-/// Retrieves nodes by their unique ID, with an optional node_type filter and multiple attribute filters.
+/// Retrieves nodes matching an optional node_type and a list of (possibly nested) filters.
 pub fn get_nodes(
-    graph: &mut DiGraph<Node, Relation>,
+    graph: &mut JournaledGraph,
     filter_node_type: Option<&str>,
-    filters: Option<Vec<HashMap<String, String>>>
-) -> Vec<usize> {
-    graph.node_indices().filter_map(|node_index| {
+    filters: Option<Vec<&PyDict>>,
+    options: FilterOptions,
+) -> PyResult<Vec<usize>> {
+    // Parse the filter dictionaries once, before scanning the nodes
+    let filter = filters.map(|filters| Filter::from_list(filters, options)).transpose()?;
+
+    Ok(graph.node_indices().filter_map(|node_index| {
         let node = graph.node_weight(node_index)?;
 
-        let Node::StandardNode { node_type, unique_id, attributes, title } = node else { return None };
+        let Node::StandardNode { node_type, .. } = node else { return None };
 
         // Apply node_type filter if provided
         if let Some(filter_type) = filter_node_type {
@@ -25,33 +34,63 @@ pub fn get_nodes(
             }
         }
 
-        // Check if the node matches all the specified attribute filters
-        if let Some(filters) = &filters {
-            for filter in filters {
-                let mut matches = true;
-                for (key, value) in filter {
-                    matches = match key.as_str() {
-                        "unique_id" => unique_id == value,
-                        "title" => title.as_deref() == Some(value),
-                        _ => attributes.get(key).map_or(false, |v| v.to_string() == *value),
-                    };
-                    if !matches {
-                        break;
-                    }
-                }
-                if !matches {
-                    return None;
-                }
+        // Check if the node matches the combined attribute filters
+        if let Some(filter) = &filter {
+            if !filter.matches_node(node) {
+                return None;
             }
         }
 
         Some(node_index.index())  // Return the index of the matching node
+    }).collect())
+}
+
+/// Maps the unique IDs of all nodes of `node_type` to their node index
+pub fn unique_id_lookup<'a>(graph: &'a DiGraph<Node, Relation>, node_type: &str) -> HashMap<&'a str, usize> {
+    graph.node_indices().filter_map(|index| match &graph[index] {
+        Node::StandardNode { node_type: nt, unique_id, .. } if nt == node_type => Some((unique_id.as_str(), index.index())),
+        _ => None,
     }).collect()
 }
 
+/// Resolves a batch of unique IDs of one node type to node indices in a single pass over the graph,
+/// returning the (id, index) pairs found in input order together with the IDs that were not found.
+/// UUIDs are found in any spelling, e.g. upper case or without hyphens
+pub fn lookup_ids(
+    graph: &DiGraph<Node, Relation>,
+    node_type: &str,
+    ids: Vec<String>,
+) -> (Vec<(String, usize)>, Vec<String>) {
+    let lookup = unique_id_lookup(graph, node_type);
+    let mut found = Vec::new();
+    let mut missing = Vec::new();
+    for id in ids {
+        let canonical = || AttributeValue::parse_uuid(&id).map(|uuid| uuid.to_string());
+        match lookup.get(id.as_str()).or_else(|| lookup.get(canonical()?.as_str())) {
+            Some(&index) => found.push((id, index)),
+            None => missing.push(id),
+        }
+    }
+    (found, missing)
+}
+
+/// Keeps the nodes in `indices` for which a boolean expression such as `profit / revenue > 0.2` holds
+pub fn filter_expr(
+    graph: &DiGraph<Node, Relation>,
+    indices: Vec<usize>,
+    expression: &str,
+    aliases: Option<&HashMap<String, String>>,
+) -> PyResult<Vec<usize>> {
+    Ok(evaluate_nodes(graph, indices, expression, aliases)?
+        .into_iter()
+        .filter(|(_, result)| result.is_true())
+        .map(|(index, _)| index)
+        .collect())
+}
+
 /// Retrieves relationships for specified nodes
 pub fn get_relationships(
-    graph: &mut DiGraph<Node, Relation>,
+    graph: &mut JournaledGraph,
     py: Python, 
     indices: Vec<usize>
 ) -> PyResult<PyObject> {
@@ -90,41 +129,29 @@ pub fn get_relationships(
 }
 
 
+#[allow(clippy::too_many_arguments)]
 pub fn traverse_nodes(
     graph: &DiGraph<Node, Relation>,
     indices: Vec<usize>,
-    relationship_type: String,
-    is_incoming: bool,
+    relationship_types: &[String],
+    directions: &[Direction],
     sort_attribute: Option<&str>,
     ascending: Option<bool>,
     max_relations: Option<usize>,
+    offset: Option<usize>,
+    relationship_filter: Option<&Filter>,
 ) -> Vec<usize> {
     let mut final_nodes: Vec<usize> = Vec::new();
-    let direction = if is_incoming { Direction::Incoming } else { Direction::Outgoing };
 
     for index in indices {
-        let node_index = NodeIndex::new(index);
-        let mut nodes_with_attrs: Vec<(usize, Option<AttributeValue>)> = Vec::new();
+        let mut sorted_or_filtered_nodes = related_nodes(
+            graph, NodeIndex::new(index), relationship_types, directions, relationship_filter, sort_attribute, ascending,
+        );
 
-        for edge in graph.edges_directed(node_index, direction).filter(|edge| edge.weight().relation_type == relationship_type) {
-            let target_node_index = if is_incoming { edge.source() } else { edge.target() };
-            let target_node = graph.node_weight(target_node_index).expect("Node must exist");
-
-            if let Node::StandardNode { attributes, .. } = target_node {
-                let attr_value = sort_attribute.and_then(|attr| attributes.get(attr).cloned());
-                nodes_with_attrs.push((target_node_index.index(), attr_value));
-            }
+        // Skip `offset` nodes, then limit the number of nodes based on `max_relations` after sorting or filtering.
+        if let Some(skip) = offset {
+            sorted_or_filtered_nodes.drain(..skip.min(sorted_or_filtered_nodes.len()));
         }
-
-        // If sorting is enabled, sort the nodes based on the attribute value.
-        let mut sorted_or_filtered_nodes = if let Some(_attr) = sort_attribute {
-            sort_nodes_by_attribute(nodes_with_attrs, ascending.unwrap_or(true))
-        } else {
-            // If sorting is not enabled, simply collect the node indices without sorting.
-            nodes_with_attrs.into_iter().map(|(idx, _)| idx).collect::<Vec<_>>()
-        };
-
-        // Limit the number of nodes based on `max_relations` after sorting or filtering.
         if let Some(max) = max_relations {
             sorted_or_filtered_nodes.truncate(max);
         }
@@ -135,25 +162,194 @@ pub fn traverse_nodes(
     final_nodes
 }
 
-fn sort_nodes_by_attribute(nodes_with_attrs: Vec<(usize, Option<AttributeValue>)>, ascending: bool) -> Vec<usize> {
+/// Interprets a "incoming"/"outgoing"/"both" direction argument, defaulting to outgoing
+pub fn parse_directions(direction: Option<&str>) -> PyResult<Vec<Direction>> {
+    match direction.unwrap_or("outgoing") {
+        "incoming" | "in" => Ok(vec![Direction::Incoming]),
+        "outgoing" | "out" => Ok(vec![Direction::Outgoing]),
+        "both" => Ok(vec![Direction::Outgoing, Direction::Incoming]),
+        other => Err(PyValueError::new_err(format!(
+            "Invalid direction '{}', expected 'incoming', 'outgoing' or 'both'", other
+        ))),
+    }
+}
+
+/// Keeps the nodes whose number of matching edges lies within `min_degree..=max_degree`
+pub fn filter_by_degree(
+    graph: &DiGraph<Node, Relation>,
+    indices: Vec<usize>,
+    min_degree: usize,
+    max_degree: Option<usize>,
+    directions: &[Direction],
+    relationship_types: Option<&[String]>,
+    edge_filter: Option<&Filter>,
+) -> Vec<usize> {
+    indices.into_iter().filter(|&index| {
+        let degree: usize = directions.iter()
+            .map(|&direction| graph.edges_directed(NodeIndex::new(index), direction)
+                .filter(|edge| relationship_types.is_none_or(|types| edge.weight().relation_type.is_in(types)))
+                .filter(|edge| edge_filter.is_none_or(|filter| filter.matches_relation(edge.weight())))
+                .count())
+            .sum();
+        degree >= min_degree && max_degree.is_none_or(|max| degree <= max)
+    }).collect()
+}
+
+/// Follows matching edges transitively with a breadth-first search from all `indices` at once,
+/// returning each node reached within `min_depth..=max_depth` hops with its (shortest) depth
+pub fn traverse_deep(
+    graph: &DiGraph<Node, Relation>,
+    indices: Vec<usize>,
+    relationship_types: &[String],
+    directions: &[Direction],
+    min_depth: usize,
+    max_depth: Option<usize>,
+    edge_filter: Option<&Filter>,
+) -> Vec<(usize, usize)> {
+    let mut visited: HashSet<NodeIndex> = HashSet::new();
+    let mut frontier: Vec<NodeIndex> = Vec::new();
+    let mut reached = Vec::new();
+
+    for index in indices {
+        let node_index = NodeIndex::new(index);
+        if graph.node_weight(node_index).is_some() && visited.insert(node_index) {
+            frontier.push(node_index);
+            if min_depth == 0 {
+                reached.push((index, 0));
+            }
+        }
+    }
+
+    let mut depth = 0;
+    while !frontier.is_empty() && max_depth.is_none_or(|max| depth < max) {
+        depth += 1;
+        let mut next_frontier = Vec::new();
+        for node_index in frontier {
+            for &direction in directions {
+                for edge in graph.edges_directed(node_index, direction) {
+                    if !edge.weight().relation_type.is_in(relationship_types)
+                        || edge_filter.is_some_and(|filter| !filter.matches_relation(edge.weight())) {
+                        continue;
+                    }
+                    let neighbor = if direction == Direction::Incoming { edge.source() } else { edge.target() };
+                    if visited.insert(neighbor) {
+                        next_frontier.push(neighbor);
+                        if depth >= min_depth {
+                            reached.push((neighbor.index(), depth));
+                        }
+                    }
+                }
+            }
+        }
+        frontier = next_frontier;
+    }
+    reached
+}
+
+/// Keeps the top `k` related nodes per parent ranked by `sort_attribute`, returned as (parent, child) pairs
+pub fn top_k(
+    graph: &DiGraph<Node, Relation>,
+    indices: Vec<usize>,
+    relationship_types: &[String],
+    k: usize,
+    sort_attribute: &str,
+    directions: &[Direction],
+    ascending: Option<bool>,
+) -> Vec<(usize, usize)> {
+    let mut pairs = Vec::new();
+    for parent in indices {
+        let children = related_nodes(
+            graph, NodeIndex::new(parent), relationship_types, directions, None, Some(sort_attribute), Some(ascending.unwrap_or(false)),
+        );
+        pairs.extend(children.into_iter().take(k).map(|child| (parent, child)));
+    }
+    pairs
+}
+
+// Distinct nodes connected to `node_index` through matching edges, optionally sorted by an attribute
+pub fn related_nodes(
+    graph: &DiGraph<Node, Relation>,
+    node_index: NodeIndex,
+    relationship_types: &[String],
+    directions: &[Direction],
+    relationship_filter: Option<&Filter>,
+    sort_attribute: Option<&str>,
+    ascending: Option<bool>,
+) -> Vec<usize> {
+    let mut nodes_with_attrs: Vec<(usize, Option<AttributeValue>, Option<u64>)> = Vec::new();
+    let mut seen = HashSet::new();
+
+    for &direction in directions {
+        let matching_edges = graph.edges_directed(node_index, direction).filter(|edge| {
+            edge.weight().relation_type.is_in(relationship_types)
+                && relationship_filter.is_none_or(|filter| filter.matches_relation(edge.weight()))
+        });
+        for edge in matching_edges {
+            let target_node_index = if direction == Direction::Incoming { edge.source() } else { edge.target() };
+            if !seen.insert(target_node_index) {
+                continue;
+            }
+            let target_node = graph.node_weight(target_node_index).expect("Node must exist");
+
+            if let Node::StandardNode { sequence, .. } = target_node {
+                let attr_value = sort_attribute.and_then(|attr| target_node.get_field(attr).map(Cow::into_owned));
+                nodes_with_attrs.push((target_node_index.index(), attr_value, *sequence));
+            }
+        }
+    }
+
+    // If sorting is enabled, sort the nodes based on the attribute value.
+    if sort_attribute.is_some() {
+        sort_nodes_by_attribute(nodes_with_attrs, ascending.unwrap_or(true))
+    } else {
+        // If sorting is not enabled, simply collect the node indices without sorting.
+        nodes_with_attrs.into_iter().map(|(idx, _, _)| idx).collect::<Vec<_>>()
+    }
+}
+
+/// Sorts the given nodes by an attribute (or "title"/"unique_id"), then applies offset and limit
+pub fn sort_nodes(
+    graph: &DiGraph<Node, Relation>,
+    indices: Vec<usize>,
+    sort_attribute: &str,
+    ascending: Option<bool>,
+    limit: Option<usize>,
+    offset: Option<usize>,
+) -> Vec<usize> {
+    let nodes_with_attrs = indices.into_iter().filter_map(|index| {
+        let node = graph.node_weight(NodeIndex::new(index))?;
+        let attr_value = node.get_field(sort_attribute).map(Cow::into_owned);
+        Some((index, attr_value, node.sequence()))
+    }).collect();
+
+    sort_nodes_by_attribute(nodes_with_attrs, ascending.unwrap_or(true))
+        .into_iter()
+        .skip(offset.unwrap_or(0))
+        .take(limit.unwrap_or(usize::MAX))
+        .collect()
+}
+
+fn sort_nodes_by_attribute(nodes_with_attrs: Vec<(usize, Option<AttributeValue>, Option<u64>)>, ascending: bool) -> Vec<usize> {
     let mut sorted_nodes = nodes_with_attrs;
 
     // Sort based on the attribute value, handling different types of AttributeValue
     sorted_nodes.sort_by(|a, b| {
-        match (&a.1, &b.1) {
+        let ordering = match (&a.1, &b.1) {
             (Some(AttributeValue::Int(a_val)), Some(AttributeValue::Int(b_val))) => a_val.cmp(b_val),
             (Some(AttributeValue::Float(a_val)), Some(AttributeValue::Float(b_val))) => a_val.partial_cmp(b_val).unwrap_or(std::cmp::Ordering::Equal),
             (Some(AttributeValue::DateTime(a_val)), Some(AttributeValue::DateTime(b_val))) => a_val.cmp(b_val),
+            (Some(AttributeValue::Date(a_val)), Some(AttributeValue::Date(b_val))) => a_val.cmp(b_val),
+            (Some(AttributeValue::Duration(a_val)), Some(AttributeValue::Duration(b_val))) => a_val.cmp(b_val),
+            (Some(AttributeValue::Uuid(a_val)), Some(AttributeValue::Uuid(b_val))) => a_val.cmp(b_val),
             (Some(AttributeValue::String(a_val)), Some(AttributeValue::String(b_val))) => a_val.cmp(b_val),
+            (Some(AttributeValue::Category(a_val)), Some(AttributeValue::Category(b_val))) => a_val.label().cmp(b_val.label()),
             _ => std::cmp::Ordering::Equal, // If no attribute or non-comparable types, consider them equal
-        }
+        };
+        let ordering = if ascending { ordering } else { ordering.reverse() };
+        // Fall back to insertion order when the attribute values tie
+        ordering.then_with(|| a.2.cmp(&b.2))
     });
 
-    // Reverse the order if descending
-    if !ascending {
-        sorted_nodes.reverse();
-    }
-
     // Return the sorted node indices
-    sorted_nodes.into_iter().map(|(idx, _)| idx).collect()
-}
\ No newline at end of file
+    sorted_nodes.into_iter().map(|(idx, _, _)| idx).collect()
+}
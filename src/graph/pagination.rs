@@ -0,0 +1,46 @@
+use pyo3::prelude::*;
+use crate::graph::get_attributes::get_node_attributes;
+use crate::graph::KnowledgeGraph;
+
+/// Iterator over a selection yielding node data one page at a time, so large selections can be
+/// streamed without building a single list of every node
+#[pyclass]
+pub struct NodePages {
+    graph: Py<KnowledgeGraph>,
+    indices: Vec<usize>,
+    page_size: usize,
+    position: usize,
+    specified_attributes: Option<Vec<String>>,
+}
+
+impl NodePages {
+    pub fn new(
+        graph: Py<KnowledgeGraph>, indices: Vec<usize>, page_size: usize, specified_attributes: Option<Vec<String>>,
+    ) -> Self {
+        NodePages { graph, indices, page_size: page_size.max(1), position: 0, specified_attributes }
+    }
+}
+
+#[pymethods]
+impl NodePages {
+    fn __iter__(slf: PyRef<Self>) -> PyRef<Self> {
+        slf
+    }
+
+    fn __next__(&mut self, py: Python) -> PyResult<Option<PyObject>> {
+        if self.position >= self.indices.len() {
+            return Ok(None);
+        }
+        let end = (self.position + self.page_size).min(self.indices.len());
+        let page = self.indices[self.position..end].to_vec();
+        self.position = end;
+
+        let mut kg = self.graph.borrow_mut(py);
+        get_node_attributes(&mut kg.graph, py, page, self.specified_attributes.clone(), None).map(Some)
+    }
+
+    // Number of pages, including those already consumed
+    fn __len__(&self) -> usize {
+        self.indices.len().div_ceil(self.page_size)
+    }
+}
@@ -0,0 +1,199 @@
+use pyo3::prelude::*;
+use pyo3::exceptions::PyValueError;
+use pyo3::types::{PyDict, PyList};
+use petgraph::graph::{DiGraph, NodeIndex};
+use petgraph::visit::EdgeRef;
+use petgraph::Direction;
+use std::collections::HashMap;
+use crate::graph::filters::{Filter, FilterOptions};
+use crate::schema::{Node, Relation};
+use crate::data_types::AttributeValue;
+
+/// A pattern node: an alias bound to one graph node of an optional type matching an optional filter
+pub struct PatternNode {
+    pub alias: String,
+    pub node_type: Option<String>,
+    pub filter: Option<Filter>,
+}
+
+/// A directed pattern edge between two pattern nodes, addressed by position
+pub struct PatternEdge {
+    pub source: usize,
+    pub target: usize,
+    pub relationship_type: Option<String>,
+    pub filter: Option<Filter>,
+}
+
+/// A small graph pattern such as a chain or a triad, matched by `match_pattern`
+#[derive(Default)]
+pub struct Pattern {
+    pub nodes: Vec<PatternNode>,
+    pub edges: Vec<PatternEdge>,
+}
+
+impl Pattern {
+    /// Parses the Python pattern description.
+    ///
+    /// `nodes` maps aliases to a node type or to a dictionary with optional "type" and "filters"
+    /// (a filter dictionary or a list of them). `edges` holds (source alias, relation type, target
+    /// alias) tuples, with an optional filter dictionary on the relation as fourth element; a
+    /// relation type of None matches any relation.
+    pub fn from_py(nodes: &PyDict, edges: Option<&PyList>) -> PyResult<Self> {
+        let mut pattern = Pattern::default();
+        for (alias, spec) in nodes.iter() {
+            let alias: String = alias.extract()?;
+            let (node_type, filter) = match spec.extract::<String>() {
+                Ok(node_type) => (Some(node_type), None),
+                Err(_) => {
+                    let spec: &PyDict = spec.downcast()
+                        .map_err(|_| PyValueError::new_err(format!("Pattern node '{}' must be a node type or a dictionary", alias)))?;
+                    let node_type = spec.get_item("type").map(|t| t.extract::<String>()).transpose()?;
+                    (node_type, spec.get_item("filters").map(parse_filter).transpose()?)
+                },
+            };
+            pattern.nodes.push(PatternNode { alias, node_type, filter });
+        }
+
+        for edge in edges.into_iter().flatten() {
+            let parts: Vec<&PyAny> = edge.extract()?;
+            if !(3..=4).contains(&parts.len()) {
+                return Err(PyValueError::new_err(
+                    "Pattern edges must be (source, relation_type, target) or (source, relation_type, target, filters)"
+                ));
+            }
+            let source = pattern.position(parts[0].extract()?)?;
+            let target = pattern.position(parts[2].extract()?)?;
+            let relationship_type: Option<String> = parts[1].extract()?;
+            let filter = parts.get(3).map(|filters| parse_filter(filters)).transpose()?;
+            pattern.edges.push(PatternEdge { source, target, relationship_type, filter });
+        }
+        if pattern.nodes.is_empty() {
+            return Err(PyValueError::new_err("A pattern needs at least one node"));
+        }
+        Ok(pattern)
+    }
+
+    /// Restricts every pattern edge to relations valid at `at`, the date set by `as_of`
+    pub fn valid_at(&mut self, at: &AttributeValue) {
+        for edge in &mut self.edges {
+            let valid = Filter::valid_at(at.clone());
+            edge.filter = Some(match edge.filter.take() {
+                Some(filter) => Filter::And(vec![filter, valid]),
+                None => valid,
+            });
+        }
+    }
+
+    /// Position of the pattern node with the given alias
+    pub fn position(&self, alias: &str) -> PyResult<usize> {
+        self.nodes.iter().position(|node| node.alias == alias)
+            .ok_or_else(|| PyValueError::new_err(format!("Unknown pattern alias '{}'", alias)))
+    }
+
+    // Order in which nodes are bound, each preferably connected to an earlier node so candidates
+    // come from the neighbors of a bound node rather than a full scan
+    fn binding_order(&self) -> Vec<(usize, Option<&PatternEdge>)> {
+        let mut order: Vec<(usize, Option<&PatternEdge>)> = Vec::with_capacity(self.nodes.len());
+        let mut placed = vec![false; self.nodes.len()];
+        while order.len() < self.nodes.len() {
+            let anchored = self.edges.iter().find_map(|edge| match (placed[edge.source], placed[edge.target]) {
+                (true, false) => Some((edge.target, Some(edge))),
+                (false, true) => Some((edge.source, Some(edge))),
+                _ => None,
+            });
+            let next = anchored.unwrap_or_else(|| {
+                (placed.iter().position(|&p| !p).expect("An unplaced node must remain"), None)
+            });
+            placed[next.0] = true;
+            order.push(next);
+        }
+        order
+    }
+}
+
+fn parse_filter(filters: &PyAny) -> PyResult<Filter> {
+    match filters.downcast::<PyDict>() {
+        Ok(dict) => Filter::from_dict(dict, FilterOptions::default()),
+        Err(_) => Filter::from_list(filters.extract()?, FilterOptions::default()),
+    }
+}
+
+/// Finds bindings of the pattern, each a node index per pattern node (in pattern order), stopping
+/// after `max_matches`. Distinct aliases always bind distinct nodes.
+pub fn match_pattern(graph: &DiGraph<Node, Relation>, pattern: &Pattern, max_matches: usize) -> Vec<Vec<usize>> {
+    let order = pattern.binding_order();
+    let mut bound: Vec<Option<NodeIndex>> = vec![None; pattern.nodes.len()];
+    let mut matches = Vec::new();
+    let mut scans: HashMap<usize, Vec<NodeIndex>> = HashMap::new();
+    extend_match(graph, pattern, &order, &mut bound, &mut scans, &mut matches, max_matches);
+    matches
+}
+
+fn extend_match(
+    graph: &DiGraph<Node, Relation>,
+    pattern: &Pattern,
+    order: &[(usize, Option<&PatternEdge>)],
+    bound: &mut Vec<Option<NodeIndex>>,
+    scans: &mut HashMap<usize, Vec<NodeIndex>>,
+    matches: &mut Vec<Vec<usize>>,
+    max_matches: usize,
+) {
+    let depth = bound.iter().filter(|b| b.is_some()).count();
+    let Some(&(position, anchor)) = order.get(depth) else {
+        matches.push(bound.iter().map(|b| b.expect("All nodes are bound").index()).collect());
+        return;
+    };
+
+    let candidates: Vec<NodeIndex> = match anchor {
+        Some(edge) if edge.target == position => neighbors(graph, bound[edge.source].expect("Anchor is bound"), edge, Direction::Outgoing),
+        Some(edge) => neighbors(graph, bound[edge.target].expect("Anchor is bound"), edge, Direction::Incoming),
+        None => scans.entry(position).or_insert_with(|| {
+            graph.node_indices().filter(|&node| node_matches(&graph[node], &pattern.nodes[position])).collect()
+        }).clone(),
+    };
+
+    for candidate in candidates {
+        if matches.len() >= max_matches {
+            return;
+        }
+        if bound.contains(&Some(candidate)) || !node_matches(&graph[candidate], &pattern.nodes[position]) {
+            continue;
+        }
+        bound[position] = Some(candidate);
+        if edges_hold(graph, pattern, bound, position) {
+            extend_match(graph, pattern, order, bound, scans, matches, max_matches);
+        }
+        bound[position] = None;
+    }
+}
+
+fn neighbors(graph: &DiGraph<Node, Relation>, node: NodeIndex, edge: &PatternEdge, direction: Direction) -> Vec<NodeIndex> {
+    let mut found: Vec<NodeIndex> = graph.edges_directed(node, direction)
+        .filter(|e| relation_matches(e.weight(), edge))
+        .map(|e| if direction == Direction::Outgoing { e.target() } else { e.source() })
+        .collect();
+    found.sort_unstable();
+    found.dedup();
+    found
+}
+
+fn node_matches(node: &Node, pattern_node: &PatternNode) -> bool {
+    let Node::StandardNode { node_type, .. } = node else { return false };
+    pattern_node.node_type.as_ref().is_none_or(|t| t == node_type)
+        && pattern_node.filter.as_ref().is_none_or(|filter| filter.matches_node(node))
+}
+
+fn relation_matches(relation: &Relation, edge: &PatternEdge) -> bool {
+    edge.relationship_type.as_ref().is_none_or(|t| *t == relation.relation_type)
+        && edge.filter.as_ref().is_none_or(|filter| filter.matches_relation(relation))
+}
+
+// Every pattern edge touching `position` whose other end is bound must exist in the graph
+fn edges_hold(graph: &DiGraph<Node, Relation>, pattern: &Pattern, bound: &[Option<NodeIndex>], position: usize) -> bool {
+    pattern.edges.iter()
+        .filter(|edge| edge.source == position || edge.target == position)
+        .all(|edge| match (bound[edge.source], bound[edge.target]) {
+            (Some(source), Some(target)) => graph.edges_connecting(source, target).any(|e| relation_matches(e.weight(), edge)),
+            _ => true,
+        })
+}
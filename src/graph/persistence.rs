@@ -0,0 +1,449 @@
+use pyo3::prelude::*;
+use pyo3::exceptions::{PyIOError, PyValueError};
+use petgraph::graph::DiGraph;
+use bincode::Options;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use crate::graph::journal::JournaledGraph;
+use crate::graph::categories;
+use crate::data_types::AttributeValue;
+use crate::schema::{Node, Relation};
+
+// Files start with a magic tag and a format version so incompatible files fail with a clear error.
+// Version 2 adds a codec byte after the version; version 1 files are uncompressed. Version 3 stores
+// Int as 64-bit, version 4 schema constraints, version 5 relation endpoints, version 6 property
+// defaults and version 7 the key columns of node types; older files are read through the `legacy`
+// layout.
+const MAGIC: &[u8; 6] = b"RGRAPH";
+const FORMAT_VERSION: u32 = 7;
+
+// Size of the chunks handed to the compressor and written to disk
+const CHUNK_SIZE: usize = 1 << 20;
+
+/// Compression applied to everything after the file header
+#[derive(Clone, Copy)]
+pub enum Codec {
+    None,
+    Zstd,
+    Lz4,
+}
+
+impl Codec {
+    pub fn parse(codec: Option<&str>) -> PyResult<Self> {
+        match codec.unwrap_or("none") {
+            "none" => Ok(Codec::None),
+            "zstd" => Ok(Codec::Zstd),
+            "lz4" => Ok(Codec::Lz4),
+            other => Err(PyValueError::new_err(format!("Unknown codec '{}', expected 'none', 'zstd' or 'lz4'", other))),
+        }
+    }
+
+    fn tag(self) -> u8 {
+        match self {
+            Codec::None => 0,
+            Codec::Zstd => 1,
+            Codec::Lz4 => 2,
+        }
+    }
+
+    fn from_tag(tag: u8) -> Option<Self> {
+        [Codec::None, Codec::Zstd, Codec::Lz4].into_iter().find(|codec| codec.tag() == tag)
+    }
+}
+
+/// Everything needed to restore a graph: nodes, edges, schema nodes, ingestion sequence and
+/// saved selections
+#[derive(Serialize, Deserialize)]
+pub struct GraphState {
+    pub graph: JournaledGraph,
+    pub track_insertion_order: bool,
+    pub next_sequence: u64,
+    pub selections: HashMap<String, Vec<usize>>,
+}
+
+// Borrowing twin of `GraphState`, so saving doesn't clone the graph
+#[derive(Serialize)]
+pub struct GraphStateRef<'a> {
+    pub graph: &'a DiGraph<Node, Relation>,
+    pub track_insertion_order: bool,
+    pub next_sequence: u64,
+    pub selections: &'a HashMap<String, Vec<usize>>,
+}
+
+/// Writes the graph state as a versioned bincode file, streamed through the codec in chunks so
+/// the serialized graph is never held in memory as a whole
+pub fn save(path: &str, state: &GraphStateRef, codec: Codec) -> PyResult<()> {
+    let file = File::create(path).map_err(|e| PyIOError::new_err(format!("Cannot create '{}': {}", path, e)))?;
+    write_state(BufWriter::with_capacity(CHUNK_SIZE, file), state, codec, path)
+}
+
+/// Reads a file written by `save`, with any codec
+pub fn load(path: &str) -> PyResult<GraphState> {
+    let file = File::open(path).map_err(|e| PyIOError::new_err(format!("Cannot open '{}': {}", path, e)))?;
+    read_state(BufReader::with_capacity(CHUNK_SIZE, file), path)
+}
+
+/// Writes the `save` file layout to any writer; `name` identifies the destination in errors
+pub fn write_state<W: Write>(mut writer: W, state: &GraphStateRef, codec: Codec, name: &str) -> PyResult<()> {
+    let write_error = |e: std::io::Error| PyIOError::new_err(format!("Cannot write '{}': {}", name, e));
+    writer.write_all(MAGIC)
+        .and_then(|_| writer.write_all(&FORMAT_VERSION.to_le_bytes()))
+        .and_then(|_| writer.write_all(&[codec.tag()]))
+        .map_err(write_error)?;
+    let serialize = |writer: &mut dyn Write| bincode::serialize_into(writer, state)
+        .map_err(|e| PyValueError::new_err(format!("Cannot serialize graph: {}", e)));
+    match codec {
+        Codec::None => serialize(&mut writer)?,
+        Codec::Zstd => {
+            let mut encoder = zstd::Encoder::new(writer, 3).map_err(write_error)?;
+            serialize(&mut BufWriter::with_capacity(CHUNK_SIZE, &mut encoder))?;
+            writer = encoder.finish().map_err(write_error)?;
+        },
+        Codec::Lz4 => {
+            let mut encoder = lz4_flex::frame::FrameEncoder::new(writer);
+            serialize(&mut BufWriter::with_capacity(CHUNK_SIZE, &mut encoder))?;
+            writer = encoder.finish().map_err(|e| PyIOError::new_err(format!("Cannot write '{}': {}", name, e)))?;
+        },
+    }
+    writer.flush().map_err(write_error)
+}
+
+/// Reads what `write_state` wrote; `name` identifies the source in errors
+pub fn read_state<R: BufRead>(mut reader: R, name: &str) -> PyResult<GraphState> {
+    let mut header = [0u8; 10];
+    reader.read_exact(&mut header)
+        .map_err(|_| PyValueError::new_err(format!("'{}' is not a saved graph", name)))?;
+    if &header[..6] != MAGIC {
+        return Err(PyValueError::new_err(format!(
+            "'{}' is not a saved graph (files from save_to_file are read with load_from_file)", name
+        )));
+    }
+    let version = u32::from_le_bytes([header[6], header[7], header[8], header[9]]);
+    let codec = match version {
+        1 => Codec::None,
+        2..=FORMAT_VERSION => {
+            let mut tag = [0u8; 1];
+            reader.read_exact(&mut tag).map_err(|_| PyValueError::new_err(format!("'{}' is truncated", name)))?;
+            Codec::from_tag(tag[0]).ok_or_else(|| PyValueError::new_err(format!("'{}' uses an unknown codec ({})", name, tag[0])))?
+        },
+        _ => return Err(PyValueError::new_err(format!(
+            "'{}' uses graph format version {}, but this version of rusty_graph reads versions 1 to {}", name, version, FORMAT_VERSION
+        ))),
+    };
+    use legacy::{Constraints, Defaults, Endpoints, State, Value};
+    let mut state: GraphState = match version {
+        1 | 2 => decode::<State<Value, (), (), ()>, R>(reader, codec, name).map(GraphState::from),
+        3 => decode::<State<AttributeValue, (), (), ()>, R>(reader, codec, name).map(GraphState::from),
+        4 => decode::<State<AttributeValue, Constraints, (), ()>, R>(reader, codec, name).map(GraphState::from),
+        5 => decode::<State<AttributeValue, Constraints, Endpoints, ()>, R>(reader, codec, name).map(GraphState::from),
+        6 => decode::<State<AttributeValue, Constraints, Endpoints, Defaults>, R>(reader, codec, name).map(GraphState::from),
+        _ => decode(reader, codec, name),
+    }?;
+    categories::encode_all(&mut state.graph);
+    Ok(state)
+}
+
+fn decode<T: DeserializeOwned, R: BufRead>(reader: R, codec: Codec, name: &str) -> PyResult<T> {
+    let read_error = |e: bincode::Error| PyValueError::new_err(format!("Cannot read graph from '{}': {}", name, e));
+    match codec {
+        Codec::None => bincode::deserialize_from(reader).map_err(read_error),
+        Codec::Zstd => {
+            let decoder = zstd::Decoder::with_buffer(reader).map_err(|e| PyIOError::new_err(format!("Cannot read '{}': {}", name, e)))?;
+            bincode::deserialize_from(BufReader::with_capacity(CHUNK_SIZE, decoder)).map_err(read_error)
+        },
+        Codec::Lz4 => bincode::deserialize_from(BufReader::with_capacity(CHUNK_SIZE, lz4_flex::frame::FrameDecoder::new(reader))).map_err(read_error),
+    }
+}
+
+/// Reads a bare graph as written by `save_to_file`. These files carry no version, so the current
+/// layout is tried first, then the layouts without key columns, without defaults, without
+/// endpoints, without constraints, with 32-bit Int, without calculated-property flags and without
+/// ingestion sequence, which is the layout of the first releases; trailing bytes rule a layout out.
+pub fn read_unversioned(bytes: &[u8]) -> Result<DiGraph<Node, Relation>, bincode::Error> {
+    use legacy::{Calculated, Constraints, Defaults, Endpoints, Sequence, Value};
+    let graph: DiGraph<Node, Relation> = unversioned_options().deserialize(bytes).or_else(|error| {
+        read_legacy::<AttributeValue, Constraints, Endpoints, Defaults, Calculated, Sequence>(bytes)
+            .or_else(|_| read_legacy::<AttributeValue, Constraints, Endpoints, (), Calculated, Sequence>(bytes))
+            .or_else(|_| read_legacy::<AttributeValue, Constraints, (), (), Calculated, Sequence>(bytes))
+            .or_else(|_| read_legacy::<AttributeValue, (), (), (), Calculated, Sequence>(bytes))
+            .or_else(|_| read_legacy::<Value, (), (), (), Calculated, Sequence>(bytes))
+            .or_else(|_| read_legacy::<Value, (), (), (), (), Sequence>(bytes))
+            .or_else(|_| read_legacy::<Value, (), (), (), (), ()>(bytes))
+            .map_err(|_| error)
+    })?;
+    let mut graph = JournaledGraph::new(graph);
+    categories::encode_all(&mut graph);
+    Ok(graph.into_inner())
+}
+
+fn unversioned_options() -> impl bincode::Options {
+    bincode::DefaultOptions::new().with_fixint_encoding().reject_trailing_bytes()
+}
+
+fn read_legacy<V, C, E, D, K, S>(bytes: &[u8]) -> Result<DiGraph<Node, Relation>, bincode::Error>
+where
+    V: DeserializeOwned + Into<AttributeValue>,
+    C: legacy::StoredConstraints,
+    E: legacy::StoredEndpoints,
+    D: legacy::StoredDefaults,
+    K: legacy::StoredCalculated,
+    S: legacy::StoredSequence,
+{
+    unversioned_options().deserialize::<DiGraph<legacy::Node<V, C, E, D, K, S>, legacy::Relation<V>>>(bytes).map(legacy::convert_graph)
+}
+
+// Layouts of format versions 1 to 6: schema nodes without key columns, with attribute values `V`,
+// schema constraints `C`, relation endpoints `E`, property defaults `D` and calculated-property
+// flags `K`; versions 1 to 5 stored no defaults, versions 1 to 4 no endpoints and versions 1 to 3
+// no constraints (`()`, which takes no bytes), and versions 1 and 2 stored Int as 32-bit (`Value`). Bare graphs saved before calculated properties were
+// flagged have no `K` either, and those saved before ingestion order was tracked no sequence `S`.
+mod legacy {
+    use petgraph::graph::DiGraph;
+    use serde::Deserialize;
+    use std::collections::{HashMap, HashSet};
+    use serde::de::DeserializeOwned;
+    use crate::data_types::AttributeValue;
+    use crate::schema::{self, Constraint};
+    use super::GraphState;
+
+    pub type Constraints = HashMap<String, Vec<Constraint>>;
+
+    pub trait StoredConstraints: DeserializeOwned {
+        fn into_constraints(self) -> Constraints;
+    }
+
+    impl StoredConstraints for () {
+        fn into_constraints(self) -> Constraints {
+            HashMap::new()
+        }
+    }
+
+    impl StoredConstraints for Constraints {
+        fn into_constraints(self) -> Constraints {
+            self
+        }
+    }
+
+    pub type Endpoints = Option<schema::Endpoints>;
+
+    pub trait StoredEndpoints: DeserializeOwned {
+        fn into_endpoints(self) -> Endpoints;
+    }
+
+    impl StoredEndpoints for () {
+        fn into_endpoints(self) -> Endpoints {
+            None
+        }
+    }
+
+    impl StoredEndpoints for Endpoints {
+        fn into_endpoints(self) -> Endpoints {
+            self
+        }
+    }
+
+    pub type Defaults = HashMap<String, AttributeValue>;
+
+    pub trait StoredDefaults: DeserializeOwned {
+        fn into_defaults(self) -> Defaults;
+    }
+
+    impl StoredDefaults for () {
+        fn into_defaults(self) -> Defaults {
+            HashMap::new()
+        }
+    }
+
+    impl StoredDefaults for Defaults {
+        fn into_defaults(self) -> Defaults {
+            self
+        }
+    }
+
+    pub type Calculated = HashSet<String>;
+
+    pub trait StoredCalculated: DeserializeOwned {
+        fn into_calculated(self) -> Calculated;
+    }
+
+    impl StoredCalculated for () {
+        fn into_calculated(self) -> Calculated {
+            HashSet::new()
+        }
+    }
+
+    impl StoredCalculated for Calculated {
+        fn into_calculated(self) -> Calculated {
+            self
+        }
+    }
+
+    pub type Sequence = Option<u64>;
+
+    pub trait StoredSequence: DeserializeOwned {
+        fn into_sequence(self) -> Sequence;
+    }
+
+    impl StoredSequence for () {
+        fn into_sequence(self) -> Sequence {
+            None
+        }
+    }
+
+    impl StoredSequence for Sequence {
+        fn into_sequence(self) -> Sequence {
+            self
+        }
+    }
+
+    #[derive(Deserialize)]
+    pub enum Value {
+        Int(i32),
+        Float(f64),
+        DateTime(i64),
+        String(String),
+        Boolean(bool),
+        Null,
+        List(Vec<Value>),
+        Map(HashMap<String, Value>),
+    }
+
+    #[derive(Deserialize)]
+    #[serde(bound = "V: DeserializeOwned, C: StoredConstraints, E: StoredEndpoints, D: StoredDefaults, K: StoredCalculated, S: StoredSequence")]
+    pub enum Node<V, C, E, D, K, S> {
+        StandardNode {
+            node_type: String,
+            unique_id: String,
+            attributes: HashMap<String, V>,
+            title: Option<String>,
+            sequence: S,
+        },
+        DataTypeNode {
+            data_type: String,
+            name: String,
+            attributes: HashMap<String, String>,
+            calculated: K,
+            constraints: C,
+            endpoints: E,
+            defaults: D,
+        },
+    }
+
+    #[derive(Deserialize)]
+    #[serde(bound = "V: DeserializeOwned")]
+    pub struct Relation<V> {
+        relation_type: String,
+        attributes: Option<HashMap<String, V>>,
+    }
+
+    pub type StoredGraph<V, C, E, D, K, S> = DiGraph<Node<V, C, E, D, K, S>, Relation<V>>;
+
+    #[derive(Deserialize)]
+    #[serde(bound = "V: DeserializeOwned, C: StoredConstraints, E: StoredEndpoints, D: StoredDefaults")]
+    pub struct State<V, C, E, D> {
+        graph: StoredGraph<V, C, E, D, Calculated, Sequence>,
+        track_insertion_order: bool,
+        next_sequence: u64,
+        selections: HashMap<String, Vec<usize>>,
+    }
+
+    impl From<Value> for AttributeValue {
+        fn from(value: Value) -> Self {
+            match value {
+                Value::Int(v) => AttributeValue::Int(v as i64),
+                Value::Float(v) => AttributeValue::Float(v),
+                Value::DateTime(v) => AttributeValue::DateTime(v),
+                Value::String(v) => AttributeValue::String(v),
+                Value::Boolean(v) => AttributeValue::Boolean(v),
+                Value::Null => AttributeValue::Null,
+                Value::List(values) => AttributeValue::List(values.into_iter().map(AttributeValue::from).collect()),
+                Value::Map(entries) => AttributeValue::Map(convert_attributes(entries)),
+            }
+        }
+    }
+
+    fn convert_attributes<V: Into<AttributeValue>>(attributes: HashMap<String, V>) -> HashMap<String, AttributeValue> {
+        attributes.into_iter().map(|(key, value)| (key, value.into())).collect()
+    }
+
+    // Rebuilds the graph in index order, so node and edge indices stay the same
+    pub fn convert_graph<V, C, E, D, K, S>(graph: StoredGraph<V, C, E, D, K, S>) -> DiGraph<schema::Node, schema::Relation>
+    where
+        V: Into<AttributeValue>,
+        C: StoredConstraints,
+        E: StoredEndpoints,
+        D: StoredDefaults,
+        K: StoredCalculated,
+        S: StoredSequence,
+    {
+        let (nodes, edges) = graph.into_nodes_edges();
+        let mut converted = DiGraph::with_capacity(nodes.len(), edges.len());
+        for node in nodes {
+            converted.add_node(match node.weight {
+                Node::StandardNode { node_type, unique_id, attributes, title, sequence } => schema::Node::StandardNode {
+                    node_type: node_type.into(), unique_id, attributes: convert_attributes(attributes), title, sequence: sequence.into_sequence(),
+                },
+                Node::DataTypeNode { data_type, name, attributes, calculated, constraints, endpoints, defaults } => schema::Node::DataTypeNode {
+                    data_type, name, attributes, calculated: calculated.into_calculated(), constraints: constraints.into_constraints(),
+                    endpoints: endpoints.into_endpoints(), defaults: defaults.into_defaults(), keys: schema::KeyColumns::default(),
+                    categories: HashMap::new(),
+                },
+            });
+        }
+        for edge in edges {
+            converted.add_edge(edge.source(), edge.target(), schema::Relation {
+                relation_type: edge.weight.relation_type.into(),
+                attributes: edge.weight.attributes.map(convert_attributes),
+            });
+        }
+        converted
+    }
+
+    impl<V: Into<AttributeValue>, C: StoredConstraints, E: StoredEndpoints, D: StoredDefaults> From<State<V, C, E, D>> for GraphState {
+        fn from(state: State<V, C, E, D>) -> Self {
+            GraphState {
+                graph: convert_graph(state.graph).into(),
+                track_insertion_order: state.track_insertion_order,
+                next_sequence: state.next_sequence,
+                selections: state.selections,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // `test_file` was written by the first release's `save_to_file`
+    #[test]
+    fn reads_graphs_saved_by_the_first_release() {
+        let graph = read_unversioned(include_bytes!("../../test_file")).unwrap();
+        let school = graph.node_indices()
+            .find(|&index| matches!(&graph[index], Node::StandardNode { title: Some(title), .. } if title == "Skole A"))
+            .unwrap();
+        assert_eq!(graph.neighbors_directed(school, petgraph::Direction::Incoming).count(), 3);
+        let students: Vec<_> = graph.node_weights()
+            .filter_map(|node| node.get_field("elever").map(|value| value.into_owned()))
+            .collect();
+        assert_eq!(students.len(), 3);
+        assert!(students.iter().all(|value| matches!(value, AttributeValue::Int(_))));
+        assert!(graph.node_weights().any(|node| matches!(node, Node::DataTypeNode { name, calculated, .. } if name == "Klasse" && calculated.is_empty())));
+        assert!(graph.node_weights().all(|node| node.sequence().is_none()));
+    }
+
+    #[test]
+    fn round_trips_the_current_layout() {
+        let mut graph = read_unversioned(include_bytes!("../../test_file")).unwrap();
+        let node = graph.node_indices().find(|&index| matches!(graph[index], Node::StandardNode { .. })).unwrap();
+        graph[node].set_sequence(Some(7));
+        let bytes = bincode::serialize(&graph).unwrap();
+        let read = read_unversioned(&bytes).unwrap();
+        assert_eq!(read.node_count(), graph.node_count());
+        assert_eq!(read.edge_count(), graph.edge_count());
+        assert_eq!(read[node].sequence(), Some(7));
+    }
+}
@@ -0,0 +1,639 @@
+use pyo3::prelude::*;
+use pyo3::exceptions::PyValueError;
+use pyo3::types::{PyDict, PyList};
+use petgraph::graph::{DiGraph, NodeIndex};
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use crate::graph::calculations::{aggregate_placeholder, aggregate_values, extract_aggregates, parse_expression, value_to_py};
+use crate::graph::equation_parser::{BinaryOp, Evaluator, Expr, Value};
+use crate::graph::filters::{Condition, Filter, FilterOptions};
+use crate::graph::pattern::{match_pattern, Pattern, PatternEdge, PatternNode};
+use crate::schema::{Node, Relation};
+use crate::data_types::AttributeValue;
+
+/// A parsed query in the supported Cypher subset:
+///
+/// `MATCH <pattern> [WHERE <predicate>] RETURN <items> [ORDER BY <columns>] [LIMIT <n>]`
+///
+/// Patterns are comma-separated chains of `(alias:Type {prop: value})` nodes joined by directed
+/// `-[:TYPE]->` / `<-[:TYPE]-` relations. WHERE and RETURN use the calculation expression syntax,
+/// with `alias.property` variables and `count`, `sum`, `avg`, `min` and `max` aggregates in RETURN.
+struct Query {
+    pattern: Pattern,
+    predicate: Option<Expr>,
+    items: Vec<ReturnItem>,
+    order_by: Vec<(usize, bool)>,  // (column, descending)
+    limit: Option<usize>,
+}
+
+struct ReturnItem {
+    name: String,
+    expr: Expr,
+    aggregates: Vec<(String, Option<Expr>)>,
+    node: bool,  // A bare alias, returned as the node index
+}
+
+/// Runs a query, returning one dictionary per result row keyed by the RETURN column names.
+///
+/// Simple `alias.property <op> literal` conditions joined by AND in WHERE are pushed down into the
+/// pattern as node filters; the rest of the predicate is evaluated per match. RETURN items without
+/// aggregates group the rows when any item aggregates.
+pub fn run_query(graph: &DiGraph<Node, Relation>, py: Python, text: &str, as_of: Option<&AttributeValue>) -> PyResult<PyObject> {
+    let mut query = Query::parse(text)?;
+    if let Some(at) = as_of {
+        query.pattern.valid_at(at);
+    }
+    let aliases: HashMap<String, usize> = query.pattern.nodes.iter().enumerate()
+        .map(|(i, node)| (node.alias.clone(), i))
+        .collect();
+    let aggregating = query.items.iter().any(|item| !item.aggregates.is_empty());
+    let error = |clause: &str, e: String| PyValueError::new_err(format!("Failed to evaluate {} clause: {}", clause, e));
+
+    // Without later filtering, grouping or sorting, matching can stop at the limit
+    let max_matches = match (&query.predicate, aggregating, query.order_by.is_empty()) {
+        (None, false, true) => query.limit.unwrap_or(usize::MAX),
+        _ => usize::MAX,
+    };
+    let mut bindings = match_pattern(graph, &query.pattern, max_matches);
+    if let Some(predicate) = &query.predicate {
+        let mut kept = Vec::with_capacity(bindings.len());
+        for binding in bindings {
+            let evaluator = Evaluator::new(|field: &str| resolve(graph, &aliases, &binding, field));
+            if evaluator.evaluate(predicate).map_err(|e| error("WHERE", e))?.is_true() {
+                kept.push(binding);
+            }
+        }
+        bindings = kept;
+    }
+
+    let mut rows: Vec<Vec<Value>> = Vec::new();
+    if !aggregating {
+        for binding in &bindings {
+            let row = query.items.iter()
+                .map(|item| evaluate_item(graph, &aliases, item, Some(binding), &HashMap::new()))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| error("RETURN", e))?;
+            rows.push(row);
+        }
+    } else {
+        for group in group_bindings(graph, &aliases, &query.items, &bindings).map_err(|e| error("RETURN", e))? {
+            let first = group.first().copied();
+            let mut row = Vec::with_capacity(query.items.len());
+            for item in &query.items {
+                let mut values = HashMap::new();
+                for (i, (function, argument)) in item.aggregates.iter().enumerate() {
+                    let value = match argument {
+                        None if function == "count" => Value::Number(group.len() as f64),
+                        None => return Err(error("RETURN", format!("{}(*) is not supported, only count(*)", function))),
+                        Some(argument) => {
+                            let inputs = group.iter()
+                                .map(|binding| Evaluator::new(|field: &str| resolve(graph, &aliases, binding, field)).evaluate(argument))
+                                .collect::<Result<Vec<_>, _>>()
+                                .map_err(|e| error("RETURN", e))?;
+                            aggregate_values(function, inputs).map_err(|e| error("RETURN", e))?
+                        },
+                    };
+                    if let Some(value) = value.into_attribute() {
+                        values.insert(aggregate_placeholder(i), value);
+                    }
+                }
+                row.push(evaluate_item(graph, &aliases, item, first, &values).map_err(|e| error("RETURN", e))?);
+            }
+            rows.push(row);
+        }
+    }
+
+    if !query.order_by.is_empty() {
+        rows.sort_by(|a, b| {
+            query.order_by.iter().fold(Ordering::Equal, |ordering, &(column, descending)| {
+                ordering.then_with(|| {
+                    let ordering = order_values(&a[column], &b[column]);
+                    if descending { ordering.reverse() } else { ordering }
+                })
+            })
+        });
+    }
+    if let Some(limit) = query.limit {
+        rows.truncate(limit);
+    }
+
+    let result = PyList::empty(py);
+    for row in rows {
+        let dict = PyDict::new(py);
+        for (item, value) in query.items.iter().zip(row) {
+            match value {
+                Value::Number(index) if item.node => dict.set_item(&item.name, index as usize)?,
+                value => dict.set_item(&item.name, value_to_py(py, &value))?,
+            }
+        }
+        result.append(dict)?;
+    }
+    Ok(result.into())
+}
+
+// `alias.property` resolves to a property of the bound node, a bare alias to its unique ID
+fn resolve<'a>(
+    graph: &'a DiGraph<Node, Relation>,
+    aliases: &HashMap<String, usize>,
+    binding: &[usize],
+    field: &str,
+) -> Option<Cow<'a, AttributeValue>> {
+    let (alias, property) = field.split_once('.').unwrap_or((field, "unique_id"));
+    graph[NodeIndex::new(binding[*aliases.get(alias)?])].get_field(property)
+}
+
+fn evaluate_item(
+    graph: &DiGraph<Node, Relation>,
+    aliases: &HashMap<String, usize>,
+    item: &ReturnItem,
+    binding: Option<&Vec<usize>>,
+    aggregates: &HashMap<String, AttributeValue>,
+) -> Result<Value, String> {
+    if item.node {
+        let Expr::Variable(alias) = &item.expr else { unreachable!("node columns are bare aliases") };
+        return Ok(binding.map_or(Value::Null, |binding| Value::Number(binding[aliases[alias]] as f64)));
+    }
+    let evaluator = Evaluator::new(|field: &str| match aggregates.get(field) {
+        Some(value) => Some(Cow::Borrowed(value)),
+        None => binding.and_then(|binding| resolve(graph, aliases, binding, field)),
+    });
+    evaluator.evaluate(&item.expr)
+}
+
+// Groups the matches by the values of the non-aggregate items, in order of first appearance.
+// Without grouping items, all matches form one group, even when there are none.
+fn group_bindings<'b>(
+    graph: &DiGraph<Node, Relation>,
+    aliases: &HashMap<String, usize>,
+    items: &[ReturnItem],
+    bindings: &'b [Vec<usize>],
+) -> Result<Vec<Vec<&'b Vec<usize>>>, String> {
+    let keys: Vec<&ReturnItem> = items.iter().filter(|item| item.aggregates.is_empty()).collect();
+    if keys.is_empty() {
+        return Ok(vec![bindings.iter().collect()]);
+    }
+    let mut positions: HashMap<String, usize> = HashMap::new();
+    let mut groups: Vec<Vec<&Vec<usize>>> = Vec::new();
+    for binding in bindings {
+        let key = keys.iter()
+            .map(|item| evaluate_item(graph, aliases, item, Some(binding), &HashMap::new()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let position = *positions.entry(format!("{:?}", key)).or_insert_with(|| {
+            groups.push(Vec::new());
+            groups.len() - 1
+        });
+        groups[position].push(binding);
+    }
+    Ok(groups)
+}
+
+// Nulls sort last; values of different kinds order numbers, strings, booleans, lists, then maps
+fn order_values(a: &Value, b: &Value) -> Ordering {
+    let rank = |value: &Value| match value {
+        Value::Number(_) | Value::Decimal(_) | Value::Duration(_) => 0,
+        Value::Str(_) => 1,
+        Value::Bool(_) => 2,
+        Value::List(_) => 3,
+        Value::Map(_) => 4,
+        Value::Null => 5,
+    };
+    match (a, b) {
+        (Value::Number(x), Value::Number(y)) => x.total_cmp(y),
+        (Value::Decimal(x), Value::Decimal(y)) => x.partial_cmp(y).unwrap_or(Ordering::Equal),
+        (Value::Duration(x), Value::Duration(y)) => x.cmp(y),
+        (Value::Number(_) | Value::Decimal(_) | Value::Duration(_), Value::Number(_) | Value::Decimal(_) | Value::Duration(_)) => {
+            a.as_number().unwrap_or(f64::NAN).total_cmp(&b.as_number().unwrap_or(f64::NAN))
+        },
+        (Value::Str(x), Value::Str(y)) => x.cmp(y),
+        (Value::Bool(x), Value::Bool(y)) => x.cmp(y),
+        _ => rank(a).cmp(&rank(b)),
+    }
+}
+
+impl Query {
+    fn parse(text: &str) -> PyResult<Self> {
+        let clauses = split_clauses(text)?;
+        let clause = |keyword: &str| clauses.iter().find(|(k, _)| *k == keyword).map(|(_, body)| body.as_str());
+
+        let mut pattern = parse_pattern(clause("MATCH").unwrap_or_default())?;
+        let predicate = match clause("WHERE") {
+            Some(body) => push_down(parse_expression(&replace_unquoted(body, "<>", "!="))?, &mut pattern),
+            None => None,
+        };
+
+        let mut items = Vec::new();
+        for text in split_top_level(clause("RETURN").unwrap_or_default()) {
+            let (source, name) = match find_keyword(&text, "AS") {
+                Some(position) => (text[..position].trim().to_string(), text[position + 2..].trim().to_string()),
+                None => (text.clone(), text.clone()),
+            };
+            let mut aggregates = Vec::new();
+            let expr = extract_aggregates(parse_expression(&source)?, &mut aggregates);
+            let node = matches!(&expr, Expr::Variable(alias) if pattern.position(alias).is_ok());
+            items.push((source, ReturnItem { name, expr, aggregates, node }));
+        }
+        if items.is_empty() {
+            return Err(PyValueError::new_err("RETURN needs at least one item"));
+        }
+
+        let mut order_by = Vec::new();
+        for text in split_top_level(clause("ORDER BY").unwrap_or_default()) {
+            let (key, descending) = match text.rsplit_once(char::is_whitespace) {
+                Some((key, direction)) if direction.eq_ignore_ascii_case("DESC") => (key.trim(), true),
+                Some((key, direction)) if direction.eq_ignore_ascii_case("ASC") => (key.trim(), false),
+                _ => (text.as_str(), false),
+            };
+            let column = items.iter().position(|(source, item)| item.name == key || source == key)
+                .ok_or_else(|| PyValueError::new_err(format!("ORDER BY '{}' must name a RETURN column", key)))?;
+            order_by.push((column, descending));
+        }
+
+        let limit = clause("LIMIT")
+            .map(|body| body.trim().parse::<usize>()
+                .map_err(|_| PyValueError::new_err(format!("LIMIT expects a non-negative integer, got '{}'", body.trim()))))
+            .transpose()?;
+
+        Ok(Query { pattern, predicate, items: items.into_iter().map(|(_, item)| item).collect(), order_by, limit })
+    }
+}
+
+const KEYWORDS: [&str; 5] = ["MATCH", "WHERE", "RETURN", "ORDER BY", "LIMIT"];
+
+// Splits the query into (keyword, body) clauses, checking they appear once each and in order
+fn split_clauses(text: &str) -> PyResult<Vec<(&'static str, String)>> {
+    let mut found: Vec<(&'static str, usize, usize)> = Vec::new();  // (keyword, start, body start)
+    let mut quote = None;
+    for (i, c) in text.char_indices() {
+        if let Some(q) = quote {
+            if c == q {
+                quote = None;
+            }
+            continue;
+        }
+        if c == '\'' || c == '"' {
+            quote = Some(c);
+            continue;
+        }
+        for keyword in KEYWORDS {
+            if let Some(length) = keyword_at(text, i, keyword) {
+                found.push((keyword, i, i + length));
+            }
+        }
+    }
+
+    if found.first().is_none_or(|(keyword, start, _)| *keyword != "MATCH" || !text[..*start].trim().is_empty()) {
+        return Err(PyValueError::new_err("A query must start with MATCH"));
+    }
+    let order = |keyword: &str| KEYWORDS.iter().position(|k| *k == keyword).unwrap_or(0);
+    if found.windows(2).any(|pair| order(pair[0].0) >= order(pair[1].0)) {
+        return Err(PyValueError::new_err(
+            "Clauses must appear once each, in the order MATCH, WHERE, RETURN, ORDER BY, LIMIT"
+        ));
+    }
+    if !found.iter().any(|(keyword, _, _)| *keyword == "RETURN") {
+        return Err(PyValueError::new_err("A query needs a RETURN clause"));
+    }
+
+    Ok(found.iter().enumerate().map(|(i, &(keyword, _, body_start))| {
+        let end = found.get(i + 1).map_or(text.len(), |next| next.1);
+        (keyword, text[body_start..end].trim().to_string())
+    }).collect())
+}
+
+// Length of `keyword` when it appears as whole words at byte `position`, ignoring case and
+// allowing any whitespace between the words of "ORDER BY"
+fn keyword_at(text: &str, position: usize, keyword: &str) -> Option<usize> {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_' || c == '.';
+    if text[..position].chars().next_back().is_some_and(is_word) {
+        return None;
+    }
+    let mut offset = position;
+    for (i, word) in keyword.split(' ').enumerate() {
+        if i > 0 {
+            let spaces = text[offset..].len() - text[offset..].trim_start().len();
+            if spaces == 0 {
+                return None;
+            }
+            offset += spaces;
+        }
+        let candidate = text.get(offset..offset + word.len())?;
+        if !candidate.eq_ignore_ascii_case(word) {
+            return None;
+        }
+        offset += word.len();
+    }
+    if text[offset..].chars().next().is_some_and(is_word) {
+        return None;
+    }
+    Some(offset - position)
+}
+
+// Byte position of a standalone keyword outside quotes and parentheses
+fn find_keyword(text: &str, keyword: &str) -> Option<usize> {
+    let (mut quote, mut depth) = (None, 0);
+    for (i, c) in text.char_indices() {
+        match (quote, c) {
+            (Some(q), _) if c == q => quote = None,
+            (Some(_), _) => (),
+            (None, '\'' | '"') => quote = Some(c),
+            (None, '(') => depth += 1,
+            (None, ')') => depth -= 1,
+            (None, _) if depth == 0 && keyword_at(text, i, keyword).is_some() => return Some(i),
+            _ => (),
+        }
+    }
+    None
+}
+
+// Splits on commas outside quotes and brackets
+fn split_top_level(text: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let (mut quote, mut depth, mut current) = (None, 0, String::new());
+    for c in text.chars() {
+        match (quote, c) {
+            (Some(q), _) if c == q => quote = None,
+            (Some(_), _) => (),
+            (None, '\'' | '"') => quote = Some(c),
+            (None, '(' | '[' | '{') => depth += 1,
+            (None, ')' | ']' | '}') => depth -= 1,
+            (None, ',') if depth == 0 => {
+                parts.push(current.trim().to_string());
+                current.clear();
+                continue;
+            },
+            _ => (),
+        }
+        current.push(c);
+    }
+    if !current.trim().is_empty() {
+        parts.push(current.trim().to_string());
+    }
+    parts
+}
+
+fn replace_unquoted(text: &str, from: &str, to: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut quote = None;
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if quote.is_none() && rest.starts_with(from) {
+            result.push_str(to);
+            rest = &rest[from.len()..];
+            continue;
+        }
+        match quote {
+            Some(q) if c == q => quote = None,
+            None if c == '\'' || c == '"' => quote = Some(c),
+            _ => (),
+        }
+        result.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    result
+}
+
+// Moves `alias.property <op> literal` conjuncts into the filters of the pattern nodes, returning
+// the remaining predicate
+fn push_down(predicate: Expr, pattern: &mut Pattern) -> Option<Expr> {
+    let mut conjuncts = Vec::new();
+    split_conjuncts(predicate, &mut conjuncts);
+    let mut remaining = Vec::new();
+    for conjunct in conjuncts {
+        match node_condition(&conjunct, pattern) {
+            Some((position, filter)) => add_filter(&mut pattern.nodes[position], filter),
+            None => remaining.push(conjunct),
+        }
+    }
+    remaining.into_iter().reduce(|lhs, rhs| Expr::Binary(BinaryOp::And, Box::new(lhs), Box::new(rhs)))
+}
+
+fn split_conjuncts(expr: Expr, conjuncts: &mut Vec<Expr>) {
+    match expr {
+        Expr::Binary(BinaryOp::And, lhs, rhs) => {
+            split_conjuncts(*lhs, conjuncts);
+            split_conjuncts(*rhs, conjuncts);
+        },
+        other => conjuncts.push(other),
+    }
+}
+
+fn node_condition(expr: &Expr, pattern: &Pattern) -> Option<(usize, Filter)> {
+    let Expr::Binary(op, lhs, rhs) = expr else { return None };
+    let (variable, literal, op) = match (lhs.as_ref(), rhs.as_ref()) {
+        (Expr::Variable(variable), Expr::Literal(literal)) => (variable, literal, *op),
+        (Expr::Literal(literal), Expr::Variable(variable)) => (variable, literal, flip(*op)?),
+        _ => return None,
+    };
+    let operator = match op {
+        BinaryOp::Equal => "==",
+        BinaryOp::NotEqual => "!=",
+        BinaryOp::Greater => ">",
+        BinaryOp::GreaterEqual => ">=",
+        BinaryOp::Less => "<",
+        BinaryOp::LessEqual => "<=",
+        _ => return None,
+    };
+    let operand = match literal {
+        Value::Number(v) => AttributeValue::Float(*v),
+        Value::Str(v) => AttributeValue::String(v.clone()),
+        _ => return None,
+    };
+    let (alias, property) = variable.split_once('.')?;
+    let position = pattern.position(alias).ok()?;
+    let options = FilterOptions::default();
+    let condition = Condition::comparison(operator, operand, options)?;
+    Some((position, Filter::Condition { field: property.to_string(), condition, options }))
+}
+
+// The operator with its operands swapped
+fn flip(op: BinaryOp) -> Option<BinaryOp> {
+    Some(match op {
+        BinaryOp::Greater => BinaryOp::Less,
+        BinaryOp::GreaterEqual => BinaryOp::LessEqual,
+        BinaryOp::Less => BinaryOp::Greater,
+        BinaryOp::LessEqual => BinaryOp::GreaterEqual,
+        BinaryOp::Equal | BinaryOp::NotEqual => op,
+        _ => return None,
+    })
+}
+
+fn add_filter(node: &mut PatternNode, filter: Filter) {
+    node.filter = Some(match node.filter.take() {
+        Some(existing) => Filter::And(vec![existing, filter]),
+        None => filter,
+    });
+}
+
+// Character cursor over the MATCH clause
+struct Cursor {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl Cursor {
+    fn skip_whitespace(&mut self) {
+        while self.chars.get(self.position).is_some_and(|c| c.is_whitespace()) {
+            self.position += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    // Consumes `token` (after any whitespace) when it comes next
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_whitespace();
+        let matches = token.chars().enumerate().all(|(i, c)| self.chars.get(self.position + i) == Some(&c));
+        if matches {
+            self.position += token.chars().count();
+        }
+        matches
+    }
+
+    fn expect(&mut self, token: &str) -> PyResult<()> {
+        if self.eat(token) {
+            return Ok(());
+        }
+        let found: String = self.chars[self.position..].iter().take(20).collect();
+        Err(PyValueError::new_err(format!("Expected '{}' in MATCH at '{}'", token, found)))
+    }
+
+    fn identifier(&mut self) -> Option<String> {
+        self.skip_whitespace();
+        if !self.peek().is_some_and(|c| c.is_alphabetic() || c == '_') {
+            return None;
+        }
+        let start = self.position;
+        while self.peek().is_some_and(|c| c.is_alphanumeric() || c == '_') {
+            self.position += 1;
+        }
+        Some(self.chars[start..self.position].iter().collect())
+    }
+
+    fn literal(&mut self) -> PyResult<AttributeValue> {
+        self.skip_whitespace();
+        if let Some(quote) = self.peek().filter(|&c| c == '\'' || c == '"') {
+            let start = self.position + 1;
+            let end = self.chars[start..].iter().position(|&c| c == quote)
+                .ok_or_else(|| PyValueError::new_err("Unterminated string in MATCH"))?;
+            self.position = start + end + 1;
+            return Ok(AttributeValue::String(self.chars[start..start + end].iter().collect()));
+        }
+        let start = self.position;
+        while self.peek().is_some_and(|c| c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E')) {
+            self.position += 1;
+        }
+        let text: String = self.chars[start..self.position].iter().collect();
+        text.parse::<f64>()
+            .map(AttributeValue::Float)
+            .map_err(|_| PyValueError::new_err(format!("Expected a string or number property value in MATCH, got '{}'", text)))
+    }
+}
+
+fn parse_pattern(text: &str) -> PyResult<Pattern> {
+    let mut cursor = Cursor { chars: text.chars().collect(), position: 0 };
+    let mut pattern = Pattern::default();
+    loop {
+        let mut previous = parse_node(&mut cursor, &mut pattern)?;
+        loop {
+            let incoming = if cursor.eat("<-") {
+                true
+            } else if cursor.eat("-") {
+                false
+            } else {
+                break;
+            };
+            let (relationship_type, filter) = if cursor.eat("[") {
+                // A relation variable is accepted but not bound
+                cursor.identifier();
+                let relationship_type = if cursor.eat(":") {
+                    Some(cursor.identifier().ok_or_else(|| PyValueError::new_err("Expected a relation type after ':' in MATCH"))?)
+                } else {
+                    None
+                };
+                let filter = parse_properties(&mut cursor)?;
+                cursor.expect("]")?;
+                (relationship_type, filter)
+            } else {
+                (None, None)
+            };
+            let outgoing = cursor.eat("->");
+            if !outgoing {
+                cursor.expect("-")?;
+            }
+            if incoming == outgoing {
+                return Err(PyValueError::new_err(
+                    "Relations in MATCH must have exactly one direction, e.g. -[:TYPE]-> or <-[:TYPE]-"
+                ));
+            }
+            let next = parse_node(&mut cursor, &mut pattern)?;
+            let (source, target) = if outgoing { (previous, next) } else { (next, previous) };
+            pattern.edges.push(PatternEdge { source, target, relationship_type, filter });
+            previous = next;
+        }
+        if cursor.eat(",") {
+            continue;
+        }
+        cursor.skip_whitespace();
+        if cursor.peek().is_some() {
+            let rest: String = cursor.chars[cursor.position..].iter().collect();
+            return Err(PyValueError::new_err(format!("Unexpected '{}' in MATCH", rest)));
+        }
+        return Ok(pattern);
+    }
+}
+
+// Parses `(alias:Type {prop: value})`, reusing the pattern node when the alias was seen before
+fn parse_node(cursor: &mut Cursor, pattern: &mut Pattern) -> PyResult<usize> {
+    cursor.expect("(")?;
+    let alias = cursor.identifier();
+    let node_type = if cursor.eat(":") {
+        Some(cursor.identifier().ok_or_else(|| PyValueError::new_err("Expected a node type after ':' in MATCH"))?)
+    } else {
+        None
+    };
+    let filter = parse_properties(cursor)?;
+    cursor.expect(")")?;
+
+    let existing = alias.as_deref().and_then(|alias| pattern.position(alias).ok());
+    let Some(position) = existing else {
+        let alias = alias.unwrap_or_else(|| format!("_{}", pattern.nodes.len()));
+        pattern.nodes.push(PatternNode { alias, node_type, filter });
+        return Ok(pattern.nodes.len() - 1);
+    };
+    let node = &mut pattern.nodes[position];
+    if let Some(node_type) = node_type {
+        if node.node_type.as_ref().is_some_and(|existing| *existing != node_type) {
+            return Err(PyValueError::new_err(format!("Conflicting types for '{}' in MATCH", node.alias)));
+        }
+        node.node_type = Some(node_type);
+    }
+    if let Some(filter) = filter {
+        add_filter(node, filter);
+    }
+    Ok(position)
+}
+
+// Parses an optional `{prop: value, ...}` map into equality conditions
+fn parse_properties(cursor: &mut Cursor) -> PyResult<Option<Filter>> {
+    if !cursor.eat("{") {
+        return Ok(None);
+    }
+    let options = FilterOptions::default();
+    let mut conditions = Vec::new();
+    while !cursor.eat("}") {
+        if !conditions.is_empty() {
+            cursor.expect(",")?;
+        }
+        let field = cursor.identifier().ok_or_else(|| PyValueError::new_err("Expected a property name in MATCH"))?;
+        cursor.expect(":")?;
+        let condition = match cursor.literal()? {
+            AttributeValue::String(value) => Condition::Equals(value),
+            value => Condition::comparison("==", value, options).expect("'==' is a comparison"),
+        };
+        conditions.push(Filter::Condition { field, condition, options });
+    }
+    Ok(Some(Filter::And(conditions)))
+}
@@ -0,0 +1,99 @@
+use pyo3::prelude::*;
+use pyo3::exceptions::PyValueError;
+use rand::prelude::*;
+use rand::rngs::StdRng;
+use petgraph::graph::{DiGraph, NodeIndex};
+use petgraph::Direction;
+use crate::graph::navigate_graph::related_nodes;
+use crate::schema::{Node, Relation};
+use crate::data_types::AttributeValue;
+
+// Seeded generator for reproducible samples, or an entropy-seeded one
+pub fn rng_from_seed(seed: Option<u64>) -> StdRng {
+    match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    }
+}
+
+/// Randomly picks `n` of the given nodes (all of them if fewer), keeping their original order
+pub fn sample(indices: Vec<usize>, n: usize, seed: Option<u64>) -> Vec<usize> {
+    if n >= indices.len() {
+        return indices;
+    }
+    let mut rng = rng_from_seed(seed);
+    let mut positions = rand::seq::index::sample(&mut rng, indices.len(), n).into_vec();
+    positions.sort_unstable();
+    positions.into_iter().map(|position| indices[position]).collect()
+}
+
+/// Randomly picks a fraction of the given nodes, rounded to the nearest whole node
+pub fn sample_frac(indices: Vec<usize>, frac: f64, seed: Option<u64>) -> PyResult<Vec<usize>> {
+    if !(0.0..=1.0).contains(&frac) {
+        return Err(PyValueError::new_err("frac must be between 0 and 1"));
+    }
+    let n = (indices.len() as f64 * frac).round() as usize;
+    Ok(sample(indices, n, seed))
+}
+
+/// Randomly samples up to `n` children reached from the given parents, returned as (parent, child) pairs.
+///
+/// With `per_parent` the sample is drawn within each parent group, otherwise across all children.
+/// With `weight_property` children are drawn with probability proportional to that attribute;
+/// children with a missing or non-positive weight are never drawn.
+#[allow(clippy::too_many_arguments)]
+pub fn children_sample(
+    graph: &DiGraph<Node, Relation>,
+    indices: Vec<usize>,
+    relationship_types: &[String],
+    directions: &[Direction],
+    n: usize,
+    per_parent: bool,
+    weight_property: Option<&str>,
+    seed: Option<u64>,
+) -> Vec<(usize, usize)> {
+    let mut rng = rng_from_seed(seed);
+    let groups: Vec<Vec<(usize, usize)>> = indices.into_iter().map(|parent| {
+        related_nodes(graph, NodeIndex::new(parent), relationship_types, directions, None, None, None)
+            .into_iter()
+            .map(|child| (parent, child))
+            .collect()
+    }).collect();
+
+    let mut draw = |pairs: Vec<(usize, usize)>| -> Vec<(usize, usize)> {
+        let picked: Vec<usize> = match weight_property {
+            None => {
+                let mut positions = rand::seq::index::sample(&mut rng, pairs.len(), n.min(pairs.len())).into_vec();
+                positions.sort_unstable();
+                positions
+            },
+            Some(property) => {
+                // Efraimidis-Spirakis: keep the n largest keys u^(1/w)
+                let mut keyed: Vec<(f64, usize)> = pairs.iter().enumerate().filter_map(|(position, (_, child))| {
+                    let weight = child_weight(graph, *child, property)?;
+                    Some((rng.gen::<f64>().powf(1.0 / weight), position))
+                }).collect();
+                keyed.sort_by(|a, b| b.0.total_cmp(&a.0));
+                let mut positions: Vec<usize> = keyed.into_iter().take(n).map(|(_, position)| position).collect();
+                positions.sort_unstable();
+                positions
+            },
+        };
+        picked.into_iter().map(|position| pairs[position]).collect()
+    };
+
+    if per_parent {
+        groups.into_iter().flat_map(&mut draw).collect()
+    } else {
+        draw(groups.into_iter().flatten().collect())
+    }
+}
+
+fn child_weight(graph: &DiGraph<Node, Relation>, child: usize, property: &str) -> Option<f64> {
+    let weight = match graph.node_weight(NodeIndex::new(child))?.get_field(property)?.as_ref() {
+        AttributeValue::Int(v) => *v as f64,
+        AttributeValue::Float(v) => *v,
+        _ => return None,
+    };
+    (weight > 0.0).then_some(weight)
+}
@@ -0,0 +1,109 @@
+use pyo3::prelude::*;
+use petgraph::graph::{DiGraph, NodeIndex};
+use petgraph::visit::EdgeRef;
+use std::collections::{HashMap, HashSet};
+use crate::graph::journal::JournaledGraph;
+use crate::graph::get_schema::update_or_retrieve_schema;
+use crate::schema::{Node, Relation};
+use crate::data_types::AttributeValue;
+
+/// Copies the selected nodes, the edges between them and the schema nodes describing them into a
+/// new graph. Node indices are renumbered in the copy, keeping the original order.
+pub fn extract_subgraph(
+    graph: &DiGraph<Node, Relation>,
+    indices: &[usize],
+    relationship_types: Option<&[String]>,
+) -> DiGraph<Node, Relation> {
+    let selected: HashSet<NodeIndex> = indices.iter()
+        .map(|&index| NodeIndex::new(index))
+        .filter(|&node| matches!(graph.node_weight(node), Some(Node::StandardNode { .. })))
+        .collect();
+    let keep_edge = |source: NodeIndex, target: NodeIndex, relation: &Relation| {
+        selected.contains(&source) && selected.contains(&target)
+            && relationship_types.is_none_or(|types| relation.relation_type.is_in(types))
+    };
+
+    let node_types: HashSet<&str> = selected.iter().filter_map(|&node| match &graph[node] {
+        Node::StandardNode { node_type, .. } => Some(node_type.as_str()),
+        Node::DataTypeNode { .. } => None,
+    }).collect();
+    let relation_types: HashSet<&str> = graph.edge_references()
+        .filter(|edge| keep_edge(edge.source(), edge.target(), edge.weight()))
+        .map(|edge| edge.weight().relation_type.as_str())
+        .collect();
+
+    graph.filter_map(
+        |index, node| {
+            let keep = match node {
+                Node::StandardNode { .. } => selected.contains(&index),
+                Node::DataTypeNode { data_type, name, .. } => match data_type.as_str() {
+                    "Node" => node_types.contains(name.as_str()),
+                    "Relation" => relation_types.contains(name.as_str()),
+                    _ => false,
+                },
+            };
+            keep.then(|| node.clone())
+        },
+        |edge, relation| {
+            let (source, target) = graph.edge_endpoints(edge)?;
+            keep_edge(source, target, relation).then(|| relation.clone())
+        },
+    )
+}
+
+/// Builds the condensation of a graph: one `component_type` node per component (with its `size`
+/// and comma-separated `members` unique IDs) and one relation per type between distinct components,
+/// weighted by the number of relations it stands for. With strongly connected components the
+/// result is a DAG.
+pub fn condense(
+    graph: &DiGraph<Node, Relation>,
+    components: &[Vec<usize>],
+    relationship_types: Option<&[String]>,
+    component_type: &str,
+) -> PyResult<JournaledGraph> {
+    let mut condensed = JournaledGraph::default();
+    let column_types = HashMap::from([
+        ("size".to_string(), "Int".to_string()),
+        ("members".to_string(), "String".to_string()),
+    ]);
+    update_or_retrieve_schema(&mut condensed, "Node", component_type, Some(column_types.keys().cloned().collect()), Some(column_types))?;
+
+    let mut component_of: HashMap<NodeIndex, NodeIndex> = HashMap::new();
+    for (id, members) in components.iter().enumerate() {
+        let unique_ids: Vec<&str> = members.iter().filter_map(|&index| match &graph[NodeIndex::new(index)] {
+            Node::StandardNode { unique_id, .. } => Some(unique_id.as_str()),
+            Node::DataTypeNode { .. } => None,
+        }).collect();
+        let attributes = HashMap::from([
+            ("size".to_string(), AttributeValue::Int(members.len() as i64)),
+            ("members".to_string(), AttributeValue::String(unique_ids.join(","))),
+        ]);
+        let node = condensed.add_node(Node::new(component_type, &id.to_string(), Some(attributes), None));
+        component_of.extend(members.iter().map(|&index| (NodeIndex::new(index), node)));
+    }
+
+    let mut weights: HashMap<(NodeIndex, NodeIndex, &str), i64> = HashMap::new();
+    for edge in graph.edge_references() {
+        let relation_type = edge.weight().relation_type.as_str();
+        if relationship_types.is_some_and(|types| !types.iter().any(|t| t == relation_type)) {
+            continue;
+        }
+        if let (Some(&source), Some(&target)) = (component_of.get(&edge.source()), component_of.get(&edge.target())) {
+            if source != target {
+                *weights.entry((source, target, relation_type)).or_insert(0) += 1;
+            }
+        }
+    }
+    let mut edges: Vec<_> = weights.into_iter().collect();
+    edges.sort_unstable();
+    let relation_types: HashSet<&str> = edges.iter().map(|((_, _, relation_type), _)| *relation_type).collect();
+    for relation_type in relation_types {
+        let column_types = HashMap::from([("weight".to_string(), "Int".to_string())]);
+        update_or_retrieve_schema(&mut condensed, "Relation", relation_type, Some(vec!["weight".to_string()]), Some(column_types))?;
+    }
+    for ((source, target, relation_type), weight) in edges {
+        let attributes = HashMap::from([("weight".to_string(), AttributeValue::Int(weight))]);
+        condensed.add_edge(source, target, Relation::new(relation_type, Some(attributes)));
+    }
+    Ok(condensed)
+}
@@ -0,0 +1,130 @@
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use pyo3::exceptions::PyValueError;
+use petgraph::graph::NodeIndex;
+use rand::prelude::*;
+use rand::rngs::StdRng;
+use std::collections::HashMap;
+use crate::graph::journal::JournaledGraph;
+use crate::graph::get_schema::update_or_retrieve_schema;
+use crate::graph::sampling::rng_from_seed;
+use crate::schema::{Node, Relation};
+use crate::data_types::AttributeValue;
+
+/// Random distribution a synthetic property is drawn from
+enum Distribution {
+    Uniform { min: f64, max: f64 },
+    Normal { mean: f64, std: f64 },
+    Int { min: i64, max: i64 },
+    Choice(Vec<String>),
+}
+
+impl Distribution {
+    // Parses e.g. {"type": "normal", "mean": 2500.0, "std": 400.0} or {"type": "choice", "values": ["OIL", "GAS"]}
+    fn from_dict(property: &str, spec: &PyDict) -> PyResult<Self> {
+        let get = |key: &str| -> PyResult<&PyAny> {
+            spec.get_item(key).ok_or_else(|| PyValueError::new_err(format!(
+                "Distribution for '{}' is missing '{}'", property, key
+            )))
+        };
+        let kind: String = get("type")?.extract()?;
+        match kind.as_str() {
+            "uniform" => Ok(Distribution::Uniform { min: get("min")?.extract()?, max: get("max")?.extract()? }),
+            "normal" => Ok(Distribution::Normal { mean: get("mean")?.extract()?, std: get("std")?.extract()? }),
+            "int" => Ok(Distribution::Int { min: get("min")?.extract()?, max: get("max")?.extract()? }),
+            "choice" => Ok(Distribution::Choice(get("values")?.extract()?)),
+            _ => Err(PyValueError::new_err(format!(
+                "Unknown distribution '{}' for '{}', expected 'uniform', 'normal', 'int' or 'choice'", kind, property
+            ))),
+        }
+    }
+
+    fn data_type(&self) -> &'static str {
+        match self {
+            Distribution::Uniform { .. } | Distribution::Normal { .. } => "Float",
+            Distribution::Int { .. } => "Int",
+            Distribution::Choice(_) => "String",
+        }
+    }
+
+    fn sample(&self, rng: &mut StdRng) -> AttributeValue {
+        match self {
+            Distribution::Uniform { min, max } => AttributeValue::Float(min + (max - min) * rng.gen::<f64>()),
+            Distribution::Normal { mean, std } => {
+                // Box-Muller transform
+                let u1: f64 = 1.0 - rng.gen::<f64>();
+                let u2: f64 = rng.gen();
+                let z = (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos();
+                AttributeValue::Float(mean + std * z)
+            },
+            Distribution::Int { min, max } => AttributeValue::Int(rng.gen_range(*min..=*max)),
+            Distribution::Choice(values) => {
+                AttributeValue::String(values.choose(rng).cloned().unwrap_or_default())
+            },
+        }
+    }
+}
+
+/// Builds a random graph with the requested number of nodes per type.
+///
+/// # Arguments
+///
+/// * `node_types` - Node type to node count, e.g. {"Field": 100, "Well": 5000}
+/// * `connections` - (source_type, relation_type, target_type) triples; every target node
+///   is connected from one randomly chosen source node
+/// * `property_distributions` - Node type to {property: distribution spec}
+/// * `seed` - Optional seed for reproducible graphs
+pub fn generate_synthetic(
+    graph: &mut JournaledGraph,
+    node_types: HashMap<String, usize>,
+    connections: Option<Vec<(String, String, String)>>,
+    property_distributions: Option<HashMap<String, HashMap<String, &PyDict>>>,
+    seed: Option<u64>,
+) -> PyResult<()> {
+    let mut rng = rng_from_seed(seed);
+    let property_distributions = property_distributions.unwrap_or_default();
+
+    // Generate node types in a stable order so a seed always produces the same graph
+    let mut node_types: Vec<(String, usize)> = node_types.into_iter().collect();
+    node_types.sort();
+
+    let mut nodes_by_type: HashMap<String, Vec<NodeIndex>> = HashMap::new();
+    for (node_type, count) in node_types {
+        let mut distributions = Vec::new();
+        if let Some(specs) = property_distributions.get(&node_type) {
+            for (property, spec) in specs {
+                distributions.push((property.clone(), Distribution::from_dict(property, spec)?));
+            }
+        }
+        distributions.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let columns: Vec<String> = distributions.iter().map(|(property, _)| property.clone()).collect();
+        let column_types: HashMap<String, String> = distributions.iter()
+            .map(|(property, distribution)| (property.clone(), distribution.data_type().to_string()))
+            .collect();
+        update_or_retrieve_schema(graph, "Node", &node_type, Some(columns), Some(column_types))?;
+
+        let indices = nodes_by_type.entry(node_type.clone()).or_default();
+        for i in 1..=count {
+            let attributes = distributions.iter()
+                .map(|(property, distribution)| (property.clone(), distribution.sample(&mut rng)))
+                .collect();
+            let title = format!("{} {}", node_type, i);
+            let node = Node::new(&node_type, &i.to_string(), Some(attributes), Some(&title));
+            indices.push(graph.add_node(node));
+        }
+    }
+
+    for (source_type, relation_type, target_type) in connections.unwrap_or_default() {
+        let sources = nodes_by_type.get(&source_type).filter(|nodes| !nodes.is_empty()).ok_or_else(|| {
+            PyValueError::new_err(format!("No nodes of source type '{}' to connect from", source_type))
+        })?;
+        let targets = nodes_by_type.get(&target_type).cloned().unwrap_or_default();
+        for target in targets {
+            let source = sources[rng.gen_range(0..sources.len())];
+            graph.add_edge(source, target, Relation::new(&relation_type, None));
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,131 @@
+// pyo3 expands `__getattr__` into an impl inside the method, which rustc flags
+#![allow(non_local_definitions)]
+use pyo3::prelude::*;
+use pyo3::exceptions::PyAttributeError;
+use pyo3::types::{PyDict, PyTuple};
+use std::collections::HashMap;
+use crate::graph::KnowledgeGraph;
+
+/// A point the graph can be put back to, with its saved selections and merge log, as by `restore`
+/// or a transaction whose staged calls fail. The graph itself is not copied: the position in its event
+/// log is kept, and the events since are undone.
+#[derive(Clone)]
+pub struct SavedState {
+    pub position: usize,
+    next_sequence: u64,
+    selections: HashMap<String, Vec<usize>>,
+    merge_log: Vec<(String, String, String)>,
+}
+
+impl SavedState {
+    /// Saves the current state; the graph must be logging its events, and keep them until the
+    /// state is restored or dropped
+    pub fn capture(kg: &KnowledgeGraph) -> Self {
+        SavedState {
+            position: kg.graph.position(),
+            next_sequence: kg.next_sequence,
+            selections: kg.selections.clone(),
+            merge_log: kg.merge_log.clone(),
+        }
+    }
+
+    /// Puts the graph back, logging the changes undone as `operation` when the change log is on.
+    /// Checkpoints taken after this state are dropped, as the events they would undo are gone.
+    pub fn restore(self, kg: &mut KnowledgeGraph, operation: &str) {
+        if let Some(log) = &mut kg.change_log {
+            log.record(operation, kg.graph.events_since(log.recorded), &kg.graph);
+            log.record_undo(operation, kg.graph.events_since(self.position), &kg.graph);
+        }
+        kg.graph.undo_to(self.position);
+        if let Some(log) = &mut kg.change_log {
+            log.recorded = kg.graph.position();
+        }
+        kg.next_sequence = self.next_sequence;
+        kg.selections = self.selections;
+        kg.merge_log = self.merge_log;
+        kg.checkpoints.retain(|_, state| state.position <= self.position);
+        kg.sync_journal();
+    }
+}
+
+/// Context manager making a batch of changes all-or-nothing. Graph methods called on it inside the
+/// `with` block are staged rather than run; when the block ends they are run in order, and if one
+/// raises, the changes of those before it are undone and the error is raised. If the block raises,
+/// or `rollback` is called, the staged calls are dropped and the graph is left untouched.
+#[pyclass]
+pub struct Transaction {
+    graph: Py<KnowledgeGraph>,
+    staged: Vec<(String, Py<PyTuple>, Option<Py<PyDict>>)>,  // Method name, positional and keyword arguments
+}
+
+impl Transaction {
+    pub fn new(graph: Py<KnowledgeGraph>) -> Self {
+        Transaction { graph, staged: Vec::new() }
+    }
+
+    // Runs the staged calls, undoing them all if one raises
+    fn apply(&mut self, py: Python) -> PyResult<()> {
+        let staged = std::mem::take(&mut self.staged);
+        let saved = {
+            let mut kg = self.graph.borrow_mut(py);
+            kg.open_transactions += 1;
+            kg.sync_journal();
+            SavedState::capture(&kg)
+        };
+        let graph = self.graph.as_ref(py);
+        let result = staged.iter().try_for_each(|(name, args, kwargs)| {
+            graph.call_method(name.as_str(), args.as_ref(py), kwargs.as_ref().map(|kwargs| kwargs.as_ref(py))).map(drop)
+        });
+        let mut kg = self.graph.borrow_mut(py);
+        kg.open_transactions -= 1;
+        match result {
+            Ok(()) => kg.sync_journal(),
+            Err(_) => saved.restore(&mut kg, "rollback"),
+        }
+        result
+    }
+}
+
+#[pymethods]
+impl Transaction {
+    fn __enter__(slf: PyRef<Self>) -> PyRef<Self> {
+        slf
+    }
+
+    fn __exit__(&mut self, py: Python, exc_type: &PyAny, _exc_value: &PyAny, _traceback: &PyAny) -> PyResult<bool> {
+        if exc_type.is_none() {
+            self.apply(py)?;
+        } else {
+            self.staged.clear();
+        }
+        Ok(false)
+    }
+
+    // Any graph method can be staged, e.g. `tx.add_nodes(...)`; staged calls return None
+    fn __getattr__(slf: PyRef<Self>, name: &str) -> PyResult<StagedCall> {
+        if !slf.graph.as_ref(slf.py()).getattr(name)?.is_callable() {
+            return Err(PyAttributeError::new_err(format!("'{}' is not a graph method and cannot be staged", name)));
+        }
+        Ok(StagedCall { transaction: slf.into(), name: name.to_string() })
+    }
+
+    // Drop the calls staged so far; calls staged after this still run when the block ends
+    fn rollback(&mut self) {
+        self.staged.clear();
+    }
+}
+
+/// A graph method looked up on a transaction; calling it stages the call
+#[pyclass]
+pub struct StagedCall {
+    transaction: Py<Transaction>,
+    name: String,
+}
+
+#[pymethods]
+impl StagedCall {
+    #[pyo3(signature = (*args, **kwargs))]
+    fn __call__(&self, py: Python, args: &PyTuple, kwargs: Option<&PyDict>) {
+        self.transaction.borrow_mut(py).staged.push((self.name.clone(), args.into(), kwargs.map(Into::into)));
+    }
+}
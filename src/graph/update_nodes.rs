@@ -0,0 +1,119 @@
+use pyo3::prelude::*;
+use pyo3::exceptions::{PyValueError, PyUserWarning};
+use petgraph::graph::NodeIndex;
+use std::collections::{HashMap, HashSet};
+use crate::graph::journal::JournaledGraph;
+use crate::graph::constraints;
+use crate::graph::get_schema::find_schema_node;
+use crate::schema::{Node, Symbol};
+use crate::data_types::{AttributeValue, Decimal};
+
+/// Stores calculated values on nodes under `property` and flags the property as calculated in the schema.
+///
+/// # Arguments
+///
+/// * `graph` - The graph object containing all nodes and relations
+/// * `property` - The attribute name the values are stored under
+/// * `values` - Mapping of node index to the value to store
+/// * `overwrite` - What to do if `property` already exists as an ingested attribute:
+///   "error" (default), "warn" or "allow". Previously calculated properties are always overwritten.
+///
+/// Nothing is stored when a value breaks a constraint of its node type.
+pub fn update_node_properties(
+    graph: &mut JournaledGraph,
+    py: Python,
+    property: &str,
+    values: HashMap<usize, AttributeValue>,
+    overwrite: Option<String>,
+) -> PyResult<usize> {
+    let overwrite = overwrite.unwrap_or_else(|| "error".to_string());
+    if !["error", "warn", "allow"].contains(&overwrite.as_str()) {
+        return Err(PyValueError::new_err(format!(
+            "Invalid overwrite value '{}', expected 'error', 'warn' or 'allow'", overwrite
+        )));
+    }
+
+    let values = unify_value_types(property, values)?;
+    let data_type = match values.values().next() {
+        Some(value) => value.type_name().to_string(),
+        None => return Ok(0),
+    };
+
+    // Collect the node types affected by the update
+    let mut node_types = HashSet::new();
+    for index in values.keys() {
+        match graph.node_weight(NodeIndex::new(*index)) {
+            Some(Node::StandardNode { node_type, .. }) => { node_types.insert(node_type.clone()); },
+            _ => return Err(PyValueError::new_err(format!("Node index {} does not exist", index))),
+        }
+    }
+
+    // Guard ingested attributes against being clobbered, then flag the property as calculated. An
+    // ingested attribute overwritten with "warn" or "allow" stays ingested, so it stays guarded
+    let mut ingested = HashSet::new();
+    for node_type in &node_types {
+        let Some(schema_index) = find_schema_node(graph, "Node", node_type) else { continue };
+        if let Node::DataTypeNode { attributes, calculated, .. } = &graph[schema_index] {
+            if attributes.contains_key(property) && !calculated.contains(property) {
+                ingested.insert(node_type.clone());
+                let message = format!(
+                    "Property '{}' already exists as an ingested attribute on node type '{}'", property, node_type
+                );
+                match overwrite.as_str() {
+                    "error" => return Err(PyValueError::new_err(message)),
+                    "warn" => PyErr::warn(py, py.get_type::<PyUserWarning>(), &message, 1)?,
+                    _ => (),
+                }
+            }
+        }
+    }
+    let types: Vec<&str> = node_types.iter().map(Symbol::as_str).collect();
+    let snapshot = constraints::snapshot(graph, &types);
+    for node_type in &node_types {
+        let schema_index = match find_schema_node(graph, "Node", node_type) {
+            Some(index) => index,
+            None => graph.add_node(Node::new_data_type("Node", node_type, HashMap::new())),
+        };
+        if let Node::DataTypeNode { attributes, calculated, .. } = &mut graph[schema_index] {
+            attributes.insert(property.to_string(), data_type.clone());
+            if !ingested.contains(node_type) {
+                calculated.insert(property.to_string());
+            }
+        }
+    }
+
+    let updated: Vec<usize> = values.keys().copied().collect();
+    for (index, value) in values {
+        if let Node::StandardNode { attributes, .. } = &mut graph[NodeIndex::new(index)] {
+            attributes.insert(property.to_string(), value);
+        }
+    }
+    if let Some(snapshot) = snapshot {
+        constraints::enforce(graph, &updated, snapshot)?;
+    }
+    Ok(updated.len())
+}
+
+// Calculated values must share one schema type; mixed Int and Float values are widened to Float,
+// Int and Decimal to Decimal, and Decimal and Float to Float
+fn unify_value_types(
+    property: &str,
+    values: HashMap<usize, AttributeValue>,
+) -> PyResult<HashMap<usize, AttributeValue>> {
+    let types: HashSet<&str> = values.values().map(AttributeValue::type_name).collect();
+    if types.len() <= 1 {
+        return Ok(values);
+    }
+    if types.iter().all(|t| matches!(*t, "Int" | "Float" | "Decimal")) {
+        let to_float = types.contains("Float");
+        return Ok(values.into_iter().map(|(index, value)| match value {
+            AttributeValue::Int(v) if to_float => (index, AttributeValue::Float(v as f64)),
+            AttributeValue::Decimal(v) if to_float => (index, AttributeValue::Float(v.to_f64())),
+            AttributeValue::Int(v) => (index, AttributeValue::Decimal(Decimal::new(v as i128, 0))),
+            other => (index, other),
+        }).collect());
+    }
+    Err(PyValueError::new_err(format!(
+        "Values for property '{}' have mixed types: {:?}", property, types
+    )))
+}
@@ -4,10 +4,14 @@ mod schema;
 mod graph;
 mod data_types;
 
-use graph::KnowledgeGraph;
+use graph::{AsOf, KnowledgeGraph, NodePages, StagedCall, Transaction};
 
 #[pymodule]
 fn rusty_graph(_py: Python, m: &PyModule) -> PyResult<()> {
     m.add_class::<KnowledgeGraph>()?;
+    m.add_class::<AsOf>()?;
+    m.add_class::<NodePages>()?;
+    m.add_class::<Transaction>()?;
+    m.add_class::<StagedCall>()?;
     Ok(())
 }
\ No newline at end of file
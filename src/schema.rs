@@ -1,34 +1,320 @@
 // In schema.rs
 
-use crate::data_types::AttributeValue;
-use std::collections::HashMap;
-use serde::{Serialize, Deserialize};
+use crate::data_types::{split_composite_id, AttributeValue, CategoryDictionary};
+use pyo3::prelude::*;
+use std::borrow::{Borrow, Cow};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+use std::sync::{Arc, Mutex, OnceLock};
+use serde::{Serialize, Deserialize, Serializer, Deserializer};
+
+/// Interned text for names repeated on every node and edge, such as node and relation types:
+/// equal names share one allocation, so each node or edge holds a pointer rather than a copy
+#[derive(Clone)]
+pub struct Symbol(Arc<str>);
+
+// Every name interned so far; names are few, so the pool is never pruned
+static SYMBOLS: OnceLock<Mutex<HashSet<Arc<str>>>> = OnceLock::new();
+
+impl Symbol {
+    pub fn new(name: &str) -> Self {
+        let mut symbols = SYMBOLS.get_or_init(Default::default).lock().unwrap_or_else(|e| e.into_inner());
+        match symbols.get(name) {
+            Some(symbol) => Symbol(Arc::clone(symbol)),
+            None => {
+                let symbol: Arc<str> = Arc::from(name);
+                symbols.insert(Arc::clone(&symbol));
+                Symbol(symbol)
+            },
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn is_in(&self, names: &[String]) -> bool {
+        names.iter().any(|name| **name == *self.0)
+    }
+}
+
+impl Deref for Symbol {
+    type Target = str;
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Borrow<str> for Symbol {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for Symbol {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+// Interned symbols of the same name share their allocation, so most comparisons are a pointer check
+impl PartialEq for Symbol {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0) || self.0 == other.0
+    }
+}
+impl Eq for Symbol {}
+
+impl PartialEq<str> for Symbol {
+    fn eq(&self, other: &str) -> bool {
+        &*self.0 == other
+    }
+}
+impl PartialEq<&str> for Symbol {
+    fn eq(&self, other: &&str) -> bool {
+        &*self.0 == *other
+    }
+}
+impl PartialEq<String> for Symbol {
+    fn eq(&self, other: &String) -> bool {
+        *self.0 == **other
+    }
+}
+impl PartialEq<Symbol> for String {
+    fn eq(&self, other: &Symbol) -> bool {
+        **self == *other.0
+    }
+}
+impl PartialEq<Symbol> for &str {
+    fn eq(&self, other: &Symbol) -> bool {
+        **self == *other.0
+    }
+}
+
+// Hashes as the text, so maps keyed by symbols can be looked up with &str
+impl Hash for Symbol {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state)
+    }
+}
+
+impl PartialOrd for Symbol {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for Symbol {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.0.cmp(&other.0)
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&*self.0, f)
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(name: &str) -> Self {
+        Symbol::new(name)
+    }
+}
+impl From<&String> for Symbol {
+    fn from(name: &String) -> Self {
+        Symbol::new(name)
+    }
+}
+impl From<String> for Symbol {
+    fn from(name: String) -> Self {
+        Symbol::new(&name)
+    }
+}
+impl From<Symbol> for String {
+    fn from(symbol: Symbol) -> Self {
+        symbol.0.to_string()
+    }
+}
+
+impl ToPyObject for Symbol {
+    fn to_object(&self, py: Python) -> PyObject {
+        self.as_str().to_object(py)
+    }
+}
+impl IntoPy<PyObject> for Symbol {
+    fn into_py(self, py: Python) -> PyObject {
+        self.as_str().into_py(py)
+    }
+}
+impl IntoPy<PyObject> for &Symbol {
+    fn into_py(self, py: Python) -> PyObject {
+        self.as_str().into_py(py)
+    }
+}
+
+// Stored as plain text and interned again when read, so files don't change
+impl Serialize for Symbol {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+impl<'de> Deserialize<'de> for Symbol {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Symbol::new(&Cow::<str>::deserialize(deserializer)?))
+    }
+}
 
 // Node structure definition
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[allow(clippy::large_enum_variant)]
 pub enum Node {
     StandardNode {
-        node_type: String,
+        node_type: Symbol,
         unique_id: String,
         attributes: HashMap<String, AttributeValue>,
         title: Option<String>,
+        sequence: Option<u64>,  // Ingestion order, only set when the graph tracks insertion order
     },
     DataTypeNode {
         data_type: String,  // 'Node' or 'Relation'
         name: String,
         attributes: HashMap<String, String>,  // Attribute name to data type ('Int', 'Float', etc.)
+        calculated: HashSet<String>,  // Attributes stored from calculations rather than ingested
+        constraints: HashMap<String, Vec<Constraint>>,  // Attribute name to the constraints its values must meet
+        endpoints: Option<Endpoints>,  // Declared node types and cardinality of a relation type
+        defaults: HashMap<String, AttributeValue>,  // Attribute name to the value nodes missing it take
+        keys: KeyColumns,  // Columns the unique ID and title were ingested from
+        // Labels of the Category attributes; rebuilt from the values when a graph is read, so not stored
+        #[serde(skip)]
+        categories: HashMap<String, CategoryDictionary>,
     },
     // Add other variants as needed
 }
 
+/// A rule the values of one property must follow across the nodes of a type
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Constraint {
+    Unique,  // No two nodes share a value
+    Required,  // Every node has a value
+    Range(Option<AttributeValue>, Option<AttributeValue>),  // Inclusive bounds; None leaves a side open
+}
+
+impl Constraint {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Constraint::Unique => "unique",
+            Constraint::Required => "required",
+            Constraint::Range(..) => "range",
+        }
+    }
+
+    // Reads as the rule a value meets, e.g. "between 0 and 100"
+    pub fn describe(&self) -> String {
+        match self {
+            Constraint::Range(Some(min), Some(max)) => format!("between {} and {}", min.to_string(), max.to_string()),
+            Constraint::Range(Some(min), None) => format!("at least {}", min.to_string()),
+            Constraint::Range(None, Some(max)) => format!("at most {}", max.to_string()),
+            other => other.kind().to_string(),
+        }
+    }
+}
+
+/// How many relations of a type a node may have at each end. "1:N" lets a source have many targets
+/// but each target only one source; "N:1" the reverse.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Cardinality {
+    OneToOne,
+    OneToMany,
+    ManyToOne,
+    ManyToMany,
+}
+
+impl Cardinality {
+    pub fn parse(text: &str) -> Option<Self> {
+        match text.trim().to_ascii_uppercase().as_str() {
+            "1:1" => Some(Cardinality::OneToOne),
+            "1:N" => Some(Cardinality::OneToMany),
+            "N:1" => Some(Cardinality::ManyToOne),
+            "N:M" | "M:N" | "N:N" => Some(Cardinality::ManyToMany),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Cardinality::OneToOne => "1:1",
+            Cardinality::OneToMany => "1:N",
+            Cardinality::ManyToOne => "N:1",
+            Cardinality::ManyToMany => "N:M",
+        }
+    }
+
+    // Whether a source node may have several relations of the type
+    pub fn many_targets(&self) -> bool {
+        matches!(self, Cardinality::OneToMany | Cardinality::ManyToMany)
+    }
+
+    // Whether a target node may have several relations of the type
+    pub fn many_sources(&self) -> bool {
+        matches!(self, Cardinality::ManyToOne | Cardinality::ManyToMany)
+    }
+}
+
+/// The node types a relation type runs from and to, and its cardinality
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Endpoints {
+    pub source_type: String,
+    pub target_type: String,
+    pub cardinality: Cardinality,
+}
+
+/// The columns a node type's unique ID and title were ingested from. They are listed with the
+/// other attributes of the schema, but their values are held in a node's `unique_id` and `title`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct KeyColumns {
+    pub unique_id: Vec<String>,  // Several for a composite key, in key order
+    pub title: Option<String>,
+}
+
+impl KeyColumns {
+    pub fn contains(&self, column: &str) -> bool {
+        self.unique_id.iter().any(|field| field == column) || self.title.as_deref() == Some(column)
+    }
+
+    // The value a node holds for a key column: its unique ID, its part of a composite ID, or its title
+    pub fn value(&self, column: &str, unique_id: &str, title: Option<&str>) -> Option<String> {
+        match self.unique_id.iter().position(|field| field == column) {
+            Some(_) if self.unique_id.len() == 1 => Some(unique_id.to_string()),
+            Some(position) => split_composite_id(unique_id).into_iter().nth(position),
+            None if self.title.as_deref() == Some(column) => title.map(str::to_string),
+            None => None,
+        }
+    }
+}
+
+// Missing values are left out rather than stored, so a property is either set or absent
+fn without_nulls(mut attributes: HashMap<String, AttributeValue>) -> HashMap<String, AttributeValue> {
+    attributes.retain(|_, value| !value.is_null());
+    attributes
+}
+
 impl Node {
     // Implement constructor methods for each variant if needed
     pub fn new(node_type: &str, unique_id: &str, attributes: Option<HashMap<String, AttributeValue>>, node_title: Option<&str>) -> Self {
         Node::StandardNode {
-            node_type: node_type.to_string(),
+            node_type: Symbol::new(node_type),
             unique_id: unique_id.to_string(),
-            attributes: attributes.unwrap_or_else(HashMap::new),
+            attributes: attributes.map(without_nulls).unwrap_or_default(),
             title: node_title.map(|t| t.to_string()),
+            sequence: None,
         }
     }
 
@@ -37,14 +323,46 @@ impl Node {
             data_type: data_type.to_string(),
             name: name.to_string(),
             attributes,
+            calculated: HashSet::new(),
+            constraints: HashMap::new(),
+            endpoints: None,
+            defaults: HashMap::new(),
+            keys: KeyColumns::default(),
+            categories: HashMap::new(),
+        }
+    }
+
+    pub fn sequence(&self) -> Option<u64> {
+        match self {
+            Node::StandardNode { sequence, .. } => *sequence,
+            Node::DataTypeNode { .. } => None,
+        }
+    }
+
+    pub fn set_sequence(&mut self, value: Option<u64>) {
+        if let Node::StandardNode { sequence, .. } = self {
+            *sequence = value;
+        }
+    }
+
+    // Resolve a field name to a value; "unique_id" and "title" map to the built-in fields and dotted
+    // paths reach into map properties
+    pub fn get_field(&self, field: &str) -> Option<Cow<'_, AttributeValue>> {
+        match self {
+            Node::StandardNode { unique_id, title, attributes, .. } => match field {
+                "unique_id" => Some(Cow::Owned(AttributeValue::String(unique_id.clone()))),
+                "title" => title.as_ref().map(|t| Cow::Owned(AttributeValue::String(t.clone()))),
+                _ => AttributeValue::lookup(attributes, field).map(Cow::Borrowed),
+            },
+            Node::DataTypeNode { .. } => None,
         }
     }
 }
 
 // Relation structure definition
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Relation {
-    pub relation_type: String,
+    pub relation_type: Symbol,
     pub attributes: Option<HashMap<String, AttributeValue>>,  // Now an Option
 }
 
@@ -52,8 +370,16 @@ impl Relation {
     // Adjust the constructor to accept an Option for attributes
     pub fn new(name: &str, attributes: Option<HashMap<String, AttributeValue>>) -> Self {
         Relation {
-            relation_type: name.to_string(),
-            attributes,  // Directly passed as an Option
+            relation_type: Symbol::new(name),
+            attributes: attributes.map(without_nulls).filter(|attributes| !attributes.is_empty()),
+        }
+    }
+
+    // Resolve a field name to a value; "relation_type" maps to the built-in field
+    pub fn get_field(&self, field: &str) -> Option<Cow<'_, AttributeValue>> {
+        match field {
+            "relation_type" => Some(Cow::Owned(AttributeValue::String(self.relation_type.to_string()))),
+            _ => AttributeValue::lookup(self.attributes.as_ref()?, field).map(Cow::Borrowed),
         }
     }
 }
\ No newline at end of file